@@ -0,0 +1,131 @@
+//! Adaptive concurrency control for `RollingRequests`.
+//!
+//! This module provides the `AdaptiveConcurrency` settings, which let the scheduler
+//! grow the number of simultaneous requests while responses are fast and successful
+//! and shrink it quickly when the server shows signs of overload (AIMD), and the
+//! `RampUp` schedule, which raises the limit gradually at the start of each run.
+
+use std::time::{Duration, Instant};
+
+/// Settings for additive-increase/multiplicative-decrease concurrency control.
+#[derive(Clone, Debug)]
+pub struct AdaptiveConcurrency {
+    /// The lowest effective limit the scheduler may fall back to.
+    pub min_limit: usize,
+    /// The highest effective limit the scheduler may grow to.
+    pub max_limit: usize,
+    /// Responses slower than this do not grow the limit.
+    pub latency_threshold: Duration,
+    /// The factor applied to the limit when overload is detected.
+    pub decrease_factor: f64,
+}
+
+impl Default for AdaptiveConcurrency {
+    fn default() -> Self {
+        AdaptiveConcurrency {
+            min_limit: 1,                              // Default lower bound
+            max_limit: 64,                             // Default upper bound
+            latency_threshold: Duration::from_secs(1), // Default "fast" response
+            decrease_factor: 0.5,                      // Default halving
+        }
+    }
+}
+
+/// The outcome of a request as seen by the concurrency controller.
+pub(crate) enum Feedback {
+    /// The request succeeded after the given latency.
+    Success(Duration),
+    /// The request timed out, failed to connect, or was rejected with 429/503.
+    Overload,
+    /// The request finished in a way that says nothing about server load.
+    Neutral,
+}
+
+/// Tracks the effective concurrency limit using AIMD.
+pub(crate) struct AimdLimiter {
+    /// The settings the limiter was created with.
+    settings: AdaptiveConcurrency,
+    /// The current limit, kept fractional so increases can be spread across a window.
+    limit: f64,
+    /// When the limit was last decreased.
+    last_decrease: Option<Instant>,
+}
+
+impl AimdLimiter {
+    /// Creates a limiter starting from `initial`, clamped to the configured bounds.
+    pub(crate) fn new(settings: AdaptiveConcurrency, initial: usize) -> Self {
+        let min = settings.min_limit.max(1);
+        let max = settings.max_limit.max(min);
+        AimdLimiter {
+            limit: initial.clamp(min, max) as f64,
+            settings,
+            last_decrease: None,
+        }
+    }
+
+    /// Returns the current effective limit.
+    pub(crate) fn limit(&self) -> usize {
+        self.limit as usize
+    }
+
+    /// Updates the limit with the outcome of a request dispatched at `dispatched_at`.
+    ///
+    /// The limit grows by one per window of fast successes and is cut at most once
+    /// per window: failures of requests dispatched before the last decrease are ignored.
+    pub(crate) fn record(&mut self, dispatched_at: Instant, feedback: Feedback) {
+        let min = self.settings.min_limit.max(1) as f64;
+        let max = self.settings.max_limit.max(1) as f64;
+
+        match feedback {
+            Feedback::Success(latency) if latency <= self.settings.latency_threshold => {
+                self.limit = (self.limit + 1.0 / self.limit).min(max.max(min));
+            }
+            Feedback::Overload => {
+                if self.last_decrease.is_some_and(|at| dispatched_at < at) {
+                    return;
+                }
+                self.limit = (self.limit * self.settings.decrease_factor)
+                    .floor()
+                    .max(min);
+                self.last_decrease = Some(Instant::now());
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A schedule that starts each run at one simultaneous request and raises the
+/// limit step by step up to a target.
+#[derive(Clone, Debug)]
+pub struct RampUp {
+    /// The limit reached at the end of the ramp.
+    pub target_limit: usize,
+    /// How many simultaneous requests are added at each step.
+    pub step: usize,
+    /// The time between two steps.
+    pub interval: Duration,
+}
+
+impl RampUp {
+    /// Returns the limit allowed `elapsed` after the start of a run.
+    pub(crate) fn limit_at(&self, elapsed: Duration) -> usize {
+        let target = self.target_limit.max(1);
+        if self.interval.is_zero() {
+            return target;
+        }
+        let steps = (elapsed.as_nanos() / self.interval.as_nanos()) as usize;
+        self.step
+            .saturating_mul(steps)
+            .saturating_add(1)
+            .min(target)
+    }
+
+    /// Returns the time since the start of a run at which the next step happens.
+    pub(crate) fn next_step(&self, elapsed: Duration) -> Duration {
+        if self.interval.is_zero() {
+            return elapsed;
+        }
+        let steps = (elapsed.as_nanos() / self.interval.as_nanos()) as u32 + 1;
+        self.interval * steps
+    }
+}
@@ -0,0 +1,63 @@
+//! Credential refresh used by the request scheduler.
+//!
+//! This module keeps the `Authorization` value obtained from the user's
+//! `on_unauthorized` hook and makes sure concurrent 401 responses trigger a
+//! single call to the hook.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+
+/// The future returned by a refresh hook.
+pub(crate) type RefreshFuture = Pin<Box<dyn Future<Output = Option<String>> + Send>>;
+
+/// A hook returning a fresh `Authorization` header value, or `None` on failure.
+pub(crate) type RefreshHook = Box<dyn Fn() -> RefreshFuture + Send + Sync>;
+
+/// The credential shared by every request of a `RollingRequests` instance.
+pub(crate) struct Credentials {
+    /// The hook called when a request is rejected with 401.
+    hook: RefreshHook,
+    /// How many refreshes happened so far, and the latest credential.
+    current: Mutex<(u64, Option<String>)>,
+    /// Held while the hook runs, so only one refresh happens at a time.
+    refreshing: tokio::sync::Mutex<()>,
+}
+
+impl Credentials {
+    /// Creates credentials refreshed by `hook`.
+    pub(crate) fn new(hook: RefreshHook) -> Self {
+        Credentials {
+            hook,
+            current: Mutex::new((0, None)),
+            refreshing: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Returns the refresh generation and the credential to send, if refreshed yet.
+    pub(crate) fn current(&self) -> (u64, Option<String>) {
+        self.current.lock().unwrap().clone()
+    }
+
+    /// Refreshes the credential after a request sent at `generation` got a 401.
+    ///
+    /// When another request already refreshed it in the meantime, the hook is
+    /// not called again and the newer credential is returned.
+    pub(crate) async fn refresh(&self, generation: u64) -> Option<String> {
+        let _guard = self.refreshing.lock().await;
+        {
+            let current = self.current.lock().unwrap();
+            if current.0 != generation {
+                return current.1.clone();
+            }
+        }
+
+        let value = (self.hook)().await;
+        let mut current = self.current.lock().unwrap();
+        current.0 += 1;
+        if value.is_some() {
+            current.1 = value;
+        }
+        current.1.clone()
+    }
+}
@@ -0,0 +1,655 @@
+//! In-memory caching of responses for `RollingRequests`.
+//!
+//! This module provides the `ResponseCache` settings, which let the scheduler
+//! answer repeated GET and HEAD requests from responses it received earlier
+//! instead of sending them again, and the `ValidatorStore`, which remembers
+//! the `ETag` and `Last-Modified` headers of responses to make later requests
+//! for the same URLs conditional.
+
+use crate::error::BodyTooLarge;
+use crate::request::Request;
+use crate::rolling::{body_error, read_capped_body};
+use crate::throttle::Bandwidth;
+use bytes::Bytes;
+use reqwest::header::{
+    AUTHORIZATION, CACHE_CONTROL, COOKIE, ETAG, HeaderMap, HeaderName, HeaderValue, LAST_MODIFIED,
+    VARY,
+};
+use reqwest::{Method, StatusCode, Url, Version};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Settings for the in-memory response cache.
+///
+/// Responses to GET and HEAD requests without a body are stored by method,
+/// URL and the `Authorization` and `Cookie` headers of the request, and told
+/// apart by the request headers their `Vary` header names. Only
+/// statuses that are cacheable by default (200, 203, 204, 300, 301, 308, 404,
+/// 405, 410, 414 and 501) are stored, and neither responses nor requests with
+/// `Cache-Control: no-store` or `no-cache`. An entry is kept for the `ttl`, or
+/// the `max-age` of the response when shorter, and the least recently used
+/// entry is evicted when the cache is full.
+#[derive(Clone, Debug)]
+pub struct ResponseCache {
+    /// How long a response is served from the cache.
+    pub ttl: Duration,
+    /// The maximum number of responses kept.
+    pub max_entries: usize,
+}
+
+impl Default for ResponseCache {
+    fn default() -> Self {
+        ResponseCache {
+            ttl: Duration::from_secs(300), // Default five minutes
+            max_entries: 1024,             // Default entry count
+        }
+    }
+}
+
+/// Where a response rebuilt by the response cache comes from, stored in its
+/// extensions, as `Response::url` of such a response is a placeholder.
+///
+/// Responses that are stored are rebuilt around their body after it was
+/// read, so both cache hits and the responses that filled the cache carry it.
+///
+/// #### Examples
+///
+/// ```no_run
+/// use rollingrequests::cache::{CacheInfo, ResponseCache};
+/// use rollingrequests::request::Request;
+/// use rollingrequests::rolling::RollingRequestsBuilder;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let mut rolling_requests = RollingRequestsBuilder::new()
+///         .response_cache(ResponseCache::default())
+///         .build();
+///     rolling_requests.add_request(Request::get("http://example.com"));
+///     rolling_requests.add_request(Request::get("http://example.com"));
+///
+///     for response in rolling_requests.execute_all().await.into_iter().flatten() {
+///         if let Some(info) = response.extensions().get::<CacheInfo>() {
+///             println!("{} from cache: {}", info.url, info.hit);
+///         }
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CacheInfo {
+    /// The URL the response was received from.
+    pub url: Url,
+    /// Whether the response was served from the cache instead of sent.
+    pub hit: bool,
+}
+
+/// A response stored in the cache.
+#[derive(Clone)]
+pub(crate) struct CachedResponse {
+    /// The URL the response was received from.
+    url: Url,
+    /// The status of the response.
+    status: StatusCode,
+    /// The HTTP version of the response.
+    version: Version,
+    /// The headers of the response.
+    headers: HeaderMap,
+    /// The body of the response.
+    body: Bytes,
+}
+
+impl CachedResponse {
+    /// Rebuilds the response, marked as a cache hit if `hit` is true.
+    pub(crate) fn to_response(&self, hit: bool) -> reqwest::Response {
+        let mut response = http::Response::new(self.body.clone());
+        *response.status_mut() = self.status;
+        *response.version_mut() = self.version;
+        *response.headers_mut() = self.headers.clone();
+        response.extensions_mut().insert(CacheInfo {
+            url: self.url.clone(),
+            hit,
+        });
+        reqwest::Response::from(response)
+    }
+}
+
+/// A cache entry, valid for requests with the given values of the headers
+/// named by `Vary`.
+struct Entry {
+    /// The request headers named by `Vary`, with their values.
+    vary: Vec<(HeaderName, Option<HeaderValue>)>,
+    /// The stored response.
+    response: CachedResponse,
+    /// When the entry stops being served.
+    expires_at: Instant,
+    /// When the entry was last stored or served, for eviction.
+    last_used: u64,
+}
+
+impl Entry {
+    /// Returns true if `headers` have the values the entry was stored with.
+    fn matches(&self, headers: &HeaderMap) -> bool {
+        self.vary
+            .iter()
+            .all(|(name, value)| headers.get(name) == value.as_ref())
+    }
+}
+
+/// The entries of the cache.
+#[derive(Default)]
+struct CacheState {
+    /// The entries by cache key.
+    entries: HashMap<String, Vec<Entry>>,
+    /// The number of entries across all keys.
+    len: usize,
+    /// The counter ordering uses of entries.
+    clock: u64,
+}
+
+/// The response cache of a `RollingRequests` instance.
+pub(crate) struct Cache {
+    /// The settings the cache was created with.
+    settings: ResponseCache,
+    /// The stored responses.
+    state: Mutex<CacheState>,
+    /// The keys of the requests being fetched to fill the cache, with the
+    /// number of attempts fetching each.
+    fetching: Mutex<HashMap<String, usize>>,
+}
+
+impl Cache {
+    /// Creates an empty cache.
+    pub(crate) fn new(settings: ResponseCache) -> Self {
+        Cache {
+            settings,
+            state: Mutex::new(CacheState::default()),
+            fetching: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the key `request` is cached under, or `None` if it is not
+    /// cacheable.
+    ///
+    /// `url` is the URL the request is sent to, with its placeholders
+    /// replaced and resolved against the base URL. The `Authorization` and
+    /// `Cookie` headers and the cookies of the request are part of the key,
+    /// so a response is only served to requests with the same credentials.
+    /// Requests streaming their body to an output file, a body sink or a line
+    /// or event handler are not cacheable, so their bodies are never held in
+    /// memory.
+    pub(crate) fn key(request: &Request, url: &str) -> Option<String> {
+        let streams = request.output_file.is_some()
+            || request.body_sink.is_some()
+            || request.line_handler.is_some()
+            || request.event_handler.is_some();
+        if streams || forbids_storing(&request.headers) {
+            return None;
+        }
+        let url = cacheable_url(request, url)?;
+        let mut key = format!("{} {}", request.method, url);
+        for name in [AUTHORIZATION, COOKIE] {
+            for value in request.headers.get_all(&name) {
+                let value = String::from_utf8_lossy(value.as_bytes());
+                key.push_str(&format!("\n{}: {}", name, value));
+            }
+        }
+        for (name, value) in &request.cookies {
+            key.push_str(&format!("\ncookie: {}={}", name, value));
+        }
+        Some(key)
+    }
+
+    /// Returns the response stored under `key` for a request with `headers`.
+    pub(crate) fn get(&self, key: &str, headers: &HeaderMap) -> Option<CachedResponse> {
+        let mut state = self.state.lock().unwrap();
+        state.clock += 1;
+        let clock = state.clock;
+        let now = Instant::now();
+        let entry = state
+            .entries
+            .get_mut(key)?
+            .iter_mut()
+            .find(|entry| entry.expires_at > now && entry.matches(headers))?;
+        entry.last_used = clock;
+        Some(entry.response.clone())
+    }
+
+    /// Stores `response` under `key`, if it may be cached, and returns it
+    /// rebuilt around the body read.
+    ///
+    /// The body is read through `bandwidth` when given. A response whose
+    /// `Content-Length` is over `limit` is not stored and handed on unread.
+    ///
+    /// #### Errors
+    ///
+    /// Returns the error of reading the body of a cacheable response, or a
+    /// `BodyTooLarge` error once it grows past `limit`.
+    pub(crate) async fn store(
+        &self,
+        key: &str,
+        request_headers: &HeaderMap,
+        response: reqwest::Response,
+        bandwidth: Option<&Bandwidth>,
+        limit: Option<u64>,
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        let Some(ttl) = self.ttl_of(&response) else {
+            return Ok(response);
+        };
+        let vary = match vary_of(response.headers(), request_headers) {
+            Some(vary) => vary,
+            None => return Ok(response),
+        };
+        let length = response.content_length();
+        if length
+            .zip(limit)
+            .is_some_and(|(length, limit)| length > limit)
+        {
+            return Ok(response);
+        }
+        let url = response.url().clone();
+        let status = response.status();
+        let version = response.version();
+        let headers = response.headers().clone();
+        // One byte past the limit tells a body over it from one filling it
+        let cap = limit.map(|limit| usize::try_from(limit.saturating_add(1)).unwrap_or(usize::MAX));
+        let mut body = Vec::new();
+        read_capped_body(response, bandwidth, cap, &mut body).await?;
+        let size = body.len() as u64;
+        if let Some(limit) = limit.filter(|limit| size > *limit) {
+            return Err(body_error(BodyTooLarge { size, limit }).await);
+        }
+        let cached = CachedResponse {
+            url,
+            status,
+            version,
+            headers,
+            body: Bytes::from(body),
+        };
+        let rebuilt = cached.to_response(false);
+        self.insert(key, vary, cached, ttl);
+        Ok(rebuilt)
+    }
+
+    /// Returns how long `response` may be served from the cache, or `None` if
+    /// it may not be stored.
+    fn ttl_of(&self, response: &reqwest::Response) -> Option<Duration> {
+        let cacheable = matches!(
+            response.status().as_u16(),
+            200 | 203 | 204 | 300 | 301 | 308 | 404 | 405 | 410 | 414 | 501
+        );
+        if !cacheable || forbids_storing(response.headers()) {
+            return None;
+        }
+        let ttl = match max_age(response.headers()) {
+            Some(max_age) => self.settings.ttl.min(max_age),
+            None => self.settings.ttl,
+        };
+        Some(ttl).filter(|ttl| !ttl.is_zero())
+    }
+
+    /// Returns the response stored under `key` for a request with `headers`
+    /// after the server confirmed it is unchanged, serving it for another
+    /// `ttl` even if it had expired.
+    pub(crate) fn revalidate(&self, key: &str, headers: &HeaderMap) -> Option<CachedResponse> {
+        let mut state = self.state.lock().unwrap();
+        state.clock += 1;
+        let clock = state.clock;
+        let entry = state
+            .entries
+            .get_mut(key)?
+            .iter_mut()
+            .find(|entry| entry.matches(headers))?;
+        entry.expires_at = Instant::now() + self.settings.ttl;
+        entry.last_used = clock;
+        Some(entry.response.clone())
+    }
+
+    /// Adds an entry, replacing one stored for the same request headers and
+    /// evicting the least recently used entry when the cache is full.
+    fn insert(
+        &self,
+        key: &str,
+        vary: Vec<(HeaderName, Option<HeaderValue>)>,
+        response: CachedResponse,
+        ttl: Duration,
+    ) {
+        if self.settings.max_entries == 0 {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        state.clock += 1;
+        let entry = Entry {
+            vary,
+            response,
+            expires_at: Instant::now() + ttl,
+            last_used: state.clock,
+        };
+        let entries = state.entries.entry(key.to_string()).or_default();
+        let before = entries.len();
+        entries.retain(|stored| stored.vary != entry.vary);
+        let replaced = before - entries.len();
+        entries.push(entry);
+        state.len = state.len + 1 - replaced;
+        if state.len > self.settings.max_entries {
+            state.evict();
+        }
+    }
+
+    /// Marks `key` as being fetched until the returned guard is dropped.
+    pub(crate) fn start_fetch(self: &Arc<Self>, key: String) -> Fetch {
+        *self
+            .fetching
+            .lock()
+            .unwrap()
+            .entry(key.clone())
+            .or_default() += 1;
+        Fetch {
+            cache: self.clone(),
+            key,
+        }
+    }
+
+    /// Returns true if a request with `key` is being fetched.
+    pub(crate) fn is_fetching(&self, key: &str) -> bool {
+        self.fetching.lock().unwrap().contains_key(key)
+    }
+
+    /// Removes every stored response.
+    pub(crate) fn clear(&self) {
+        *self.state.lock().unwrap() = CacheState::default();
+    }
+}
+
+/// A fetch filling the cache, holding back requests for its key.
+///
+/// The key is released when the fetch is dropped, so an attempt that is
+/// aborted, such as a losing hedge, does not hold them back for good.
+pub(crate) struct Fetch {
+    /// The cache being filled.
+    pub(crate) cache: Arc<Cache>,
+    /// The key the response is stored under.
+    pub(crate) key: String,
+}
+
+impl Drop for Fetch {
+    fn drop(&mut self) {
+        let mut fetching = self.cache.fetching.lock().unwrap();
+        if let Some(count) = fetching.get_mut(&self.key) {
+            *count -= 1;
+            if *count == 0 {
+                fetching.remove(&self.key);
+            }
+        }
+    }
+}
+
+impl CacheState {
+    /// Removes the least recently used entry.
+    fn evict(&mut self) {
+        let oldest = self
+            .entries
+            .iter()
+            .flat_map(|(key, entries)| entries.iter().map(move |entry| (entry.last_used, key)))
+            .min_by_key(|(last_used, _)| *last_used)
+            .map(|(last_used, key)| (last_used, key.clone()));
+        let Some((last_used, key)) = oldest else {
+            return;
+        };
+        if let Some(entries) = self.entries.get_mut(&key) {
+            entries.retain(|entry| entry.last_used != last_used);
+            if entries.is_empty() {
+                self.entries.remove(&key);
+            }
+        }
+        self.len -= 1;
+    }
+}
+
+/// The validators of a response, sent back to ask whether it changed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Validators {
+    /// The `ETag` header of the response, sent as `If-None-Match`.
+    pub etag: Option<String>,
+    /// The `Last-Modified` header of the response, sent as `If-Modified-Since`.
+    pub last_modified: Option<String>,
+}
+
+/// A store of the validators of responses, by URL.
+///
+/// With `RollingRequestsBuilder::validator_store`, the `ETag` and
+/// `Last-Modified` headers of successful responses to GET and HEAD requests
+/// are recorded, and later GET and HEAD requests for the same URL carry them
+/// as `If-None-Match` and `If-Modified-Since`, unless they already have those
+/// headers. A `304 Not Modified` answer is marked in
+/// `RollingResult::not_modified`; with the response cache enabled as well, it
+/// is replaced by the cached response when one is still stored.
+///
+/// The store can be saved to a file and loaded in a later run.
+///
+/// #### Examples
+///
+/// ```no_run
+/// use rollingrequests::cache::ValidatorStore;
+/// use rollingrequests::request::Request;
+/// use rollingrequests::rolling::RollingRequestsBuilder;
+/// use std::sync::Arc;
+///
+/// #[tokio::main]
+/// async fn main() -> std::io::Result<()> {
+///     let store = Arc::new(ValidatorStore::load("validators.json").unwrap_or_default());
+///     let mut rolling_requests = RollingRequestsBuilder::new()
+///         .validator_store(store.clone())
+///         .build();
+///     rolling_requests.add_request(Request::get("http://example.com/feed"));
+///
+///     for result in rolling_requests.execute_all_detailed().await {
+///         if result.not_modified {
+///             println!("unchanged");
+///         }
+///     }
+///     store.save("validators.json")
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct ValidatorStore {
+    /// The validators by URL.
+    entries: Mutex<HashMap<String, Validators>>,
+}
+
+impl ValidatorStore {
+    /// Creates an empty store.
+    pub fn new() -> Self {
+        ValidatorStore::default()
+    }
+
+    /// Loads a store saved with `save`.
+    ///
+    /// #### Arguments
+    ///
+    /// * `path` - The file to read.
+    ///
+    /// #### Errors
+    ///
+    /// Returns an error if the file cannot be read, or is not a saved store.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let saved: Map<String, Value> = serde_json::from_slice(&fs::read(path)?)?;
+        let field = |value: &Value, name: &str| value.get(name)?.as_str().map(String::from);
+        let entries = saved
+            .into_iter()
+            .map(|(url, value)| {
+                let validators = Validators {
+                    etag: field(&value, "etag"),
+                    last_modified: field(&value, "last_modified"),
+                };
+                (url, validators)
+            })
+            .collect();
+        Ok(ValidatorStore {
+            entries: Mutex::new(entries),
+        })
+    }
+
+    /// Saves the store as JSON, to be read back with `load`.
+    ///
+    /// #### Arguments
+    ///
+    /// * `path` - The file to write, replaced if it exists.
+    ///
+    /// #### Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let saved: Map<String, Value> = self
+            .entries
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(url, validators)| {
+                let mut value = Map::new();
+                if let Some(etag) = &validators.etag {
+                    value.insert("etag".to_string(), Value::from(etag.as_str()));
+                }
+                if let Some(last_modified) = &validators.last_modified {
+                    value.insert(
+                        "last_modified".to_string(),
+                        Value::from(last_modified.as_str()),
+                    );
+                }
+                (url.clone(), Value::Object(value))
+            })
+            .collect();
+        fs::write(path, serde_json::to_vec_pretty(&saved)?)
+    }
+
+    /// Returns the validators recorded for `url`.
+    pub fn get(&self, url: &str) -> Option<Validators> {
+        self.entries.lock().unwrap().get(url).cloned()
+    }
+
+    /// Records the validators of `url`, replacing earlier ones.
+    pub fn insert(&self, url: &str, validators: Validators) {
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(url.to_string(), validators);
+    }
+
+    /// Forgets the validators of `url`, returning them.
+    pub fn remove(&self, url: &str) -> Option<Validators> {
+        self.entries.lock().unwrap().remove(url)
+    }
+
+    /// Returns the number of URLs with validators.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    /// Returns true if no validators are recorded.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Forgets every validator.
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    /// Returns the URL `request` is recorded under, or `None` if it is not
+    /// a GET or HEAD request without a body.
+    pub(crate) fn key(request: &Request, url: &str) -> Option<String> {
+        cacheable_url(request, url).map(String::from)
+    }
+
+    /// Records the validators among the response `headers` of `url`, if any.
+    pub(crate) fn record(&self, url: &str, headers: &HeaderMap) {
+        let header = |name| {
+            let value = headers.get(name)?.to_str().ok()?;
+            Some(value.to_string())
+        };
+        let validators = Validators {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+        };
+        if validators.etag.is_some() || validators.last_modified.is_some() {
+            self.insert(url, validators);
+        }
+    }
+}
+
+/// Returns the URL a GET or HEAD request without a body is sent to, with its
+/// query parameters, or `None` for other requests.
+fn cacheable_url(request: &Request, url: &str) -> Option<Url> {
+    if request.method != Method::GET && request.method != Method::HEAD {
+        return None;
+    }
+    if request.post_data.is_some()
+        || request.body_file.is_some()
+        || request.multipart_form_data.is_some()
+    {
+        return None;
+    }
+    let mut url = Url::parse(url).ok()?;
+    if !request.query_params.is_empty() {
+        url.query_pairs_mut().extend_pairs(&request.query_params);
+    }
+    Some(url)
+}
+
+/// Returns true if `Cache-Control` in `headers` forbids using a cache.
+fn forbids_storing(headers: &HeaderMap) -> bool {
+    cache_directives(headers).any(|directive| {
+        directive.eq_ignore_ascii_case("no-store") || directive.eq_ignore_ascii_case("no-cache")
+    })
+}
+
+/// Returns the `max-age` directive of `Cache-Control` in `headers`.
+fn max_age(headers: &HeaderMap) -> Option<Duration> {
+    cache_directives(headers).find_map(|directive| {
+        let (name, value) = directive.split_once('=')?;
+        if !name.trim().eq_ignore_ascii_case("max-age") {
+            return None;
+        }
+        let seconds = value.trim().trim_matches('"').parse().ok()?;
+        Some(Duration::from_secs(seconds))
+    })
+}
+
+/// Returns the directives of every `Cache-Control` header in `headers`.
+fn cache_directives(headers: &HeaderMap) -> impl Iterator<Item = &str> {
+    headers
+        .get_all(CACHE_CONTROL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+}
+
+/// Returns the request headers named by the `Vary` headers of a response,
+/// with their values, or `None` for `Vary: *`.
+fn vary_of(
+    response_headers: &HeaderMap,
+    request_headers: &HeaderMap,
+) -> Option<Vec<(HeaderName, Option<HeaderValue>)>> {
+    let mut vary = Vec::new();
+    let names = response_headers
+        .get_all(VARY)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .filter(|name| !name.is_empty());
+    for name in names {
+        if name == "*" {
+            return None;
+        }
+        if let Ok(name) = HeaderName::from_bytes(name.as_bytes()) {
+            let value = request_headers.get(&name).cloned();
+            vary.push((name, value));
+        }
+    }
+    Some(vary)
+}
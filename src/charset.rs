@@ -0,0 +1,102 @@
+//! Decoding of response bodies as text.
+//!
+//! With the `charset` feature, the text of a response is decoded with the
+//! encoding declared by a byte order mark, the charset of its
+//! `Content-Type`, or a `meta` tag of an HTML body, in that order, and with
+//! the default charset of the `RollingRequests` instance otherwise.
+
+use encoding_rs::{Encoding, UTF_8, UTF_16BE, UTF_16LE};
+use reqwest::header::{CONTENT_TYPE, HeaderMap};
+
+/// The number of bytes of an HTML body searched for a `meta` charset.
+const META_SCAN_LIMIT: usize = 1024;
+
+/// Returns the encoding of a response body.
+///
+/// #### Arguments
+///
+/// * `body` - The body, or its beginning.
+/// * `headers` - The headers of the response.
+/// * `default` - The encoding of bodies declaring none; `None` is UTF-8.
+pub(crate) fn detect(
+    body: &[u8],
+    headers: &HeaderMap,
+    default: Option<&'static Encoding>,
+) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(body) {
+        return encoding;
+    }
+    let content_type = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok());
+    if let Some(encoding) = content_type.and_then(declared_charset) {
+        return encoding;
+    }
+    let mime = content_type
+        .and_then(|value| value.split(';').next())
+        .map(|mime| mime.trim().to_ascii_lowercase());
+    let is_html = mime
+        .as_deref()
+        .is_none_or(|mime| mime == "text/html" || mime == "application/xhtml+xml");
+    if let Some(encoding) = is_html.then(|| meta_charset(body)).flatten() {
+        return encoding;
+    }
+    default.unwrap_or(UTF_8)
+}
+
+/// Decodes `body` with `encoding`, replacing malformed sequences.
+pub(crate) fn decode(body: &[u8], encoding: &'static Encoding) -> String {
+    let (text, _) = encoding.decode_with_bom_removal(body);
+    text.into_owned()
+}
+
+/// Returns the encoding named by the `charset` parameter of a `Content-Type`.
+fn declared_charset(content_type: &str) -> Option<&'static Encoding> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        if !name.trim().eq_ignore_ascii_case("charset") {
+            return None;
+        }
+        Encoding::for_label(value.trim().trim_matches('"').as_bytes())
+    })
+}
+
+/// Returns the encoding declared by a `meta` tag near the start of an HTML
+/// body, either `<meta charset="...">` or the `content` of an `http-equiv`
+/// tag.
+fn meta_charset(body: &[u8]) -> Option<&'static Encoding> {
+    let head = body[..body.len().min(META_SCAN_LIMIT)].to_ascii_lowercase();
+    let mut rest = head.as_slice();
+    while let Some(start) = find(rest, b"<meta") {
+        rest = &rest[start + 5..];
+        let tag = &rest[..find(rest, b">").unwrap_or(rest.len())];
+        let Some(at) = find(tag, b"charset=") else {
+            continue;
+        };
+        let value = &tag[at + 8..];
+        let value = value
+            .strip_prefix(b"\"")
+            .or_else(|| value.strip_prefix(b"'"))
+            .unwrap_or(value);
+        let end = value
+            .iter()
+            .position(|&byte| {
+                matches!(byte, b'"' | b'\'' | b';' | b'/') || byte.is_ascii_whitespace()
+            })
+            .unwrap_or(value.len());
+        let encoding = Encoding::for_label(&value[..end])?;
+        // A body that could be read as ASCII to find the tag is not UTF-16
+        if encoding == UTF_16LE || encoding == UTF_16BE {
+            return Some(UTF_8);
+        }
+        return Some(encoding);
+    }
+    None
+}
+
+/// Returns the position of the first occurrence of `needle` in `haystack`.
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
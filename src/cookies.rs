@@ -0,0 +1,195 @@
+//! The cookie jar shared by the clients of a `RollingRequests` instance.
+//!
+//! This module keeps the cookies set by responses when
+//! `RollingRequestsBuilder::cookie_store` is enabled, and hands them to the
+//! HTTP clients through reqwest's `CookieStore` trait. Under the
+//! `persistent-cookies` feature, the jar can also be loaded from and saved to
+//! a file, see `RollingRequestsBuilder::cookie_store_path`; without it, the
+//! cookies are kept in reqwest's own jar.
+
+use reqwest::cookie::CookieStore;
+use reqwest::header::HeaderValue;
+#[cfg(feature = "persistent-cookies")]
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, BufWriter, Write},
+    path::PathBuf,
+    sync::RwLock,
+};
+use url::Url;
+
+/// The cookies received by every request of a `RollingRequests` instance.
+#[derive(Default)]
+pub(crate) struct CookieJar {
+    /// The cookies, by domain, path and name.
+    #[cfg(feature = "persistent-cookies")]
+    store: RwLock<cookie_store::CookieStore>,
+    /// The cookies, by domain, path and name.
+    #[cfg(not(feature = "persistent-cookies"))]
+    store: reqwest::cookie::Jar,
+    /// The file the cookies are saved to, if any.
+    #[cfg(feature = "persistent-cookies")]
+    path: Option<PathBuf>,
+}
+
+impl CookieJar {
+    /// Creates a jar saved to `path`, holding the unexpired cookies saved
+    /// there; a missing file starts it empty.
+    ///
+    /// #### Errors
+    ///
+    /// Returns an error if the file cannot be read or holds something other
+    /// than saved cookies, so it is never overwritten by the jar.
+    #[cfg(feature = "persistent-cookies")]
+    pub(crate) fn load(path: PathBuf) -> io::Result<Self> {
+        let store = match File::open(&path) {
+            Ok(file) => cookie_store::CookieStore::load_json(BufReader::new(file))
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Default::default(),
+            Err(err) => return Err(err),
+        };
+        Ok(CookieJar {
+            store: RwLock::new(store),
+            path: Some(path),
+        })
+    }
+
+    /// Writes the unexpired cookies, including those without an expiry, to
+    /// the file of the jar, one JSON object per line.
+    ///
+    /// The cookies are written to a temporary file renamed over the previous
+    /// one, so an interrupted save leaves the previous cookies readable. On
+    /// Unix, a file created by the jar is readable by its owner only.
+    #[cfg(feature = "persistent-cookies")]
+    pub(crate) fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent)?;
+        }
+        let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+        temp_name.push(".tmp");
+        let temp = path.with_file_name(temp_name);
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut writer = BufWriter::new(options.open(&temp)?);
+        for cookie in self.store.read().unwrap().iter_unexpired() {
+            serde_json::to_writer(&mut writer, cookie)?;
+            writeln!(writer)?;
+        }
+        writer.flush()?;
+        drop(writer);
+        fs::rename(temp, path)
+    }
+
+    /// Returns the name and value of the unexpired cookies sent to `url`.
+    #[cfg(feature = "persistent-cookies")]
+    pub(crate) fn pairs(&self, url: &Url) -> Vec<(String, String)> {
+        self.store
+            .read()
+            .unwrap()
+            .get_request_values(url)
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    /// Returns the name and value of the unexpired cookies sent to `url`.
+    #[cfg(not(feature = "persistent-cookies"))]
+    pub(crate) fn pairs(&self, url: &Url) -> Vec<(String, String)> {
+        let Some(header) = self.store.cookies(url) else {
+            return Vec::new();
+        };
+        header
+            .to_str()
+            .unwrap_or_default()
+            .split("; ")
+            .filter_map(|pair| pair.split_once('='))
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    /// Stores a cookie in `Set-Cookie` syntax as if `url` had set it,
+    /// returning false when it is malformed or not allowed for `url`.
+    #[cfg(feature = "persistent-cookies")]
+    pub(crate) fn insert(&self, cookie: &str, url: &Url) -> bool {
+        self.store.write().unwrap().parse(cookie, url).is_ok()
+    }
+
+    /// Stores a cookie in `Set-Cookie` syntax as if `url` had set it,
+    /// returning false when it is malformed or not allowed for `url`.
+    #[cfg(not(feature = "persistent-cookies"))]
+    pub(crate) fn insert(&self, cookie: &str, url: &Url) -> bool {
+        // reqwest drops the cookies it rejects without telling, so the same
+        // checks are made here first
+        let mut parts = cookie.split(';');
+        let named = parts
+            .next()
+            .and_then(|pair| pair.split_once('='))
+            .is_some_and(|(name, _)| !name.trim().is_empty());
+        let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+        let domain_allowed = parts
+            .filter_map(|attribute| attribute.split_once('='))
+            .filter(|(name, _)| name.trim().eq_ignore_ascii_case("domain"))
+            .all(|(_, domain)| {
+                let domain = domain.trim().trim_start_matches('.').to_ascii_lowercase();
+                host == domain || host.ends_with(&format!(".{}", domain))
+            });
+        if !named || !domain_allowed {
+            return false;
+        }
+        self.store.add_cookie_str(cookie, url);
+        true
+    }
+}
+
+#[cfg(feature = "persistent-cookies")]
+impl CookieStore for CookieJar {
+    fn set_cookies(&self, cookie_headers: &mut dyn Iterator<Item = &HeaderValue>, url: &Url) {
+        let mut store = self.store.write().unwrap();
+        for header in cookie_headers {
+            // Malformed cookies are ignored, as browsers do
+            if let Ok(cookie) = header.to_str() {
+                let _ = store.parse(cookie, url);
+            }
+        }
+    }
+
+    fn cookies(&self, url: &Url) -> Option<HeaderValue> {
+        let header = self
+            .pairs(url)
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("; ");
+        match header.is_empty() {
+            true => None,
+            false => HeaderValue::from_str(&header).ok(),
+        }
+    }
+}
+
+#[cfg(not(feature = "persistent-cookies"))]
+impl CookieStore for CookieJar {
+    fn set_cookies(&self, cookie_headers: &mut dyn Iterator<Item = &HeaderValue>, url: &Url) {
+        self.store.set_cookies(cookie_headers, url);
+    }
+
+    fn cookies(&self, url: &Url) -> Option<HeaderValue> {
+        self.store.cookies(url)
+    }
+}
+
+#[cfg(feature = "persistent-cookies")]
+impl Drop for CookieJar {
+    fn drop(&mut self) {
+        // The jar is dropped with the last client using it, which is the
+        // last chance to save; errors have nowhere to go
+        let _ = self.save();
+    }
+}
@@ -0,0 +1,613 @@
+//! Downloads of response bodies to files.
+//!
+//! This module provides the `Download` type reported by the detailed
+//! executions for requests with an output file, see
+//! `Request::set_output_file`, the `SaveResponses` settings naming output
+//! files for every response, and the `Progress` reports handed to the
+//! `on_progress` hook. Bodies are streamed to a `.part` file next to the
+//! output file and renamed into place once complete. Resumable downloads
+//! keep the `.part` file on failure, with the `ETag` or `Last-Modified` of
+//! the response in a `.part.validator` file, and continue it with a `Range`
+//! request. With the `checksum` feature, bodies are hashed as they are
+//! written and checked against the `Checksum` expected by the request.
+
+use crate::error::{ChecksumMismatch, RollingError};
+use crate::request::{ContentRange, Request};
+use crate::throttle::Bandwidth;
+use futures_util::StreamExt;
+use reqwest::StatusCode;
+use reqwest::header::{CONTENT_LENGTH, CONTENT_RANGE, ETAG, LAST_MODIFIED};
+use std::ffi::OsString;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// A hook receiving the progress of downloads, see `RollingRequestsBuilder::on_progress`.
+pub type ProgressHook = Box<dyn Fn(&Progress) + Send + Sync>;
+
+/// The progress of a download, with the totals of the execution it is part of.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Progress {
+    /// The identifier of the request, if it was queued.
+    pub request_id: Option<u64>,
+    /// The number of bytes of the body written so far.
+    pub bytes_done: u64,
+    /// The size of the body from its `Content-Length`, if known.
+    pub total: Option<u64>,
+    /// The number of bytes written so far by every download of the execution.
+    pub all_bytes_done: u64,
+    /// The sum of the sizes of the downloads started so far, unless one of
+    /// them has an unknown size.
+    pub all_total: Option<u64>,
+}
+
+/// The byte counts shared by the downloads of one execution.
+#[derive(Default)]
+pub(crate) struct ProgressTotals {
+    /// The bytes written by every download.
+    done: AtomicU64,
+    /// The sum of the known sizes of the downloads started.
+    total: AtomicU64,
+    /// Whether a download of unknown size was started.
+    unknown: AtomicBool,
+}
+
+/// Reports the progress of one download to the hook.
+pub(crate) struct ProgressReporter<'a> {
+    /// The hook receiving the reports.
+    pub(crate) hook: &'a ProgressHook,
+    /// The minimum time between two reports; `None` reports every chunk.
+    pub(crate) interval: Option<Duration>,
+    /// The totals of the execution.
+    pub(crate) totals: &'a ProgressTotals,
+    /// The identifier of the request.
+    pub(crate) request_id: Option<u64>,
+}
+
+impl ProgressReporter<'_> {
+    /// Records the start of a download expecting `remaining` more bytes.
+    fn start(&self, remaining: Option<u64>) {
+        match remaining {
+            Some(remaining) => {
+                self.totals.total.fetch_add(remaining, Ordering::Relaxed);
+            }
+            None => self.totals.unknown.store(true, Ordering::Relaxed),
+        }
+    }
+
+    /// Removes the `missing` bytes a failed download did not receive from
+    /// the expected total; a resumed attempt adds them again.
+    fn abandon(&self, missing: u64) {
+        self.totals.total.fetch_sub(missing, Ordering::Relaxed);
+    }
+
+    /// Records `written` more bytes and calls the hook with the `bytes_done`
+    /// so far.
+    fn report(&self, written: u64, bytes_done: u64, total: Option<u64>) {
+        let all_bytes_done = self.totals.done.fetch_add(written, Ordering::Relaxed) + written;
+        let all_total = if self.totals.unknown.load(Ordering::Relaxed) {
+            None
+        } else {
+            Some(self.totals.total.load(Ordering::Relaxed))
+        };
+        (self.hook)(&Progress {
+            request_id: self.request_id,
+            bytes_done,
+            total,
+            all_bytes_done,
+            all_total,
+        });
+    }
+}
+
+/// A response body written to its output file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Download {
+    /// The path of the written file.
+    pub path: PathBuf,
+    /// The number of bytes written.
+    pub size: u64,
+    /// The status of the response.
+    pub status: StatusCode,
+    /// The digest of the file, with the `checksum` feature.
+    pub checksum: Option<Checksum>,
+}
+
+/// Where the detailed executions save the response bodies of requests
+/// without an output file, see `RollingRequestsBuilder::save_responses_to`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SaveResponses {
+    /// The directory the bodies are written to, created when missing.
+    pub dir: PathBuf,
+    /// The template of the file names, with the placeholders `{id}`,
+    /// `{extra_info}`, `{url_hash}` and `{status}`.
+    pub template: String,
+}
+
+impl SaveResponses {
+    /// Returns the file name of a response with `status` to the request
+    /// with identifier `id`, `extra_info` and `url`.
+    ///
+    /// Characters not allowed in file names are replaced by `_` in the
+    /// values, and unknown placeholders are kept as written.
+    pub(crate) fn file_name(
+        &self,
+        id: Option<u64>,
+        extra_info: Option<&str>,
+        url: &str,
+        status: StatusCode,
+    ) -> String {
+        let mut name = String::with_capacity(self.template.len());
+        let mut rest = self.template.as_str();
+        while let Some(start) = rest.find('{') {
+            name.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let Some(end) = rest.find('}') else {
+                break;
+            };
+            let value = match &rest[1..end] {
+                "id" => id.map(|id| id.to_string()).unwrap_or_default(),
+                "extra_info" => extra_info.unwrap_or_default().to_string(),
+                "url_hash" => format!("{:016x}", fnv1a(url.as_bytes())),
+                "status" => status.as_u16().to_string(),
+                _ => {
+                    name.push_str(&rest[..=end]);
+                    rest = &rest[end + 1..];
+                    continue;
+                }
+            };
+            name.extend(value.chars().map(|c| match c {
+                '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+                c if c.is_control() => '_',
+                c => c,
+            }));
+            rest = &rest[end + 1..];
+        }
+        name.push_str(rest);
+        name
+    }
+}
+
+/// What happens when the file a response is saved to is taken, by an
+/// existing file or another response being written to it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SaveCollision {
+    /// A numeric suffix is added before the extension, e.g. `name-1.json`.
+    #[default]
+    Suffix,
+    /// Existing files are replaced; a file another response is still being
+    /// written to gets a suffix instead.
+    Overwrite,
+    /// The request fails with `RollingErrorKind::Write`.
+    Error,
+}
+
+/// Returns the file name `name` with the numeric `suffix` added before its
+/// extension.
+pub(crate) fn with_number(name: &str, suffix: u32) -> String {
+    match name.rfind('.').filter(|&dot| dot > 0) {
+        Some(dot) => format!("{}-{}{}", &name[..dot], suffix, &name[dot..]),
+        None => format!("{}-{}", name, suffix),
+    }
+}
+
+/// Returns the 64-bit FNV-1a hash of `bytes`, stable across runs and builds.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// A hash algorithm used to verify downloads.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ChecksumAlgorithm {
+    /// SHA-256, the algorithm used when the request expects no checksum.
+    Sha256,
+    /// SHA-512.
+    Sha512,
+}
+
+impl ChecksumAlgorithm {
+    /// Returns the lowercase name of the algorithm, e.g. `sha256`.
+    pub fn name(self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Sha256 => "sha256",
+            ChecksumAlgorithm::Sha512 => "sha512",
+        }
+    }
+
+    /// Returns the length of a digest in hexadecimal digits.
+    fn hex_len(self) -> usize {
+        match self {
+            ChecksumAlgorithm::Sha256 => 64,
+            ChecksumAlgorithm::Sha512 => 128,
+        }
+    }
+}
+
+/// The digest of a body, as lowercase hexadecimal digits.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Checksum {
+    /// The algorithm that produced the digest.
+    pub algorithm: ChecksumAlgorithm,
+    /// The digest in lowercase hexadecimal digits.
+    pub hex: String,
+}
+
+impl Checksum {
+    /// Returns true if `hex` has the length of a digest of the algorithm
+    /// and only hexadecimal digits.
+    pub(crate) fn is_valid(&self) -> bool {
+        self.hex.len() == self.algorithm.hex_len()
+            && self.hex.bytes().all(|byte| byte.is_ascii_hexdigit())
+    }
+}
+
+impl fmt::Display for Checksum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.algorithm.name(), self.hex)
+    }
+}
+
+/// Hashes a body as it is written; does nothing without the `checksum` feature.
+#[cfg(feature = "checksum")]
+enum BodyHasher {
+    Sha256(sha2::Sha256),
+    Sha512(sha2::Sha512),
+}
+
+#[cfg(feature = "checksum")]
+impl BodyHasher {
+    /// Creates a hasher for `algorithm`.
+    fn new(algorithm: ChecksumAlgorithm) -> Self {
+        use sha2::Digest;
+        match algorithm {
+            ChecksumAlgorithm::Sha256 => BodyHasher::Sha256(sha2::Sha256::new()),
+            ChecksumAlgorithm::Sha512 => BodyHasher::Sha512(sha2::Sha512::new()),
+        }
+    }
+
+    /// Hashes the next bytes of the body.
+    fn update(&mut self, data: &[u8]) {
+        use sha2::Digest;
+        match self {
+            BodyHasher::Sha256(hasher) => hasher.update(data),
+            BodyHasher::Sha512(hasher) => hasher.update(data),
+        }
+    }
+
+    /// Returns the digest of the body.
+    fn finish(self) -> Option<Checksum> {
+        use sha2::Digest;
+        let (algorithm, digest) = match self {
+            BodyHasher::Sha256(hasher) => (ChecksumAlgorithm::Sha256, hasher.finalize().to_vec()),
+            BodyHasher::Sha512(hasher) => (ChecksumAlgorithm::Sha512, hasher.finalize().to_vec()),
+        };
+        let hex = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+        Some(Checksum { algorithm, hex })
+    }
+}
+
+/// Hashes a body as it is written; does nothing without the `checksum` feature.
+#[cfg(not(feature = "checksum"))]
+struct BodyHasher;
+
+#[cfg(not(feature = "checksum"))]
+impl BodyHasher {
+    fn new(_algorithm: ChecksumAlgorithm) -> Self {
+        BodyHasher
+    }
+
+    fn update(&mut self, _data: &[u8]) {}
+
+    fn finish(self) -> Option<Checksum> {
+        None
+    }
+}
+
+/// Feeds the content of the file at `path` to `hasher`.
+async fn hash_file(path: &Path, hasher: &mut BodyHasher) -> io::Result<()> {
+    let mut file = fs::File::open(path).await?;
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            return Ok(());
+        }
+        hasher.update(&buffer[..read]);
+    }
+}
+
+/// Returns the path a download to `path` is streamed into before the rename.
+pub(crate) fn part_path(path: &Path) -> PathBuf {
+    with_suffix(path, ".part")
+}
+
+/// Returns the path the validator of a resumable download to `path` is kept in.
+fn validator_path(path: &Path) -> PathBuf {
+    with_suffix(path, ".part.validator")
+}
+
+/// Appends `suffix` to the file name of `path`.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// Returns where a resumable download to `path` continues, with the
+/// validator of the partial file for an `If-Range` header.
+///
+/// Returns `None` when there is no partial file to continue.
+pub(crate) async fn resume_point(path: &Path) -> Option<(u64, Option<String>)> {
+    let len = fs::metadata(part_path(path)).await.ok()?.len();
+    if len == 0 {
+        return None;
+    }
+    let validator = fs::read_to_string(validator_path(path))
+        .await
+        .ok()
+        .map(|validator| validator.trim().to_string())
+        .filter(|validator| !validator.is_empty());
+    Some((len, validator))
+}
+
+/// What a `416 Range Not Satisfiable` answer to a resumed download means
+/// for its partial file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Unsatisfied {
+    /// The partial file already holds the whole representation.
+    Complete,
+    /// The partial file does not match the representation and was removed,
+    /// so the next attempt starts over.
+    Restart,
+}
+
+/// Checks the partial file of a download to `path`, `offset` bytes long,
+/// against the length of the representation in the `Content-Range: bytes
+/// */<length>` header of a `416` response, removing it unless it is complete.
+pub(crate) async fn settle_unsatisfied(
+    response: &reqwest::Response,
+    path: &Path,
+    offset: u64,
+) -> Unsatisfied {
+    let length = response
+        .headers()
+        .get(CONTENT_RANGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().split_once(' '))
+        .filter(|(unit, _)| unit.eq_ignore_ascii_case("bytes"))
+        .and_then(|(_, range)| range.trim().strip_prefix("*/")?.parse::<u64>().ok());
+    if length == Some(offset) {
+        return Unsatisfied::Complete;
+    }
+    let _ = fs::remove_file(part_path(path)).await;
+    let _ = fs::remove_file(validator_path(path)).await;
+    Unsatisfied::Restart
+}
+
+/// Returns the validator identifying the representation in `response`: its
+/// strong `ETag`, or else its `Last-Modified` date.
+fn validator(response: &reqwest::Response) -> Option<&str> {
+    let header = |name| response.headers().get(name)?.to_str().ok();
+    header(ETAG)
+        .filter(|etag| !etag.starts_with("W/"))
+        .or_else(|| header(LAST_MODIFIED))
+}
+
+/// Returns the size of the body of `response`, from its `Content-Length`
+/// header when the body was wrapped, e.g. to be recorded.
+pub(crate) fn content_length(response: &reqwest::Response) -> Option<u64> {
+    response.content_length().or_else(|| {
+        response
+            .headers()
+            .get(CONTENT_LENGTH)?
+            .to_str()
+            .ok()?
+            .parse()
+            .ok()
+    })
+}
+
+/// Streams the body of `response` to `path`, the output file of `request`,
+/// waiting on `bandwidth` and reporting to `progress` when given.
+///
+/// For resumable requests, a `206 Partial Content` response is appended to
+/// the partial file left by an earlier attempt, after checking that it
+/// starts where the file ends; other responses replace it. A partial file
+/// the server reported complete with a `416` response is renamed into place
+/// as is, with status `200 OK`. The partial file is removed when the body
+/// cannot be read, written or verified, unless the request is resumable and
+/// the body could not be read.
+pub(crate) async fn download(
+    response: reqwest::Response,
+    bandwidth: Option<&Bandwidth>,
+    progress: Option<ProgressReporter<'_>>,
+    path: &Path,
+    request: &Request,
+) -> Result<Download, RollingError> {
+    let (status, written) = match request.unsatisfied {
+        Some(Unsatisfied::Complete) => {
+            (StatusCode::OK, complete_part(progress, path, request).await)
+        }
+        _ => (
+            response.status(),
+            write_part(response, bandwidth, progress, path, request).await,
+        ),
+    };
+    match written {
+        Ok((size, checksum)) => {
+            let _ = fs::remove_file(validator_path(path)).await;
+            Ok(Download {
+                path: path.to_path_buf(),
+                size,
+                status,
+                checksum,
+            })
+        }
+        Err(err) => {
+            // Only interrupted transfers are worth continuing
+            if !request.resume || err.inner().is_none() {
+                let _ = fs::remove_file(part_path(path)).await;
+                let _ = fs::remove_file(validator_path(path)).await;
+            }
+            Err(err)
+        }
+    }
+}
+
+/// Writes the body of `response` to the partial file of `path`, checks its
+/// digest and renames it to `path`, returning the size and digest of the file.
+async fn write_part(
+    response: reqwest::Response,
+    bandwidth: Option<&Bandwidth>,
+    progress: Option<ProgressReporter<'_>>,
+    path: &Path,
+    request: &Request,
+) -> Result<(u64, Option<Checksum>), RollingError> {
+    let (resume, attempts) = (request.resume, request.attempts);
+    let expected = request.expected_checksum.as_ref();
+    let mut hasher = BodyHasher::new(expected.map_or(ChecksumAlgorithm::Sha256, |c| c.algorithm));
+    let write = |err| RollingError::write(err, attempts);
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent).await.map_err(write)?;
+    }
+    let part = part_path(path);
+    let existing = if resume {
+        fs::metadata(&part)
+            .await
+            .map_or(0, |metadata| metadata.len())
+    } else {
+        0
+    };
+    let (mut file, offset, total) =
+        if existing > 0 && response.status() == StatusCode::PARTIAL_CONTENT {
+            let range = ContentRange::from_response(&response);
+            if range.map(|range| range.start) != Some(existing) {
+                return Err(write(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "resumed response does not start at byte {} of the partial file",
+                        existing
+                    ),
+                )));
+            }
+            hash_file(&part, &mut hasher).await.map_err(write)?;
+            let file = fs::OpenOptions::new()
+                .append(true)
+                .open(&part)
+                .await
+                .map_err(write)?;
+            (file, existing, range.and_then(|range| range.total))
+        } else {
+            if resume {
+                match validator(&response) {
+                    Some(validator) => fs::write(validator_path(path), validator).await,
+                    None => match fs::remove_file(validator_path(path)).await {
+                        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+                        result => result,
+                    },
+                }
+                .map_err(write)?;
+            }
+            let file = fs::File::create(&part).await.map_err(write)?;
+            (file, 0, content_length(&response))
+        };
+    let remaining = total.map(|total| total.saturating_sub(offset));
+    if let Some(progress) = &progress {
+        progress.start(remaining);
+    }
+    let mut stream = response.bytes_stream();
+    let mut size = offset;
+    // Bytes written since the last report, and when it was made
+    let mut unreported = 0;
+    let mut reported_at = Instant::now();
+    while let Some(chunk) = stream.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(err) => {
+                if let (Some(progress), Some(remaining)) = (&progress, remaining) {
+                    progress.abandon(remaining.saturating_sub(size - offset));
+                }
+                return Err(RollingError::new(err, attempts));
+            }
+        };
+        if let Some(bandwidth) = bandwidth {
+            bandwidth.consume(chunk.len()).await;
+        }
+        file.write_all(&chunk).await.map_err(write)?;
+        hasher.update(&chunk);
+        size += chunk.len() as u64;
+        unreported += chunk.len() as u64;
+        if let Some(progress) = &progress {
+            if progress
+                .interval
+                .is_none_or(|interval| reported_at.elapsed() >= interval)
+            {
+                progress.report(std::mem::take(&mut unreported), size, total);
+                reported_at = Instant::now();
+            }
+        }
+    }
+    // The final size is always reported, even when nothing was received
+    if let Some(progress) = &progress {
+        if unreported > 0 || size == offset {
+            progress.report(unreported, size, total);
+        }
+    }
+    file.sync_all().await.map_err(write)?;
+    drop(file);
+    let checksum = finish_part(hasher, path, request).await?;
+    Ok((size, checksum))
+}
+
+/// Renames the partial file of `path`, which already holds the whole
+/// representation, into place, returning its size and digest.
+async fn complete_part(
+    progress: Option<ProgressReporter<'_>>,
+    path: &Path,
+    request: &Request,
+) -> Result<(u64, Option<Checksum>), RollingError> {
+    let write = |err| RollingError::write(err, request.attempts);
+    let part = part_path(path);
+    let size = fs::metadata(&part).await.map_err(write)?.len();
+    let expected = request.expected_checksum.as_ref();
+    let mut hasher = BodyHasher::new(expected.map_or(ChecksumAlgorithm::Sha256, |c| c.algorithm));
+    hash_file(&part, &mut hasher).await.map_err(write)?;
+    if let Some(progress) = &progress {
+        progress.start(Some(0));
+        progress.report(0, size, Some(size));
+    }
+    let checksum = finish_part(hasher, path, request).await?;
+    Ok((size, checksum))
+}
+
+/// Checks the digest fed to `hasher` against the one `request` expects and
+/// renames the partial file of `path` into place, returning the digest.
+async fn finish_part(
+    hasher: BodyHasher,
+    path: &Path,
+    request: &Request,
+) -> Result<Option<Checksum>, RollingError> {
+    let checksum = hasher.finish();
+    if let (Some(expected), Some(actual)) = (&request.expected_checksum, &checksum) {
+        if !expected.hex.eq_ignore_ascii_case(&actual.hex) {
+            let mismatch = ChecksumMismatch {
+                expected: expected.clone(),
+                actual: actual.clone(),
+            };
+            return Err(RollingError::mismatched(mismatch, request.attempts));
+        }
+    }
+    fs::rename(part_path(path), path)
+        .await
+        .map_err(|err| RollingError::write(err, request.attempts))?;
+    Ok(checksum)
+}
@@ -0,0 +1,712 @@
+//! Errors returned by `RollingRequests`.
+//!
+//! This module provides the `RollingError` type, which wraps the underlying
+//! `reqwest::Error` (or the reason a request was rejected before sending)
+//! together with what the scheduler knows about the request, the
+//! `RollingErrorKind` classification of failures, and the numeric
+//! `ErrorCode`s reported for them.
+
+use crate::download::Checksum;
+use crate::request::{Metadata, Request};
+use crate::sink::SinkError;
+use std::any::Any;
+use std::error::Error as _;
+use std::fmt;
+
+/// The kind of failure behind a `RollingError`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RollingErrorKind {
+    /// The connection could not be established within the connect timeout.
+    ConnectTimeout,
+    /// The connection was established but the response did not arrive in time.
+    ReadTimeout,
+    /// The host name could not be resolved.
+    Dns,
+    /// The TLS handshake failed, e.g. because of an invalid certificate.
+    Tls,
+    /// The connection could not be established for another reason, e.g. it was refused.
+    Connect,
+    /// The response had a non-success status under `error_for_status`.
+    Status(u16),
+    /// The request could not be sent as built, e.g. a URL placeholder has no value.
+    InvalidRequest,
+    /// The body or a multipart part exceeds the maximum body size.
+    BodyTooLarge,
+    /// The task sending the request panicked, e.g. in a credentials refresh hook.
+    Panicked,
+    /// The response body could not be written to the output file.
+    Write,
+    /// The downloaded body does not have the expected checksum.
+    ChecksumMismatch,
+    /// The body sink of the request rejected a chunk of the response body.
+    Sink,
+    /// A redirect chain exceeded its hop limit or looped.
+    Redirect,
+    /// Any other failure.
+    Other,
+}
+
+impl RollingErrorKind {
+    /// Classifies a `reqwest::Error`.
+    ///
+    /// Connect timeouts are only told apart from read timeouts when the client
+    /// has a `connect_timeout`; DNS and TLS failures are recognized from the
+    /// messages and debug output of the underlying errors.
+    ///
+    /// #### Arguments
+    ///
+    /// * `error` - The error to classify.
+    pub fn of(error: &reqwest::Error) -> Self {
+        if let Some(status) = error.status() {
+            return RollingErrorKind::Status(status.as_u16());
+        }
+        if BodyTooLarge::find(error).is_some() {
+            return RollingErrorKind::BodyTooLarge;
+        }
+        if error.is_redirect() {
+            return RollingErrorKind::Redirect;
+        }
+        if error.is_timeout() {
+            return if error.is_connect() {
+                RollingErrorKind::ConnectTimeout
+            } else {
+                RollingErrorKind::ReadTimeout
+            };
+        }
+        if error.is_connect() {
+            let mut messages = Vec::new();
+            let mut source = error.source();
+            while let Some(cause) = source {
+                messages.push(cause.to_string().to_ascii_lowercase());
+                // TLS backends name themselves in their debug output when the
+                // handshake fails without a descriptive message
+                messages.push(format!("{:?}", cause).to_ascii_lowercase());
+                source = cause.source();
+            }
+            let mentions = |words: &[&str]| {
+                messages
+                    .iter()
+                    .any(|message| words.iter().any(|word| message.contains(word)))
+            };
+            if mentions(&["dns error", "failed to lookup address"]) {
+                return RollingErrorKind::Dns;
+            }
+            if mentions(&["certificate", "tls", "ssl", "handshake"]) {
+                return RollingErrorKind::Tls;
+            }
+            return RollingErrorKind::Connect;
+        }
+        RollingErrorKind::Other
+    }
+}
+
+/// The error returned for a request that did not produce a response, or whose
+/// response had a non-success status under `error_for_status`.
+#[derive(Debug)]
+pub struct RollingError {
+    /// The error returned by the last attempt, or why the request was not sent.
+    source: Source,
+    /// The number of attempts made before giving up.
+    attempts: u32,
+    /// The classification of the error.
+    kind: RollingErrorKind,
+    /// The metadata of the failed request.
+    metadata: Option<Metadata>,
+    /// The identifier of the failed request.
+    request_id: Option<u64>,
+    /// The beginning of the body of a response with an error status.
+    body_snippet: Option<Box<str>>,
+}
+
+/// The cause of a `RollingError`.
+#[derive(Debug)]
+enum Source {
+    /// An attempt failed in the HTTP client.
+    Http(reqwest::Error),
+    /// The request was rejected before any attempt, for the given reason.
+    InvalidRequest(String),
+    /// The body was found too large before any attempt.
+    BodyTooLarge(BodyTooLarge),
+    /// An attempt panicked with the given message.
+    Panicked(String),
+    /// The response body could not be written to the output file.
+    Write(std::io::Error),
+    /// The downloaded body does not have the expected checksum.
+    ChecksumMismatch(ChecksumMismatch),
+    /// The body sink rejected a chunk of the response body.
+    Sink(SinkError),
+    /// A recorded redirect chain was stopped.
+    Redirect(Box<RedirectError>),
+}
+
+impl RollingError {
+    /// Wraps the error of the last of `attempts` attempts.
+    pub(crate) fn new(source: reqwest::Error, attempts: u32) -> Self {
+        RollingError {
+            kind: RollingErrorKind::of(&source),
+            source: Source::Http(source),
+            attempts,
+            metadata: None,
+            request_id: None,
+            body_snippet: None,
+        }
+    }
+
+    /// Creates the error of a request that could not be sent as built.
+    pub(crate) fn invalid_request(reason: String) -> Self {
+        RollingError {
+            kind: RollingErrorKind::InvalidRequest,
+            source: Source::InvalidRequest(reason),
+            attempts: 0,
+            metadata: None,
+            request_id: None,
+            body_snippet: None,
+        }
+    }
+
+    /// Creates the error of a request whose body exceeds the maximum body size.
+    pub(crate) fn too_large(error: BodyTooLarge) -> Self {
+        RollingError {
+            kind: RollingErrorKind::BodyTooLarge,
+            source: Source::BodyTooLarge(error),
+            attempts: 0,
+            metadata: None,
+            request_id: None,
+            body_snippet: None,
+        }
+    }
+
+    /// Creates the error of an attempt that panicked with `message`.
+    pub(crate) fn panicked(message: String, attempts: u32) -> Self {
+        RollingError {
+            kind: RollingErrorKind::Panicked,
+            source: Source::Panicked(message),
+            attempts,
+            metadata: None,
+            request_id: None,
+            body_snippet: None,
+        }
+    }
+
+    /// Creates the error of a response body that could not be written to its
+    /// output file after `attempts` attempts.
+    pub(crate) fn write(error: std::io::Error, attempts: u32) -> Self {
+        RollingError {
+            kind: RollingErrorKind::Write,
+            source: Source::Write(error),
+            attempts,
+            metadata: None,
+            request_id: None,
+            body_snippet: None,
+        }
+    }
+
+    /// Creates the error of a downloaded body whose digest differs from the
+    /// expected one after `attempts` attempts.
+    pub(crate) fn mismatched(mismatch: ChecksumMismatch, attempts: u32) -> Self {
+        RollingError {
+            kind: RollingErrorKind::ChecksumMismatch,
+            source: Source::ChecksumMismatch(mismatch),
+            attempts,
+            metadata: None,
+            request_id: None,
+            body_snippet: None,
+        }
+    }
+
+    /// Creates the error of a body sink that rejected a chunk after
+    /// `attempts` attempts.
+    pub(crate) fn sink(error: SinkError, attempts: u32) -> Self {
+        RollingError {
+            kind: RollingErrorKind::Sink,
+            source: Source::Sink(error),
+            attempts,
+            metadata: None,
+            request_id: None,
+            body_snippet: None,
+        }
+    }
+
+    /// Creates the error of a recorded redirect chain that was stopped on
+    /// attempt `attempts`.
+    pub(crate) fn redirect(error: RedirectError, attempts: u32) -> Self {
+        RollingError {
+            kind: RollingErrorKind::Redirect,
+            source: Source::Redirect(Box::new(error)),
+            attempts,
+            metadata: None,
+            request_id: None,
+            body_snippet: None,
+        }
+    }
+
+    /// Attaches the beginning of the body of the response with an error status.
+    pub(crate) fn with_body_snippet(mut self, body_snippet: Option<Box<str>>) -> Self {
+        self.body_snippet = body_snippet;
+        self
+    }
+
+    /// Attaches the identifier and the metadata of the failed request.
+    pub(crate) fn for_request(self, request: &Request) -> Self {
+        self.tagged(request.id, request.metadata.clone())
+    }
+
+    /// Attaches the identifier and the metadata of a request.
+    pub(crate) fn tagged(mut self, request_id: Option<u64>, metadata: Option<Metadata>) -> Self {
+        self.request_id = request_id;
+        self.metadata = metadata;
+        self
+    }
+
+    /// Returns the identifier of the failed request, if it was queued.
+    pub fn request_id(&self) -> Option<u64> {
+        self.request_id
+    }
+
+    /// Returns the error returned by the HTTP client, if an attempt was made.
+    fn http(&self) -> Option<&reqwest::Error> {
+        match &self.source {
+            Source::Http(error) => Some(error),
+            _ => None,
+        }
+    }
+
+    /// Returns the classification of the error.
+    pub fn kind(&self) -> RollingErrorKind {
+        self.kind
+    }
+
+    /// Returns the numeric code of the error, as listed on `ErrorCode`.
+    ///
+    /// #### Examples
+    ///
+    /// ```no_run
+    /// use rollingrequests::request::Request;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut rolling_requests = RollingRequestsBuilder::new().build();
+    ///     rolling_requests.add_request(Request::get("http://example.com"));
+    ///
+    ///     for result in rolling_requests.execute_requests().await {
+    ///         if let Err(error) = result {
+    ///             eprintln!("failed with code {}: {}", error.code(), error);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn code(&self) -> u16 {
+        match &self.source {
+            Source::Http(error) => error_code(error),
+            Source::InvalidRequest(_) => ErrorCode::InvalidRequest.code(),
+            Source::BodyTooLarge(_) => ErrorCode::BodyTooLarge.code(),
+            Source::Panicked(_) => ErrorCode::TaskPanic.code(),
+            Source::Write(_) | Source::Sink(_) => ErrorCode::Write.code(),
+            Source::ChecksumMismatch(_) => ErrorCode::ChecksumMismatch.code(),
+            Source::Redirect(_) => ErrorCode::TooManyRedirects.code(),
+        }
+    }
+
+    /// Returns the metadata of the failed request if it is a `T`.
+    pub fn metadata<T: Any>(&self) -> Option<&T> {
+        self.metadata.as_ref().and_then(Metadata::get)
+    }
+
+    /// Returns the number of attempts made before giving up.
+    ///
+    /// This is zero for requests rejected before sending.
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// Returns the size and limit of a body that exceeded the maximum body
+    /// size, whether found before sending or while streaming it.
+    pub fn body_too_large(&self) -> Option<&BodyTooLarge> {
+        match &self.source {
+            Source::Http(error) => BodyTooLarge::find(error),
+            Source::BodyTooLarge(error) => Some(error),
+            _ => None,
+        }
+    }
+
+    /// Returns the expected and actual digests of a downloaded body that
+    /// failed verification.
+    pub fn checksum_mismatch(&self) -> Option<&ChecksumMismatch> {
+        match &self.source {
+            Source::ChecksumMismatch(mismatch) => Some(mismatch),
+            _ => None,
+        }
+    }
+
+    /// Returns why a recorded redirect chain was stopped.
+    pub fn redirect_error(&self) -> Option<&RedirectError> {
+        match &self.source {
+            Source::Redirect(error) => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the last attempt timed out.
+    pub fn is_timeout(&self) -> bool {
+        self.http().is_some_and(reqwest::Error::is_timeout)
+    }
+
+    /// Returns true if the last attempt failed to connect.
+    pub fn is_connect(&self) -> bool {
+        self.http().is_some_and(reqwest::Error::is_connect)
+    }
+
+    /// Returns true if the error comes from a non-success status under `error_for_status`.
+    pub fn is_status(&self) -> bool {
+        self.http().is_some_and(reqwest::Error::is_status)
+    }
+
+    /// Returns the status of the response that caused the error, if any.
+    pub fn status(&self) -> Option<reqwest::StatusCode> {
+        self.http().and_then(reqwest::Error::status)
+    }
+
+    /// Returns the beginning of the body of the response that caused a status
+    /// error, up to the `error_body_snippet` limit, if it had a body.
+    ///
+    /// #### Examples
+    ///
+    /// ```no_run
+    /// use rollingrequests::request::Request;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut rolling_requests = RollingRequestsBuilder::new()
+    ///         .error_for_status(true)
+    ///         .build();
+    ///     rolling_requests.add_request(Request::get("http://example.com/invalid"));
+    ///
+    ///     for result in rolling_requests.execute_requests().await {
+    ///         if let Err(error) = result {
+    ///             eprintln!("{:?}", error.body_snippet());
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn body_snippet(&self) -> Option<&str> {
+        self.body_snippet.as_deref()
+    }
+
+    /// Returns the URL of the last attempt, if known.
+    ///
+    /// For requests with fallback URLs, this is the URL that failed last.
+    pub fn url(&self) -> Option<&reqwest::Url> {
+        self.http().and_then(reqwest::Error::url)
+    }
+
+    /// Returns the error returned by the last attempt.
+    ///
+    /// Returns `None` for requests rejected before sending.
+    pub fn inner(&self) -> Option<&reqwest::Error> {
+        self.http()
+    }
+
+    /// Consumes the error, returning the error of the last attempt.
+    ///
+    /// Returns `None` for requests rejected before sending.
+    pub fn into_inner(self) -> Option<reqwest::Error> {
+        match self.source {
+            Source::Http(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for RollingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let source = match &self.source {
+            Source::Http(error) => error,
+            Source::InvalidRequest(reason) => return write!(f, "invalid request: {}", reason),
+            Source::BodyTooLarge(error) => return write!(f, "{}", error),
+            Source::Panicked(message) => return write!(f, "request panicked: {}", message),
+            Source::Write(error) => {
+                return write!(f, "failed to write the response body: {}", error);
+            }
+            Source::ChecksumMismatch(mismatch) => return write!(f, "{}", mismatch),
+            Source::Sink(error) => return write!(f, "body sink failed: {}", error),
+            Source::Redirect(error) => return write!(f, "{}", error),
+        };
+        match self.attempts {
+            1 => write!(f, "{}", source)?,
+            attempts => write!(f, "{} (after {} attempts)", source, attempts)?,
+        }
+        match &self.body_snippet {
+            Some(body_snippet) => write!(f, ": {}", body_snippet),
+            None => Ok(()),
+        }
+    }
+}
+
+impl std::error::Error for RollingError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.source {
+            Source::Http(error) => Some(error),
+            Source::BodyTooLarge(error) => Some(error),
+            Source::Write(error) => Some(error),
+            Source::ChecksumMismatch(mismatch) => Some(mismatch),
+            Source::Sink(error) => Some(error.as_ref()),
+            Source::Redirect(error) => Some(error.as_ref()),
+            Source::InvalidRequest(_) | Source::Panicked(_) => None,
+        }
+    }
+}
+
+/// The numeric codes reported for failed requests, e.g. in `response_errno`.
+///
+/// Codes shared with curl keep curl's numbers; the others start at 90.
+///
+/// | Code | Variant            | Failure                                          |
+/// |------|--------------------|--------------------------------------------------|
+/// | 0    | `Ok`               | None, a response was read                        |
+/// | 3    | `InvalidRequest`   | The request could not be sent as built           |
+/// | 6    | `Dns`              | The host name could not be resolved              |
+/// | 7    | `Connect`          | The connection could not be established          |
+/// | 22   | `ClientError`      | A 4xx status under `error_for_status`            |
+/// | 23   | `Write`            | The body could not be written to file or sink    |
+/// | 28   | `ReadTimeout`      | The response did not arrive in time              |
+/// | 35   | `Tls`              | The TLS handshake failed                         |
+/// | 47   | `TooManyRedirects` | Redirects exceeded their limit or looped         |
+/// | 61   | `BodyDecode`       | The response body could not be decoded           |
+/// | 63   | `BodyTooLarge`     | The body exceeds the maximum body size           |
+/// | 90   | `ConnectTimeout`   | The connection was not established in time       |
+/// | 91   | `TaskPanic`        | The task sending the request panicked            |
+/// | 92   | `ServerError`      | A 5xx status under `error_for_status`            |
+/// | 93   | `ChecksumMismatch` | The downloaded body has an unexpected digest     |
+/// | 99   | `Other`            | Any other failure                                |
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(u16)]
+pub enum ErrorCode {
+    /// No failure.
+    Ok = 0,
+    /// The request could not be sent as built.
+    InvalidRequest = 3,
+    /// The host name could not be resolved.
+    Dns = 6,
+    /// The connection could not be established.
+    Connect = 7,
+    /// The response had a 4xx status under `error_for_status`.
+    ClientError = 22,
+    /// The response body could not be written to the output file or body sink.
+    Write = 23,
+    /// The response did not arrive in time.
+    ReadTimeout = 28,
+    /// The TLS handshake failed.
+    Tls = 35,
+    /// Redirects exceeded their limit or looped.
+    TooManyRedirects = 47,
+    /// The response body could not be decoded.
+    BodyDecode = 61,
+    /// The body exceeds the maximum body size.
+    BodyTooLarge = 63,
+    /// The connection was not established within the connect timeout.
+    ConnectTimeout = 90,
+    /// The task sending the request panicked.
+    TaskPanic = 91,
+    /// The response had a 5xx status under `error_for_status`.
+    ServerError = 92,
+    /// The downloaded body does not have the expected checksum.
+    ChecksumMismatch = 93,
+    /// Any other failure.
+    Other = 99,
+}
+
+impl ErrorCode {
+    /// Returns the numeric value of the code.
+    pub fn code(self) -> u16 {
+        self as u16
+    }
+}
+
+/// Returns the numeric `ErrorCode` of a `reqwest::Error`.
+///
+/// #### Arguments
+///
+/// * `error` - The error to map.
+///
+/// #### Examples
+///
+/// ```no_run
+/// use rollingrequests::error::{ErrorCode, error_code};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     if let Err(error) = reqwest::get("http://nonexistent.invalid").await {
+///         assert_eq!(error_code(&error), ErrorCode::Dns.code());
+///     }
+/// }
+/// ```
+pub fn error_code(error: &reqwest::Error) -> u16 {
+    let code = match error.status() {
+        Some(status) if status.is_server_error() => ErrorCode::ServerError,
+        Some(_) => ErrorCode::ClientError,
+        None if error.is_redirect() => ErrorCode::TooManyRedirects,
+        None if error.is_decode() => ErrorCode::BodyDecode,
+        None if error.is_builder() => ErrorCode::InvalidRequest,
+        None => match RollingErrorKind::of(error) {
+            RollingErrorKind::BodyTooLarge => ErrorCode::BodyTooLarge,
+            RollingErrorKind::ConnectTimeout => ErrorCode::ConnectTimeout,
+            RollingErrorKind::ReadTimeout => ErrorCode::ReadTimeout,
+            RollingErrorKind::Dns => ErrorCode::Dns,
+            RollingErrorKind::Tls => ErrorCode::Tls,
+            RollingErrorKind::Connect => ErrorCode::Connect,
+            _ => ErrorCode::Other,
+        },
+    };
+    code.code()
+}
+
+/// A request body or multipart part larger than the maximum body size.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BodyTooLarge {
+    /// The size found; for a file that grew while streaming, the bytes read so far.
+    pub size: u64,
+    /// The maximum body size.
+    pub limit: u64,
+}
+
+impl BodyTooLarge {
+    /// Finds a `BodyTooLarge` among the causes of `error`, e.g. raised by a
+    /// file body while streaming.
+    fn find(error: &reqwest::Error) -> Option<&BodyTooLarge> {
+        let mut source = error.source();
+        while let Some(cause) = source {
+            // I/O errors do not list the error they wrap among their sources
+            let wrapped = cause
+                .downcast_ref::<std::io::Error>()
+                .and_then(std::io::Error::get_ref)
+                .and_then(|inner| inner.downcast_ref::<BodyTooLarge>());
+            if let Some(found) = wrapped.or_else(|| cause.downcast_ref()) {
+                return Some(found);
+            }
+            source = cause.source();
+        }
+        None
+    }
+}
+
+impl fmt::Display for BodyTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "body of {} bytes exceeds the limit of {} bytes",
+            self.size, self.limit
+        )
+    }
+}
+
+impl std::error::Error for BodyTooLarge {}
+
+/// A downloaded body whose digest differs from the expected checksum.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChecksumMismatch {
+    /// The checksum expected by the request.
+    pub expected: Checksum,
+    /// The digest of the body received.
+    pub actual: Checksum,
+}
+
+impl fmt::Display for ChecksumMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} checksum mismatch: expected {}, got {}",
+            self.expected.algorithm.name(),
+            self.expected.hex,
+            self.actual.hex
+        )
+    }
+}
+
+impl std::error::Error for ChecksumMismatch {}
+
+/// The reason a recorded redirect chain was stopped, see
+/// `Request::set_record_redirects`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RedirectError {
+    /// The response redirected again after the maximum number of hops.
+    TooManyHops {
+        /// The maximum number of hops followed.
+        limit: u32,
+    },
+    /// The response redirected to a URL visited before.
+    Loop {
+        /// The URL visited twice.
+        url: reqwest::Url,
+    },
+}
+
+impl fmt::Display for RedirectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RedirectError::TooManyHops { limit } => {
+                write!(f, "too many redirects: more than {} hops", limit)
+            }
+            RedirectError::Loop { url } => write!(f, "redirect loop back to {}", url),
+        }
+    }
+}
+
+impl std::error::Error for RedirectError {}
+
+/// A request dropped by `RollingRequests::try_add_request` because an equal
+/// request was added before, see `RollingRequestsBuilder::deduplicate`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DuplicateRequest {
+    /// The identifier of the equal request added before.
+    pub id: u64,
+}
+
+impl fmt::Display for DuplicateRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "duplicate of request {}", self.id)
+    }
+}
+
+impl std::error::Error for DuplicateRequest {}
+
+/// A URL rejected by `RollingRequests::add_fanout`: a host without a scheme
+/// and authority, or a request URL without a scheme.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidOrigin {
+    /// The URL as given.
+    pub url: String,
+}
+
+impl fmt::Display for InvalidOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid origin {:?}", self.url)
+    }
+}
+
+impl std::error::Error for InvalidOrigin {}
+
+/// A cookie rejected by `RollingRequests::add_cookie`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CookieError {
+    /// The cookie store is not enabled, see
+    /// `RollingRequestsBuilder::cookie_store`.
+    Disabled,
+    /// The URL, as given, could not be parsed.
+    InvalidUrl(String),
+    /// The cookie, as given, is malformed or not allowed for the URL.
+    InvalidCookie(String),
+}
+
+impl fmt::Display for CookieError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CookieError::Disabled => write!(f, "the cookie store is not enabled"),
+            CookieError::InvalidUrl(url) => write!(f, "invalid cookie URL {:?}", url),
+            CookieError::InvalidCookie(cookie) => write!(f, "invalid cookie {:?}", cookie),
+        }
+    }
+}
+
+impl std::error::Error for CookieError {}
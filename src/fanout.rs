@@ -0,0 +1,312 @@
+//! Sending one request to several hosts.
+//!
+//! This module provides the `FanOut` handle returned by
+//! `RollingRequests::add_fanout`, which identifies the copies of a request
+//! queued for each host so `RollingRequests::execute_fanout` can run them
+//! together and report one result per host, and the `FanoutComparison` of
+//! those results, which tells the hosts serving different content apart.
+
+use crate::error::InvalidOrigin;
+use crate::result::RollingResult;
+use reqwest::StatusCode;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use url::{Position, Url};
+
+/// The copies of a request queued by `RollingRequests::add_fanout`, one per
+/// host, in the order the hosts were given.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FanOut {
+    /// The host and the identifier of the copy queued for it.
+    members: Vec<(String, u64)>,
+}
+
+impl FanOut {
+    /// Creates a handle for the copies queued for each host.
+    pub(crate) fn new(members: Vec<(String, u64)>) -> Self {
+        FanOut { members }
+    }
+
+    /// Returns the hosts, in the order they were given.
+    pub fn hosts(&self) -> impl Iterator<Item = &str> {
+        self.members.iter().map(|(host, _)| host.as_str())
+    }
+
+    /// Returns the identifiers of the queued copies, in host order.
+    pub fn ids(&self) -> impl Iterator<Item = u64> + '_ {
+        self.members.iter().map(|(_, id)| *id)
+    }
+
+    /// Returns the host of the copy with the identifier `id`, if it is one.
+    pub fn host_of(&self, id: u64) -> Option<&str> {
+        self.members
+            .iter()
+            .find(|(_, member)| *member == id)
+            .map(|(host, _)| host.as_str())
+    }
+
+    /// Returns the number of hosts.
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Returns true if the request was fanned out to no host.
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+}
+
+/// Returns `url` with its scheme and authority replaced by those of `host`,
+/// keeping the path, query and fragment as written, so path parameters
+/// survive.
+///
+/// A relative `url` is resolved against `base` first, and rejected without
+/// one.
+pub(crate) fn rebase(url: &str, host: &str, base: Option<&Url>) -> Result<String, InvalidOrigin> {
+    let origin = Url::parse(host)
+        .ok()
+        .filter(|origin| origin.has_host())
+        .ok_or_else(|| InvalidOrigin {
+            url: host.to_string(),
+        })?;
+    let rest = match (url.split_once("://"), base) {
+        (Some((_, after_scheme)), _) => after_authority(after_scheme).to_string(),
+        (None, Some(base)) => resolve_relative(url, base),
+        (None, None) => {
+            return Err(InvalidOrigin {
+                url: url.to_string(),
+            });
+        }
+    };
+    Ok(format!("{}{}", &origin[..Position::AfterPort], rest))
+}
+
+/// Returns what follows the authority at the start of `url`.
+fn after_authority(url: &str) -> &str {
+    let end = url.find(['/', '?', '#']).unwrap_or(url.len());
+    &url[end..]
+}
+
+/// Returns the path, query and fragment the relative `url` refers to
+/// against `base`, leaving `url` as written; dot segments are removed once
+/// the request is sent.
+fn resolve_relative(url: &str, base: &Url) -> String {
+    if let Some(network_path) = url.strip_prefix("//") {
+        return after_authority(network_path).to_string();
+    }
+    match url.chars().next() {
+        Some('/') => url.to_string(),
+        Some('?') => format!("{}{}", base.path(), url),
+        Some('#') => format!(
+            "{}{}",
+            &base[Position::BeforePath..Position::AfterQuery],
+            url
+        ),
+        None => base[Position::BeforePath..Position::AfterQuery].to_string(),
+        Some(_) => {
+            let path = base.path();
+            let directory = &path[..path.rfind('/').map_or(0, |slash| slash + 1)];
+            format!("{}{}", directory, url)
+        }
+    }
+}
+
+/// How responses are normalized before `FanoutComparison::new` compares
+/// them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Normalization {
+    /// The headers compared across hosts, or `None` to compare every header
+    /// but the ignored ones. Names are case-insensitive.
+    pub headers: Option<Vec<String>>,
+    /// The headers never compared, such as those naming the host or the time
+    /// of the response. Names are case-insensitive.
+    pub ignored_headers: Vec<String>,
+    /// Whether leading and trailing whitespace is trimmed from bodies before
+    /// they are hashed; `Content-Length` then usually needs to be ignored
+    /// too.
+    pub trim_whitespace: bool,
+}
+
+impl Default for Normalization {
+    fn default() -> Self {
+        Normalization {
+            headers: None, // Default to every header
+            ignored_headers: vec!["date".to_string(), "server".to_string()], // Default to Date and Server
+            trim_whitespace: false, // Default to hashing bodies as received
+        }
+    }
+}
+
+/// What a host answered to a fanned-out request, normalized for comparison.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MirrorResponse {
+    /// The host, as given to `add_fanout`.
+    pub host: String,
+    /// The status of the response.
+    pub status: StatusCode,
+    /// The 64-bit FNV-1a hash of the normalized body, which is the same
+    /// across runs, platforms and versions of this crate, or `None` when the
+    /// body was cut off at the `result_body_limit` or written to a file, so
+    /// it cannot be compared.
+    pub body_hash: Option<u64>,
+    /// The compared headers, by lowercase name, with the values of repeated
+    /// headers joined by `", "`.
+    pub headers: BTreeMap<String, String>,
+}
+
+/// How the responses of the hosts of a fan-out group compare, see
+/// `FanoutComparison::new`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FanoutComparison {
+    /// The normalized response of every host that answered, in host order.
+    pub responses: Vec<MirrorResponse>,
+    /// The hosts whose status, body or compared headers differ from those of
+    /// the most common response; ties go to the response of the earliest
+    /// host.
+    pub divergent: Vec<String>,
+    /// The hosts whose request failed without a response, or whose body
+    /// could not be read.
+    pub failed: Vec<String>,
+}
+
+impl FanoutComparison {
+    /// Compares the results of `RollingRequests::execute_fanout`.
+    ///
+    /// Bodies are compared as read into the results. Bodies cut off at the
+    /// `result_body_limit` or written to output files are not compared, so
+    /// only the status and headers of those responses are.
+    ///
+    /// #### Arguments
+    ///
+    /// * `results` - The results of the hosts, as returned by `execute_fanout`.
+    /// * `normalization` - What is ignored when comparing responses.
+    ///
+    /// #### Examples
+    ///
+    /// ```no_run
+    /// use rollingrequests::fanout::{FanoutComparison, Normalization};
+    /// use rollingrequests::request::Request;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut rolling_requests = RollingRequestsBuilder::new().build();
+    ///     let fanout = rolling_requests
+    ///         .add_fanout(
+    ///             Request::get("http://localhost/releases/latest.tar.gz"),
+    ///             &["https://mirror-1.example.com", "https://mirror-2.example.com"],
+    ///         )
+    ///         .unwrap();
+    ///
+    ///     let results = rolling_requests.execute_fanout(&fanout).await;
+    ///     let comparison = FanoutComparison::new(&results, &Normalization::default());
+    ///     for host in &comparison.divergent {
+    ///         println!("{} serves different content", host);
+    ///     }
+    /// }
+    /// ```
+    pub fn new(results: &[(String, RollingResult)], normalization: &Normalization) -> Self {
+        let mut responses = Vec::new();
+        let mut failed = Vec::new();
+        for (host, result) in results {
+            match (result.status, &result.error) {
+                (Some(status), error)
+                    if error.as_ref().is_none_or(|error| error.status().is_some()) =>
+                {
+                    responses.push(normalize(host, status, result, normalization));
+                }
+                _ => failed.push(host.clone()),
+            }
+        }
+
+        let same = |a: &MirrorResponse, b: &MirrorResponse| {
+            let bodies_match = match (a.body_hash, b.body_hash) {
+                (Some(a), Some(b)) => a == b,
+                _ => true,
+            };
+            (a.status, &a.headers) == (b.status, &b.headers) && bodies_match
+        };
+        let reference = responses
+            .iter()
+            .enumerate()
+            .max_by_key(|(index, candidate)| {
+                let votes = responses
+                    .iter()
+                    .filter(|other| same(candidate, other))
+                    .count();
+                // The reversed position lets the earliest host win ties
+                (votes, Reverse(*index))
+            })
+            .map(|(_, reference)| reference.clone());
+        let divergent = responses
+            .iter()
+            .filter(|response| {
+                reference
+                    .as_ref()
+                    .is_some_and(|reference| !same(reference, response))
+            })
+            .map(|response| response.host.clone())
+            .collect();
+        FanoutComparison {
+            responses,
+            divergent,
+            failed,
+        }
+    }
+
+    /// Returns true if every host answered with the same content.
+    pub fn is_consistent(&self) -> bool {
+        self.divergent.is_empty() && self.failed.is_empty()
+    }
+}
+
+/// Normalizes the response of `host` for comparison.
+fn normalize(
+    host: &str,
+    status: StatusCode,
+    result: &RollingResult,
+    normalization: &Normalization,
+) -> MirrorResponse {
+    let mut body = result.body.as_slice();
+    if normalization.trim_whitespace {
+        body = body.trim_ascii();
+    }
+    let comparable = !result.body_truncated && result.download.is_none();
+
+    let compared = |name: &str| {
+        let listed =
+            |names: &[String]| names.iter().any(|listed| listed.eq_ignore_ascii_case(name));
+        normalization.headers.as_deref().is_none_or(listed)
+            && !listed(&normalization.ignored_headers)
+    };
+    let mut headers = BTreeMap::<String, String>::new();
+    for (name, value) in &result.headers {
+        if !compared(name.as_str()) {
+            continue;
+        }
+        let value = String::from_utf8_lossy(value.as_bytes());
+        headers
+            .entry(name.as_str().to_string())
+            .and_modify(|joined| {
+                joined.push_str(", ");
+                joined.push_str(&value);
+            })
+            .or_insert_with(|| value.into_owned());
+    }
+    MirrorResponse {
+        host: host.to_string(),
+        status,
+        body_hash: comparable.then(|| fnv1a(body)),
+        headers,
+    }
+}
+
+/// Hashes `bytes` with 64-bit FNV-1a, whose values never change, unlike
+/// those of the hasher of the standard library.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    bytes.iter().fold(OFFSET_BASIS, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    })
+}
@@ -0,0 +1,460 @@
+//! Recording of executed traffic as HAR.
+//!
+//! This module provides the `HarRecording` settings, which make
+//! `RollingRequests` record every request it sends together with its
+//! response, so a run can be exported as a HAR 1.2 file with
+//! `RollingRequests::export_har` and imported into browser developer tools.
+
+use crate::request::Request;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use bytes::Bytes;
+use futures_util::StreamExt;
+use reqwest::header::{CONTENT_TYPE, HeaderMap, LOCATION};
+use reqwest::{ResponseBuilderExt, Url, Version};
+use serde_json::{Value, json};
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// The value recorded in place of a redacted header.
+const REDACTED: &str = "[redacted]";
+
+/// The month names of HTTP dates, in calendar order.
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Settings for recording traffic as HAR.
+///
+/// Every attempt sent is recorded, including retries and hedges, but not
+/// requests answered from the response cache, nor the hops of redirects
+/// followed by the HTTP client. Bodies are recorded up to `body_limit`
+/// bytes, as text when they are valid UTF-8 and as base64 otherwise, as far
+/// as they are read by the execution; headers-only results record none.
+/// Once `max_entries` are recorded, each new entry drops the oldest one.
+///
+/// #### Examples
+///
+/// ```
+/// use rollingrequests::har::HarRecording;
+/// use rollingrequests::rolling::RollingRequestsBuilder;
+///
+/// let mut recording = HarRecording::default();
+/// recording.redact_headers.push("X-Api-Key".to_string());
+/// let builder = RollingRequestsBuilder::new().record_har(recording);
+/// ```
+#[derive(Clone, Debug)]
+pub struct HarRecording {
+    /// The number of bytes of each request and response body recorded.
+    pub body_limit: usize,
+    /// The names of headers whose values are replaced by `[redacted]`, in any case.
+    pub redact_headers: Vec<String>,
+    /// The number of entries kept, the most recent ones.
+    pub max_entries: usize,
+}
+
+impl Default for HarRecording {
+    fn default() -> Self {
+        HarRecording {
+            body_limit: 64 * 1024, // Default 64 KiB per body
+            redact_headers: vec![
+                "Authorization".to_string(),
+                "Proxy-Authorization".to_string(),
+                "Cookie".to_string(),
+                "Set-Cookie".to_string(),
+            ], // Default credential headers
+            max_entries: 10_000,   // Default 10,000 entries
+        }
+    }
+}
+
+/// The recorded response of an entry.
+struct HarResponse {
+    /// The status of the response.
+    status: u16,
+    /// The reason phrase of the status.
+    status_text: String,
+    /// The HTTP version of the response.
+    http_version: String,
+    /// The headers of the response, redacted.
+    headers: Vec<(String, String)>,
+    /// The `Content-Type` of the response.
+    mime_type: String,
+    /// The `Location` of a redirect response.
+    redirect_url: String,
+    /// The first `body_limit` bytes of the body.
+    body: Vec<u8>,
+    /// The number of body bytes read.
+    body_size: usize,
+    /// The time from the response headers until the last chunk was read.
+    receive: Duration,
+}
+
+/// One recorded request with its response, or the error it failed with.
+struct HarEntry {
+    /// When the attempt was dispatched.
+    started: SystemTime,
+    /// The method of the request.
+    method: String,
+    /// The URL of the request, with its query parameters.
+    url: Url,
+    /// The headers of the request, redacted.
+    headers: Vec<(String, String)>,
+    /// The `Content-Type` of the request body.
+    mime_type: String,
+    /// The first `body_limit` bytes of the request body, if it has one in memory.
+    body: Option<Vec<u8>>,
+    /// The size of the request body in memory.
+    body_size: usize,
+    /// The time from dispatch until the response headers or the failure.
+    wait: Duration,
+    /// The response, if one was received.
+    response: Option<HarResponse>,
+    /// The message of the error, if the request failed.
+    error: Option<String>,
+}
+
+/// The traffic recorded by a `RollingRequests` instance.
+pub(crate) struct HarLog {
+    /// The settings the log was created with.
+    settings: HarRecording,
+    /// The most recent entries, in the order the attempts were sent.
+    entries: Mutex<VecDeque<Arc<Mutex<HarEntry>>>>,
+}
+
+impl HarLog {
+    /// Creates an empty log.
+    pub(crate) fn new(settings: HarRecording) -> Self {
+        HarLog {
+            settings,
+            entries: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Records an attempt of `request` dispatched at `started`, and returns
+    /// its response with the body recorded as it is read.
+    ///
+    /// #### Arguments
+    ///
+    /// * `request` - The request as it was sent.
+    /// * `started` - When the attempt was dispatched.
+    /// * `wait` - The time until the response headers or the failure.
+    /// * `response` - The outcome of the attempt.
+    pub(crate) fn record(
+        &self,
+        request: &Request,
+        started: SystemTime,
+        wait: Duration,
+        response: Result<reqwest::Response, reqwest::Error>,
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        let mut url = Url::parse(&request.url).unwrap_or_else(|_| {
+            // Requests with invalid URLs fail before sending, so this is not reached
+            Url::parse("http://invalid.url").unwrap()
+        });
+        if !request.query_params.is_empty() {
+            url.query_pairs_mut().extend_pairs(&request.query_params);
+        }
+        let mut entry = HarEntry {
+            started,
+            method: request.method.to_string(),
+            url,
+            headers: self.redact(&request.headers),
+            mime_type: content_type(&request.headers),
+            body: request
+                .post_data
+                .as_ref()
+                .map(|body| body[..body.len().min(self.settings.body_limit)].to_vec()),
+            body_size: request.post_data.as_ref().map_or(0, Vec::len),
+            wait,
+            response: None,
+            error: None,
+        };
+        let response = match response {
+            Ok(response) => response,
+            Err(err) => {
+                entry.error = Some(err.to_string());
+                self.push(entry);
+                return Err(err);
+            }
+        };
+        entry.response = Some(HarResponse {
+            status: response.status().as_u16(),
+            status_text: response
+                .status()
+                .canonical_reason()
+                .unwrap_or_default()
+                .to_string(),
+            http_version: http_version(response.version()),
+            headers: self.redact(response.headers()),
+            mime_type: content_type(response.headers()),
+            redirect_url: response
+                .headers()
+                .get(LOCATION)
+                .and_then(|location| location.to_str().ok())
+                .unwrap_or_default()
+                .to_string(),
+            body: Vec::new(),
+            body_size: 0,
+            receive: Duration::ZERO,
+        });
+        let entry = self.push(entry);
+
+        let mut builder = http::Response::builder()
+            .status(response.status())
+            .version(response.version())
+            .url(response.url().clone());
+        if let Some(headers) = builder.headers_mut() {
+            *headers = response.headers().clone();
+        }
+        let limit = self.settings.body_limit;
+        let headers_at = Instant::now();
+        let body = response.bytes_stream().inspect(move |chunk| {
+            if let Ok(chunk) = chunk {
+                entry.lock().unwrap().record_chunk(chunk, limit, headers_at);
+            }
+        });
+        let response = builder
+            .body(reqwest::Body::wrap_stream(body))
+            .expect("the parts of a received response are valid");
+        Ok(reqwest::Response::from(response))
+    }
+
+    /// Adds `entry` to the log, dropping the oldest entry when it is full,
+    /// and returns it shared with the log.
+    fn push(&self, entry: HarEntry) -> Arc<Mutex<HarEntry>> {
+        let entry = Arc::new(Mutex::new(entry));
+        let mut entries = self.entries.lock().unwrap();
+        if self.settings.max_entries > 0 {
+            if entries.len() >= self.settings.max_entries {
+                entries.pop_front();
+            }
+            entries.push_back(entry.clone());
+        }
+        entry
+    }
+
+    /// Returns the headers as name and value pairs, with the values of the
+    /// redacted headers replaced.
+    fn redact(&self, headers: &HeaderMap) -> Vec<(String, String)> {
+        headers
+            .iter()
+            .map(|(name, value)| {
+                let redacted = self
+                    .settings
+                    .redact_headers
+                    .iter()
+                    .any(|redacted| redacted.eq_ignore_ascii_case(name.as_str()));
+                let value = match redacted {
+                    true => REDACTED.to_string(),
+                    false => String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                };
+                (name.to_string(), value)
+            })
+            .collect()
+    }
+
+    /// Returns the number of recorded entries.
+    pub(crate) fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    /// Removes every recorded entry.
+    pub(crate) fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    /// Writes the log to `path` as a HAR 1.2 file.
+    ///
+    /// #### Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub(crate) fn export(&self, path: &Path) -> io::Result<()> {
+        let entries: Vec<Value> = self
+            .entries
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|entry| entry.lock().unwrap().to_json())
+            .collect();
+        let har = json!({
+            "log": {
+                "version": "1.2",
+                "creator": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+                "pages": [],
+                "entries": entries,
+            }
+        });
+        fs::write(path, serde_json::to_vec_pretty(&har)?)
+    }
+}
+
+impl HarEntry {
+    /// Records a chunk of the response body read `headers_at` after the headers.
+    fn record_chunk(&mut self, chunk: &Bytes, limit: usize, headers_at: Instant) {
+        let Some(response) = &mut self.response else {
+            return;
+        };
+        let room = limit.saturating_sub(response.body.len());
+        response
+            .body
+            .extend_from_slice(&chunk[..chunk.len().min(room)]);
+        response.body_size += chunk.len();
+        response.receive = headers_at.elapsed();
+    }
+
+    /// Returns the entry as a HAR entry object.
+    fn to_json(&self) -> Value {
+        let http_version = self
+            .response
+            .as_ref()
+            .map_or_else(String::new, |response| response.http_version.clone());
+        let mut request = json!({
+            "method": self.method,
+            "url": self.url.as_str(),
+            "httpVersion": http_version,
+            "cookies": [],
+            "headers": name_values(&self.headers),
+            "queryString": name_values(
+                &self
+                    .url
+                    .query_pairs()
+                    .map(|(name, value)| (name.into_owned(), value.into_owned()))
+                    .collect::<Vec<_>>()
+            ),
+            "headersSize": -1,
+            "bodySize": self.body_size,
+        });
+        if let Some(body) = &self.body {
+            request["postData"] = json!({
+                "mimeType": self.mime_type,
+                "text": String::from_utf8_lossy(body),
+            });
+        }
+
+        let (response, receive) = match &self.response {
+            Some(response) => {
+                let mut content = json!({
+                    "size": response.body_size,
+                    "mimeType": response.mime_type,
+                });
+                match std::str::from_utf8(&response.body) {
+                    Ok(text) => content["text"] = json!(text),
+                    Err(_) => {
+                        content["text"] = json!(BASE64.encode(&response.body));
+                        content["encoding"] = json!("base64");
+                    }
+                }
+                if response.body.len() < response.body_size {
+                    content["comment"] =
+                        json!(format!("truncated to {} bytes", response.body.len()));
+                }
+                let response_json = json!({
+                    "status": response.status,
+                    "statusText": response.status_text,
+                    "httpVersion": response.http_version,
+                    "cookies": [],
+                    "headers": name_values(&response.headers),
+                    "content": content,
+                    "redirectURL": response.redirect_url,
+                    "headersSize": -1,
+                    "bodySize": response.body_size,
+                });
+                (response_json, response.receive)
+            }
+            // Failed requests are recorded with status 0, like browsers do
+            None => {
+                let response_json = json!({
+                    "status": 0,
+                    "statusText": "",
+                    "httpVersion": "",
+                    "cookies": [],
+                    "headers": [],
+                    "content": {"size": 0, "mimeType": ""},
+                    "redirectURL": "",
+                    "headersSize": -1,
+                    "bodySize": -1,
+                    "_error": self.error,
+                });
+                (response_json, Duration::ZERO)
+            }
+        };
+
+        let wait = millis(self.wait);
+        let receive = millis(receive);
+        json!({
+            "startedDateTime": iso8601(self.started),
+            "time": wait + receive,
+            "request": request,
+            "response": response,
+            "cache": {},
+            "timings": {
+                "blocked": -1,
+                "dns": -1,
+                "connect": -1,
+                "send": 0,
+                "wait": wait,
+                "receive": receive,
+                "ssl": -1,
+            },
+        })
+    }
+}
+
+/// Returns headers or query parameters as HAR name and value objects.
+fn name_values(pairs: &[(String, String)]) -> Vec<Value> {
+    pairs
+        .iter()
+        .map(|(name, value)| json!({"name": name, "value": value}))
+        .collect()
+}
+
+/// Returns the `Content-Type` among `headers`, or an empty string.
+fn content_type(headers: &HeaderMap) -> String {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Returns the HAR name of an HTTP version, such as `HTTP/1.1`.
+fn http_version(version: Version) -> String {
+    format!("{:?}", version)
+}
+
+/// Returns a duration in fractional milliseconds.
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Formats a time as an ISO 8601 UTC timestamp with milliseconds, from the
+/// fields of its HTTP date, e.g. `Sun, 06 Nov 1994 08:49:37 GMT`.
+fn iso8601(time: SystemTime) -> String {
+    // HTTP dates cannot represent times before the epoch
+    let time = time.max(UNIX_EPOCH);
+    let date = httpdate::fmt_http_date(time);
+    let fields: Vec<&str> = date
+        .split([',', ' '])
+        .filter(|field| !field.is_empty())
+        .collect();
+    let month = MONTHS
+        .iter()
+        .position(|month| *month == fields[2])
+        .unwrap_or(0)
+        + 1;
+    let millis = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_millis();
+    format!(
+        "{}-{:02}-{}T{}.{:03}Z",
+        fields[3], month, fields[1], fields[4], millis
+    )
+}
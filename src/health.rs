@@ -0,0 +1,77 @@
+//! Liveness checks built on top of `RollingRequests`.
+//!
+//! This module provides the `HealthCheck` rules used by
+//! `RollingRequests::check_health` to classify every polled endpoint as up,
+//! degraded or down, and the `HealthReport` values it returns.
+
+use std::time::Duration;
+
+/// The health of an endpoint as seen by a check.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HealthStatus {
+    /// The endpoint answered as expected and in time.
+    Up,
+    /// The endpoint answered as expected but slowly, or failed fewer times in a
+    /// row than the failure threshold.
+    Degraded,
+    /// The endpoint failed at least the failure threshold of checks in a row.
+    Down,
+}
+
+/// Rules classifying the result of a health check.
+#[derive(Clone, Debug)]
+pub struct HealthCheck {
+    /// The statuses counted as healthy; any other status is a failure.
+    pub up_statuses: Vec<u16>,
+    /// Healthy responses slower than this are reported as degraded.
+    pub latency_threshold: Duration,
+    /// When set, responses whose body does not contain this text are failures.
+    pub body_contains: Option<String>,
+    /// How many checks in a row must fail before an endpoint is reported down.
+    pub failure_threshold: u32,
+}
+
+impl Default for HealthCheck {
+    fn default() -> Self {
+        HealthCheck {
+            up_statuses: (200..300).collect(),         // Default 2xx
+            latency_threshold: Duration::from_secs(1), // Default "fast" response
+            body_contains: None,                       // Default no body check
+            failure_threshold: 1,                      // Default down on first failure
+        }
+    }
+}
+
+/// The outcome of a health check for one endpoint.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HealthReport {
+    /// The classification of the endpoint.
+    pub status: HealthStatus,
+    /// The response status, if a response was received.
+    pub status_code: Option<u16>,
+    /// How long the last attempt took.
+    pub latency: Duration,
+    /// How many checks in a row have failed, including this one.
+    pub consecutive_failures: u32,
+    /// Why the check failed, if it did.
+    pub error: Option<String>,
+}
+
+impl HealthCheck {
+    /// Classifies a check from its outcome and the number of failures in a row.
+    ///
+    /// `failure` is the reason the check failed, if it did.
+    pub(crate) fn classify(
+        &self,
+        failure: Option<&str>,
+        latency: Duration,
+        consecutive_failures: u32,
+    ) -> HealthStatus {
+        match failure {
+            Some(_) if consecutive_failures >= self.failure_threshold.max(1) => HealthStatus::Down,
+            Some(_) => HealthStatus::Degraded,
+            None if latency > self.latency_threshold => HealthStatus::Degraded,
+            None => HealthStatus::Up,
+        }
+    }
+}
@@ -5,10 +5,69 @@
 //!
 //! #### Modules
 //!
+//! - `adaptive`: Provides the `AdaptiveConcurrency` settings for growing and shrinking
+//!   the number of simultaneous requests based on server feedback.
+//! - `cache`: Provides the `ResponseCache` settings for answering repeated GET
+//!   requests from memory, and the `ValidatorStore` for making them conditional.
+//! - `download`: Provides the `Download` type describing a response body
+//!   streamed to a file.
+//! - `error`: Provides the `RollingError` type returned for failed requests.
+//! - `fanout`: Provides the `FanOut` handle of a request sent to several hosts,
+//!   and the `FanoutComparison` of their responses.
+//! - `har`: Provides the `HarRecording` settings for exporting the traffic of a
+//!   run as a HAR file (requires the `har` feature).
+//! - `health`: Provides the `HealthCheck` rules for classifying endpoints as up,
+//!   degraded or down.
+//! - `proxy`: Provides the `ProxyRotation` and `ProxyEjection` settings of a
+//!   pool of proxies requests are spread across.
 //! - `request`: Defines the `Request` struct and its associated methods for creating
 //!   and managing individual HTTP requests.
+//! - `result`: Provides the `RollingResult` type holding a request's response
+//!   read into memory, with its timing and attempt count.
+//! - `retry`: Provides the `RetryPolicy` settings for retrying transient failures.
 //! - `rolling`: Provides the `RollingRequests` struct for managing and executing
 //!   multiple requests concurrently.
+//! - `sink`: Provides the `BodySink` trait for streaming response bodies to
+//!   custom consumers chunk by chunk.
+//! - `stats`: Provides the `Stats` snapshot of the requests executed, their
+//!   outcomes and the bytes transferred.
+//! - `throttle`: Provides the settings and state types used to pace requests per host.
+//!
+//! #### Features
+//!
+//! - `serde`: Implements `Serialize` and `Deserialize` for `Request`, to persist
+//!   queues or define requests in configuration files.
+//! - `gzip`, `brotli` and `deflate`: Request compressed responses with
+//!   `Accept-Encoding` and decode them automatically. A request can opt out
+//!   with `Request::set_accept_encoding` or `Request::set_decompress`.
+//! - `checksum`: Hash bodies downloaded to output files, reporting their
+//!   digest and checking it against `Request::set_expected_checksum`.
+//! - `har`: Record the requests sent and their responses, and export them as
+//!   HAR with `RollingRequests::export_har`.
+//! - `charset`: Decode response text with the charset of its `Content-Type`
+//!   or HTML `meta` tag, or the default set with
+//!   `RollingRequestsBuilder::default_charset`, instead of as UTF-8.
+//! - `persistent-cookies`: Load the cookie jar from a file when building and
+//!   save it back when the instance is dropped, with
+//!   `RollingRequestsBuilder::cookie_store_path`.
 
+pub mod adaptive;
+mod auth;
+pub mod cache;
+#[cfg(feature = "charset")]
+mod charset;
+mod cookies;
+pub mod download;
+pub mod error;
+pub mod fanout;
+#[cfg(feature = "har")]
+pub mod har;
+pub mod health;
+pub mod proxy;
 pub mod request;
+pub mod result;
+pub mod retry;
 pub mod rolling;
+pub mod sink;
+pub mod stats;
+pub mod throttle;
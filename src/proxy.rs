@@ -0,0 +1,137 @@
+//! Rotation of requests across a pool of proxies.
+//!
+//! This module provides the `ProxyRotation` strategy and the `ProxyEjection`
+//! settings of the proxy pool set with `RollingRequestsBuilder::proxy_pool`,
+//! which sends each attempt through the next proxy of the pool and takes
+//! proxies that keep failing out of rotation for a while.
+
+use rand::Rng;
+use std::time::{Duration, Instant};
+
+/// How the proxy of each attempt is chosen from the pool.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ProxyRotation {
+    /// The proxies are used in turn, in the order they were given.
+    #[default]
+    RoundRobin,
+    /// A proxy is picked at random for each attempt.
+    Random,
+}
+
+/// When a failing proxy is taken out of rotation, and for how long.
+///
+/// Attempts that fail to connect or time out, and responses with status
+/// `407 Proxy Authentication Required`, count as failures of their proxy;
+/// any other outcome resets its count.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProxyEjection {
+    /// The number of consecutive failures that eject a proxy; `0` never
+    /// ejects one.
+    pub failure_threshold: u32,
+    /// How long an ejected proxy stays out of rotation.
+    pub cooldown: Duration,
+}
+
+impl Default for ProxyEjection {
+    fn default() -> Self {
+        ProxyEjection {
+            failure_threshold: 3,              // Default three failures in a row
+            cooldown: Duration::from_secs(30), // Default half a minute out
+        }
+    }
+}
+
+/// A proxy of the pool and its recent failures.
+struct Member {
+    /// The proxy URL, as given.
+    url: String,
+    /// The number of consecutive failures since the proxy was last ejected
+    /// or succeeded.
+    failures: u32,
+    /// When the proxy returns to rotation, while it is ejected.
+    ejected_until: Option<Instant>,
+}
+
+/// Chooses the proxy of each attempt and tracks the failing ones.
+pub(crate) struct ProxyPool {
+    /// The proxies, in the order they were given.
+    members: Vec<Member>,
+    /// How proxies are chosen.
+    rotation: ProxyRotation,
+    /// When proxies are ejected.
+    ejection: ProxyEjection,
+    /// The position round-robin rotation continues from.
+    next: usize,
+}
+
+impl ProxyPool {
+    /// Creates a pool of the proxies `urls`, which must not be empty.
+    pub(crate) fn new(urls: Vec<String>, rotation: ProxyRotation, ejection: ProxyEjection) -> Self {
+        ProxyPool {
+            members: urls
+                .into_iter()
+                .map(|url| Member {
+                    url,
+                    failures: 0,
+                    ejected_until: None,
+                })
+                .collect(),
+            rotation,
+            ejection,
+            next: 0,
+        }
+    }
+
+    /// Chooses the proxy of an attempt dispatched `now`, returning its
+    /// position in the pool and its URL.
+    ///
+    /// When every proxy is ejected, the one returning first is used rather
+    /// than holding the attempt back.
+    pub(crate) fn select(&mut self, now: Instant) -> (usize, String) {
+        let len = self.members.len();
+        for member in &mut self.members {
+            if member.ejected_until.is_some_and(|until| until <= now) {
+                member.ejected_until = None;
+            }
+        }
+        let available: Vec<usize> = (0..len)
+            .filter(|&index| self.members[index].ejected_until.is_none())
+            .collect();
+        let index = match (available.is_empty(), self.rotation) {
+            (true, _) => (0..len)
+                .min_by_key(|&index| self.members[index].ejected_until)
+                .unwrap_or(0),
+            (false, ProxyRotation::RoundRobin) => {
+                // The first available proxy at or after the turn of the rotation
+                let index = (0..len)
+                    .map(|offset| (self.next + offset) % len)
+                    .find(|index| available.contains(index))
+                    .unwrap_or(available[0]);
+                self.next = index + 1;
+                index
+            }
+            (false, ProxyRotation::Random) => {
+                available[rand::thread_rng().gen_range(0..available.len())]
+            }
+        };
+        (index, self.members[index].url.clone())
+    }
+
+    /// Records the outcome of an attempt sent through the proxy at `index`,
+    /// ejecting the proxy once it failed `failure_threshold` times in a row.
+    pub(crate) fn record(&mut self, index: usize, failed: bool, now: Instant) {
+        let Some(member) = self.members.get_mut(index) else {
+            return;
+        };
+        if !failed {
+            member.failures = 0;
+            return;
+        }
+        member.failures += 1;
+        if self.ejection.failure_threshold > 0 && member.failures >= self.ejection.failure_threshold
+        {
+            member.failures = 0;
+            member.ejected_until = Some(now + self.ejection.cooldown);
+        }
+    }
+}
@@ -0,0 +1,328 @@
+use crate::request::{InvalidHeader, InvalidOption, Request};
+use crate::result::RollingResult;
+use crate::retry::RetryPolicy;
+use reqwest::Method;
+use serde::Serialize;
+use std::fmt;
+use std::time::Duration;
+use url::Url;
+
+/// The reason a request could not be built by a `RequestBuilder` or converted.
+#[derive(Debug)]
+pub enum BuildError {
+    /// The URL could not be parsed.
+    InvalidUrl {
+        /// The URL as given.
+        url: String,
+        /// Why the URL was rejected.
+        source: url::ParseError,
+    },
+    /// A header name or value is invalid.
+    InvalidHeader(InvalidHeader),
+    /// A `{name}` placeholder in the URL has no value.
+    MissingPathParam(String),
+    /// An option has a value that cannot be applied.
+    InvalidOption(InvalidOption),
+    /// The JSON body could not be serialized.
+    Json(serde_json::Error),
+    /// The form body or the query parameters could not be encoded.
+    UrlEncoded(serde_urlencoded::ser::Error),
+    /// The HTTP client rejected the request.
+    Client(reqwest::Error),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::InvalidUrl { url, source } => {
+                write!(f, "invalid URL {:?}: {}", url, source)
+            }
+            BuildError::InvalidHeader(header) => write!(f, "{}", header),
+            BuildError::MissingPathParam(name) => {
+                write!(f, "no value for URL placeholder {{{}}}", name)
+            }
+            BuildError::InvalidOption(option) => write!(f, "{}", option),
+            BuildError::Json(error) => write!(f, "failed to serialize JSON body: {}", error),
+            BuildError::UrlEncoded(error) => write!(f, "failed to encode form data: {}", error),
+            BuildError::Client(error) => write!(f, "failed to build request: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BuildError::InvalidUrl { source, .. } => Some(source),
+            BuildError::InvalidHeader(header) => Some(header),
+            BuildError::MissingPathParam(_) => None,
+            BuildError::InvalidOption(option) => Some(option),
+            BuildError::Json(error) => Some(error),
+            BuildError::UrlEncoded(error) => Some(error),
+            BuildError::Client(error) => Some(error),
+        }
+    }
+}
+
+/// A builder for `Request` values, chained by value in a single expression.
+///
+/// Every method takes the builder and returns it. Errors are not reported
+/// until `build`, which validates the URL and the headers and returns the
+/// first problem found.
+///
+/// #### Examples
+///
+/// ```
+/// use rollingrequests::request::Request;
+/// use reqwest::Method;
+///
+/// let request = Request::builder("http://example.com/users", Method::POST)
+///     .header("Accept", "application/json")
+///     .body("name=alice")
+///     .extra_info("create-user")
+///     .build()
+///     .expect("Failed to build request");
+/// assert_eq!(request.get_post_data(), Some("name=alice"));
+/// ```
+#[must_use]
+pub struct RequestBuilder {
+    /// The request being built.
+    request: Request,
+    /// The first body or query encoding error, reported by `build`.
+    error: Option<BuildError>,
+}
+
+impl Request {
+    /// Creates a `RequestBuilder` for the specified URL and method.
+    ///
+    /// #### Arguments
+    ///
+    /// * `url` - The URL for the request, checked when the request is built.
+    /// * `method` - The HTTP method to use.
+    pub fn builder(url: &str, method: Method) -> RequestBuilder {
+        RequestBuilder {
+            request: Request::new(url, method),
+            error: None,
+        }
+    }
+}
+
+impl RequestBuilder {
+    /// Sets a header, replacing any value set before under the same name.
+    ///
+    /// #### Arguments
+    ///
+    /// * `key` - The header name.
+    /// * `value` - The header value.
+    pub fn header(mut self, key: &str, value: &str) -> Self {
+        self.request.add_header(key, value);
+        self
+    }
+
+    /// Adds a value to a header, keeping the values set before.
+    ///
+    /// #### Arguments
+    ///
+    /// * `key` - The header name.
+    /// * `value` - The header value.
+    pub fn append_header(mut self, key: &str, value: &str) -> Self {
+        self.request.append_header(key, value);
+        self
+    }
+
+    /// Adds a cookie, replacing any cookie set before under the same name.
+    ///
+    /// #### Arguments
+    ///
+    /// * `name` - The cookie name.
+    /// * `value` - The cookie value, encoded as by `Request::add_cookie`.
+    pub fn cookie(mut self, name: &str, value: &str) -> Self {
+        self.request.add_cookie(name, value);
+        self
+    }
+
+    /// Appends a query parameter, sent in addition to any query in the URL.
+    ///
+    /// #### Arguments
+    ///
+    /// * `key` - The parameter name.
+    /// * `value` - The parameter value.
+    pub fn query_param(mut self, key: &str, value: &str) -> Self {
+        self.request.add_query_param(key, value);
+        self
+    }
+
+    /// Replaces the query parameters with the serialized fields of `value`.
+    ///
+    /// #### Arguments
+    ///
+    /// * `value` - The value to serialize, such as a struct or a list of pairs.
+    pub fn query<T: Serialize + ?Sized>(mut self, value: &T) -> Self {
+        if let Err(error) = self.request.set_query(value) {
+            self.error.get_or_insert(BuildError::UrlEncoded(error));
+        }
+        self
+    }
+
+    /// Sets the request body to the given text.
+    ///
+    /// #### Arguments
+    ///
+    /// * `body` - The body to send.
+    pub fn body(mut self, body: &str) -> Self {
+        self.request.set_post_data(Some(body));
+        self
+    }
+
+    /// Sets the request body to the given bytes.
+    ///
+    /// #### Arguments
+    ///
+    /// * `body` - The body to send.
+    pub fn body_bytes(mut self, body: &[u8]) -> Self {
+        self.request.set_body_bytes(body);
+        self
+    }
+
+    /// Serializes `value` as JSON into the request body.
+    ///
+    /// #### Arguments
+    ///
+    /// * `value` - The value to serialize.
+    pub fn json<T: Serialize + ?Sized>(mut self, value: &T) -> Self {
+        if let Err(error) = self.request.set_json(value) {
+            self.error.get_or_insert(BuildError::Json(error));
+        }
+        self
+    }
+
+    /// Encodes `value` as `application/x-www-form-urlencoded` into the request body.
+    ///
+    /// #### Arguments
+    ///
+    /// * `value` - The value to encode, such as a struct or a list of pairs.
+    pub fn form<T: Serialize + ?Sized>(mut self, value: &T) -> Self {
+        if let Err(error) = self.request.set_form(value) {
+            self.error.get_or_insert(BuildError::UrlEncoded(error));
+        }
+        self
+    }
+
+    /// Sets extra information for the request.
+    ///
+    /// #### Arguments
+    ///
+    /// * `extra_info` - Additional information to associate with the request.
+    pub fn extra_info(mut self, extra_info: &str) -> Self {
+        self.request.set_extra_info(extra_info);
+        self
+    }
+
+    /// Sets the hook called with the final result of the request, see
+    /// `Request::set_on_complete`.
+    ///
+    /// #### Arguments
+    ///
+    /// * `hook` - The hook called with the final result.
+    pub fn on_complete<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&RollingResult) + Send + Sync + 'static,
+    {
+        self.request.set_on_complete(hook);
+        self
+    }
+
+    /// Tags the request, see `Request::set_tag`.
+    ///
+    /// #### Arguments
+    ///
+    /// * `tag` - The tag of the request.
+    pub fn tag(mut self, tag: &str) -> Self {
+        self.request.set_tag(tag);
+        self
+    }
+
+    /// Sets the timeout of the request, overriding the client timeout.
+    ///
+    /// #### Arguments
+    ///
+    /// * `timeout` - The time allowed for each attempt.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.request.set_timeout(timeout);
+        self
+    }
+
+    /// Sets whether redirects are followed for this request.
+    ///
+    /// #### Arguments
+    ///
+    /// * `follow` - Whether to follow redirects.
+    pub fn follow_redirects(mut self, follow: bool) -> Self {
+        self.request.set_follow_redirects(follow);
+        self
+    }
+
+    /// Sets the retry policy of the request.
+    ///
+    /// #### Arguments
+    ///
+    /// * `policy` - The retry policy to use.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.request.set_retry_policy(policy);
+        self
+    }
+
+    /// Limits the number of attempts made for the request.
+    ///
+    /// #### Arguments
+    ///
+    /// * `max_attempts` - The maximum number of attempts.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.request.set_max_attempts(max_attempts);
+        self
+    }
+
+    /// Adds a URL tried when the previous ones fail.
+    ///
+    /// #### Arguments
+    ///
+    /// * `url` - The fallback URL, checked when the request is built.
+    pub fn fallback_url(mut self, url: &str) -> Self {
+        self.request.add_fallback_url(url);
+        self
+    }
+
+    /// Sets how many times the request is sent.
+    ///
+    /// #### Arguments
+    ///
+    /// * `repeat` - The number of times to send the request.
+    pub fn repeat(mut self, repeat: u32) -> Self {
+        self.request.set_repeat(repeat);
+        self
+    }
+
+    /// Builds the request.
+    ///
+    /// #### Errors
+    ///
+    /// Returns an error if the URL or a fallback URL cannot be parsed, if a
+    /// header is invalid, or if a body or the query could not be encoded.
+    pub fn build(self) -> Result<Request, BuildError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        let request = self.request;
+        for url in std::iter::once(&request.url).chain(&request.fallback_urls) {
+            if let Err(source) = Url::parse(url) {
+                return Err(BuildError::InvalidUrl {
+                    url: url.clone(),
+                    source,
+                });
+            }
+        }
+        if let Some(header) = request.header_errors.first() {
+            return Err(BuildError::InvalidHeader(header.clone()));
+        }
+        Ok(request)
+    }
+}
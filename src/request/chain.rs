@@ -0,0 +1,173 @@
+use crate::request::Request;
+use crate::result::RollingResult;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::Arc;
+
+/// The hook returning the follow-up requests of a finished request, see
+/// `Request::set_continuation`.
+pub type Continuation = Arc<dyn Fn(&RollingResult) -> Vec<Request> + Send + Sync>;
+
+/// The hook called with the final result of a request, see
+/// `Request::set_on_complete`.
+pub type CompletionHook = Arc<dyn Fn(&RollingResult) + Send + Sync>;
+
+impl Request {
+    /// Sets the hook returning the requests that follow this one.
+    ///
+    /// Once the request produced its final result, the hook is called with
+    /// it, including the whole body, and the requests it returns are queued
+    /// right away, so they run while the rest of the execution is in flight.
+    /// Follow-ups without a tag inherit the tag of this request, and those at
+    /// the default priority of `0` inherit its priority. They are
+    /// executed and reported as requests of their own, with new identifiers,
+    /// by every execution taking requests from the queue; `execute_all` and
+    /// its variants run every follow-up before they return. The replays of
+    /// `execute_for` and `check_health` leave the queue untouched and do not
+    /// call the hook. It is called once for a request, not again for its
+    /// repetitions or next pages.
+    ///
+    /// The body of a response to a request with a continuation is read into
+    /// memory before the response is handed on, failing the request with
+    /// `RollingErrorKind::BodyTooLarge` past its maximum body size, see
+    /// `set_max_body_size`. The hook is skipped for
+    /// failed requests and non-success statuses, unless
+    /// `set_continue_on_failure` is set.
+    ///
+    /// #### Arguments
+    ///
+    /// * `continuation` - The hook returning the follow-up requests; a panic
+    ///   is taken as no follow-ups.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    ///
+    /// let mut request = Request::post("http://example.com/users", Some("name=alice"));
+    /// request.set_continuation(|result| {
+    ///     result
+    ///         .headers
+    ///         .get("Location")
+    ///         .and_then(|location| location.to_str().ok())
+    ///         .map(|location| vec![Request::get(location)])
+    ///         .unwrap_or_default()
+    /// });
+    /// assert!(request.get_continuation().is_some());
+    /// ```
+    pub fn set_continuation<F>(&mut self, continuation: F) -> &mut Self
+    where
+        F: Fn(&RollingResult) -> Vec<Request> + Send + Sync + 'static,
+    {
+        self.continuation = Some(Arc::new(continuation));
+        self
+    }
+
+    /// Retrieves the hook returning the follow-up requests, if set.
+    pub fn get_continuation(&self) -> Option<&Continuation> {
+        self.continuation.as_ref()
+    }
+
+    /// Sets whether the continuation is also called for failed requests and
+    /// non-success statuses, `false` by default.
+    ///
+    /// #### Arguments
+    ///
+    /// * `enabled` - Whether failures are handed to the continuation.
+    pub fn set_continue_on_failure(&mut self, enabled: bool) -> &mut Self {
+        self.continue_on_failure = enabled;
+        self
+    }
+
+    /// Retrieves the number of continuations that led to this request, `0`
+    /// unless it was returned by one.
+    pub fn get_chain_depth(&self) -> u32 {
+        self.chain_depth
+    }
+
+    /// Sets the hook called with the final result of the request.
+    ///
+    /// The hook is called once the request succeeded or failed for good,
+    /// after its retries and fallbacks, by every execution, replays included;
+    /// attempts that are retried or lose to a hedge do not call it, nor do
+    /// the repetitions and next pages of the request. The result includes the
+    /// whole body, which is read into memory before the response is handed
+    /// on, up to the maximum body size as for `set_continuation`. A panicking
+    /// hook is ignored and does not affect the execution.
+    ///
+    /// #### Arguments
+    ///
+    /// * `hook` - The hook called with the final result.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    ///
+    /// let mut request = Request::get("http://example.com/rows/123");
+    /// request.set_on_complete(|result| println!("row 123: {:?}", result.status));
+    /// assert!(request.get_on_complete().is_some());
+    /// ```
+    pub fn set_on_complete<F>(&mut self, hook: F) -> &mut Self
+    where
+        F: Fn(&RollingResult) + Send + Sync + 'static,
+    {
+        self.on_complete = Some(Arc::new(hook));
+        self
+    }
+
+    /// Retrieves the hook called with the final result, if set.
+    pub fn get_on_complete(&self) -> Option<&CompletionHook> {
+        self.on_complete.as_ref()
+    }
+
+    /// Returns true if the hooks of the request need the body of a response
+    /// with the given success, so it should be read into memory.
+    pub(crate) fn buffers_body(&self, success: bool) -> bool {
+        self.on_complete.is_some()
+            || (self.continuation.is_some() && (success || self.continue_on_failure))
+    }
+
+    /// Returns true if the request has a hook taking its final result.
+    pub(crate) fn has_hooks(&self) -> bool {
+        self.on_complete.is_some() || self.continuation.is_some()
+    }
+
+    /// Calls the completion hook with `result`, ignoring a panic.
+    pub(crate) fn complete(&self, result: &RollingResult) {
+        if let Some(hook) = &self.on_complete {
+            let _ = catch_unwind(AssertUnwindSafe(|| hook(result)));
+        }
+    }
+
+    /// Calls the continuation with `result`, returning the follow-up requests
+    /// to queue, or none when the request has no continuation, the result is
+    /// a failure it skips, or the follow-ups would be deeper than `max_depth`.
+    pub(crate) fn follow_ups(
+        &self,
+        result: &RollingResult,
+        max_depth: Option<u32>,
+    ) -> Vec<Request> {
+        let Some(continuation) = &self.continuation else {
+            return Vec::new();
+        };
+        let depth = self.chain_depth + 1;
+        if max_depth.is_some_and(|max_depth| depth > max_depth)
+            || !(result.is_success() || self.continue_on_failure)
+        {
+            return Vec::new();
+        }
+        // A panicking continuation has no follow-ups
+        let mut follow_ups =
+            catch_unwind(AssertUnwindSafe(|| continuation(result))).unwrap_or_default();
+        for request in &mut follow_ups {
+            request.chain_depth = depth;
+            if request.tag.is_none() {
+                request.tag = self.tag.clone();
+            }
+            if request.priority == 0 {
+                request.priority = self.priority;
+            }
+        }
+        follow_ups
+    }
+}
@@ -0,0 +1,136 @@
+use crate::request::form::{file_body, file_size};
+use crate::request::{BuildError, Request};
+use reqwest::Client;
+use reqwest::header::{AUTHORIZATION, CONTENT_LENGTH, COOKIE, HeaderValue};
+use url::Url;
+
+impl Request {
+    /// Converts the request into a `reqwest::Request` built with `client`.
+    ///
+    /// Options are applied, path parameters substituted and query parameters
+    /// appended as when the request is executed. The proxy, redirect and retry
+    /// settings, fallback URLs and the response fields have no `reqwest`
+    /// equivalent and are dropped.
+    ///
+    /// #### Arguments
+    ///
+    /// * `client` - The client whose defaults the request is built with.
+    ///
+    /// #### Errors
+    ///
+    /// Returns an error if an option is invalid, if a URL placeholder has no
+    /// value, if the URL cannot be parsed, if a header is invalid, or if the
+    /// client rejects the request.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    ///
+    /// let mut request = Request::post("http://example.com/items/{id}", Some("a=1"));
+    /// request.set_path_params(&[("id", "7")]).add_header("X-Trace", "abc");
+    ///
+    /// let converted = request.into_reqwest(&reqwest::Client::new()).unwrap();
+    /// assert_eq!(converted.url().as_str(), "http://example.com/items/7");
+    /// assert_eq!(converted.headers()["x-trace"], "abc");
+    /// ```
+    pub fn into_reqwest(mut self, client: &Client) -> Result<reqwest::Request, BuildError> {
+        self.apply_options().map_err(BuildError::InvalidOption)?;
+        let url = self
+            .expand_url(&self.url)
+            .map_err(BuildError::MissingPathParam)?;
+        if let Err(source) = Url::parse(&url) {
+            return Err(BuildError::InvalidUrl { url, source });
+        }
+        if let Some(header) = self.header_errors.first() {
+            return Err(BuildError::InvalidHeader(header.clone()));
+        }
+
+        let body_file_length = match (&self.multipart_form_data, &self.body_file) {
+            (None, Some(path)) => file_size(path),
+            _ => None,
+        };
+        let mut request = self;
+        request.url = url;
+        request
+            .reqwest_builder(client, None, body_file_length)
+            .build()
+            .map_err(BuildError::Client)
+    }
+
+    /// Builds the `reqwest` request for one attempt.
+    ///
+    /// Multipart form data is built anew, so the request can be sent again.
+    /// When given, `authorization` replaces the `Authorization` header, and
+    /// `body_file_length` is announced as the length of a file body.
+    pub(crate) fn reqwest_builder(
+        &self,
+        client: &Client,
+        authorization: Option<&str>,
+        body_file_length: Option<u64>,
+    ) -> reqwest::RequestBuilder {
+        let mut req_builder = client.request(self.method.clone(), &self.url);
+        if !self.query_params.is_empty() {
+            req_builder = req_builder.query(&self.query_params);
+        }
+        if let Some(timeout) = self.timeout {
+            req_builder = req_builder.timeout(timeout);
+        }
+        if let Some(version) = self.http_version {
+            req_builder = req_builder.version(version);
+        }
+
+        let mut header_map = self.headers.clone();
+        if let Some(Ok(value)) = authorization.map(HeaderValue::from_str) {
+            header_map.insert(AUTHORIZATION, value);
+        }
+        if !self.cookies.is_empty() {
+            if let Some(value) = self.cookie_header(header_map.get(COOKIE)) {
+                header_map.insert(COOKIE, value);
+            }
+        }
+        if !header_map.is_empty() {
+            req_builder = req_builder.headers(header_map);
+        }
+
+        if let Some(form) = &self.multipart_form_data {
+            req_builder = req_builder.multipart(form.build(self.max_body_size));
+        } else if let Some(path) = &self.body_file {
+            // An unreadable file fails the request through its body stream
+            if let Some(length) = body_file_length {
+                req_builder = req_builder.header(CONTENT_LENGTH, length);
+            }
+            req_builder = req_builder.body(file_body(path, self.max_body_size));
+        } else if let Some(data) = &self.post_data {
+            req_builder = req_builder.body(data.clone());
+        }
+        req_builder
+    }
+}
+
+impl TryFrom<http::Request<Vec<u8>>> for Request {
+    type Error = BuildError;
+
+    /// Converts an `http::Request`, keeping its method, URI, headers and body.
+    ///
+    /// An empty body is treated as no body. Extensions and the HTTP version
+    /// are dropped.
+    ///
+    /// #### Errors
+    ///
+    /// Returns an error if the URI is not an absolute URL.
+    fn try_from(request: http::Request<Vec<u8>>) -> Result<Self, Self::Error> {
+        let (parts, body) = request.into_parts();
+        let url = parts.uri.to_string();
+        if let Err(source) = Url::parse(&url) {
+            return Err(BuildError::InvalidUrl { url, source });
+        }
+
+        let mut converted = Request::new(&url, parts.method);
+        converted.headers = parts.headers;
+        if !body.is_empty() {
+            converted.post_data = Some(body);
+        }
+        Ok(converted)
+    }
+}
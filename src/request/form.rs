@@ -0,0 +1,239 @@
+use crate::error::BodyTooLarge;
+use futures_util::future;
+use futures_util::{TryFutureExt, TryStreamExt};
+use reqwest::Body;
+use reqwest::multipart::{Form, Part};
+use std::io;
+use std::path::{Path, PathBuf};
+use tokio_util::io::ReaderStream;
+
+/// A multipart form kept as a description of its parts.
+///
+/// The `reqwest` form is built again for every attempt, so the form survives
+/// clones of the request, retries and repeated executions.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct MultipartForm {
+    /// The parts of the form, in the order they are sent.
+    parts: Vec<FormPart>,
+}
+
+/// A single part of a `MultipartForm`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum FormPart {
+    /// A text field.
+    Text {
+        /// The name of the form field.
+        name: String,
+        /// The value of the field.
+        value: String,
+    },
+    /// Content held in memory, optionally announced as a file.
+    Bytes {
+        /// The name of the form field.
+        name: String,
+        /// The content of the part.
+        data: Vec<u8>,
+        /// The file name announced in the part headers.
+        file_name: Option<String>,
+        /// The content type of the part; invalid values are ignored when sending.
+        mime: Option<String>,
+    },
+    /// A file read from disk while the request is sent.
+    File(StreamedFile),
+}
+
+/// A file sent as a multipart part, read from disk while the request is sent.
+///
+/// Only the path is kept until then, so the file is never loaded into memory
+/// as a whole and the part can be sent again by clones and retries.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct StreamedFile {
+    /// The name of the form field.
+    pub name: String,
+    /// The path of the file to send.
+    pub path: PathBuf,
+    /// The file name announced in the part headers.
+    pub file_name: String,
+    /// The length of the file, if known; without it the request is chunked.
+    pub content_length: Option<u64>,
+    /// The content type of the part; invalid values are ignored when sending.
+    pub mime: Option<String>,
+}
+
+impl MultipartForm {
+    /// Creates an empty form.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a part to the form.
+    ///
+    /// #### Arguments
+    ///
+    /// * `part` - The part to append.
+    pub fn push(&mut self, part: FormPart) -> &mut Self {
+        self.parts.push(part);
+        self
+    }
+
+    /// Returns the parts of the form, in the order they are sent.
+    pub fn parts(&self) -> &[FormPart] {
+        &self.parts
+    }
+
+    /// Returns whether the form has no parts.
+    pub fn is_empty(&self) -> bool {
+        self.parts.is_empty()
+    }
+
+    /// Builds the `reqwest` form for one attempt, failing streamed files that
+    /// grow past `limit` bytes.
+    pub(crate) fn build(&self, limit: Option<u64>) -> Form {
+        self.parts
+            .iter()
+            .fold(Form::new(), |form, part| match part {
+                FormPart::Text { name, value } => form.text(name.clone(), value.clone()),
+                FormPart::Bytes {
+                    name,
+                    data,
+                    file_name,
+                    mime,
+                } => {
+                    let mut bytes = Part::bytes(data.clone());
+                    if let Some(file_name) = file_name {
+                        bytes = bytes.file_name(file_name.clone());
+                    }
+                    form.part(name.clone(), with_mime(bytes, mime.as_deref()))
+                }
+                FormPart::File(file) => form.part(file.name.clone(), file.part(limit)),
+            })
+    }
+}
+
+impl StreamedFile {
+    /// Builds the multipart part, opening the file when the body is first polled.
+    ///
+    /// Failures to open or read the file, or reading more than `limit` bytes,
+    /// surface as body errors of the request.
+    pub(crate) fn part(&self, limit: Option<u64>) -> Part {
+        let body = file_body(&self.path, limit);
+        let part = match self.content_length {
+            Some(length) => Part::stream_with_length(body, length),
+            None => Part::stream(body),
+        };
+        with_mime(part.file_name(self.file_name.clone()), self.mime.as_deref())
+    }
+}
+
+/// Streams the file at `path`, opening it when the body is first polled.
+///
+/// Failures to open or read the file surface as body errors of the request,
+/// as does reading more than `limit` bytes from a file that grew.
+pub(crate) fn file_body(path: &Path, limit: Option<u64>) -> Body {
+    let mut read = 0u64;
+    let stream = tokio::fs::File::open(path.to_path_buf())
+        .map_ok(ReaderStream::new)
+        .try_flatten_stream()
+        .and_then(move |chunk| {
+            read += chunk.len() as u64;
+            let exceeded = limit.filter(|limit| read > *limit).map(|limit| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    BodyTooLarge { size: read, limit },
+                )
+            });
+            future::ready(exceeded.map_or(Ok(chunk), Err))
+        });
+    Body::wrap_stream(stream)
+}
+
+/// Returns the size of the file at `path`, if it can be read.
+pub(crate) fn file_size(path: &Path) -> Option<u64> {
+    std::fs::metadata(path).ok().map(|metadata| metadata.len())
+}
+
+/// Checks that `mime` is a valid content type for a part.
+pub(crate) fn validate_mime(mime: &str) -> io::Result<()> {
+    Part::bytes(Vec::new())
+        .mime_str(mime)
+        .map(|_| ())
+        .map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{:?} is not a valid MIME type", mime),
+            )
+        })
+}
+
+/// Content types inferred from file extensions, compared case-insensitively.
+const MIME_TYPES: [(&str, &str); 40] = [
+    ("7z", "application/x-7z-compressed"),
+    ("avif", "image/avif"),
+    ("bmp", "image/bmp"),
+    ("css", "text/css"),
+    ("csv", "text/csv"),
+    ("doc", "application/msword"),
+    (
+        "docx",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    ),
+    ("gif", "image/gif"),
+    ("gz", "application/gzip"),
+    ("htm", "text/html"),
+    ("html", "text/html"),
+    ("ico", "image/vnd.microsoft.icon"),
+    ("jpeg", "image/jpeg"),
+    ("jpg", "image/jpeg"),
+    ("js", "text/javascript"),
+    ("json", "application/json"),
+    ("md", "text/markdown"),
+    ("mov", "video/quicktime"),
+    ("mp3", "audio/mpeg"),
+    ("mp4", "video/mp4"),
+    ("ogg", "audio/ogg"),
+    ("pdf", "application/pdf"),
+    ("png", "image/png"),
+    ("svg", "image/svg+xml"),
+    ("tar", "application/x-tar"),
+    ("tif", "image/tiff"),
+    ("tiff", "image/tiff"),
+    ("toml", "application/toml"),
+    ("tsv", "text/tab-separated-values"),
+    ("txt", "text/plain"),
+    ("wasm", "application/wasm"),
+    ("wav", "audio/wav"),
+    ("webm", "video/webm"),
+    ("webp", "image/webp"),
+    ("xls", "application/vnd.ms-excel"),
+    (
+        "xlsx",
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    ),
+    ("xml", "application/xml"),
+    ("yaml", "application/yaml"),
+    ("yml", "application/yaml"),
+    ("zip", "application/zip"),
+];
+
+/// Infers the content type of a file from the extension of `file_name`.
+///
+/// Unknown extensions and names without one yield `application/octet-stream`.
+pub(crate) fn guess_mime(file_name: &str) -> &'static str {
+    Path::new(file_name)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .and_then(|extension| {
+            MIME_TYPES
+                .iter()
+                .find(|(known, _)| known.eq_ignore_ascii_case(extension))
+        })
+        .map_or("application/octet-stream", |(_, mime)| mime)
+}
+
+/// Sets the content type of `part`, leaving it unset when `mime` is invalid.
+fn with_mime(part: Part, mime: Option<&str>) -> Part {
+    match mime {
+        Some(mime) if validate_mime(mime).is_ok() => part.mime_str(mime).unwrap(),
+        _ => part,
+    }
+}
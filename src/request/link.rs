@@ -0,0 +1,127 @@
+use crate::request::Request;
+use reqwest::Url;
+use reqwest::header::{AUTHORIZATION, COOKIE, HeaderMap, LINK};
+
+/// Returns the target of the `rel="next"` link of a `Link` header, as given.
+///
+/// Every `Link` header is searched, each holding comma-separated entries
+/// such as `<https://api.example.com/items?page=2>; rel="next"`.
+pub(crate) fn next_link(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get_all(LINK)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .find_map(|value| {
+            let mut rest = value;
+            loop {
+                let start = rest.find('<')?;
+                let end = start + rest[start..].find('>')?;
+                let target = &rest[start + 1..end];
+                rest = &rest[end + 1..];
+                let params_end = rest.find('<').unwrap_or(rest.len());
+                if rest[..params_end].split(';').any(is_next_rel) {
+                    return Some(target.trim());
+                }
+                rest = &rest[params_end..];
+            }
+        })
+}
+
+/// Returns true if `param` is a `rel` parameter listing the `next` relation.
+fn is_next_rel(param: &str) -> bool {
+    let Some((name, value)) = param.split_once('=') else {
+        return false;
+    };
+    let value = value
+        .trim()
+        .trim_end_matches(',')
+        .trim_end()
+        .trim_matches('"');
+    name.trim().eq_ignore_ascii_case("rel")
+        && value
+            .split_whitespace()
+            .any(|rel| rel.eq_ignore_ascii_case("next"))
+}
+
+impl Request {
+    /// Follows `rel="next"` links of successful responses, up to `max_pages`
+    /// pages including this one.
+    ///
+    /// When a response to the request carries a `Link` header with a `next`
+    /// link, a copy of the request for that URL is queued, keeping its
+    /// headers, credentials, extra information and metadata, with its page
+    /// counter one higher and its query parameters and fallback URLs cleared.
+    /// Each page is executed and reported as a request of its own, with a
+    /// new identifier, by every execution taking requests from the queue;
+    /// `execute_all` and its variants fetch every page before they return.
+    ///
+    /// #### Arguments
+    ///
+    /// * `max_pages` - The maximum number of pages fetched; `1` follows none.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    ///
+    /// let mut request = Request::get("http://example.com/items");
+    /// request.set_max_pages(10);
+    /// assert_eq!(request.get_max_pages(), Some(10));
+    /// assert_eq!(request.get_page(), 1);
+    /// ```
+    pub fn set_max_pages(&mut self, max_pages: u32) -> &mut Self {
+        self.max_pages = Some(max_pages);
+        self
+    }
+
+    /// Retrieves the maximum number of pages followed, if pagination is enabled.
+    pub fn get_max_pages(&self) -> Option<u32> {
+        self.max_pages
+    }
+
+    /// Retrieves the page counter, `1` unless the request follows a `next` link.
+    pub fn get_page(&self) -> u32 {
+        self.page
+    }
+
+    /// Returns the request for the page after this one, if the response at
+    /// `url` with `headers` links to one and the page limit allows it.
+    ///
+    /// A page on another origin is requested without the `Authorization`
+    /// header, the cookies and the credentials of the instance, like a
+    /// redirect to another origin.
+    pub(crate) fn next_page(&self, url: &Url, headers: &HeaderMap) -> Option<Request> {
+        if self
+            .max_pages
+            .is_none_or(|max_pages| self.page >= max_pages)
+        {
+            return None;
+        }
+        let next = url.join(next_link(headers)?).ok()?;
+        let mut request = self.clone();
+        if next.origin() != url.origin() {
+            request
+                .remove_header(AUTHORIZATION.as_str())
+                .remove_header(COOKIE.as_str());
+            request.cookies.clear();
+            request.cross_origin = true;
+        }
+        request.url = next.to_string();
+        request.query_params.clear();
+        request.fallback_urls.clear();
+        request.page += 1;
+        // The hooks run once, for the first page
+        request.on_complete = None;
+        request.continuation = None;
+        request.attempts = 0;
+        request.head_fallback = false;
+        request.cached = false;
+        request.not_modified = false;
+        request.buffered_body = None;
+        request.response_text = None;
+        request.response_info = None;
+        request.response_error = None;
+        request.response_errno = None;
+        Some(request)
+    }
+}
@@ -0,0 +1,53 @@
+use std::any::Any;
+use std::fmt;
+use std::sync::Arc;
+
+/// Typed data attached to a request with `Request::set_metadata`.
+///
+/// The value is shared, not copied, by clones of the request and its retries.
+/// It is stored in the extensions of the responses returned by
+/// `RollingRequests` and kept by the errors of failed requests.
+///
+/// #### Examples
+///
+/// ```no_run
+/// use rollingrequests::request::{Metadata, Request};
+/// use rollingrequests::rolling::RollingRequestsBuilder;
+///
+/// struct Record {
+///     id: u64,
+/// }
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let mut rolling_requests = RollingRequestsBuilder::new().build();
+///     let mut request = Request::get("http://example.com/records/7");
+///     request.set_metadata(Record { id: 7 });
+///     rolling_requests.add_request(request);
+///
+///     for response in rolling_requests.execute_requests().await.into_iter().flatten() {
+///         let metadata = response.extensions().get::<Metadata>().unwrap();
+///         println!("record {} fetched", metadata.get::<Record>().unwrap().id);
+///     }
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Metadata(Arc<dyn Any + Send + Sync>);
+
+impl Metadata {
+    /// Wraps `value`.
+    pub(crate) fn new<T: Any + Send + Sync>(value: T) -> Self {
+        Metadata(Arc::new(value))
+    }
+
+    /// Returns the value if it is a `T`.
+    pub fn get<T: Any>(&self) -> Option<&T> {
+        self.0.downcast_ref()
+    }
+}
+
+impl fmt::Debug for Metadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<metadata>")
+    }
+}
@@ -2,8 +2,33 @@
 //!
 //! This module provides the `Request` struct, which allows you to define HTTP requests
 //! with various parameters such as URL, method, headers, and body data. It also provides
-//! methods to set and retrieve additional information related to the request and response.
+//! methods to set and retrieve additional information related to the request and response,
+//! the `RequestBuilder` for building a request in a single expression, the
+//! `MultipartForm` type describing multipart form data, the `Metadata`
+//! attached to requests, and the `ContentRange` of partial responses. Requests
+//! can follow the `rel="next"` links of paginated responses, queue the
+//! follow-up requests returned by a `Continuation`, and report their final
+//! result to a `CompletionHook`. A `RequestSummary` describes a queued request
+//! without its body and headers.
 
+mod builder;
+mod chain;
+mod convert;
+mod form;
+mod link;
+mod metadata;
+mod range;
+#[allow(clippy::module_inception)]
 mod request;
+#[cfg(feature = "serde")]
+mod serialize;
+mod summary;
 
-pub use request::Request;
+pub use builder::{BuildError, RequestBuilder};
+pub use chain::{CompletionHook, Continuation};
+pub use form::{FormPart, MultipartForm, StreamedFile};
+pub use metadata::Metadata;
+pub use range::ContentRange;
+pub(crate) use request::redact_url;
+pub use request::{InvalidHeader, InvalidMethod, InvalidOption, Request, RequestId};
+pub use summary::RequestSummary;
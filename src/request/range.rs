@@ -0,0 +1,148 @@
+use crate::request::Request;
+use reqwest::StatusCode;
+use reqwest::header::{CONTENT_RANGE, RANGE};
+
+/// The byte range of a partial response, parsed from its `Content-Range` header.
+///
+/// #### Examples
+///
+/// ```no_run
+/// use rollingrequests::request::{ContentRange, Request};
+/// use rollingrequests::rolling::RollingRequestsBuilder;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let mut rolling_requests = RollingRequestsBuilder::new().build();
+///     let mut request = Request::get("http://example.com/archive.zip");
+///     request.set_range(0, Some(99));
+///     rolling_requests.add_request(request);
+///
+///     for response in rolling_requests.execute_requests().await.into_iter().flatten() {
+///         match ContentRange::from_response(&response) {
+///             Some(range) => println!("bytes {}-{} of {:?}", range.start, range.end, range.total),
+///             None => println!("full body returned"),
+///         }
+///     }
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ContentRange {
+    /// The offset of the first byte returned.
+    pub start: u64,
+    /// The offset of the last byte returned, inclusive.
+    pub end: u64,
+    /// The length of the full representation, if the server knows it.
+    pub total: Option<u64>,
+}
+
+impl ContentRange {
+    /// Returns the range of a `206 Partial Content` response.
+    ///
+    /// Returns `None` for other statuses and when the `Content-Range` header
+    /// is missing or cannot be parsed.
+    ///
+    /// #### Arguments
+    ///
+    /// * `response` - The response to inspect.
+    pub fn from_response(response: &reqwest::Response) -> Option<Self> {
+        if response.status() != StatusCode::PARTIAL_CONTENT {
+            return None;
+        }
+        ContentRange::parse(response.headers().get(CONTENT_RANGE)?.to_str().ok()?)
+    }
+
+    /// Parses a `Content-Range` value such as `bytes 0-99/1234` or `bytes 0-99/*`.
+    ///
+    /// Returns `None` for other units, unsatisfied ranges (`bytes */1234`) and
+    /// ranges whose end precedes their start or exceeds the total.
+    ///
+    /// #### Arguments
+    ///
+    /// * `value` - The header value.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::ContentRange;
+    ///
+    /// let range = ContentRange::parse("bytes 0-99/1234").unwrap();
+    /// assert_eq!((range.start, range.end, range.total), (0, 99, Some(1234)));
+    /// assert_eq!(range.len(), 100);
+    /// ```
+    pub fn parse(value: &str) -> Option<Self> {
+        let (unit, rest) = value.trim().split_once(' ')?;
+        if !unit.eq_ignore_ascii_case("bytes") {
+            return None;
+        }
+        let (range, total) = rest.trim().split_once('/')?;
+        let (start, end) = range.split_once('-')?;
+        let start: u64 = start.trim().parse().ok()?;
+        let end: u64 = end.trim().parse().ok()?;
+        let total = match total.trim() {
+            "*" => None,
+            total => Some(total.parse::<u64>().ok()?),
+        };
+        if end < start || total.is_some_and(|total| end >= total) {
+            return None;
+        }
+        Some(ContentRange { start, end, total })
+    }
+
+    /// Returns the number of bytes in the range.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> u64 {
+        self.end - self.start + 1
+    }
+}
+
+impl Request {
+    /// Requests the bytes from `start` to `end`, inclusive, with a `Range` header.
+    ///
+    /// Replaces any range set before. Without `end`, the bytes from `start` to
+    /// the end of the representation are requested.
+    ///
+    /// #### Arguments
+    ///
+    /// * `start` - The offset of the first byte.
+    /// * `end` - The offset of the last byte, inclusive.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    ///
+    /// let mut request = Request::get("http://example.com/archive.zip");
+    /// request.set_range(0, Some(99));
+    /// assert_eq!(request.get_headers()["range"], "bytes=0-99");
+    /// request.set_range(100, None);
+    /// assert_eq!(request.get_headers()["range"], "bytes=100-");
+    /// ```
+    pub fn set_range(&mut self, start: u64, end: Option<u64>) -> &mut Self {
+        let value = match end {
+            Some(end) => format!("bytes={}-{}", start, end),
+            None => format!("bytes={}-", start),
+        };
+        self.add_header(RANGE.as_str(), &value)
+    }
+
+    /// Requests the last `len` bytes with a `Range` header.
+    ///
+    /// Replaces any range set before.
+    ///
+    /// #### Arguments
+    ///
+    /// * `len` - The number of bytes to request from the end.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    ///
+    /// let mut request = Request::get("http://example.com/server.log");
+    /// request.set_range_suffix(500);
+    /// assert_eq!(request.get_headers()["range"], "bytes=-500");
+    /// ```
+    pub fn set_range_suffix(&mut self, len: u64) -> &mut Self {
+        self.add_header(RANGE.as_str(), &format!("bytes=-{}", len))
+    }
+}
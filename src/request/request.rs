@@ -1,59 +1,554 @@
-use reqwest::Method;
-use reqwest::multipart::{Form, Part};
+use crate::download::{Checksum, ChecksumAlgorithm, Unsatisfied};
+use crate::error::{BodyTooLarge, RedirectError, RollingError};
+use crate::request::form::{file_size, guess_mime, validate_mime};
+use crate::request::{
+    CompletionHook, Continuation, FormPart, Metadata, MultipartForm, StreamedFile,
+};
+use crate::result::RedirectHop;
+use crate::retry::RetryPolicy;
+use crate::sink::{BodySink, Event, EventHandler, EventSink, LineHandler, LineSink, StreamSink};
+use bytes::Bytes;
+use reqwest::header::{
+    ACCEPT, ACCEPT_ENCODING, CACHE_CONTROL, COOKIE, HeaderMap, HeaderName, HeaderValue,
+};
+use reqwest::{Method, Url, Version};
+use serde::Serialize;
+use std::any::Any;
 use std::collections::HashMap;
+use std::fmt;
 use std::fs;
-use std::path::Path;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 impl Clone for Request {
     /// Creates a clone of the `Request` instance.
-    ///
-    /// Note: The `multipart_form_data` field is not cloned.
     fn clone(&self) -> Self {
         Request {
             url: self.url.clone(),
             method: self.method.clone(),
             post_data: self.post_data.clone(),
+            body_file: self.body_file.clone(),
+            output_file: self.output_file.clone(),
+            resume: self.resume,
+            expected_checksum: self.expected_checksum.clone(),
+            body_sink: self.body_sink.clone(),
+            line_handler: self.line_handler.clone(),
+            event_handler: self.event_handler.clone(),
+            max_line_length: self.max_line_length,
             headers: self.headers.clone(),
+            header_errors: self.header_errors.clone(),
+            cookies: self.cookies.clone(),
             options: self.options.clone(),
             extra_info: self.extra_info.clone(),
+            tag: self.tag.clone(),
+            priority: self.priority,
             response_text: self.response_text.clone(),
             response_info: self.response_info.clone(),
             response_error: self.response_error.clone(),
             response_errno: self.response_errno,
-            multipart_form_data: None, // Multipart data is not cloned
+            attempts: self.attempts,
+            repeat: self.repeat,
+            retry_policy: self.retry_policy.clone(),
+            fallback_urls: self.fallback_urls.clone(),
+            query_params: self.query_params.clone(),
+            path_params: self.path_params.clone(),
+            error_for_status: self.error_for_status,
+            max_attempts: self.max_attempts,
+            timeout: self.timeout,
+            max_body_size: self.max_body_size,
+            follow_redirects: self.follow_redirects,
+            proxy: self.proxy.clone(),
+            decompress: self.decompress,
+            http_version: self.http_version,
+            multipart_form_data: self.multipart_form_data.clone(),
+            metadata: self.metadata.clone(),
+            continuation: self.continuation.clone(),
+            continue_on_failure: self.continue_on_failure,
+            on_complete: self.on_complete.clone(),
+            chain_depth: self.chain_depth,
+            max_pages: self.max_pages,
+            page: self.page,
+            record_redirects: self.record_redirects,
+            redirects: self.redirects.clone(),
+            redirect_error: self.redirect_error.clone(),
+            body_snippet: self.body_snippet.clone(),
+            buffered_body: self.buffered_body.clone(),
+            id: self.id,
+            queued_at: self.queued_at,
+            head_fallback: self.head_fallback,
+            cached: self.cached,
+            not_modified: self.not_modified,
+            pooled_proxy: self.pooled_proxy.clone(),
+            cross_origin: self.cross_origin,
+            downloads: self.downloads,
+            checks_link: self.checks_link,
+            unsatisfied: self.unsatisfied,
+        }
+    }
+}
+
+impl PartialEq for Request {
+    /// Compares what is sent: the method, the URL, the body, the headers and
+    /// cookies and the query and path parameters.
+    ///
+    /// Two requests are equal when sending them would produce the same HTTP
+    /// request. Header names are compared case-insensitively and values in the
+    /// order they were added under each name. Everything else is ignored,
+    /// including `extra_info`, options, the response fields, fallback URLs and
+    /// settings such as retries and timeouts.
+    fn eq(&self, other: &Self) -> bool {
+        self.method == other.method
+            && self.url == other.url
+            && self.post_data == other.post_data
+            && self.body_file == other.body_file
+            && self.multipart_form_data == other.multipart_form_data
+            && self.headers == other.headers
+            && self.cookies == other.cookies
+            && self.query_params == other.query_params
+            && self.path_params == other.path_params
+    }
+}
+
+impl Eq for Request {}
+
+impl Hash for Request {
+    /// Hashes the fields compared by `eq`.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.method.hash(state);
+        self.url.hash(state);
+        self.post_data.hash(state);
+        self.body_file.hash(state);
+        self.multipart_form_data.hash(state);
+        // Header maps compare equal regardless of the order of their names
+        let mut names: Vec<_> = self.headers.keys().collect();
+        names.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        names.len().hash(state);
+        for name in names {
+            name.hash(state);
+            for value in self.headers.get_all(name) {
+                value.hash(state);
+            }
+        }
+        self.cookies.hash(state);
+        self.query_params.hash(state);
+        self.path_params.hash(state);
+    }
+}
+
+/// A header that could not be set because its name or value is invalid.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidHeader {
+    /// The header name as given.
+    pub name: String,
+    /// The header value as given.
+    pub value: String,
+}
+
+impl fmt::Display for InvalidHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid header {:?}: {:?}", self.name, self.value)
+    }
+}
+
+impl std::error::Error for InvalidHeader {}
+
+/// The identifier of the request a response belongs to, stored in the
+/// extensions of the responses returned by `RollingRequests`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RequestId(pub u64);
+
+/// An option whose value cannot be applied.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidOption {
+    /// The option name.
+    pub key: String,
+    /// The value as given.
+    pub value: String,
+}
+
+impl fmt::Display for InvalidOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid value for option {:?}: {:?}",
+            self.key, self.value
+        )
+    }
+}
+
+impl std::error::Error for InvalidOption {}
+
+/// A method name that is not a valid HTTP token.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidMethod {
+    /// The method name as given.
+    pub method: String,
+}
+
+impl fmt::Display for InvalidMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid method {:?}", self.method)
+    }
+}
+
+impl std::error::Error for InvalidMethod {}
+
+/// Replaces sensitive values in the `Debug` and `Display` output of a `Request`.
+const REDACTED: &str = "***";
+
+/// Parts of header, option and query parameter names that mark their values as secret.
+const SENSITIVE_NAMES: [&str; 9] = [
+    "auth",
+    "cookie",
+    "token",
+    "secret",
+    "password",
+    "passwd",
+    "key",
+    "session",
+    "signature",
+];
+
+impl fmt::Debug for Request {
+    /// Formats every field, with secrets redacted.
+    ///
+    /// Values of headers, options and query parameters whose names look
+    /// sensitive (`Authorization`, `Cookie`, anything mentioning a token, a
+    /// secret, a key or a password, ...) and passwords in URLs are replaced with
+    /// `***`, and bodies are shown by their length only.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let headers: Vec<_> = self
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                let value = match value.to_str() {
+                    Ok(value) => redact(name.as_str(), value),
+                    Err(_) => "<binary>",
+                };
+                (name.as_str(), value)
+            })
+            .collect();
+        let header_errors: Vec<_> = self
+            .header_errors
+            .iter()
+            .map(|header| (header.name.as_str(), redact(&header.name, &header.value)))
+            .collect();
+        let cookies: Vec<_> = self
+            .cookies
+            .iter()
+            .map(|(name, _)| (name.as_str(), REDACTED))
+            .collect();
+        let mut options: Vec<_> = self
+            .options
+            .iter()
+            .map(|(key, value)| (key.as_str(), redact(key, value)))
+            .collect();
+        options.sort();
+        let query_params: Vec<_> = self
+            .query_params
+            .iter()
+            .map(|(key, value)| (key.as_str(), redact(key, value)))
+            .collect();
+        let fallback_urls: Vec<_> = self
+            .fallback_urls
+            .iter()
+            .map(|url| redact_url(url))
+            .collect();
+
+        f.debug_struct("Request")
+            .field("id", &self.id)
+            .field("url", &redact_url(&self.url))
+            .field("method", &self.method)
+            .field(
+                "post_data",
+                &self.post_data.as_ref().map(|data| ByteCount(data.len())),
+            )
+            .field("body_file", &self.body_file)
+            .field("output_file", &self.output_file)
+            .field("resume", &self.resume)
+            .field("expected_checksum", &self.expected_checksum)
+            .field("body_sink", &self.body_sink.as_ref().map(|_| "BodySink"))
+            .field(
+                "line_handler",
+                &self.line_handler.as_ref().map(|_| "LineHandler"),
+            )
+            .field(
+                "event_handler",
+                &self.event_handler.as_ref().map(|_| "EventHandler"),
+            )
+            .field("max_line_length", &self.max_line_length)
+            .field("headers", &headers)
+            .field("header_errors", &header_errors)
+            .field("cookies", &cookies)
+            .field("options", &options)
+            .field("extra_info", &self.extra_info)
+            .field("tag", &self.tag)
+            .field("priority", &self.priority)
+            .field(
+                "response_text",
+                &self
+                    .response_text
+                    .as_ref()
+                    .map(|text| ByteCount(text.len())),
+            )
+            .field("response_info", &self.response_info)
+            .field("response_error", &self.response_error)
+            .field("response_errno", &self.response_errno)
+            .field("attempts", &self.attempts)
+            .field("repeat", &self.repeat)
+            .field("retry_policy", &self.retry_policy)
+            .field("fallback_urls", &fallback_urls)
+            .field("query_params", &query_params)
+            .field("path_params", &self.path_params)
+            .field("error_for_status", &self.error_for_status)
+            .field("max_attempts", &self.max_attempts)
+            .field("timeout", &self.timeout)
+            .field("max_body_size", &self.max_body_size)
+            .field("follow_redirects", &self.follow_redirects)
+            .field("proxy", &self.proxy.as_deref().map(redact_url))
+            .field("decompress", &self.decompress)
+            .field("http_version", &self.http_version)
+            .field(
+                "multipart_form_data",
+                &self
+                    .multipart_form_data
+                    .as_ref()
+                    .map(|form| PartCount(form.parts().len())),
+            )
+            .field("metadata", &self.metadata)
+            .field(
+                "continuation",
+                &self.continuation.as_ref().map(|_| "Continuation"),
+            )
+            .field("continue_on_failure", &self.continue_on_failure)
+            .field(
+                "on_complete",
+                &self.on_complete.as_ref().map(|_| "CompletionHook"),
+            )
+            .field("chain_depth", &self.chain_depth)
+            .field("max_pages", &self.max_pages)
+            .field("page", &self.page)
+            .field("record_redirects", &self.record_redirects)
+            .field("redirects", &self.redirects)
+            .finish()
+    }
+}
+
+impl fmt::Display for Request {
+    /// Formats the method and URL, followed by the ID and the extra information
+    /// if any, e.g. `GET https://example.com/path (id=3, extra_info=abc)`.
+    ///
+    /// Passwords and sensitive query parameters in the URL are redacted.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.method, redact_url(&self.url))?;
+        match (self.id, &self.extra_info) {
+            (Some(id), Some(extra_info)) => write!(f, " (id={}, extra_info={})", id, extra_info),
+            (Some(id), None) => write!(f, " (id={})", id),
+            (None, Some(extra_info)) => write!(f, " (extra_info={})", extra_info),
+            (None, None) => Ok(()),
         }
     }
 }
 
+/// Shows the length of a body in `Debug` output instead of its content.
+struct ByteCount(usize);
+
+impl fmt::Debug for ByteCount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<{} bytes>", self.0)
+    }
+}
+
+/// Shows the number of parts of a form in `Debug` output instead of their content.
+struct PartCount(usize);
+
+impl fmt::Debug for PartCount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<{} parts>", self.0)
+    }
+}
+
+/// Returns whether values named `name` should be kept out of logs.
+fn is_sensitive(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    SENSITIVE_NAMES.iter().any(|word| name.contains(word))
+}
+
+/// Returns `value`, or the redaction marker when `name` looks sensitive.
+fn redact<'a>(name: &str, value: &'a str) -> &'a str {
+    if is_sensitive(name) { REDACTED } else { value }
+}
+
+/// Redacts the password and the sensitive query parameters of `url`.
+pub(crate) fn redact_url(url: &str) -> String {
+    let Ok(mut parsed) = Url::parse(url) else {
+        return url.to_string();
+    };
+    let mut redacted = false;
+    if parsed.password().is_some() {
+        redacted = parsed.set_password(Some(REDACTED)).is_ok();
+    }
+    if parsed.query_pairs().any(|(key, _)| is_sensitive(&key)) {
+        let pairs: Vec<(String, String)> = parsed
+            .query_pairs()
+            .map(|(key, value)| {
+                let value = redact(&key, &value).to_string();
+                (key.into_owned(), value)
+            })
+            .collect();
+        parsed.query_pairs_mut().clear().extend_pairs(pairs);
+        redacted = true;
+    }
+    if redacted {
+        parsed.to_string()
+    } else {
+        url.to_string()
+    }
+}
+
 /// Represents an HTTP request with customizable parameters.
 pub struct Request {
     /// The URL of the request.
     pub url: String,
     /// The HTTP method (e.g., GET, POST).
     pub method: Method,
-    /// Optional data for POST requests.
-    pub post_data: Option<String>,
-    /// Optional HTTP headers.
-    pub headers: Option<HashMap<String, String>>,
+    /// Optional request body, sent as is.
+    pub post_data: Option<Vec<u8>>,
+    /// A file streamed as the request body, replacing `post_data`.
+    pub body_file: Option<PathBuf>,
+    /// The file a successful response body is written to by the detailed executions.
+    pub output_file: Option<PathBuf>,
+    /// Whether an interrupted download to the output file, or a dropped event
+    /// stream, is continued.
+    pub resume: bool,
+    /// The digest the body written to the output file must have.
+    pub expected_checksum: Option<Checksum>,
+    /// The consumer a successful response body is streamed to by the detailed
+    /// and paired executions.
+    pub body_sink: Option<Arc<dyn BodySink>>,
+    /// The handler the lines of a successful response body are passed to,
+    /// when there is no body sink.
+    pub line_handler: Option<LineHandler>,
+    /// The handler the events of a successful event stream response are
+    /// passed to, when there is no body sink.
+    pub event_handler: Option<EventHandler>,
+    /// The maximum length in bytes of a line passed to the line or event handler.
+    pub max_line_length: Option<usize>,
+    /// The HTTP headers, sent in order; names may repeat.
+    pub headers: HeaderMap,
+    /// Headers that could not be set because their name or value is invalid.
+    pub header_errors: Vec<InvalidHeader>,
+    /// Cookies sent in the `Cookie` header, with their values encoded.
+    pub cookies: Vec<(String, String)>,
     /// Additional options for the request.
     pub options: HashMap<String, String>,
     /// Extra information for custom use.
     pub extra_info: Option<String>,
-    /// The response text from the server.
+    /// The tag grouping the request with others; `None` for the default group.
+    pub tag: Option<String>,
+    /// The priority reported for the request; it does not change the dispatch order.
+    pub priority: i32,
+    /// The response text from the server, set by the paired executions.
     pub response_text: Option<String>,
-    /// Additional response information.
+    /// The status line and headers of the response, set by the paired executions.
     pub response_info: Option<String>,
     /// Any error message from the response.
     pub response_error: Option<String>,
-    /// Error number from the response.
+    /// The `ErrorCode` of the request, or `0` on success, set by the paired executions.
     pub response_errno: Option<i32>,
-    /// Optional multipart form data.
-    pub multipart_form_data: Option<Form>,
+    /// The number of times the request has been dispatched.
+    pub attempts: u32,
+    /// How many times the request is executed before it leaves the queue.
+    pub repeat: u32,
+    /// The retry policy overriding the one set on the builder.
+    pub retry_policy: Option<RetryPolicy>,
+    /// URLs tried in order when the request fails, e.g. mirrors of the resource.
+    pub fallback_urls: Vec<String>,
+    /// Query parameters appended to the URL when the request is sent.
+    pub query_params: Vec<(String, String)>,
+    /// Values substituted for `{name}` placeholders in the URLs when the request is sent.
+    pub path_params: Vec<(String, String)>,
+    /// Whether a non-success status is returned as an error, overriding the builder.
+    pub error_for_status: Option<bool>,
+    /// The number of attempts allowed for the request, overriding the retry policy.
+    pub max_attempts: Option<u32>,
+    /// The timeout of each attempt, overriding the client timeout; `None` keeps it.
+    pub timeout: Option<Duration>,
+    /// The maximum size in bytes of the body and of each multipart part,
+    /// overriding the default of `RollingRequests`.
+    pub max_body_size: Option<u64>,
+    /// Whether redirects are followed; `None` follows them like `Some(true)`.
+    pub follow_redirects: Option<bool>,
+    /// The proxy the request is sent through, overriding the client's.
+    pub proxy: Option<String>,
+    /// Whether compressed responses are decoded, when a compression feature is enabled.
+    pub decompress: Option<bool>,
+    /// The HTTP version the request is sent with.
+    pub http_version: Option<Version>,
+    /// Optional multipart form data, built anew for every attempt.
+    pub multipart_form_data: Option<MultipartForm>,
+    /// Typed data travelling with the request into its result.
+    pub metadata: Option<Metadata>,
+    /// The hook returning the requests that follow this one, when set.
+    pub continuation: Option<Continuation>,
+    /// Whether the continuation is also called for failures.
+    pub continue_on_failure: bool,
+    /// The hook called with the final result of the request, when set.
+    pub on_complete: Option<CompletionHook>,
+    /// The number of continuations that led to this request.
+    pub chain_depth: u32,
+    /// The maximum number of pages fetched by following `next` links, when set.
+    pub max_pages: Option<u32>,
+    /// The page counter, starting at `1` and increased for each `next` link followed.
+    pub page: u32,
+    /// The maximum number of redirects followed while recording the redirect
+    /// chain, when set.
+    pub record_redirects: Option<u32>,
+    /// The redirects of the last attempt, when recording the redirect chain.
+    pub(crate) redirects: Vec<RedirectHop>,
+    /// Why the last attempt stopped following redirects, if it had to.
+    pub(crate) redirect_error: Option<RedirectError>,
+    /// The beginning of the body of the last response with an error status.
+    pub(crate) body_snippet: Option<Box<str>>,
+    /// The body of the last response, read for the continuation.
+    pub(crate) buffered_body: Option<Bytes>,
+    /// The identifier assigned when the request was queued.
+    pub(crate) id: Option<u64>,
+    /// When the request was queued by `RollingRequests::add_request`.
+    pub(crate) queued_at: Option<Instant>,
+    /// Whether the server rejected a HEAD sent in place of this GET request.
+    pub(crate) head_fallback: bool,
+    /// Whether the last attempt was answered from the response cache.
+    pub(crate) cached: bool,
+    /// Whether the last attempt was answered with `304 Not Modified`.
+    pub(crate) not_modified: bool,
+    /// The position and URL of the proxy of the pool the last attempt was
+    /// sent through.
+    pub(crate) pooled_proxy: Option<(usize, String)>,
+    /// Whether the request follows a `next` link to another origin, so it is
+    /// sent without the credentials of the instance.
+    pub(crate) cross_origin: bool,
+    /// Whether the execution writes the body to the output file, so an
+    /// interrupted download is continued with a `Range` request.
+    pub(crate) downloads: bool,
+    /// Whether the execution checks links, so a GET request is sent as HEAD
+    /// first.
+    pub(crate) checks_link: bool,
+    /// What the `416 Range Not Satisfiable` answer to the last attempt meant
+    /// for the partial file, when a resumed download got one.
+    pub(crate) unsatisfied: Option<Unsatisfied>,
 }
 
 impl Request {
     /// Creates a new `Request` with the specified URL and method.
     ///
+    /// The URL is kept as given and only parsed when the request is sent, so an
+    /// invalid URL surfaces as an error of that request. Use `try_new` to check
+    /// it up front.
+    ///
     /// #### Arguments
     ///
     /// * `url` - The URL for the request.
@@ -65,225 +560,2140 @@ impl Request {
     /// use rollingrequests::request::Request;
     /// use reqwest::Method;
     ///
-    /// let request = Request::new("http://example.com", Method::GET);
+    /// let request = Request::new("http://example.com", Method::OPTIONS);
     /// ```
     pub fn new(url: &str, method: Method) -> Self {
         Request {
             url: url.to_string(),
             method,
             post_data: None,
-            headers: None,
+            body_file: None,
+            output_file: None,
+            resume: false,
+            expected_checksum: None,
+            body_sink: None,
+            line_handler: None,
+            event_handler: None,
+            max_line_length: None,
+            headers: HeaderMap::new(),
+            header_errors: Vec::new(),
+            cookies: Vec::new(),
             options: HashMap::new(),
             extra_info: None,
+            tag: None,
+            priority: 0,
             response_text: None,
             response_info: None,
             response_error: None,
             response_errno: None,
+            attempts: 0,
+            repeat: 1,
+            retry_policy: None,
+            fallback_urls: Vec::new(),
+            query_params: Vec::new(),
+            path_params: Vec::new(),
+            error_for_status: None,
+            max_attempts: None,
+            timeout: None,
+            max_body_size: None,
+            follow_redirects: None,
+            proxy: None,
+            decompress: None,
+            http_version: None,
             multipart_form_data: None,
+            metadata: None,
+            continuation: None,
+            continue_on_failure: false,
+            on_complete: None,
+            chain_depth: 0,
+            max_pages: None,
+            page: 1,
+            record_redirects: None,
+            redirects: Vec::new(),
+            redirect_error: None,
+            body_snippet: None,
+            buffered_body: None,
+            id: None,
+            queued_at: None,
+            head_fallback: false,
+            cached: false,
+            not_modified: false,
+            pooled_proxy: None,
+            cross_origin: false,
+            downloads: false,
+            checks_link: false,
+            unsatisfied: None,
         }
     }
 
-    /// Sets extra information for the request.
+    /// Creates a new `Request` after parsing and normalizing the URL.
+    ///
+    /// The stored URL is the normalized form, with a lowercase scheme and host,
+    /// internationalized host names in their ASCII form and default ports removed.
     ///
     /// #### Arguments
     ///
-    /// * `extra_info` - Additional information to associate with the request.
-    pub fn set_extra_info(&mut self, extra_info: &str) -> &mut Self {
-        self.extra_info = Some(extra_info.to_string());
-        self
+    /// * `url` - The URL for the request.
+    /// * `method` - The HTTP method to use.
+    ///
+    /// #### Errors
+    ///
+    /// Returns an error if the URL cannot be parsed, for example when it has
+    /// no scheme or an out of range port.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    /// use reqwest::Method;
+    ///
+    /// let request = Request::try_new("HTTP://Example.com:80/a", Method::GET).unwrap();
+    /// assert_eq!(request.get_url(), "http://example.com/a");
+    ///
+    /// assert!(Request::try_new("example.com/a", Method::GET).is_err());
+    /// ```
+    pub fn try_new(url: &str, method: Method) -> Result<Self, url::ParseError> {
+        Url::parse(url).map(|url| Self::new(url.as_str(), method))
     }
 
-    /// Retrieves extra information for the request.
-    pub fn get_extra_info(&self) -> Option<&String> {
-        self.extra_info.as_ref()
+    /// Creates a new `Request` with a method given by name, such as `PURGE`,
+    /// `REPORT` or another extension method.
+    ///
+    /// The name is sent as given; names are case-sensitive. Methods other than
+    /// the standard idempotent ones are treated as non-idempotent, so they are
+    /// not retried by the default retry predicate or hedged.
+    ///
+    /// #### Arguments
+    ///
+    /// * `url` - The URL for the request.
+    /// * `method` - The method name.
+    ///
+    /// #### Errors
+    ///
+    /// Returns an error if the name is empty or contains characters not
+    /// allowed in an HTTP token, such as spaces.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    ///
+    /// let request = Request::with_method_str("http://example.com/cache", "PURGE").unwrap();
+    /// assert_eq!(request.get_method().as_str(), "PURGE");
+    ///
+    /// assert!(Request::with_method_str("http://example.com", "NOT VALID").is_err());
+    /// ```
+    pub fn with_method_str(url: &str, method: &str) -> Result<Self, InvalidMethod> {
+        Method::from_bytes(method.as_bytes())
+            .map(|parsed| Self::new(url, parsed))
+            .map_err(|_| InvalidMethod {
+                method: method.to_string(),
+            })
     }
 
-    /// Sets HTTP headers for the request.
+    /// Creates a new `GET` request for the specified URL.
     ///
     /// #### Arguments
     ///
-    /// * `headers` - A map of header names and values.
-    pub fn set_headers(&mut self, headers: HashMap<String, String>) -> &mut Self {
-        self.headers = Some(headers);
-        self
+    /// * `url` - The URL for the request.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    ///
+    /// let request = Request::get("http://example.com");
+    /// ```
+    pub fn get(url: &str) -> Self {
+        Self::new(url, Method::GET)
     }
 
-    /// Retrieves the HTTP headers for the request.
-    pub fn get_headers(&self) -> Option<&HashMap<String, String>> {
-        self.headers.as_ref()
+    /// Creates a new `POST` request for the specified URL and optional body.
+    ///
+    /// #### Arguments
+    ///
+    /// * `url` - The URL for the request.
+    /// * `body` - The body to send, if any.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    /// use serde_json::json;
+    ///
+    /// let request = Request::post("http://example.com/login", Some("user=alice"));
+    ///
+    /// let mut request = Request::post("http://example.com/users", None);
+    /// request.set_json(&json!({ "name": "alice" })).unwrap();
+    /// ```
+    pub fn post(url: &str, body: Option<&str>) -> Self {
+        Self::with_body(url, Method::POST, body)
     }
 
-    /// Sets the HTTP method for the request.
+    /// Creates a new `PUT` request for the specified URL and optional body.
     ///
     /// #### Arguments
     ///
-    /// * `method` - The HTTP method to set.
-    pub fn set_method(&mut self, method: Method) -> &mut Self {
-        self.method = method;
-        self
+    /// * `url` - The URL for the request.
+    /// * `body` - The body to send, if any.
+    pub fn put(url: &str, body: Option<&str>) -> Self {
+        Self::with_body(url, Method::PUT, body)
     }
 
-    /// Retrieves the HTTP method for the request.
-    pub fn get_method(&self) -> &Method {
-        &self.method
+    /// Creates a new `PATCH` request for the specified URL and optional body.
+    ///
+    /// #### Arguments
+    ///
+    /// * `url` - The URL for the request.
+    /// * `body` - The body to send, if any.
+    pub fn patch(url: &str, body: Option<&str>) -> Self {
+        Self::with_body(url, Method::PATCH, body)
     }
 
-    /// Sets additional options for the request.
+    /// Creates a new `DELETE` request for the specified URL.
     ///
     /// #### Arguments
     ///
-    /// * `options` - A map of option names and values.
-    pub fn set_options(&mut self, options: HashMap<String, String>) -> &mut Self {
-        self.options = options;
-        self
+    /// * `url` - The URL for the request.
+    pub fn delete(url: &str) -> Self {
+        Self::new(url, Method::DELETE)
     }
 
-    /// Adds additional options to the existing ones.
+    /// Creates a new `HEAD` request for the specified URL.
     ///
     /// #### Arguments
     ///
-    /// * `options` - A map of option names and values to add.
-    pub fn add_options(&mut self, options: HashMap<String, String>) -> &mut Self {
-        self.options.extend(options);
-        self
+    /// * `url` - The URL for the request.
+    pub fn head(url: &str) -> Self {
+        Self::new(url, Method::HEAD)
     }
 
-    /// Retrieves the options for the request.
-    pub fn get_options(&self) -> &HashMap<String, String> {
-        &self.options
+    /// Creates a new request with the given method and optional body.
+    fn with_body(url: &str, method: Method, body: Option<&str>) -> Self {
+        let mut request = Self::new(url, method);
+        request.set_post_data(body);
+        request
     }
 
-    /// Sets the POST data for the request.
+    /// Sets extra information for the request.
     ///
     /// #### Arguments
     ///
-    /// * `post_data` - The data to include in the POST request body.
-    pub fn set_post_data(&mut self, post_data: Option<&str>) -> &mut Self {
-        self.post_data = post_data.map(|s| s.to_string());
+    /// * `extra_info` - Additional information to associate with the request.
+    pub fn set_extra_info(&mut self, extra_info: &str) -> &mut Self {
+        self.extra_info = Some(extra_info.to_string());
         self
     }
 
-    /// Retrieves the POST data for the request.
-    pub fn get_post_data(&self) -> Option<&String> {
-        self.post_data.as_ref()
+    /// Retrieves extra information for the request.
+    pub fn get_extra_info(&self) -> Option<&String> {
+        self.extra_info.as_ref()
     }
 
-    /// Sets the error number from the response.
+    /// Tags the request, grouping it with the other requests of the same tag.
+    ///
+    /// Tagged requests can be executed, counted and cleared apart from the
+    /// rest of the queue, see `RollingRequests::execute_requests_tagged`.
+    /// Untagged requests belong to the default group, selected with `None`.
     ///
     /// #### Arguments
     ///
-    /// * `response_errno` - The error number to set.
-    pub fn set_response_errno(&mut self, response_errno: i32) -> &mut Self {
-        self.response_errno = Some(response_errno);
+    /// * `tag` - The tag of the request.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    ///
+    /// let mut request = Request::get("http://example.com");
+    /// request.set_tag("crawl");
+    /// assert_eq!(request.get_tag(), Some("crawl"));
+    /// ```
+    pub fn set_tag(&mut self, tag: &str) -> &mut Self {
+        self.tag = Some(tag.to_string());
         self
     }
 
-    /// Retrieves the error number from the response.
-    pub fn get_response_errno(&self) -> Option<i32> {
-        self.response_errno
+    /// Retrieves the tag of the request, or `None` in the default group.
+    pub fn get_tag(&self) -> Option<&str> {
+        self.tag.as_deref()
     }
 
-    /// Sets the error message from the response.
+    /// Sets the priority of the request, `0` by default.
+    ///
+    /// The priority is reported in `RequestSummary`, for callers inspecting
+    /// the queue with `pending_snapshot`; requests are still dispatched in
+    /// queue order. Repetitions and next pages keep the priority of the
+    /// request, as do the follow-ups of its continuation that set none.
     ///
     /// #### Arguments
     ///
-    /// * `response_error` - The error message to set.
-    pub fn set_response_error(&mut self, response_error: &str) -> &mut Self {
-        self.response_error = Some(response_error.to_string());
+    /// * `priority` - The priority of the request.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    ///
+    /// let mut request = Request::get("http://example.com/health");
+    /// request.set_priority(10);
+    /// assert_eq!(request.get_priority(), 10);
+    /// ```
+    pub fn set_priority(&mut self, priority: i32) -> &mut Self {
+        self.priority = priority;
         self
     }
 
-    /// Retrieves the error message from the response.
-    pub fn get_response_error(&self) -> Option<&String> {
-        self.response_error.as_ref()
+    /// Retrieves the priority of the request.
+    pub fn get_priority(&self) -> i32 {
+        self.priority
     }
 
-    /// Sets additional response information.
+    /// Attaches typed data to the request, replacing any set before.
+    ///
+    /// The data travels with the request through clones and retries, and comes
+    /// back as the `Metadata` extension of its response, or from
+    /// `RollingError::metadata` when it fails.
     ///
     /// #### Arguments
     ///
-    /// * `response_info` - Additional response information to set.
-    pub fn set_response_info(&mut self, response_info: &str) -> &mut Self {
-        self.response_info = Some(response_info.to_string());
+    /// * `value` - The data to attach.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// struct Shard {
+    ///     record: u64,
+    ///     shard: u8,
+    /// }
+    ///
+    /// let mut request = Request::get("http://example.com/records/7");
+    /// request.set_metadata(Shard { record: 7, shard: 2 });
+    /// assert_eq!(request.get_metadata(), Some(&Shard { record: 7, shard: 2 }));
+    /// assert_eq!(request.get_metadata::<String>(), None);
+    /// ```
+    pub fn set_metadata<T: Any + Send + Sync>(&mut self, value: T) -> &mut Self {
+        self.metadata = Some(Metadata::new(value));
         self
     }
 
-    /// Retrieves additional response information.
-    pub fn get_response_info(&self) -> Option<&String> {
-        self.response_info.as_ref()
+    /// Retrieves the attached data if it is a `T`.
+    pub fn get_metadata<T: Any>(&self) -> Option<&T> {
+        self.metadata.as_ref().and_then(Metadata::get)
     }
 
-    /// Sets the response text from the server.
+    /// Sets HTTP headers for the request.
+    ///
+    /// Replaces every header set before. Entries with an invalid name or value
+    /// are not set and are reported by `get_header_errors` instead.
     ///
     /// #### Arguments
     ///
-    /// * `response_text` - The response text to set.
-    pub fn set_response_text(&mut self, response_text: &str) -> &mut Self {
-        self.response_text = Some(response_text.to_string());
+    /// * `headers` - A map of header names and values.
+    pub fn set_headers(&mut self, headers: HashMap<String, String>) -> &mut Self {
+        self.headers.clear();
+        self.header_errors.clear();
+        for (key, value) in &headers {
+            self.append_header(key, value);
+        }
         self
     }
 
-    /// Retrieves the response text from the server.
-    pub fn get_response_text(&self) -> Option<&String> {
-        self.response_text.as_ref()
-    }
-
-    /// Sets the URL for the request.
+    /// Sets HTTP headers for the request from a `HeaderMap`.
+    ///
+    /// Replaces every header set before. Every value of a name with several
+    /// values is sent, in order.
     ///
     /// #### Arguments
     ///
-    /// * `url` - The URL to set for the request.
-    pub fn set_url(&mut self, url: &str) -> &mut Self {
-        self.url = url.to_string();
+    /// * `headers` - The headers to send.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    /// use reqwest::header::{ACCEPT, HeaderMap, HeaderValue};
+    ///
+    /// let mut headers = HeaderMap::new();
+    /// headers.append(ACCEPT, HeaderValue::from_static("text/html"));
+    /// headers.append(ACCEPT, HeaderValue::from_static("application/json"));
+    ///
+    /// let mut request = Request::get("http://example.com");
+    /// request.set_header_map(headers);
+    /// assert_eq!(request.get_headers().get_all(ACCEPT).iter().count(), 2);
+    /// ```
+    pub fn set_header_map(&mut self, headers: HeaderMap) -> &mut Self {
+        self.headers = headers;
+        self.header_errors.clear();
         self
     }
 
-    /// Retrieves the URL for the request.
-    pub fn get_url(&self) -> &String {
-        &self.url
-    }
-
-    /// Adds a text field to the multipart form data.
+    /// Sets a single header, replacing any value it had.
+    ///
+    /// Header names are matched case-insensitively, so `add_header("accept", ..)`
+    /// replaces an `Accept` header set before, including appended values. An
+    /// invalid name or value is reported by `get_header_errors` instead.
     ///
     /// #### Arguments
     ///
-    /// * `name` - The name of the form field.
-    /// * `value` - The value of the form field.
-    pub fn add_form_text(&mut self, name: &str, value: &str) -> &mut Self {
-        let mut form = self.multipart_form_data.take().unwrap_or_else(Form::new);
-        form = form.text(name.to_string(), value.to_string());
-        self.multipart_form_data = Some(form);
+    /// * `key` - The header name.
+    /// * `value` - The header value.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    ///
+    /// let mut request = Request::get("http://example.com");
+    /// request
+    ///     .add_header("Accept", "text/html")
+    ///     .add_header("accept", "application/json")
+    ///     .remove_header("X-Unused");
+    /// assert_eq!(request.get_headers()["accept"], "application/json");
+    /// ```
+    pub fn add_header(&mut self, key: &str, value: &str) -> &mut Self {
+        if let Some((name, value)) = self.parse_header(key, value) {
+            self.headers.insert(name, value);
+        }
         self
     }
 
-    /// Adds a file to the multipart form data.
+    /// Removes every value of a header, matching its name case-insensitively.
     ///
     /// #### Arguments
     ///
-    /// * `name` - The name of the form field.
-    /// * `file_path` - The path to the file to add.
-    pub fn add_form_file(&mut self, name: &str, file_path: &Path) -> &mut Self {
-        let mut form = self.multipart_form_data.take().unwrap_or_else(Form::new);
-        let file_content = fs::read(file_path).expect("Failed to read file");
-        let file_part = Part::bytes(file_content).file_name(
-            file_path
-                .file_name()
-                .unwrap()
-                .to_string_lossy()
-                .into_owned(),
-        );
-        form = form.part(name.to_string(), file_part);
-        self.multipart_form_data = Some(form);
+    /// * `key` - The header name.
+    pub fn remove_header(&mut self, key: &str) -> &mut Self {
+        self.headers.remove(key);
         self
     }
 
-    /// Sets the multipart form data for the request.
+    /// Adds a value to a header without replacing the values it already has.
+    ///
+    /// Each value is sent as its own header line. An invalid name or value is
+    /// reported by `get_header_errors` instead.
+    ///
+    /// #### Arguments
+    ///
+    /// * `key` - The header name.
+    /// * `value` - The value to add.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    ///
+    /// let mut request = Request::get("http://example.com");
+    /// request
+    ///     .append_header("Accept", "text/html")
+    ///     .append_header("Accept", "application/json");
+    /// assert_eq!(request.get_headers().get_all("accept").iter().count(), 2);
+    /// ```
+    pub fn append_header(&mut self, key: &str, value: &str) -> &mut Self {
+        if let Some((name, value)) = self.parse_header(key, value) {
+            self.headers.append(name, value);
+        }
+        self
+    }
+
+    /// Retrieves the HTTP headers for the request.
+    pub fn get_headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Retrieves the headers that could not be set since the headers were last replaced.
+    ///
+    /// A request with header errors is not sent by `RollingRequests`; it fails
+    /// with `RollingErrorKind::InvalidRequest` naming the first invalid header.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    ///
+    /// let mut request = Request::get("http://example.com");
+    /// request.add_header("X-Token", "line\nbreak");
+    /// assert_eq!(request.get_header_errors()[0].name, "X-Token");
+    /// assert!(request.get_headers().is_empty());
+    /// ```
+    pub fn get_header_errors(&self) -> &[InvalidHeader] {
+        &self.header_errors
+    }
+
+    /// Adds a cookie, replacing any cookie set before under the same name.
+    ///
+    /// The cookies are sent in one `Cookie` header, assembled when the request
+    /// is sent. They are merged with a `Cookie` header set with `add_header`,
+    /// and win over cookies of the same name there. Bytes not allowed in a
+    /// cookie value by RFC 6265, as well as `=` and `%`, are percent-encoded.
+    /// A name that is not a valid token is reported by `get_header_errors`
+    /// instead.
+    ///
+    /// #### Arguments
+    ///
+    /// * `name` - The cookie name.
+    /// * `value` - The cookie value.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    ///
+    /// let mut request = Request::get("http://example.com/tenants/7");
+    /// request
+    ///     .add_cookie("session", "abc123")
+    ///     .add_cookie("filter", "a=1; b=2");
+    /// assert_eq!(request.get_cookies()[1].1, "a%3D1%3B%20b%3D2");
+    /// ```
+    pub fn add_cookie(&mut self, name: &str, value: &str) -> &mut Self {
+        if HeaderName::from_bytes(name.as_bytes()).is_err() {
+            self.header_errors.push(InvalidHeader {
+                name: COOKIE.to_string(),
+                value: format!("{}={}", name, value),
+            });
+            return self;
+        }
+        let value = encode_cookie_value(value);
+        match self.cookies.iter_mut().find(|(key, _)| key == name) {
+            Some(cookie) => cookie.1 = value,
+            None => self.cookies.push((name.to_string(), value)),
+        }
+        self
+    }
+
+    /// Replaces the cookies of the request.
+    ///
+    /// #### Arguments
+    ///
+    /// * `cookies` - The cookie names and values, encoded as by `add_cookie`.
+    pub fn set_cookies(&mut self, cookies: &[(&str, &str)]) -> &mut Self {
+        self.cookies.clear();
+        for (name, value) in cookies {
+            self.add_cookie(name, value);
+        }
+        self
+    }
+
+    /// Retrieves the cookies of the request, with their values encoded.
+    pub fn get_cookies(&self) -> &[(String, String)] {
+        &self.cookies
+    }
+
+    /// Assembles the `Cookie` header from `existing` and the request cookies.
+    ///
+    /// Cookies in `existing` are kept in order unless the request sets a
+    /// cookie of the same name. Returns `None` when there are no cookies.
+    pub(crate) fn cookie_header(&self, existing: Option<&HeaderValue>) -> Option<HeaderValue> {
+        let existing = existing.and_then(|value| value.to_str().ok()).unwrap_or("");
+        let mut pairs: Vec<String> = existing
+            .split(';')
+            .map(str::trim)
+            .filter(|pair| !pair.is_empty())
+            .filter(|pair| {
+                let name = pair.split('=').next().unwrap_or("").trim();
+                !self.cookies.iter().any(|(key, _)| key == name)
+            })
+            .map(str::to_string)
+            .collect();
+        pairs.extend(
+            self.cookies
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, value)),
+        );
+        if pairs.is_empty() {
+            return None;
+        }
+        // Names are tokens and values are encoded, so the header is valid
+        HeaderValue::from_str(&pairs.join("; ")).ok()
+    }
+
+    /// Sets the HTTP method for the request.
+    ///
+    /// #### Arguments
+    ///
+    /// * `method` - The HTTP method to set.
+    pub fn set_method(&mut self, method: Method) -> &mut Self {
+        self.method = method;
+        self
+    }
+
+    /// Retrieves the HTTP method for the request.
+    pub fn get_method(&self) -> &Method {
+        &self.method
+    }
+
+    /// Sets additional options for the request.
+    ///
+    /// The following options are applied when the request is executed, unless
+    /// the setting they name was set with its typed setter:
+    ///
+    /// * `timeout_ms` - The timeout in milliseconds, like `set_timeout`.
+    /// * `follow_redirects` - `true` or `false`, like `set_follow_redirects`.
+    /// * `max_retries` - The number of retries after the first attempt, like
+    ///   `set_max_attempts` with one more.
+    /// * `proxy` - A proxy URL, like `set_proxy`.
+    /// * `http_version` - `1.0`, `1.1` or `2`, like `set_http_version`.
+    ///
+    /// An invalid value fails the request with `RollingErrorKind::InvalidRequest`.
+    /// Other options are kept for custom use and ignored by the execution.
+    ///
+    /// #### Arguments
+    ///
+    /// * `options` - A map of option names and values.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    /// use std::collections::HashMap;
+    ///
+    /// let mut request = Request::get("http://example.com/slow");
+    /// request.set_options(HashMap::from([
+    ///     ("timeout_ms".to_string(), "500".to_string()),
+    ///     ("follow_redirects".to_string(), "false".to_string()),
+    /// ]));
+    /// ```
+    pub fn set_options(&mut self, options: HashMap<String, String>) -> &mut Self {
+        self.options = options;
+        self
+    }
+
+    /// Adds additional options to the existing ones.
+    ///
+    /// #### Arguments
+    ///
+    /// * `options` - A map of option names and values to add.
+    pub fn add_options(&mut self, options: HashMap<String, String>) -> &mut Self {
+        self.options.extend(options);
+        self
+    }
+
+    /// Retrieves the options for the request.
+    pub fn get_options(&self) -> &HashMap<String, String> {
+        &self.options
+    }
+
+    /// Sets the POST data for the request.
+    ///
+    /// The text is stored as its UTF-8 bytes.
+    ///
+    /// #### Arguments
+    ///
+    /// * `post_data` - The data to include in the POST request body.
+    pub fn set_post_data(&mut self, post_data: Option<&str>) -> &mut Self {
+        self.post_data = post_data.map(|s| s.as_bytes().to_vec());
+        self.body_file = None;
+        self
+    }
+
+    /// Retrieves the POST data for the request.
+    ///
+    /// Returns `None` when there is no body or when it is not valid UTF-8; use
+    /// `get_body_bytes` for binary bodies.
+    pub fn get_post_data(&self) -> Option<&str> {
+        self.post_data
+            .as_deref()
+            .and_then(|data| std::str::from_utf8(data).ok())
+    }
+
+    /// Sets a binary body for the request.
+    ///
+    /// The bytes are sent over the wire untouched.
+    ///
+    /// #### Arguments
+    ///
+    /// * `body` - The bytes to send as the request body.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    ///
+    /// let mut request = Request::post("http://example.com/upload", None);
+    /// request.set_body_bytes(&[0x1f, 0x8b, 0xff]);
+    /// assert_eq!(request.get_body_bytes(), Some(&[0x1f, 0x8b, 0xff][..]));
+    /// assert_eq!(request.get_post_data(), None);
+    /// ```
+    pub fn set_body_bytes(&mut self, body: &[u8]) -> &mut Self {
+        self.post_data = Some(body.to_vec());
+        self.body_file = None;
+        self
+    }
+
+    /// Retrieves the body of the request as bytes.
+    pub fn get_body_bytes(&self) -> Option<&[u8]> {
+        self.post_data.as_deref()
+    }
+
+    /// Streams the file at `path` as the request body.
+    ///
+    /// Only the path is recorded: the file is opened and read in chunks every
+    /// time the request is sent, so it is never held in memory and clones and
+    /// retries send it again. The `Content-Length` header is taken from the
+    /// file's size at send time. A file that cannot be read then makes that
+    /// request fail with an error result. Replaces any body set before.
+    ///
+    /// #### Arguments
+    ///
+    /// * `path` - The path of the file to send.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    /// use std::path::Path;
+    ///
+    /// let mut request = Request::put("http://example.com/artifacts/build.tar", None);
+    /// request.set_body_file(Path::new("/tmp/build.tar"));
+    /// assert_eq!(request.get_body_file(), Some(Path::new("/tmp/build.tar")));
+    /// ```
+    pub fn set_body_file(&mut self, path: &Path) -> &mut Self {
+        self.body_file = Some(path.to_path_buf());
+        self.post_data = None;
+        self
+    }
+
+    /// Retrieves the path of the file streamed as the request body.
+    pub fn get_body_file(&self) -> Option<&Path> {
+        self.body_file.as_deref()
+    }
+
+    /// Writes the body of a successful response to the file at `path`
+    /// instead of memory.
+    ///
+    /// `execute_requests_detailed` and `execute_all_detailed` stream the body
+    /// into `<path>.part`, creating missing parent directories, and rename it
+    /// to `path` once complete, replacing any file there. The result then
+    /// reports the file in `RollingResult::download` and has an empty body. A
+    /// failure while streaming removes the partial file, unless the download
+    /// is resumable, see `set_resume`. Responses with other
+    /// statuses are read into memory as usual, and the other executions return
+    /// the response untouched.
+    ///
+    /// #### Arguments
+    ///
+    /// * `path` - The path of the file to write.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    /// use std::path::Path;
+    ///
+    /// let mut request = Request::get("http://example.com/archive.zip");
+    /// request.set_output_file(Path::new("downloads/archive.zip"));
+    /// assert_eq!(request.get_output_file(), Some(Path::new("downloads/archive.zip")));
+    /// ```
+    pub fn set_output_file(&mut self, path: &Path) -> &mut Self {
+        self.output_file = Some(path.to_path_buf());
+        self
+    }
+
+    /// Retrieves the path of the file the response body is written to.
+    pub fn get_output_file(&self) -> Option<&Path> {
+        self.output_file.as_deref()
+    }
+
+    /// Sets whether an interrupted download to the output file is continued
+    /// instead of started over, and whether a dropped event stream is
+    /// reconnected, see `set_event_handler`.
+    ///
+    /// When reading the body fails, `<path>.part` is kept together with the
+    /// `ETag` or `Last-Modified` of the response in `<path>.part.validator`.
+    /// The download is then continued right away while the request has
+    /// attempts left under its retry policy and the retry budget allows it,
+    /// and otherwise the next time the request is executed by an execution
+    /// writing output files: it is sent with `Range: bytes=<partial length>-`
+    /// and an `If-Range` header carrying the validator. A `206 Partial
+    /// Content` response starting at the end of the partial file is appended
+    /// to it, while a `200 OK`, sent by servers without range support or for
+    /// a changed representation, replaces it. A `416 Range Not Satisfiable`
+    /// response completes the download when its `Content-Range` length is
+    /// that of the partial file, and otherwise the partial file is removed
+    /// and the download starts over. Continuing attempts wait on the
+    /// throttle of the host like any other.
+    ///
+    /// #### Arguments
+    ///
+    /// * `resume` - Whether to continue interrupted downloads.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    /// use std::path::Path;
+    ///
+    /// let mut request = Request::get("http://example.com/dataset.tar");
+    /// request
+    ///     .set_output_file(Path::new("downloads/dataset.tar"))
+    ///     .set_resume(true)
+    ///     .set_max_attempts(5);
+    /// assert!(request.get_resume());
+    /// ```
+    pub fn set_resume(&mut self, resume: bool) -> &mut Self {
+        self.resume = resume;
+        self
+    }
+
+    /// Retrieves whether interrupted downloads are continued.
+    pub fn get_resume(&self) -> bool {
+        self.resume
+    }
+
+    /// Sets the digest the body written to the output file must have.
+    ///
+    /// Requires the `checksum` feature. The body is hashed as it is written,
+    /// and a digest that differs fails the request with
+    /// `RollingErrorKind::ChecksumMismatch` and removes the file. Without the
+    /// feature, or with a digest of the wrong length, the request fails
+    /// before it is sent.
+    ///
+    /// #### Arguments
+    ///
+    /// * `algorithm` - The hash algorithm.
+    /// * `hex` - The expected digest in hexadecimal digits, in either case.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::download::ChecksumAlgorithm;
+    /// use rollingrequests::request::Request;
+    /// use std::path::Path;
+    ///
+    /// let mut request = Request::get("http://example.com/release.tar.gz");
+    /// request.set_output_file(Path::new("release.tar.gz")).set_expected_checksum(
+    ///     ChecksumAlgorithm::Sha256,
+    ///     "9F86D081884C7D659A2FEAA0C55AD015A3BF4F1B2B0B822CD15D6C15B0F00A08",
+    /// );
+    /// assert!(request.get_expected_checksum().unwrap().hex.starts_with("9f86"));
+    /// ```
+    pub fn set_expected_checksum(&mut self, algorithm: ChecksumAlgorithm, hex: &str) -> &mut Self {
+        self.expected_checksum = Some(Checksum {
+            algorithm,
+            hex: hex.trim().to_ascii_lowercase(),
+        });
+        self
+    }
+
+    /// Retrieves the digest the downloaded body must have, if set.
+    pub fn get_expected_checksum(&self) -> Option<&Checksum> {
+        self.expected_checksum.as_ref()
+    }
+
+    /// Sets the consumer a successful response body is streamed to.
+    ///
+    /// The detailed and paired executions hand the body to `sink` chunk by
+    /// chunk instead of reading it into memory, leaving the body of the result
+    /// empty; the body of a non-success status is read as usual. An output
+    /// file takes precedence over the sink. Clones of the request share the
+    /// sink.
+    ///
+    /// #### Arguments
+    ///
+    /// * `sink` - The consumer of the body, see `BodySink`.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use bytes::Bytes;
+    /// use rollingrequests::request::Request;
+    /// use rollingrequests::sink::SinkError;
+    ///
+    /// let mut request = Request::get("http://example.com/export.ndjson");
+    /// request.set_body_sink(|chunk: Bytes| async move {
+    ///     println!("{} bytes", chunk.len());
+    ///     Ok::<(), SinkError>(())
+    /// });
+    /// assert!(request.get_body_sink().is_some());
+    /// ```
+    pub fn set_body_sink<S: BodySink + 'static>(&mut self, sink: S) -> &mut Self {
+        self.body_sink = Some(Arc::new(sink));
+        self
+    }
+
+    /// Retrieves the consumer of the response body, if set.
+    pub fn get_body_sink(&self) -> Option<&dyn BodySink> {
+        self.body_sink.as_deref()
+    }
+
+    /// Sets the handler a successful response body is passed to line by line.
+    ///
+    /// The body is streamed like with `set_body_sink`, which takes precedence,
+    /// and split on LF and CRLF endings; lines spanning chunks are joined, and
+    /// a last line without ending is passed once the body is complete. Lines
+    /// are passed without their ending, with invalid UTF-8 sequences replaced.
+    ///
+    /// #### Arguments
+    ///
+    /// * `handler` - The function receiving each line.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    ///
+    /// let mut request = Request::get("http://example.com/export.ndjson");
+    /// request
+    ///     .set_line_handler(|line| println!("{}", line))
+    ///     .set_max_line_length(1024 * 1024);
+    /// assert_eq!(request.get_max_line_length(), Some(1024 * 1024));
+    /// ```
+    pub fn set_line_handler<F>(&mut self, handler: F) -> &mut Self
+    where
+        F: Fn(&str) + Send + Sync + 'static,
+    {
+        self.line_handler = Some(Arc::new(handler));
+        self
+    }
+
+    /// Retrieves the handler of the response body lines, if set.
+    pub fn get_line_handler(&self) -> Option<&LineHandler> {
+        self.line_handler.as_ref()
+    }
+
+    /// Consumes the response as a Server-Sent Events stream.
+    ///
+    /// Sends `Accept: text/event-stream` and `Cache-Control: no-cache`, and
+    /// lets the request wait forever unless a timeout is set with
+    /// `set_timeout`. The body is streamed like with `set_body_sink`, which
+    /// takes precedence over the handler as well as over a line handler, and
+    /// parsed into `event:`, `data:`, `id:` and `retry:` fields; comments are
+    /// skipped, and each event is passed once its closing blank line arrives.
+    ///
+    /// With `set_resume`, a stream that drops is reconnected with the
+    /// `Last-Event-ID` header while the request has attempts left, waiting the
+    /// `retry:` time of the stream, or else the backoff of the retry policy.
+    /// A stream the server closes is complete.
+    ///
+    /// Event streams are consumed by `RollingRequests::execute_in_background`,
+    /// whose results do not wait for one another. As a stream may never end,
+    /// the executions returning their results together, such as
+    /// `execute_requests_detailed`, fail the request with
+    /// `RollingErrorKind::InvalidRequest` instead of sending it.
+    ///
+    /// #### Arguments
+    ///
+    /// * `handler` - The function receiving each event.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    ///
+    /// let mut request = Request::get("http://example.com/updates");
+    /// request
+    ///     .set_event_handler(|event| println!("{}: {}", event.event, event.data))
+    ///     .set_resume(true)
+    ///     .set_max_attempts(10);
+    /// assert_eq!(request.get_headers()["accept"], "text/event-stream");
+    /// ```
+    pub fn set_event_handler<F>(&mut self, handler: F) -> &mut Self
+    where
+        F: Fn(&Event) + Send + Sync + 'static,
+    {
+        self.event_handler = Some(Arc::new(handler));
+        self.timeout.get_or_insert(Duration::MAX);
+        self.add_header(ACCEPT.as_str(), "text/event-stream")
+            .add_header(CACHE_CONTROL.as_str(), "no-cache")
+    }
+
+    /// Retrieves the handler of the response events, if set.
+    pub fn get_event_handler(&self) -> Option<&EventHandler> {
+        self.event_handler.as_ref()
+    }
+
+    /// Sets the maximum length in bytes of a line passed to the line handler,
+    /// or of a line of an event stream.
+    ///
+    /// Without it, a body without line endings is buffered whole. A longer
+    /// line fails the request with `RollingErrorKind::Sink`, caused by a
+    /// `LineTooLong` error.
+    ///
+    /// #### Arguments
+    ///
+    /// * `max_line_length` - The maximum length of a line, without its ending.
+    pub fn set_max_line_length(&mut self, max_line_length: usize) -> &mut Self {
+        self.max_line_length = Some(max_line_length);
+        self
+    }
+
+    /// Retrieves the maximum length of a line of the response body, if set.
+    pub fn get_max_line_length(&self) -> Option<usize> {
+        self.max_line_length
+    }
+
+    /// Returns the sink a successful response body of this request is
+    /// streamed to, with a fresh line buffer for a line or event handler.
+    pub(crate) fn stream_sink(&self) -> Option<StreamSink> {
+        if let Some(sink) = &self.body_sink {
+            return Some(StreamSink::Body(sink.clone()));
+        }
+        if let Some(handler) = &self.event_handler {
+            let events = EventSink::new(handler.clone(), self.max_line_length);
+            return Some(StreamSink::Events(events));
+        }
+        let handler = self.line_handler.clone()?;
+        let lines = LineSink::new(handler, self.max_line_length);
+        Some(StreamSink::Body(Arc::new(lines)))
+    }
+
+    /// Serializes `value` as JSON into the request body.
+    ///
+    /// The `Content-Type` header is set to `application/json` unless the request
+    /// already has one.
+    ///
+    /// #### Arguments
+    ///
+    /// * `value` - The value to serialize.
+    ///
+    /// #### Errors
+    ///
+    /// Returns an error if `value` cannot be serialized. The request is left
+    /// unchanged in that case.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    /// use std::collections::HashMap;
+    ///
+    /// let mut request = Request::post("http://example.com/users", None);
+    /// request
+    ///     .set_json(&HashMap::from([("name", "alice")]))
+    ///     .expect("Failed to serialize");
+    /// assert_eq!(request.get_post_data().unwrap(), r#"{"name":"alice"}"#);
+    /// ```
+    pub fn set_json<T: Serialize + ?Sized>(
+        &mut self,
+        value: &T,
+    ) -> Result<&mut Self, serde_json::Error> {
+        let body = serde_json::to_vec(value)?;
+        self.post_data = Some(body);
+        self.body_file = None;
+        self.set_default_header("Content-Type", "application/json");
+        Ok(self)
+    }
+
+    /// Encodes `form` as `application/x-www-form-urlencoded` into the request body.
+    ///
+    /// Accepts anything serializable as a flat list of fields, such as a slice of
+    /// pairs or a struct. The `Content-Type` header is set unless the request
+    /// already has one.
+    ///
+    /// #### Arguments
+    ///
+    /// * `form` - The fields to encode.
+    ///
+    /// #### Errors
+    ///
+    /// Returns an error if `form` cannot be encoded, e.g. because it is nested.
+    /// The request is left unchanged in that case.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    ///
+    /// let mut request = Request::post("http://example.com/login", None);
+    /// request
+    ///     .set_form(&[("user", "alice"), ("note", "a&b c")])
+    ///     .expect("Failed to encode");
+    /// assert_eq!(request.get_post_data().unwrap(), "user=alice&note=a%26b+c");
+    /// ```
+    pub fn set_form<T: Serialize + ?Sized>(
+        &mut self,
+        form: &T,
+    ) -> Result<&mut Self, serde_urlencoded::ser::Error> {
+        let body = serde_urlencoded::to_string(form)?;
+        self.post_data = Some(body.into_bytes());
+        self.body_file = None;
+        self.set_default_header("Content-Type", "application/x-www-form-urlencoded");
+        Ok(self)
+    }
+
+    /// Sets the error number from the response.
+    ///
+    /// #### Arguments
+    ///
+    /// * `response_errno` - The error number to set.
+    pub fn set_response_errno(&mut self, response_errno: i32) -> &mut Self {
+        self.response_errno = Some(response_errno);
+        self
+    }
+
+    /// Retrieves the error number from the response.
+    pub fn get_response_errno(&self) -> Option<i32> {
+        self.response_errno
+    }
+
+    /// Sets the error message from the response.
+    ///
+    /// #### Arguments
+    ///
+    /// * `response_error` - The error message to set.
+    pub fn set_response_error(&mut self, response_error: &str) -> &mut Self {
+        self.response_error = Some(response_error.to_string());
+        self
+    }
+
+    /// Retrieves the error message from the response.
+    pub fn get_response_error(&self) -> Option<&String> {
+        self.response_error.as_ref()
+    }
+
+    /// Sets additional response information.
+    ///
+    /// #### Arguments
+    ///
+    /// * `response_info` - Additional response information to set.
+    pub fn set_response_info(&mut self, response_info: &str) -> &mut Self {
+        self.response_info = Some(response_info.to_string());
+        self
+    }
+
+    /// Retrieves additional response information.
+    pub fn get_response_info(&self) -> Option<&String> {
+        self.response_info.as_ref()
+    }
+
+    /// Sets the response text from the server.
+    ///
+    /// #### Arguments
+    ///
+    /// * `response_text` - The response text to set.
+    pub fn set_response_text(&mut self, response_text: &str) -> &mut Self {
+        self.response_text = Some(response_text.to_string());
+        self
+    }
+
+    /// Retrieves the response text from the server.
+    pub fn get_response_text(&self) -> Option<&String> {
+        self.response_text.as_ref()
+    }
+
+    /// Retrieves the identifier assigned when the request was queued.
+    ///
+    /// `RollingRequests::add_request` assigns every request an identifier that
+    /// is unique within that instance, and which clones and retries keep. It
+    /// is `None` until then.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let request = Request::get("http://example.com");
+    /// assert_eq!(request.id(), None);
+    ///
+    /// let mut rolling_requests = RollingRequestsBuilder::new().build();
+    /// let id = rolling_requests.add_request(request);
+    /// assert_eq!(id, 0);
+    /// ```
+    pub fn id(&self) -> Option<u64> {
+        self.id
+    }
+
+    /// Retrieves the number of times the request has been dispatched.
+    pub fn get_attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// Sets how many times the request is executed.
+    ///
+    /// Each execution produces its own result. Between executions, the request
+    /// goes back to the end of the queue so that it interleaves with the other
+    /// pending requests. Values below one are treated as one.
+    ///
+    /// #### Arguments
+    ///
+    /// * `repeat` - The number of executions.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    ///
+    /// let mut request = Request::get("http://example.com");
+    /// request.set_repeat(3);
+    /// assert_eq!(request.get_repeat(), 3);
+    /// ```
+    pub fn set_repeat(&mut self, repeat: u32) -> &mut Self {
+        self.repeat = repeat;
+        self
+    }
+
+    /// Retrieves how many times the request is executed.
+    pub fn get_repeat(&self) -> u32 {
+        self.repeat
+    }
+
+    /// Sets the retry policy for this request, overriding the builder's policy.
+    ///
+    /// #### Arguments
+    ///
+    /// * `policy` - The retry policy to apply to this request.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    /// use rollingrequests::retry::RetryPolicy;
+    ///
+    /// let mut request = Request::get("http://example.com");
+    /// request.set_retry_policy(RetryPolicy::default());
+    /// ```
+    pub fn set_retry_policy(&mut self, policy: RetryPolicy) -> &mut Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
+    /// Retrieves the retry policy set on this request.
+    pub fn get_retry_policy(&self) -> Option<&RetryPolicy> {
+        self.retry_policy.as_ref()
+    }
+
+    /// Sets whether a non-success status is returned as an error for this request.
+    ///
+    /// #### Arguments
+    ///
+    /// * `enabled` - Whether a non-success status becomes an error.
+    pub fn set_error_for_status(&mut self, enabled: bool) -> &mut Self {
+        self.error_for_status = Some(enabled);
+        self
+    }
+
+    /// Sets how many attempts the request gets, overriding the retry policy's count.
+    ///
+    /// One means the request is never retried. A request with a cap is retried
+    /// with the default `RetryPolicy` when the builder has no policy.
+    ///
+    /// #### Arguments
+    ///
+    /// * `max_attempts` - The total number of attempts, including the first one.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    ///
+    /// let mut request = Request::post("http://example.com/charge", None);
+    /// request.set_max_attempts(1);
+    /// ```
+    pub fn set_max_attempts(&mut self, max_attempts: u32) -> &mut Self {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+
+    /// Retrieves the number of attempts allowed for the request, if set.
+    pub fn get_max_attempts(&self) -> Option<u32> {
+        self.max_attempts
+    }
+
+    /// Sets the timeout of each attempt of the request.
+    ///
+    /// Overrides the builder's `timeout` for this request only, in either
+    /// direction. The connect timeout still applies.
+    ///
+    /// #### Arguments
+    ///
+    /// * `timeout` - The time allowed for each attempt; `Duration::MAX` waits forever.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    /// use std::time::Duration;
+    ///
+    /// let mut request = Request::post("http://example.com/reports", None);
+    /// request.set_timeout(Duration::from_secs(120));
+    /// assert_eq!(request.get_timeout(), Some(Duration::from_secs(120)));
+    /// ```
+    pub fn set_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Retrieves the timeout of the request, if it overrides the client timeout.
+    pub fn get_timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Sets the maximum size of the body and of each multipart part.
+    ///
+    /// Sizes are checked before the request is sent, using the file size for
+    /// file bodies and streamed parts, and files are cut off if they grow past
+    /// the limit while streaming. A request over the limit fails with
+    /// `RollingErrorKind::BodyTooLarge`. Files added with `add_form_file` after
+    /// the limit is set are checked before they are read. The limit also
+    /// bounds response bodies read into memory for the `set_on_complete` and
+    /// `set_continuation` hooks.
+    ///
+    /// #### Arguments
+    ///
+    /// * `max_body_size` - The maximum size in bytes.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    ///
+    /// let mut request = Request::post("http://example.com/upload", None);
+    /// request.set_max_body_size(10 * 1024 * 1024);
+    /// ```
+    pub fn set_max_body_size(&mut self, max_body_size: u64) -> &mut Self {
+        self.max_body_size = Some(max_body_size);
+        self
+    }
+
+    /// Retrieves the maximum body size of the request, if set.
+    pub fn get_max_body_size(&self) -> Option<u64> {
+        self.max_body_size
+    }
+
+    /// Sets whether the request follows redirects.
+    ///
+    /// Redirects are followed as set with `RollingRequestsBuilder::redirects`,
+    /// by default up to ten hops. When disabled, the result is the 3xx
+    /// response itself, with its `Location` header intact. Enabled for a
+    /// request of an instance set with `no_redirects`, up to ten hops are
+    /// followed.
+    ///
+    /// #### Arguments
+    ///
+    /// * `follow` - Whether to follow redirects.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    ///
+    /// let mut request = Request::get("http://example.com/short");
+    /// request.set_follow_redirects(false);
+    /// assert_eq!(request.get_follow_redirects(), Some(false));
+    /// ```
+    pub fn set_follow_redirects(&mut self, follow: bool) -> &mut Self {
+        self.follow_redirects = Some(follow);
+        self
+    }
+
+    /// Retrieves whether the request follows redirects, if set.
+    pub fn get_follow_redirects(&self) -> Option<bool> {
+        self.follow_redirects
+    }
+
+    /// Follows redirects one by one, recording each hop, up to `max_hops`.
+    ///
+    /// The request is sent without automatic redirects; each `3xx` response
+    /// with a `Location` header is recorded as a `RedirectHop` and its target
+    /// requested next. A `301`, `302` or `303` response to a request other
+    /// than GET or HEAD turns it into a body-less GET, and credentials are
+    /// only sent to the original host. A response redirecting again after
+    /// `max_hops` hops, or to a URL visited before, fails the request with
+    /// `RollingErrorKind::Redirect`. The chain of the last attempt is kept in
+    /// `RollingResult::redirects` and `get_redirects`. Overrides
+    /// `set_follow_redirects`.
+    ///
+    /// #### Arguments
+    ///
+    /// * `max_hops` - The maximum number of redirects followed.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    ///
+    /// let mut request = Request::get("http://example.com/short-link");
+    /// request.set_record_redirects(10);
+    /// assert_eq!(request.get_record_redirects(), Some(10));
+    /// ```
+    pub fn set_record_redirects(&mut self, max_hops: u32) -> &mut Self {
+        self.record_redirects = Some(max_hops);
+        self
+    }
+
+    /// Retrieves the maximum number of redirects recorded, if recording is enabled.
+    pub fn get_record_redirects(&self) -> Option<u32> {
+        self.record_redirects
+    }
+
+    /// Retrieves the redirects of the last attempt, when recording the chain.
+    pub fn get_redirects(&self) -> &[RedirectHop] {
+        &self.redirects
+    }
+
+    /// Sets the `Accept-Encoding` header and whether the response is decoded.
+    ///
+    /// With the `gzip`, `brotli` or `deflate` features, responses using those
+    /// encodings are decoded automatically. The response of this request is
+    /// decoded only if every encoding accepted here, other than `identity`,
+    /// can be decoded; otherwise the body is returned as received. Accepting
+    /// only `identity` therefore always returns the raw bytes.
+    ///
+    /// #### Arguments
+    ///
+    /// * `encoding` - The header value, e.g. `identity` or `br, gzip;q=0.8`.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    ///
+    /// let mut request = Request::get("http://example.com/broken-gzip");
+    /// request.set_accept_encoding("identity");
+    /// assert_eq!(request.get_headers()["accept-encoding"], "identity");
+    /// assert_eq!(request.get_decompress(), Some(false));
+    /// ```
+    pub fn set_accept_encoding(&mut self, encoding: &str) -> &mut Self {
+        let decodable: &[&str] = &[
+            #[cfg(feature = "gzip")]
+            "gzip",
+            #[cfg(feature = "brotli")]
+            "br",
+            #[cfg(feature = "deflate")]
+            "deflate",
+        ];
+        let mut accepted = encoding
+            .split(',')
+            .filter(|coding| {
+                // Codings weighted `q=0` are refused
+                !coding.split(';').skip(1).any(|param| {
+                    param
+                        .trim()
+                        .strip_prefix("q=")
+                        .and_then(|q| q.parse::<f32>().ok())
+                        == Some(0.0)
+                })
+            })
+            .filter_map(|coding| coding.split(';').next())
+            .map(str::trim)
+            .filter(|coding| !coding.is_empty() && !coding.eq_ignore_ascii_case("identity"))
+            .peekable();
+        let decompress = accepted.peek().is_some()
+            && accepted.all(|coding| {
+                decodable
+                    .iter()
+                    .any(|decodable| coding.eq_ignore_ascii_case(decodable))
+            });
+        self.decompress = Some(decompress);
+        self.add_header(ACCEPT_ENCODING.as_str(), encoding)
+    }
+
+    /// Sets whether a compressed response is decoded, when a compression
+    /// feature is enabled.
+    ///
+    /// #### Arguments
+    ///
+    /// * `decompress` - Whether to decode the response body.
+    pub fn set_decompress(&mut self, decompress: bool) -> &mut Self {
+        self.decompress = Some(decompress);
+        self
+    }
+
+    /// Retrieves whether a compressed response is decoded, if set.
+    pub fn get_decompress(&self) -> Option<bool> {
+        self.decompress
+    }
+
+    /// Sends the request through a proxy, used for every scheme.
+    ///
+    /// The URL is checked before the first attempt; an invalid one fails the
+    /// request with `RollingErrorKind::InvalidRequest`.
+    ///
+    /// #### Arguments
+    ///
+    /// * `proxy` - The proxy URL, e.g. `http://proxy.example.com:8080`.
+    pub fn set_proxy(&mut self, proxy: &str) -> &mut Self {
+        self.proxy = Some(proxy.to_string());
+        self
+    }
+
+    /// Retrieves the proxy of the request, if set.
+    pub fn get_proxy(&self) -> Option<&str> {
+        self.proxy.as_deref()
+    }
+
+    /// Sets the HTTP version the request is sent with.
+    ///
+    /// #### Arguments
+    ///
+    /// * `version` - The HTTP version, e.g. `Version::HTTP_11`.
+    pub fn set_http_version(&mut self, version: Version) -> &mut Self {
+        self.http_version = Some(version);
+        self
+    }
+
+    /// Retrieves the HTTP version of the request, if set.
+    pub fn get_http_version(&self) -> Option<Version> {
+        self.http_version
+    }
+
+    /// Sets the URL for the request.
+    ///
+    /// #### Arguments
+    ///
+    /// * `url` - The URL to set for the request.
+    pub fn set_url(&mut self, url: &str) -> &mut Self {
+        self.url = url.to_string();
+        self
+    }
+
+    /// Replaces the query parameters of the request.
+    ///
+    /// The parameters are percent-encoded and appended to any query already
+    /// present in the URL when the request is sent, including to fallback URLs.
+    ///
+    /// #### Arguments
+    ///
+    /// * `params` - The key/value pairs, in order; keys may repeat.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    ///
+    /// let mut request = Request::get("http://example.com/search?page=2");
+    /// request
+    ///     .set_query_params(&[("q", "rust & tokio")])
+    ///     .add_query_param("tag", "a")
+    ///     .add_query_param("tag", "b");
+    /// assert_eq!(request.get_query_params().len(), 3);
+    /// ```
+    pub fn set_query_params(&mut self, params: &[(&str, &str)]) -> &mut Self {
+        self.query_params = params
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        self
+    }
+
+    /// Replaces the query parameters of the request with a serialized value.
+    ///
+    /// Accepts anything `serde_urlencoded` can encode, such as a struct of
+    /// filters; fields set to `None` are left out. Parameters added afterwards
+    /// with `add_query_param` are appended after these ones.
+    ///
+    /// #### Arguments
+    ///
+    /// * `query` - The value to serialize into query parameters.
+    ///
+    /// #### Errors
+    ///
+    /// Returns an error if `query` cannot be encoded, e.g. because it is nested.
+    /// The request is left unchanged in that case.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    ///
+    /// let mut request = Request::get("http://example.com/items");
+    /// request
+    ///     .set_query(&[("sort", "name"), ("order", "asc")])
+    ///     .expect("Failed to encode query")
+    ///     .add_query_param("page", "2");
+    /// assert_eq!(request.get_query_params()[2], ("page".to_string(), "2".to_string()));
+    /// ```
+    pub fn set_query<T: Serialize + ?Sized>(
+        &mut self,
+        query: &T,
+    ) -> Result<&mut Self, serde_urlencoded::ser::Error> {
+        let encoded = serde_urlencoded::to_string(query)?;
+        // Decoding what was just encoded cannot fail
+        self.query_params = serde_urlencoded::from_str(&encoded).unwrap_or_default();
+        Ok(self)
+    }
+
+    /// Appends a query parameter to the request.
+    ///
+    /// #### Arguments
+    ///
+    /// * `key` - The parameter name.
+    /// * `value` - The parameter value.
+    pub fn add_query_param(&mut self, key: &str, value: &str) -> &mut Self {
+        self.query_params.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Retrieves the query parameters of the request.
+    pub fn get_query_params(&self) -> &[(String, String)] {
+        &self.query_params
+    }
+
+    /// Sets the values of `{name}` placeholders in the URL, replacing those set before.
+    ///
+    /// Placeholders are substituted in the URL and the fallback URLs when the
+    /// request is sent, with the values percent-encoded so that slashes and
+    /// spaces stay inside one path segment. A placeholder without a value fails
+    /// the request with `RollingErrorKind::InvalidRequest` instead of sending it.
+    ///
+    /// #### Arguments
+    ///
+    /// * `params` - The placeholder names and their values.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    ///
+    /// let mut request = Request::get("http://example.com/{region}/items/{id}");
+    /// request.set_path_params(&[("id", "42"), ("region", "eu")]);
+    /// ```
+    pub fn set_path_params(&mut self, params: &[(&str, &str)]) -> &mut Self {
+        self.path_params = params
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        self
+    }
+
+    /// Retrieves the values of the URL placeholders.
+    pub fn get_path_params(&self) -> &[(String, String)] {
+        &self.path_params
+    }
+
+    /// Retrieves the URL for the request.
+    pub fn get_url(&self) -> &String {
+        &self.url
+    }
+
+    /// Adds a URL to try when the request fails.
+    ///
+    /// Fallback URLs are tried in the order they were added when an attempt
+    /// fails to connect, times out, or gets one of the configured fallback
+    /// statuses. The response's `url()` tells which URL served the request.
+    ///
+    /// #### Arguments
+    ///
+    /// * `url` - The URL to fall back to.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    ///
+    /// let mut request = Request::get("http://primary.example.com/file");
+    /// request.add_fallback_url("http://mirror.example.com/file");
+    /// assert_eq!(request.get_fallback_urls().len(), 1);
+    /// ```
+    pub fn add_fallback_url(&mut self, url: &str) -> &mut Self {
+        self.fallback_urls.push(url.to_string());
+        self
+    }
+
+    /// Retrieves the fallback URLs of the request, in the order they are tried.
+    pub fn get_fallback_urls(&self) -> &[String] {
+        &self.fallback_urls
+    }
+
+    /// Retrieves the lowercase host of the request URL.
+    ///
+    /// Returns `None` when the URL cannot be parsed or has no host.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    ///
+    /// let request = Request::get("http://Example.com/path");
+    /// assert_eq!(request.get_host().as_deref(), Some("example.com"));
+    /// ```
+    pub fn get_host(&self) -> Option<String> {
+        Url::parse(&self.url)
+            .ok()
+            .and_then(|url| url.host_str().map(|host| host.to_ascii_lowercase()))
+    }
+
+    /// Adds a text field to the multipart form data.
+    ///
+    /// #### Arguments
+    ///
+    /// * `name` - The name of the form field.
+    /// * `value` - The value of the form field.
+    pub fn add_form_text(&mut self, name: &str, value: &str) -> &mut Self {
+        self.form_mut().push(FormPart::Text {
+            name: name.to_string(),
+            value: value.to_string(),
+        });
+        self
+    }
+
+    /// Adds a file to the multipart form data.
+    ///
+    /// The file is read into memory when this is called, not when the request
+    /// is sent. On error the form data is left unchanged. The part is announced
+    /// with the content type inferred from the file extension, e.g. `image/png`
+    /// for `.png`, or `application/octet-stream` for unknown extensions; use
+    /// `add_form_file_with_mime` to choose it.
+    ///
+    /// #### Arguments
+    ///
+    /// * `name` - The name of the form field.
+    /// * `file_path` - The path to the file to add.
+    ///
+    /// #### Errors
+    ///
+    /// Returns an error if the file cannot be read, e.g. because it does not
+    /// exist or is a directory, or if the path does not end in a file name.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    /// use std::path::Path;
+    ///
+    /// let mut request = Request::post("http://example.com/upload", None);
+    /// assert!(request.add_form_file("file", Path::new("/nonexistent")).is_err());
+    /// ```
+    pub fn add_form_file(&mut self, name: &str, file_path: &Path) -> io::Result<&mut Self> {
+        self.push_form_file(name, file_path, None)
+    }
+
+    /// Adds a file with an explicit content type to the multipart form data.
+    ///
+    /// Behaves like `add_form_file`, but announces the part as `mime`.
+    ///
+    /// #### Arguments
+    ///
+    /// * `name` - The name of the form field.
+    /// * `file_path` - The path to the file to add.
+    /// * `mime` - The content type of the part, e.g. `"image/png"`.
+    ///
+    /// #### Errors
+    ///
+    /// Returns an error if the file cannot be read, if the path does not end
+    /// in a file name, or if `mime` is not a valid MIME type.
+    pub fn add_form_file_with_mime(
+        &mut self,
+        name: &str,
+        file_path: &Path,
+        mime: &str,
+    ) -> io::Result<&mut Self> {
+        self.push_form_file(name, file_path, Some(mime))
+    }
+
+    /// Adds in-memory content to the multipart form data.
+    ///
+    /// #### Arguments
+    ///
+    /// * `name` - The name of the form field.
+    /// * `bytes` - The content of the part.
+    /// * `file_name` - The file name announced for the part, if any.
+    /// * `mime` - The content type of the part, if any.
+    ///
+    /// #### Errors
+    ///
+    /// Returns an error if `mime` is not a valid MIME type. The form data is
+    /// left unchanged in that case.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    ///
+    /// let mut request = Request::post("http://example.com/upload", None);
+    /// let csv = b"id,name\n1,alice\n".to_vec();
+    /// request
+    ///     .add_form_bytes("export", csv, Some("export.csv"), Some("text/csv"))
+    ///     .expect("Failed to add export");
+    /// assert!(request.add_form_bytes("bad", Vec::new(), None, Some("not a mime")).is_err());
+    /// ```
+    pub fn add_form_bytes(
+        &mut self,
+        name: &str,
+        bytes: Vec<u8>,
+        file_name: Option<&str>,
+        mime: Option<&str>,
+    ) -> io::Result<&mut Self> {
+        if let Some(mime) = mime {
+            validate_mime(mime)?;
+        }
+        self.form_mut().push(FormPart::Bytes {
+            name: name.to_string(),
+            data: bytes,
+            file_name: file_name.map(str::to_string),
+            mime: mime.map(str::to_string),
+        });
+        Ok(self)
+    }
+
+    /// Adds a file to the multipart form data, streaming it from disk.
+    ///
+    /// Unlike `add_form_file`, the file is only opened while the request is
+    /// sent and is read in chunks, so large files are never held in memory.
+    /// Its current size is sent as the part's content length, and its content
+    /// type is inferred from the extension as by `add_form_file`.
+    ///
+    /// #### Arguments
+    ///
+    /// * `name` - The name of the form field.
+    /// * `file_path` - The path to the file to add.
+    ///
+    /// #### Errors
+    ///
+    /// Returns an error if the file does not exist, is not a regular file, or
+    /// if the path does not end in a file name.
+    ///
+    /// #### Examples
+    ///
+    /// ```no_run
+    /// use rollingrequests::request::Request;
+    /// use std::path::Path;
+    ///
+    /// let mut request = Request::post("http://example.com/upload", None);
+    /// request
+    ///     .add_form_file_streaming("video", Path::new("/data/video.mp4"))
+    ///     .expect("Failed to add video");
+    /// ```
+    pub fn add_form_file_streaming(
+        &mut self,
+        name: &str,
+        file_path: &Path,
+    ) -> io::Result<&mut Self> {
+        self.push_streamed_file(name, file_path, None)
+    }
+
+    /// Adds a file with an explicit content type, streaming it from disk.
+    ///
+    /// Behaves like `add_form_file_streaming`, but announces the part as `mime`.
+    ///
+    /// #### Arguments
+    ///
+    /// * `name` - The name of the form field.
+    /// * `file_path` - The path to the file to add.
+    /// * `mime` - The content type of the part, e.g. `"video/mp4"`.
+    ///
+    /// #### Errors
+    ///
+    /// Returns an error if the file does not exist, is not a regular file, if
+    /// the path does not end in a file name, or if `mime` is not a valid MIME type.
+    pub fn add_form_file_streaming_with_mime(
+        &mut self,
+        name: &str,
+        file_path: &Path,
+        mime: &str,
+    ) -> io::Result<&mut Self> {
+        self.push_streamed_file(name, file_path, Some(mime))
+    }
+
+    /// Reads a file into a multipart part announced as `mime`, or as the
+    /// content type inferred from its extension.
+    fn push_form_file(
+        &mut self,
+        name: &str,
+        file_path: &Path,
+        mime: Option<&str>,
+    ) -> io::Result<&mut Self> {
+        let file_name = file_name_of(file_path)?;
+        if let Some(limit) = self.max_body_size {
+            let size = fs::metadata(file_path)?.len();
+            if size > limit {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    BodyTooLarge { size, limit },
+                ));
+            }
+        }
+        let data = fs::read(file_path)?;
+        let mime = mime.unwrap_or_else(|| guess_mime(&file_name));
+        self.add_form_bytes(name, data, Some(&file_name), Some(mime))
+    }
+
+    /// Adds a multipart part streamed from a file and announced as `mime`, or
+    /// as the content type inferred from its extension.
+    fn push_streamed_file(
+        &mut self,
+        name: &str,
+        file_path: &Path,
+        mime: Option<&str>,
+    ) -> io::Result<&mut Self> {
+        if let Some(mime) = mime {
+            validate_mime(mime)?;
+        }
+        let file_name = file_name_of(file_path)?;
+        let metadata = fs::metadata(file_path)?;
+        if !metadata.is_file() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is not a regular file", file_path.display()),
+            ));
+        }
+
+        self.form_mut().push(FormPart::File(StreamedFile {
+            name: name.to_string(),
+            path: file_path.to_path_buf(),
+            content_length: Some(metadata.len()),
+            mime: Some(mime.unwrap_or_else(|| guess_mime(&file_name)).to_string()),
+            file_name,
+        }));
+        Ok(self)
+    }
+
+    /// Sets the multipart form data for the request.
     ///
     /// #### Arguments
     ///
     /// * `form_data` - The multipart form data to set.
-    pub fn set_multipart_form_data(&mut self, form_data: Form) -> &mut Self {
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::{FormPart, MultipartForm, Request};
+    ///
+    /// let mut form = MultipartForm::new();
+    /// form.push(FormPart::Text {
+    ///     name: "field".to_string(),
+    ///     value: "value".to_string(),
+    /// });
+    ///
+    /// let mut request = Request::post("http://example.com/upload", None);
+    /// request.set_multipart_form_data(form);
+    /// assert_eq!(request.get_multipart_form_data().unwrap().parts().len(), 1);
+    /// ```
+    pub fn set_multipart_form_data(&mut self, form_data: MultipartForm) -> &mut Self {
         self.multipart_form_data = Some(form_data);
         self
     }
+
+    /// Retrieves the multipart form data of the request.
+    pub fn get_multipart_form_data(&self) -> Option<&MultipartForm> {
+        self.multipart_form_data.as_ref()
+    }
+
+    /// Sets the header `name` unless the request already has it, in any case.
+    pub(crate) fn set_default_header(&mut self, name: &str, value: &str) {
+        if !self.headers.contains_key(name) {
+            self.add_header(name, value);
+        }
+    }
+
+    /// Converts a header, recording it in `header_errors` when it is invalid.
+    fn parse_header(&mut self, key: &str, value: &str) -> Option<(HeaderName, HeaderValue)> {
+        match (
+            HeaderName::from_bytes(key.as_bytes()),
+            HeaderValue::from_str(value),
+        ) {
+            (Ok(name), Ok(value)) => Some((name, value)),
+            _ => {
+                self.header_errors.push(InvalidHeader {
+                    name: key.to_string(),
+                    value: value.to_string(),
+                });
+                None
+            }
+        }
+    }
+
+    /// Substitutes the path parameters for the `{name}` placeholders in `url`.
+    ///
+    /// Returns the name of the first placeholder without a value as the error.
+    pub(crate) fn expand_url(&self, url: &str) -> Result<String, String> {
+        let mut expanded = String::with_capacity(url.len());
+        let mut rest = url;
+        while let Some(start) = rest.find('{') {
+            expanded.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let name = rest[1..]
+                .find('}')
+                .map(|end| &rest[1..end + 1])
+                .filter(|name| {
+                    !name.is_empty()
+                        && name
+                            .chars()
+                            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                });
+            let Some(name) = name else {
+                expanded.push('{');
+                rest = &rest[1..];
+                continue;
+            };
+            let value = self
+                .path_params
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value)
+                .ok_or_else(|| name.to_string())?;
+            expanded.push_str(&percent_encode(value));
+            rest = &rest[name.len() + 2..];
+        }
+        expanded.push_str(rest);
+        Ok(expanded)
+    }
+
+    /// Copies the known options into the settings they name, unless those
+    /// were set with their typed setters.
+    pub(crate) fn apply_options(&mut self) -> Result<(), InvalidOption> {
+        let invalid = |key: &str, value: &str| InvalidOption {
+            key: key.to_string(),
+            value: value.to_string(),
+        };
+        if let Some(value) = self.options.get("timeout_ms") {
+            let millis = value
+                .trim()
+                .parse()
+                .map_err(|_| invalid("timeout_ms", value))?;
+            self.timeout.get_or_insert(Duration::from_millis(millis));
+        }
+        if let Some(value) = self.options.get("follow_redirects") {
+            let follow = value
+                .trim()
+                .parse()
+                .map_err(|_| invalid("follow_redirects", value))?;
+            self.follow_redirects.get_or_insert(follow);
+        }
+        if let Some(value) = self.options.get("max_retries") {
+            let retries: u32 = value
+                .trim()
+                .parse()
+                .map_err(|_| invalid("max_retries", value))?;
+            self.max_attempts.get_or_insert(retries.saturating_add(1));
+        }
+        if let Some(value) = self.options.get("proxy") {
+            self.proxy.get_or_insert_with(|| value.trim().to_string());
+        }
+        if let Some(value) = self.options.get("http_version") {
+            let version = match value.trim() {
+                "1.0" => Version::HTTP_10,
+                "1.1" => Version::HTTP_11,
+                "2" | "2.0" => Version::HTTP_2,
+                _ => return Err(invalid("http_version", value)),
+            };
+            self.http_version.get_or_insert(version);
+        }
+        if let Some(proxy) = &self.proxy {
+            if reqwest::Proxy::all(proxy.as_str()).is_err() {
+                return Err(invalid("proxy", proxy));
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the options and checks that the request can be sent as built.
+    pub(crate) fn prepare(&mut self) -> Result<(), RollingError> {
+        self.apply_options()
+            .map_err(|error| RollingError::invalid_request(error.to_string()))?;
+        for url in std::iter::once(&self.url).chain(&self.fallback_urls) {
+            if let Err(name) = self.expand_url(url) {
+                return Err(RollingError::invalid_request(format!(
+                    "no value for URL placeholder {{{}}} in {:?}",
+                    name, url
+                )));
+            }
+        }
+        if let Some(header) = self.header_errors.first() {
+            return Err(RollingError::invalid_request(format!(
+                "invalid header {:?}: {:?}",
+                header.name,
+                redact(&header.name, &header.value)
+            )));
+        }
+        if let Some(checksum) = &self.expected_checksum {
+            if cfg!(not(feature = "checksum")) {
+                return Err(RollingError::invalid_request(
+                    "checksum verification requires the `checksum` feature".to_string(),
+                ));
+            }
+            if !checksum.is_valid() {
+                return Err(RollingError::invalid_request(format!(
+                    "invalid {} checksum {:?}",
+                    checksum.algorithm.name(),
+                    checksum.hex
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the body and each multipart part against the maximum body size.
+    ///
+    /// Files are measured by their current size; unreadable files are left
+    /// to fail when they are sent.
+    pub(crate) fn check_body_size(&self) -> Result<(), BodyTooLarge> {
+        let limit = match self.max_body_size {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+        let mut sizes: Vec<Option<u64>> = vec![
+            self.post_data.as_ref().map(|data| data.len() as u64),
+            self.body_file.as_deref().and_then(file_size),
+        ];
+        if let Some(form) = &self.multipart_form_data {
+            sizes.extend(form.parts().iter().map(|part| match part {
+                FormPart::Text { value, .. } => Some(value.len() as u64),
+                FormPart::Bytes { data, .. } => Some(data.len() as u64),
+                FormPart::File(file) => file_size(&file.path),
+            }));
+        }
+        match sizes.into_iter().flatten().find(|size| *size > limit) {
+            Some(size) => Err(BodyTooLarge { size, limit }),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the size of the body, or `None` when it is unknown because a
+    /// file cannot be read; multipart framing is not counted.
+    pub(crate) fn body_size(&self) -> Option<u64> {
+        let mut size = self.post_data.as_ref().map_or(0, |data| data.len() as u64);
+        if let Some(path) = &self.body_file {
+            size += file_size(path)?;
+        }
+        if let Some(form) = &self.multipart_form_data {
+            for part in form.parts() {
+                size += match part {
+                    FormPart::Text { value, .. } => value.len() as u64,
+                    FormPart::Bytes { data, .. } => data.len() as u64,
+                    FormPart::File(file) => file_size(&file.path)?,
+                };
+            }
+        }
+        Some(size)
+    }
+
+    /// Returns the multipart form data, creating an empty form if needed.
+    fn form_mut(&mut self) -> &mut MultipartForm {
+        self.multipart_form_data
+            .get_or_insert_with(MultipartForm::new)
+    }
+}
+
+/// Percent-encodes the bytes of `value` that are not cookie octets of RFC 6265,
+/// as well as `=` and `%`.
+fn encode_cookie_value(value: &str) -> String {
+    value.bytes().fold(String::new(), |mut encoded, byte| {
+        let is_cookie_octet = matches!(byte, 0x21..=0x7E)
+            && !matches!(byte, b'"' | b',' | b';' | b'\\' | b'=' | b'%');
+        if is_cookie_octet {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+        encoded
+    })
+}
+
+/// Percent-encodes every byte of `value` except the unreserved characters of RFC 3986.
+fn percent_encode(value: &str) -> String {
+    value.bytes().fold(String::new(), |mut encoded, byte| {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+        encoded
+    })
+}
+
+/// Returns the final component of `path` as the file name announced in a part.
+fn file_name_of(path: &Path) -> io::Result<String> {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} does not name a file", path.display()),
+            )
+        })
 }
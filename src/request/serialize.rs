@@ -0,0 +1,242 @@
+//! `Serialize` and `Deserialize` for `Request`, enabled by the `serde` feature.
+
+use crate::request::form::guess_mime;
+use crate::request::{FormPart, MultipartForm, Request, StreamedFile};
+use reqwest::Method;
+use reqwest::header::{HeaderName, HeaderValue};
+use serde::de::{self, Deserializer};
+use serde::ser::{self, Serializer};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// The serialized form of a `Request`.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RequestRepr {
+    url: String,
+    method: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    headers: Vec<(String, String)>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    cookies: Vec<(String, String)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body_bytes: Option<Vec<u8>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body_file: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    output_file: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    query: Vec<(String, String)>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    path_params: Vec<(String, String)>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    options: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    extra_info: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    multipart: Option<Vec<PartRepr>>,
+}
+
+/// The serialized form of a multipart part.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+enum PartRepr {
+    Text {
+        name: String,
+        value: String,
+    },
+    File {
+        name: String,
+        path: PathBuf,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        file_name: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        content_length: Option<u64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        mime: Option<String>,
+    },
+}
+
+/// With the `serde` feature, a request is represented as a map with the
+/// following entries, all but `url` and `method` optional:
+///
+/// * `url` - The URL, as given.
+/// * `method` - The method name as sent on the request line, e.g. `"GET"` or
+///   `"PURGE"`. Names are case-sensitive.
+/// * `headers` - A list of `[name, value]` pairs in the order they are sent.
+/// * `cookies` - A list of `[name, value]` pairs, with the values encoded as
+///   by `Request::add_cookie`.
+/// * `body` - The body as text, or `body_bytes` as a list of bytes when it is
+///   not valid UTF-8.
+/// * `body_file` - The path of a file streamed as the body.
+/// * `output_file` - The path of the file the response body is written to.
+/// * `query` and `path_params` - Lists of `[name, value]` pairs.
+/// * `options` - A map of options.
+/// * `extra_info` - The extra information.
+/// * `multipart` - A list of parts tagged by `kind`: `{"kind": "text", "name",
+///   "value"}` or `{"kind": "file", "name", "path", "file_name", "content_length",
+///   "mime"}`. Files are referenced by path and read when the request is sent,
+///   and their content type is inferred from the extension when `mime` is absent.
+///   Parts held in memory cannot be serialized.
+///
+/// Everything else, such as retry settings, response fields and metadata, is
+/// left out.
+impl Serialize for Request {
+    /// Serializes the request in the representation described above.
+    ///
+    /// Fails for header values that are not valid UTF-8 and for multipart
+    /// parts held in memory.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let headers = self
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                value
+                    .to_str()
+                    .map(|value| (name.to_string(), value.to_string()))
+                    .map_err(|_| ser::Error::custom(format!("header {} is not valid UTF-8", name)))
+            })
+            .collect::<Result<_, _>>()?;
+        let (body, body_bytes) = match &self.post_data {
+            Some(data) => match String::from_utf8(data.clone()) {
+                Ok(text) => (Some(text), None),
+                Err(_) => (None, Some(data.clone())),
+            },
+            None => (None, None),
+        };
+        let multipart = self
+            .multipart_form_data
+            .as_ref()
+            .map(|form| {
+                form.parts()
+                    .iter()
+                    .map(|part| match part {
+                        FormPart::Text { name, value } => Ok(PartRepr::Text {
+                            name: name.clone(),
+                            value: value.clone(),
+                        }),
+                        FormPart::File(file) => Ok(PartRepr::File {
+                            name: file.name.clone(),
+                            path: file.path.clone(),
+                            file_name: Some(file.file_name.clone()),
+                            content_length: file.content_length,
+                            mime: file.mime.clone(),
+                        }),
+                        FormPart::Bytes { name, .. } => Err(ser::Error::custom(format!(
+                            "multipart part {:?} is held in memory and cannot be serialized",
+                            name
+                        ))),
+                    })
+                    .collect::<Result<_, _>>()
+            })
+            .transpose()?;
+
+        RequestRepr {
+            url: self.url.clone(),
+            method: self.method.to_string(),
+            headers,
+            cookies: self.cookies.clone(),
+            body,
+            body_bytes,
+            body_file: self.body_file.clone(),
+            output_file: self.output_file.clone(),
+            query: self.query_params.clone(),
+            path_params: self.path_params.clone(),
+            options: self.options.clone(),
+            extra_info: self.extra_info.clone(),
+            multipart,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Request {
+    /// Deserializes a request from the representation described on `Serialize`.
+    ///
+    /// Fails for invalid methods, headers and cookies, and when both `body`
+    /// and `body_bytes` are given.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = RequestRepr::deserialize(deserializer)?;
+        let method = Method::from_bytes(repr.method.as_bytes())
+            .map_err(|_| de::Error::custom(format!("invalid method {:?}", repr.method)))?;
+
+        let mut request = Request::new(&repr.url, method);
+        for (name, value) in repr.headers {
+            match (
+                HeaderName::from_bytes(name.as_bytes()),
+                HeaderValue::from_str(&value),
+            ) {
+                (Ok(name), Ok(value)) => {
+                    request.headers.append(name, value);
+                }
+                _ => {
+                    return Err(de::Error::custom(format!(
+                        "invalid header {:?}: {:?}",
+                        name, value
+                    )));
+                }
+            }
+        }
+        for (name, value) in repr.cookies {
+            let is_encoded = value
+                .bytes()
+                .all(|byte| matches!(byte, 0x21..=0x7E) && !b"\",;\\=".contains(&byte));
+            if HeaderName::from_bytes(name.as_bytes()).is_err() || !is_encoded {
+                return Err(de::Error::custom(format!(
+                    "invalid cookie {:?}: {:?}",
+                    name, value
+                )));
+            }
+            request.cookies.push((name, value));
+        }
+        request.post_data = match (repr.body, repr.body_bytes) {
+            (Some(_), Some(_)) => {
+                return Err(de::Error::custom(
+                    "only one of body and body_bytes may be given",
+                ));
+            }
+            (Some(text), None) => Some(text.into_bytes()),
+            (None, bytes) => bytes,
+        };
+        request.body_file = repr.body_file;
+        request.output_file = repr.output_file;
+        request.query_params = repr.query;
+        request.path_params = repr.path_params;
+        request.options = repr.options;
+        request.extra_info = repr.extra_info;
+        request.multipart_form_data = repr.multipart.map(|parts| {
+            let mut form = MultipartForm::new();
+            for part in parts {
+                form.push(match part {
+                    PartRepr::Text { name, value } => FormPart::Text { name, value },
+                    PartRepr::File {
+                        name,
+                        path,
+                        file_name,
+                        content_length,
+                        mime,
+                    } => {
+                        let file_name = file_name.unwrap_or_else(|| {
+                            path.file_name()
+                                .map(|name| name.to_string_lossy().into_owned())
+                                .unwrap_or_default()
+                        });
+                        let mime = mime.or_else(|| Some(guess_mime(&file_name).to_string()));
+                        FormPart::File(StreamedFile {
+                            name,
+                            path,
+                            file_name,
+                            content_length,
+                            mime,
+                        })
+                    }
+                });
+            }
+            form
+        });
+        Ok(request)
+    }
+}
@@ -0,0 +1,372 @@
+//! Detailed results of executed requests.
+//!
+//! This module provides the `RollingResult` type returned by
+//! `RollingRequests::execute_requests_detailed` and
+//! `RollingRequests::execute_all_detailed`, which reads every response body
+//! and keeps it together with what the scheduler knows about the request,
+//! the `RedirectHop` entries of recorded redirect chains, the `JsonError`
+//! returned when decoding a result as JSON, and the `ResultOverflow` policy
+//! of the buffer filled by `RollingRequests::execute_in_background`.
+
+use crate::download::Download;
+use crate::error::RollingError;
+use crate::request::{Metadata, Request, redact_url};
+use reqwest::header::HeaderMap;
+use reqwest::{StatusCode, Url};
+use serde::de::DeserializeOwned;
+use std::any::Any;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+
+/// The outcome of one request, with its response read into memory.
+#[derive(Debug)]
+pub struct RollingResult {
+    /// The identifier assigned when the request was queued.
+    pub request_id: Option<u64>,
+    /// The extra information of the request.
+    pub extra_info: Option<String>,
+    /// The metadata of the request.
+    pub metadata: Option<Metadata>,
+    /// The status of the final response, also set for errors raised by
+    /// `error_for_status`.
+    pub status: Option<StatusCode>,
+    /// The URL of the final response after redirects, or the URL that failed.
+    pub url: Option<Url>,
+    /// The headers of the final response, kept whether or not the body is;
+    /// empty when no response was received.
+    pub headers: HeaderMap,
+    /// The body of the final response, up to the `result_body_limit`; empty
+    /// when it was written to the output file.
+    pub body: Vec<u8>,
+    /// The output file the body was written to, see `Request::set_output_file`
+    /// and `RollingRequestsBuilder::save_responses_to`.
+    pub download: Option<Download>,
+    /// Whether the body was cut off at the `result_body_limit`.
+    pub body_truncated: bool,
+    /// The name of the encoding `text` decodes the body with, from its byte
+    /// order mark, `Content-Type` or HTML `meta` tag, or the default charset;
+    /// `None` when the body was not read into memory.
+    #[cfg(feature = "charset")]
+    pub charset: Option<&'static str>,
+    /// The time from the first attempt until the body was read; zero for
+    /// requests rejected before sending.
+    pub elapsed: Duration,
+    /// The number of attempts made.
+    pub attempts: u32,
+    /// The time spent queued and on each attempt.
+    pub timing: Timing,
+    /// Whether the server rejected HEAD under `link_check` and the request
+    /// was sent as GET.
+    pub head_fallback: bool,
+    /// Whether the response was served from the response cache.
+    pub cached: bool,
+    /// The proxy of the pool the last attempt was sent through, with its
+    /// password redacted, see `RollingRequestsBuilder::proxy_pool`.
+    pub proxy: Option<String>,
+    /// Whether the server answered `304 Not Modified`, also set when the
+    /// cached response replaced that answer, see `ValidatorStore`.
+    pub not_modified: bool,
+    /// The page counter of the request, see `Request::set_max_pages`.
+    pub page: u32,
+    /// The redirects of the last attempt, recorded for requests with
+    /// `Request::set_record_redirects`.
+    pub redirects: Vec<RedirectHop>,
+    /// The `ErrorCode` of the error, or `0` without one.
+    pub code: u16,
+    /// The error of the request, or of reading its body.
+    pub error: Option<RollingError>,
+}
+
+impl RollingResult {
+    /// Creates the result of `request` before its response is known.
+    pub(crate) fn new(request: &Request) -> Self {
+        RollingResult {
+            request_id: request.id,
+            extra_info: request.extra_info.clone(),
+            metadata: request.metadata.clone(),
+            status: None,
+            url: None,
+            headers: HeaderMap::new(),
+            body: Vec::new(),
+            download: None,
+            body_truncated: false,
+            #[cfg(feature = "charset")]
+            charset: None,
+            elapsed: Duration::ZERO,
+            attempts: request.attempts,
+            timing: Timing::default(),
+            head_fallback: request.head_fallback,
+            cached: request.cached,
+            proxy: request
+                .pooled_proxy
+                .as_ref()
+                .map(|(_, url)| redact_url(url)),
+            not_modified: request.not_modified,
+            page: request.page,
+            redirects: request.redirects.clone(),
+            code: 0,
+            error: None,
+        }
+    }
+
+    /// Sets the `code`, `elapsed` and `timing` of a result whose response
+    /// or error is known, for a request first dispatched at `started_at`.
+    pub(crate) fn finish(
+        &mut self,
+        started_at: Option<Instant>,
+        queued_at: Option<Instant>,
+        attempt_latencies: Vec<Duration>,
+    ) {
+        self.code = self.error.as_ref().map_or(0, RollingError::code);
+        self.elapsed = started_at.map_or(Duration::ZERO, |at| at.elapsed());
+        let queue_wait = started_at
+            .zip(queued_at)
+            .map_or(Duration::ZERO, |(started, queued)| {
+                started.saturating_duration_since(queued)
+            });
+        self.timing = Timing {
+            queue_wait,
+            time_to_first_byte: self.status.and(attempt_latencies.last().copied()),
+            attempts: attempt_latencies,
+            total: queue_wait + self.elapsed,
+        };
+    }
+
+    /// Returns true if a response with a success status was read without error.
+    pub fn is_success(&self) -> bool {
+        self.error.is_none() && self.status.is_some_and(|status| status.is_success())
+    }
+
+    /// Returns the body as text, replacing invalid sequences.
+    ///
+    /// The body is decoded with its `charset` under the `charset` feature,
+    /// and as UTF-8 otherwise.
+    pub fn text(&self) -> String {
+        #[cfg(feature = "charset")]
+        if let Some(encoding) = self
+            .charset
+            .and_then(|name| encoding_rs::Encoding::for_label(name.as_bytes()))
+        {
+            return crate::charset::decode(&self.body, encoding);
+        }
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    /// Returns the metadata of the request if it is a `T`.
+    pub fn metadata<T: Any>(&self) -> Option<&T> {
+        self.metadata.as_ref().and_then(Metadata::get)
+    }
+
+    /// Deserializes the body as JSON.
+    ///
+    /// A body cut off at the `result_body_limit` usually fails to decode.
+    ///
+    /// #### Examples
+    ///
+    /// ```no_run
+    /// use rollingrequests::request::Request;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct User {
+    ///     name: String,
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut rolling_requests = RollingRequestsBuilder::new().build();
+    ///     rolling_requests.add_request(Request::get("http://example.com/users/1"));
+    ///
+    ///     for result in rolling_requests.execute_requests_detailed().await {
+    ///         match result.json::<User>() {
+    ///             Ok(user) => println!("{}", user.name),
+    ///             Err(error) => eprintln!("{}", error),
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// #### Errors
+    ///
+    /// * `JsonError::Status` - The response had a non-success status.
+    /// * `JsonError::Request` - The request failed, or its body could not be read.
+    /// * `JsonError::Decode` - The body is not valid JSON for `T`.
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, JsonError> {
+        if let Some(status) = self.status.filter(|status| !status.is_success()) {
+            return Err(JsonError::Status {
+                status,
+                body: self.body.clone(),
+            });
+        }
+        if let Some(error) = &self.error {
+            return Err(JsonError::Request {
+                code: error.code(),
+                message: error.to_string(),
+            });
+        }
+        serde_json::from_slice(&self.body).map_err(|source| JsonError::Decode {
+            source,
+            body: self.body.clone(),
+        })
+    }
+}
+
+/// Where the time of a request went.
+///
+/// The HTTP client does not report connection setup separately, so
+/// `time_to_first_byte` includes connecting, TLS and the server's processing.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Timing {
+    /// The time from `add_request` until the first attempt was dispatched,
+    /// including pacing and concurrency waits; zero for requests rejected
+    /// before sending.
+    pub queue_wait: Duration,
+    /// The time from dispatch until the response headers or the failure of
+    /// each attempt, in order.
+    pub attempts: Vec<Duration>,
+    /// The time from dispatch of the last attempt until its response
+    /// headers, when a response was received.
+    pub time_to_first_byte: Option<Duration>,
+    /// The time from `add_request` until the body was read.
+    pub total: Duration,
+}
+
+/// A redirect followed by a request recording its redirect chain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RedirectHop {
+    /// The number of the hop, starting at `1`.
+    pub hop: u32,
+    /// The status of the redirect response.
+    pub status: StatusCode,
+    /// The URL that answered with the redirect.
+    pub url: Url,
+    /// The `Location` header of the redirect, as sent.
+    pub location: String,
+}
+
+/// What happens when a result arrives at a full result buffer, see
+/// `RollingRequestsBuilder::result_buffer_capacity`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ResultOverflow {
+    /// The oldest result is discarded to make room.
+    #[default]
+    DropOldest,
+    /// The background execution waits for `drain_results` to make room
+    /// before it starts more requests.
+    Block,
+}
+
+/// Holds the results of background executions until they are drained.
+pub(crate) struct ResultBuffer {
+    /// The buffered results, oldest first.
+    results: Mutex<VecDeque<RollingResult>>,
+    /// The maximum number of results kept, if any.
+    capacity: Option<usize>,
+    /// The free slots of a buffer that blocks when full.
+    space: Option<Semaphore>,
+}
+
+impl ResultBuffer {
+    /// Creates an empty buffer holding at most `capacity` results, if given.
+    pub(crate) fn new(capacity: Option<usize>, overflow: ResultOverflow) -> Self {
+        let space = match (capacity, overflow) {
+            // A blocking buffer keeps at least one result, or it would never fill
+            (Some(capacity), ResultOverflow::Block) => Some(Semaphore::new(capacity.max(1))),
+            _ => None,
+        };
+        ResultBuffer {
+            results: Mutex::new(VecDeque::new()),
+            capacity,
+            space,
+        }
+    }
+
+    /// Adds a result, applying the overflow policy when the buffer is full.
+    pub(crate) async fn push(&self, result: RollingResult) {
+        if let Some(space) = &self.space {
+            space
+                .acquire()
+                .await
+                .expect("the semaphore is never closed")
+                .forget();
+        }
+        let mut results = self.results.lock().unwrap();
+        if self.space.is_none()
+            && self
+                .capacity
+                .is_some_and(|capacity| results.len() >= capacity)
+            && results.pop_front().is_none()
+        {
+            return;
+        }
+        results.push_back(result);
+    }
+
+    /// Removes and returns every buffered result, oldest first.
+    pub(crate) fn drain(&self) -> Vec<RollingResult> {
+        let drained: Vec<_> = self.results.lock().unwrap().drain(..).collect();
+        if let Some(space) = &self.space {
+            space.add_permits(drained.len());
+        }
+        drained
+    }
+}
+
+/// The reason `RollingResult::json` could not decode a result.
+#[derive(Debug)]
+pub enum JsonError {
+    /// The request failed, or its body could not be read.
+    Request {
+        /// The `ErrorCode` of the error.
+        code: u16,
+        /// The message of the error.
+        message: String,
+    },
+    /// The response had a non-success status.
+    Status {
+        /// The status of the response.
+        status: StatusCode,
+        /// The body of the response.
+        body: Vec<u8>,
+    },
+    /// The body is not valid JSON for the requested type.
+    Decode {
+        /// Why the body was rejected.
+        source: serde_json::Error,
+        /// The body that failed to decode.
+        body: Vec<u8>,
+    },
+}
+
+impl JsonError {
+    /// Returns the body of the response, if one was read.
+    pub fn body(&self) -> Option<&[u8]> {
+        match self {
+            JsonError::Request { .. } => None,
+            JsonError::Status { body, .. } | JsonError::Decode { body, .. } => Some(body),
+        }
+    }
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonError::Request { message, .. } => write!(f, "request failed: {}", message),
+            JsonError::Status { status, .. } => write!(f, "unexpected status {}", status),
+            JsonError::Decode { source, .. } => write!(f, "failed to decode JSON body: {}", source),
+        }
+    }
+}
+
+impl std::error::Error for JsonError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            JsonError::Decode { source, .. } => Some(source),
+            JsonError::Request { .. } | JsonError::Status { .. } => None,
+        }
+    }
+}
@@ -0,0 +1,366 @@
+//! Automatic retries for failed requests.
+//!
+//! This module provides the `RetryPolicy` settings, which decide which failures
+//! are retried, how many times a request is attempted and how long the scheduler
+//! waits between attempts, the `RetryContext` passed to retry predicates, the
+//! `RetryBudget` capping retries across a whole run, and the dead-letter queue
+//! collecting requests that failed for good.
+
+use crate::error::RollingErrorKind;
+use crate::request::Request;
+use rand::Rng;
+use reqwest::{Method, StatusCode};
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// The number of attempts a request needed, stored in the extensions of the
+/// responses returned by `RollingRequests`.
+///
+/// #### Examples
+///
+/// ```no_run
+/// use rollingrequests::request::Request;
+/// use rollingrequests::retry::Attempts;
+/// use rollingrequests::rolling::RollingRequestsBuilder;
+/// use reqwest::Method;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let mut rolling_requests = RollingRequestsBuilder::new().build();
+///     rolling_requests.add_request(Request::new("http://example.com", Method::GET));
+///
+///     for response in rolling_requests.execute_requests().await.into_iter().flatten() {
+///         let attempts = response.extensions().get::<Attempts>().unwrap();
+///         println!("served after {} attempts", attempts.0);
+///     }
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Attempts(pub u32);
+
+/// A predicate deciding whether a failed attempt is retried.
+pub type RetryPredicate = Arc<dyn Fn(&RetryContext) -> bool + Send + Sync>;
+
+/// What a retry predicate knows about a failed attempt.
+pub struct RetryContext<'a> {
+    /// The number of attempts made so far, including the failed one.
+    pub attempt: u32,
+    /// The error of the attempt, if it did not produce a response.
+    pub error: Option<&'a reqwest::Error>,
+    /// The classification of `error`, if any.
+    pub kind: Option<RollingErrorKind>,
+    /// The status of the response, if the attempt produced one.
+    pub status: Option<StatusCode>,
+    /// The method of the request.
+    pub method: &'a Method,
+    /// The URL of the request.
+    pub url: &'a str,
+}
+
+/// Settings for retrying failed requests.
+#[derive(Clone)]
+pub struct RetryPolicy {
+    /// The total number of attempts, including the first one.
+    pub max_attempts: u32,
+    /// The delay before the first retry.
+    pub initial_delay: Duration,
+    /// The factor applied to the delay after every retry; a NaN factor waits
+    /// `max_delay` before every retry.
+    pub multiplier: f64,
+    /// The longest delay between two attempts.
+    pub max_delay: Duration,
+    /// Whether each delay is drawn uniformly between zero and its computed value.
+    pub jitter: bool,
+    /// Decides which failures are retried; `None` uses `default_retry_if`.
+    pub retry_if: Option<RetryPredicate>,
+}
+
+impl fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("initial_delay", &self.initial_delay)
+            .field("multiplier", &self.multiplier)
+            .field("max_delay", &self.max_delay)
+            .field("jitter", &self.jitter)
+            .field("retry_if", &self.retry_if.as_ref().map(|_| "<predicate>"))
+            .finish()
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,                           // Default attempts
+            initial_delay: Duration::from_millis(100), // Default first delay
+            multiplier: 2.0,                           // Default doubling
+            max_delay: Duration::from_secs(10),        // Default delay cap
+            jitter: true,                              // Default full jitter
+            retry_if: None,                            // Default default_retry_if
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Sets the predicate deciding which failures are retried.
+    ///
+    /// The predicate is only asked while attempts remain.
+    ///
+    /// #### Arguments
+    ///
+    /// * `predicate` - Returns true when the attempt described by the context should be retried.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::retry::RetryPolicy;
+    ///
+    /// // Only retry gateway errors, whatever the method
+    /// let policy = RetryPolicy::default().retry_if(|context| {
+    ///     context.status.is_some_and(|status| status.as_u16() == 502)
+    /// });
+    /// ```
+    pub fn retry_if<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&RetryContext) -> bool + Send + Sync + 'static,
+    {
+        self.retry_if = Some(Arc::new(predicate));
+        self
+    }
+
+    /// Returns whether the attempt described by `context` should be retried.
+    pub(crate) fn should_retry(&self, context: &RetryContext) -> bool {
+        match &self.retry_if {
+            Some(predicate) => predicate(context),
+            None => default_retry_if(context),
+        }
+    }
+
+    /// Returns how long to wait after the `attempts`-th failed attempt.
+    ///
+    /// A delay that cannot be computed, e.g. with a NaN `multiplier`, is
+    /// `max_delay`.
+    pub(crate) fn backoff(&self, attempts: u32) -> Duration {
+        let exponent = attempts.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let delay = self.initial_delay.as_secs_f64() * self.multiplier.powi(exponent);
+        let delay = match delay.is_nan() {
+            true => self.max_delay,
+            false => Duration::try_from_secs_f64(delay.max(0.0))
+                .map_or(self.max_delay, |delay| delay.min(self.max_delay)),
+        };
+        if self.jitter {
+            delay.mul_f64(rand::thread_rng().gen_range(0.0..=1.0))
+        } else {
+            delay
+        }
+    }
+}
+
+/// The predicate used when a policy has none.
+///
+/// Retries requests with an idempotent method (GET, HEAD, PUT, DELETE, OPTIONS
+/// and TRACE) that failed to connect, timed out, lost their connection, or were
+/// answered with 502, 503 or 504.
+///
+/// #### Arguments
+///
+/// * `context` - The failed attempt.
+pub fn default_retry_if(context: &RetryContext) -> bool {
+    let idempotent = is_idempotent(context.method);
+    let transient = match (context.error, context.status) {
+        (Some(error), _) => error.is_connect() || error.is_timeout() || error.is_request(),
+        (None, Some(status)) => matches!(status.as_u16(), 502..=504),
+        (None, None) => false,
+    };
+    idempotent && transient
+}
+
+/// Returns whether sending a request with `method` twice has the same effect as once.
+///
+/// Extension methods such as `PURGE` are assumed not to be idempotent.
+pub(crate) fn is_idempotent(method: &Method) -> bool {
+    [
+        Method::GET,
+        Method::HEAD,
+        Method::PUT,
+        Method::DELETE,
+        Method::OPTIONS,
+        Method::TRACE,
+    ]
+    .contains(method)
+}
+
+/// A cap on the retries made across every request within a sliding window.
+#[derive(Clone, Debug)]
+pub enum RetryBudget {
+    /// Retries may make up at most `ratio` of the first attempts dispatched within `window`.
+    Ratio {
+        /// The allowed number of retries per first attempt, e.g. 0.2 for 20%.
+        ratio: f64,
+        /// The length of the sliding window.
+        window: Duration,
+    },
+    /// At most `retries` retries may be made within `window`.
+    Fixed {
+        /// The allowed number of retries.
+        retries: usize,
+        /// The length of the sliding window.
+        window: Duration,
+    },
+}
+
+impl RetryBudget {
+    /// Returns the length of the sliding window.
+    fn window(&self) -> Duration {
+        match self {
+            RetryBudget::Ratio { window, .. } | RetryBudget::Fixed { window, .. } => *window,
+        }
+    }
+}
+
+/// A snapshot of the retries made by a `RollingRequests` instance.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RetryStats {
+    /// The number of retries dispatched.
+    pub retries: u64,
+    /// The number of retries refused because the budget was exhausted.
+    pub denied: u64,
+    /// The retries left in the current window, when a budget is configured.
+    pub budget_remaining: Option<usize>,
+}
+
+/// Counts retries and enforces the retry budget.
+pub(crate) struct RetryTracker {
+    /// The budget to enforce, if any.
+    budget: Option<RetryBudget>,
+    /// When first attempts were dispatched within the window.
+    first_attempts: VecDeque<Instant>,
+    /// When retries were granted within the window.
+    granted: VecDeque<Instant>,
+    /// The number of retries granted so far.
+    retries: u64,
+    /// The number of retries refused so far.
+    denied: u64,
+}
+
+impl RetryTracker {
+    /// Creates a tracker enforcing `budget`, if any.
+    pub(crate) fn new(budget: Option<RetryBudget>) -> Self {
+        RetryTracker {
+            budget,
+            first_attempts: VecDeque::new(),
+            granted: VecDeque::new(),
+            retries: 0,
+            denied: 0,
+        }
+    }
+
+    /// Records the first attempt of a request dispatched at `now`.
+    pub(crate) fn record_first_attempt(&mut self, now: Instant) {
+        if let Some(RetryBudget::Ratio { .. }) = self.budget {
+            self.first_attempts.push_back(now);
+        }
+    }
+
+    /// Takes one retry from the budget, returning false when it is exhausted.
+    pub(crate) fn try_retry(&mut self, now: Instant) -> bool {
+        if self.remaining(now).is_some_and(|remaining| remaining == 0) {
+            self.denied += 1;
+            return false;
+        }
+        if self.budget.is_some() {
+            self.granted.push_back(now);
+        }
+        self.retries += 1;
+        true
+    }
+
+    /// Returns a snapshot of the retries made so far.
+    pub(crate) fn stats(&mut self, now: Instant) -> RetryStats {
+        RetryStats {
+            retries: self.retries,
+            denied: self.denied,
+            budget_remaining: self.remaining(now),
+        }
+    }
+
+    /// Returns the retries left in the window ending at `now`.
+    fn remaining(&mut self, now: Instant) -> Option<usize> {
+        let budget = self.budget.as_ref()?;
+        let start = now.checked_sub(budget.window());
+        let expired = |at: &Instant| start.is_some_and(|start| *at < start);
+        while self.first_attempts.front().is_some_and(expired) {
+            self.first_attempts.pop_front();
+        }
+        while self.granted.front().is_some_and(expired) {
+            self.granted.pop_front();
+        }
+
+        let allowed = match budget {
+            RetryBudget::Ratio { ratio, .. } => (self.first_attempts.len() as f64 * ratio) as usize,
+            RetryBudget::Fixed { retries, .. } => *retries,
+        };
+        Some(allowed.saturating_sub(self.granted.len()))
+    }
+}
+
+/// What happens when a failed request arrives at a full dead-letter queue.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DeadLetterOverflow {
+    /// The oldest failed request is discarded to make room.
+    #[default]
+    DropOldest,
+    /// The newly failed request is discarded.
+    DropNewest,
+}
+
+/// Collects requests whose final result was an error.
+pub(crate) struct DeadLetterQueue {
+    /// The failed requests, oldest first.
+    requests: VecDeque<Request>,
+    /// The maximum number of requests kept, if any.
+    capacity: Option<usize>,
+    /// What to do when the queue is full.
+    overflow: DeadLetterOverflow,
+}
+
+impl DeadLetterQueue {
+    /// Creates an empty queue holding at most `capacity` requests, if given.
+    pub(crate) fn new(capacity: Option<usize>, overflow: DeadLetterOverflow) -> Self {
+        DeadLetterQueue {
+            requests: VecDeque::new(),
+            capacity,
+            overflow,
+        }
+    }
+
+    /// Adds a failed request, applying the overflow policy when the queue is full.
+    pub(crate) fn push(&mut self, request: Request) {
+        if self
+            .capacity
+            .is_some_and(|capacity| self.requests.len() >= capacity)
+        {
+            match self.overflow {
+                DeadLetterOverflow::DropOldest => {
+                    if self.requests.pop_front().is_none() {
+                        return;
+                    }
+                }
+                DeadLetterOverflow::DropNewest => return,
+            }
+        }
+        self.requests.push_back(request);
+    }
+
+    /// Returns clones of the failed requests, oldest first.
+    pub(crate) fn snapshot(&self) -> Vec<Request> {
+        self.requests.iter().cloned().collect()
+    }
+
+    /// Removes and returns every failed request, oldest first.
+    pub(crate) fn drain(&mut self) -> Vec<Request> {
+        self.requests.drain(..).collect()
+    }
+}
@@ -4,16 +4,83 @@
 //! a collection of HTTP requests and execute them with a limit on the number
 //! of simultaneous requests.
 
-use crate::request::Request;
-use reqwest::{
-    Client,
-    header::{HeaderMap, HeaderName, HeaderValue},
+use crate::adaptive::{AdaptiveConcurrency, AimdLimiter, Feedback, RampUp};
+use crate::auth::{Credentials, RefreshHook};
+use crate::cache::{Cache, CacheInfo, Fetch, ResponseCache, ValidatorStore};
+#[cfg(feature = "charset")]
+use crate::charset;
+use crate::cookies::CookieJar;
+use crate::download::{
+    Download, Progress, ProgressHook, ProgressReporter, ProgressTotals, SaveCollision,
+    SaveResponses, Unsatisfied, download, resume_point, settle_unsatisfied, with_number,
 };
+use crate::error::{
+    BodyTooLarge, CookieError, DuplicateRequest, InvalidOrigin, RedirectError, RollingError,
+    RollingErrorKind,
+};
+use crate::fanout::{self, FanOut};
+#[cfg(feature = "har")]
+use crate::har::{HarLog, HarRecording};
+use crate::health::{HealthCheck, HealthReport};
+use crate::proxy::{ProxyEjection, ProxyPool, ProxyRotation};
+use crate::request::{InvalidHeader, Request, RequestId, RequestSummary, redact_url};
+use crate::result::{RedirectHop, ResultBuffer, ResultOverflow, RollingResult};
+use crate::retry::{
+    Attempts, DeadLetterOverflow, DeadLetterQueue, RetryBudget, RetryContext, RetryPolicy,
+    RetryStats, RetryTracker, is_idempotent,
+};
+use crate::sink::{BodySink, StreamSink};
+use crate::stats::{Stats, StatsCollector};
+use crate::throttle::{Bandwidth, HostThrottle, RateLimitHeaders, RateLimitInfo, retry_after};
+use bytes::Bytes;
+use futures_util::{FutureExt, StreamExt, future, stream::FuturesUnordered};
+use rand::Rng;
+use reqwest::header::{
+    AUTHORIZATION, CONTENT_TYPE, COOKIE, HeaderMap, HeaderName, HeaderValue, IF_RANGE, LOCATION,
+    RANGE,
+};
+use reqwest::{Client, Method, ResponseBuilderExt, StatusCode, redirect::Policy};
+#[cfg(feature = "har")]
+use std::time::SystemTime;
 use std::{
-    sync::{Arc, Mutex},
-    time::Duration,
+    any::Any,
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    fmt,
+    future::Future,
+    hash::{BuildHasher, Hash, Hasher},
+    io,
+    panic::AssertUnwindSafe,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+use tokio::{
+    task::{AbortHandle, Id, JoinHandle, JoinSet},
+    time,
 };
-use tokio::task;
+use url::Url;
+use uuid::Uuid;
+
+/// The hook customizing the builders of the HTTP clients, see
+/// `RollingRequestsBuilder::configure_client`.
+pub type ClientHook = Box<dyn Fn(reqwest::ClientBuilder) -> reqwest::ClientBuilder + Send + Sync>;
+
+/// The number of times a throttled request is re-queued for `Retry-After`
+/// before it is returned as is.
+const MAX_RETRY_AFTER_REQUEUES: u32 = 4;
+
+/// How long a host is paused after a 429/503 response without `Retry-After`.
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(1);
+
+/// The cap on requests in flight in open-loop mode when `max_outstanding` is unset.
+const DEFAULT_MAX_OUTSTANDING: usize = 1024;
+
+/// The number of redirects followed by default, and by requests enabling
+/// them when the instance does not follow any.
+const DEFAULT_MAX_REDIRECTS: usize = 10;
 
 /// A struct to manage and execute HTTP requests with a concurrency limit.
 pub struct RollingRequests {
@@ -23,13 +90,377 @@ pub struct RollingRequests {
     pending_requests: Arc<Mutex<Vec<Request>>>,
     /// The HTTP client used to send requests.
     client: Client,
+    /// The HTTP client used for requests that do not follow redirects.
+    no_redirect_client: Client,
+    /// Whether `client` was supplied to the builder, so no other client may
+    /// replace it.
+    client_supplied: bool,
+    /// Whether requests follow redirects unless they say otherwise.
+    follow_redirects: bool,
+    /// The settings the clients are built with, kept for requests needing other clients.
+    client_settings: ClientSettings,
+    /// Clients built for proxied requests and requests without decompression.
+    extra_clients: Mutex<HashMap<ClientKey, Client>>,
+    /// Per-host pacing state shared by all executions.
+    throttle: Arc<Mutex<HostThrottle>>,
+    /// The adaptive concurrency controller, when enabled.
+    limiter: Option<Arc<Mutex<AimdLimiter>>>,
+    /// The rotation of the proxy pool, when one is set.
+    proxy_pool: Option<Mutex<ProxyPool>>,
+    /// Whether 429/503 responses re-queue the request and pause its host.
+    respect_retry_after: bool,
+    /// The rate limit headers to follow, when enabled.
+    rate_limit_headers: Option<RateLimitHeaders>,
+    /// The upper bound of the random delay added before each dispatch.
+    launch_jitter: Option<Duration>,
+    /// The byte budget shared by body reads, when throttling is enabled.
+    bandwidth: Option<Arc<Bandwidth>>,
+    /// The ramp-up schedule applied at the start of each run, when enabled.
+    ramp_up: Option<RampUp>,
+    /// When the current run started, used by the ramp-up schedule.
+    run_started: Mutex<Option<Instant>>,
+    /// The gap between two dispatches in open-loop mode, when enabled.
+    arrival_interval: Option<Duration>,
+    /// The cap on requests in flight in open-loop mode.
+    max_outstanding: usize,
+    /// The retry policy applied to requests without their own, when enabled.
+    retry_policy: Option<RetryPolicy>,
+    /// Retry counters and the retry budget shared by all executions.
+    retry_tracker: Mutex<RetryTracker>,
+    /// The counters behind `stats`, shared by all executions.
+    stats: StatsCollector,
+    /// Requests whose final result was an error.
+    dead_letters: Mutex<DeadLetterQueue>,
+    /// Results of background executions not drained yet.
+    background_results: ResultBuffer,
+    /// How long an attempt may run before a duplicate is sent, when hedging is enabled.
+    hedge_after: Option<Duration>,
+    /// Statuses that make a request move on to its next fallback URL.
+    fallback_statuses: Vec<u16>,
+    /// The header carrying generated idempotency keys, when enabled.
+    idempotency_key_header: Option<String>,
+    /// Whether non-success statuses are returned as errors.
+    error_for_status: bool,
+    /// The number of body bytes of an error status response kept in its error.
+    error_body_snippet: usize,
+    /// The credential refreshed on 401 responses, when a hook is set.
+    credentials: Option<Arc<Credentials>>,
+    /// The rules applied by `check_health`.
+    health_check: HealthCheck,
+    /// Consecutive failed health checks, keyed like the health reports.
+    health_failures: Mutex<HashMap<String, u32>>,
+    /// Whether the first failed request stops the execution.
+    fail_fast: bool,
+    /// The default maximum size of request bodies and multipart parts.
+    max_body_size: Option<u64>,
+    /// The number of body bytes kept by detailed results, when capped.
+    result_body_limit: Option<usize>,
+    /// Whether detailed and paired results leave response bodies unread.
+    headers_only: bool,
+    /// Whether GET requests are sent as HEAD first, with bodies left unread.
+    link_check: bool,
+    /// The hook receiving the progress of downloads.
+    on_progress: Option<ProgressHook>,
+    /// The minimum time between two progress reports of a download.
+    progress_interval: Option<Duration>,
+    /// Where response bodies are saved, when enabled.
+    save_responses: Option<SaveResponses>,
+    /// What happens when the file a response is saved to is taken.
+    save_collision: SaveCollision,
+    /// The files saved responses are being written to, so concurrent results
+    /// with the same file name collide.
+    saved_paths: Mutex<HashSet<PathBuf>>,
+    /// The URL relative request URLs are resolved against, when set.
+    base_url: Option<Url>,
+    /// The identifier assigned to the next request added.
+    next_request_id: AtomicU64,
+    /// The identifiers of the requests added, keyed by their fingerprints,
+    /// when deduplication is enabled.
+    seen_requests: Option<Mutex<HashMap<u64, u64>>>,
+    /// Whether requests stay known to deduplication once dispatched.
+    deduplicate_executed: bool,
+    /// The maximum number of continuations leading to a request, when capped.
+    max_chain_depth: Option<u32>,
+    /// The timeout applied to requests without their own, when the client
+    /// was supplied to the builder.
+    request_timeout: Option<Duration>,
+    /// The default headers and jar cookies merged into requests as they are sent.
+    send_defaults: Arc<SendDefaults>,
+    /// The cookies kept from responses, when enabled.
+    cookie_jar: Option<Arc<CookieJar>>,
+    /// The cache of GET and HEAD responses, when enabled.
+    cache: Option<Arc<Cache>>,
+    /// The validators sent with repeated GET and HEAD requests, when enabled.
+    validators: Option<Arc<ValidatorStore>>,
+    /// The traffic recorded for HAR export, when enabled.
+    #[cfg(feature = "har")]
+    har: Option<Arc<HarLog>>,
+    /// The encoding of response text declaring none, when set.
+    #[cfg(feature = "charset")]
+    default_charset: Option<&'static encoding_rs::Encoding>,
 }
 
 /// Configuration for `RollingRequests`.
 pub struct RollingRequestsConfig {
     pub simultaneous_limit: usize,
     pub timeout: Duration,
+    /// Time allowed to establish a connection; `None` only applies `timeout`.
+    pub connect_timeout: Option<Duration>,
     pub force_http2: bool,
+    /// Requests per second allowed for hosts without an explicit limit.
+    pub default_rate_limit: Option<f64>,
+    /// Requests per second allowed for specific hosts.
+    pub per_host_rate_limits: HashMap<String, f64>,
+    /// Minimum gap between two requests to the same host.
+    pub per_host_delay: Option<Duration>,
+    /// Minimum gaps for specific hosts, overriding `per_host_delay`.
+    pub per_host_delays: HashMap<String, Duration>,
+    /// Adaptive concurrency settings; `None` keeps the limit static.
+    pub adaptive_concurrency: Option<AdaptiveConcurrency>,
+    /// Whether 429/503 responses re-queue the request and pause its host.
+    pub respect_retry_after: bool,
+    /// Rate limit headers to follow; `None` ignores them.
+    pub rate_limit_headers: Option<RateLimitHeaders>,
+    /// Upper bound of a random delay added before each dispatch.
+    pub launch_jitter: Option<Duration>,
+    /// Minimum gap between any two dispatches, regardless of host.
+    pub launch_spacing: Option<Duration>,
+    /// Bytes per second allowed across all body reads; `None` or zero disables it.
+    pub max_bytes_per_second: Option<u64>,
+    /// Ramp-up schedule for the concurrency limit; `None` starts at full concurrency.
+    pub ramp_up: Option<RampUp>,
+    /// Requests started per second in open-loop mode; `None` keeps the closed loop.
+    pub arrival_rate: Option<f64>,
+    /// Cap on requests in flight in open-loop mode.
+    pub max_outstanding: Option<usize>,
+    /// Retry policy for failed requests; `None` disables retries.
+    pub retry_policy: Option<RetryPolicy>,
+    /// Cap on retries across all requests; `None` leaves retries uncapped.
+    pub retry_budget: Option<RetryBudget>,
+    /// Maximum number of failed requests kept; `None` keeps all of them.
+    pub dead_letter_capacity: Option<usize>,
+    /// What happens when a failed request arrives at a full dead-letter queue.
+    pub dead_letter_overflow: DeadLetterOverflow,
+    /// Maximum number of results of background executions kept; `None` keeps all of them.
+    pub result_buffer_capacity: Option<usize>,
+    /// What happens when a result arrives at a full result buffer.
+    pub result_buffer_overflow: ResultOverflow,
+    /// Delay after which a duplicate of a slow request is sent; `None` disables hedging.
+    pub hedge_after: Option<Duration>,
+    /// Statuses that make a request move on to its next fallback URL.
+    pub fallback_statuses: Vec<u16>,
+    /// Header carrying a generated idempotency key on POST/PATCH requests; `None` disables it.
+    pub idempotency_key_header: Option<String>,
+    /// Whether non-success statuses are returned as errors.
+    pub error_for_status: bool,
+    /// Number of body bytes of an error status response kept in its error; zero keeps none.
+    pub error_body_snippet: usize,
+    /// Hook returning a fresh `Authorization` value after a 401; `None` disables refreshes.
+    pub on_unauthorized: Option<RefreshHook>,
+    /// Rules applied by `check_health`.
+    pub health_check: HealthCheck,
+    /// Whether the first failed request stops the execution.
+    pub fail_fast: bool,
+    /// URL that relative request URLs are resolved against; `None` sends URLs as given.
+    pub base_url: Option<String>,
+    /// Maximum size in bytes of request bodies and multipart parts; `None` is unlimited.
+    pub max_body_size: Option<u64>,
+    /// Number of response body bytes kept by detailed results; `None` keeps them all.
+    pub result_body_limit: Option<usize>,
+    /// Whether detailed and paired results only record the status and headers.
+    pub headers_only: bool,
+    /// Whether GET requests are sent as HEAD, falling back to GET, with bodies left unread.
+    pub link_check: bool,
+    /// Hook receiving the progress of downloads to output files; `None` disables reports.
+    pub on_progress: Option<ProgressHook>,
+    /// Minimum time between two progress reports of a download; `None` reports every chunk.
+    pub progress_interval: Option<Duration>,
+    /// Directory and file name template every response body is saved to; `None` keeps them in memory.
+    pub save_responses: Option<SaveResponses>,
+    /// What happens when the file a response is saved to is taken.
+    pub save_collision: SaveCollision,
+    /// Settings of the in-memory cache of GET and HEAD responses; `None` disables it.
+    pub response_cache: Option<ResponseCache>,
+    /// Store of response validators making repeated requests conditional; `None` disables it.
+    pub validator_store: Option<Arc<ValidatorStore>>,
+    /// Settings for recording the traffic as HAR; `None` disables recording.
+    #[cfg(feature = "har")]
+    pub har_recording: Option<HarRecording>,
+    /// Label of the charset of response text declaring none; `None` decodes it as UTF-8.
+    #[cfg(feature = "charset")]
+    pub default_charset: Option<String>,
+    /// Whether requests equal to one already pending are dropped when added.
+    pub deduplicate: bool,
+    /// Whether deduplication also drops requests equal to one already executed.
+    pub deduplicate_executed: bool,
+    /// Maximum number of continuations leading to a request; `None` follows every chain.
+    pub max_chain_depth: Option<u32>,
+    /// The client used as-is instead of one built from these settings.
+    pub client: Option<Client>,
+    /// Timeout of requests sent with `client`; `None` keeps the timeout of the client.
+    pub client_timeout: Option<Duration>,
+    /// Hook applied to each client builder after these settings; `None` leaves them as is.
+    pub configure_client: Option<ClientHook>,
+    /// Headers sent with every request that does not set them itself.
+    pub default_headers: HeaderMap,
+    /// The `User-Agent` sent by requests that do not set one; `None` sends none.
+    pub user_agent: Option<String>,
+    /// Whether cookies set by responses are kept and sent with later requests.
+    pub cookie_store: bool,
+    /// The file the cookie jar is loaded from and saved to; `None` keeps it in memory.
+    #[cfg(feature = "persistent-cookies")]
+    pub cookie_store_path: Option<PathBuf>,
+    /// The proxy every request is sent through unless it sets its own; `None`
+    /// uses the proxies of the environment.
+    pub proxy: Option<String>,
+    /// The hosts reached without the proxy, in `NO_PROXY` syntax.
+    pub no_proxy: Vec<String>,
+    /// The username and password sent to the proxy, when set.
+    pub proxy_auth: Option<(String, String)>,
+    /// The proxies requests are spread across; empty sends them through `proxy`.
+    pub proxy_pool: Vec<String>,
+    /// How the proxy of each attempt is chosen from the pool.
+    pub proxy_rotation: ProxyRotation,
+    /// When a failing proxy of the pool is taken out of rotation.
+    pub proxy_ejection: ProxyEjection,
+    /// The maximum number of redirects followed; `0` returns redirect
+    /// responses as they are.
+    pub max_redirects: usize,
+}
+
+impl RollingRequestsConfig {
+    /// Checks that the settings are in range and compatible, as done by
+    /// `RollingRequestsBuilder::try_build`.
+    ///
+    /// A zero `max_bytes_per_second` stays valid, as it disables bandwidth
+    /// throttling.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::{ConfigError, RollingRequestsConfig};
+    ///
+    /// let config = RollingRequestsConfig {
+    ///     simultaneous_limit: 0,
+    ///     ..RollingRequestsConfig::default()
+    /// };
+    /// assert_eq!(
+    ///     config.validate(),
+    ///     Err(ConfigError::ZeroLimit("simultaneous_limit"))
+    /// );
+    /// ```
+    ///
+    /// #### Errors
+    ///
+    /// Returns the first `ConfigError` found.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.simultaneous_limit == 0 {
+            return Err(ConfigError::ZeroLimit("simultaneous_limit"));
+        }
+        if self.timeout.is_zero() {
+            return Err(ConfigError::ZeroTimeout("timeout"));
+        }
+        if self.client_timeout.is_some_and(|timeout| timeout.is_zero()) {
+            return Err(ConfigError::ZeroTimeout("client_timeout"));
+        }
+        if self
+            .connect_timeout
+            .is_some_and(|timeout| timeout.is_zero())
+        {
+            return Err(ConfigError::ZeroTimeout("connect_timeout"));
+        }
+        let rates = self
+            .default_rate_limit
+            .map(|rate| ("default_rate_limit", None, rate))
+            .into_iter()
+            .chain(
+                self.per_host_rate_limits
+                    .iter()
+                    .map(|(host, rate)| ("per_host_rate_limit", Some(host), *rate)),
+            )
+            .chain(self.arrival_rate.map(|rate| ("arrival_rate", None, rate)));
+        for (setting, host, rate) in rates {
+            // Also rejects NaN, which compares false
+            if !(rate > 0.0 && rate.is_finite()) {
+                return Err(ConfigError::InvalidRate {
+                    setting,
+                    host: host.cloned(),
+                    rate,
+                });
+            }
+        }
+        if self.max_outstanding == Some(0) {
+            return Err(ConfigError::ZeroOutstanding);
+        }
+        if let Some(ramp) = &self.ramp_up {
+            if ramp.target_limit == 0 {
+                return Err(ConfigError::ZeroLimit("ramp_up"));
+            }
+            if ramp.step == 0 {
+                return Err(ConfigError::ZeroRampStep);
+            }
+        }
+        if let Some(adaptive) = &self.adaptive_concurrency {
+            if adaptive.min_limit == 0 {
+                return Err(ConfigError::InvalidAdaptiveConcurrency(
+                    "min_limit must be at least 1",
+                ));
+            }
+            if adaptive.min_limit > adaptive.max_limit {
+                return Err(ConfigError::InvalidAdaptiveConcurrency(
+                    "min_limit must not exceed max_limit",
+                ));
+            }
+            if !(adaptive.decrease_factor > 0.0 && adaptive.decrease_factor < 1.0) {
+                return Err(ConfigError::InvalidAdaptiveConcurrency(
+                    "decrease_factor must be between 0 and 1",
+                ));
+            }
+        }
+        if let Some(user_agent) = &self.user_agent {
+            if !user_agent
+                .bytes()
+                .all(|byte| byte == b' ' || byte.is_ascii_graphic())
+            {
+                return Err(ConfigError::InvalidUserAgent(user_agent.clone()));
+            }
+        }
+        if self.proxy.is_some() && !self.proxy_pool.is_empty() {
+            return Err(ConfigError::Conflict("proxy", "proxy_pool"));
+        }
+        if self.proxy.is_none() && self.proxy_pool.is_empty() {
+            if !self.no_proxy.is_empty() {
+                return Err(ConfigError::ProxyRequired("no_proxy"));
+            }
+            if self.proxy_auth.is_some() {
+                return Err(ConfigError::ProxyRequired("proxy_auth"));
+            }
+        }
+        if self.client.is_some() {
+            if self.user_agent.is_some() {
+                return Err(ConfigError::UnsupportedWithClient("user_agent"));
+            }
+            if self.cookie_store {
+                return Err(ConfigError::UnsupportedWithClient("cookie_store"));
+            }
+            if self.proxy.is_some() {
+                return Err(ConfigError::UnsupportedWithClient("proxy"));
+            }
+            if !self.proxy_pool.is_empty() {
+                return Err(ConfigError::UnsupportedWithClient("proxy_pool"));
+            }
+            if self.max_redirects != DEFAULT_MAX_REDIRECTS {
+                return Err(ConfigError::UnsupportedWithClient("redirects"));
+            }
+            if self.connect_timeout.is_some() {
+                return Err(ConfigError::UnsupportedWithClient("connect_timeout"));
+            }
+            if self.force_http2 {
+                return Err(ConfigError::UnsupportedWithClient("force_http2"));
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Default for RollingRequestsConfig {
@@ -37,7 +468,68 @@ impl Default for RollingRequestsConfig {
         RollingRequestsConfig {
             simultaneous_limit: 1,            // Default limit
             timeout: Duration::from_secs(30), // Default timeout
+            connect_timeout: None,            // Default bounded by timeout
             force_http2: false,               // Default false
+            default_rate_limit: None,         // Default unlimited
+            per_host_rate_limits: HashMap::new(),
+            per_host_delay: None, // Default no delay
+            per_host_delays: HashMap::new(),
+            adaptive_concurrency: None, // Default static limit
+            respect_retry_after: false, // Default false
+            rate_limit_headers: None,   // Default ignore rate limit headers
+            launch_jitter: None,        // Default no jitter
+            launch_spacing: None,       // Default no spacing
+            max_bytes_per_second: None, // Default unthrottled downloads
+            ramp_up: None,              // Default no ramp-up
+            arrival_rate: None,         // Default closed loop
+            max_outstanding: None,      // Default DEFAULT_MAX_OUTSTANDING
+            retry_policy: None,         // Default no retries
+            retry_budget: None,         // Default unlimited retries
+            dead_letter_capacity: None, // Default keep every failed request
+            dead_letter_overflow: DeadLetterOverflow::DropOldest,
+            result_buffer_capacity: None, // Default keep every result
+            result_buffer_overflow: ResultOverflow::DropOldest,
+            hedge_after: None,                      // Default no hedging
+            fallback_statuses: vec![502, 503, 504], // Default gateway errors
+            idempotency_key_header: None,           // Default no idempotency keys
+            error_for_status: false,                // Default return every response
+            error_body_snippet: 4096,               // Default 4 KiB of the body
+            on_unauthorized: None,                  // Default no credential refresh
+            health_check: HealthCheck::default(),
+            fail_fast: false,        // Default run every request
+            base_url: None,          // Default absolute URLs only
+            max_body_size: None,     // Default unlimited bodies
+            result_body_limit: None, // Default whole bodies
+            headers_only: false,     // Default read bodies
+            link_check: false,       // Default send methods as given
+            on_progress: None,       // Default no progress reports
+            progress_interval: None, // Default report every chunk
+            save_responses: None,    // Default bodies in memory
+            save_collision: SaveCollision::Suffix,
+            response_cache: None,  // Default no caching
+            validator_store: None, // Default unconditional requests
+            #[cfg(feature = "har")]
+            har_recording: None, // Default no recording
+            #[cfg(feature = "charset")]
+            default_charset: None, // Default UTF-8
+            deduplicate: false,    // Default keep every request
+            deduplicate_executed: false, // Default forget requests once dispatched
+            max_chain_depth: None, // Default unlimited chains
+            client: None,          // Default built from the settings
+            client_timeout: None,  // Default timeout of the client
+            configure_client: None, // Default no customization
+            default_headers: HeaderMap::new(),
+            user_agent: None,    // Default no User-Agent
+            cookie_store: false, // Default no cookie jar
+            #[cfg(feature = "persistent-cookies")]
+            cookie_store_path: None, // Default cookies kept in memory
+            proxy: None,         // Default proxies of the environment
+            no_proxy: Vec::new(), // Default every host through the proxy
+            proxy_auth: None,    // Default no proxy credentials
+            proxy_pool: Vec::new(), // Default no proxy pool
+            proxy_rotation: ProxyRotation::RoundRobin,
+            proxy_ejection: ProxyEjection::default(),
+            max_redirects: DEFAULT_MAX_REDIRECTS, // Default ten hops
         }
     }
 }
@@ -45,6 +537,14 @@ impl Default for RollingRequestsConfig {
 /// Builder for `RollingRequests`.
 pub struct RollingRequestsBuilder {
     config: RollingRequestsConfig,
+    /// The default headers that could not be set, reported by `try_build`.
+    header_errors: Vec<InvalidHeader>,
+}
+
+impl Default for RollingRequestsBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl RollingRequestsBuilder {
@@ -60,6 +560,7 @@ impl RollingRequestsBuilder {
     pub fn new() -> Self {
         RollingRequestsBuilder {
             config: RollingRequestsConfig::default(),
+            header_errors: Vec::new(),
         }
     }
 
@@ -83,6 +584,9 @@ impl RollingRequestsBuilder {
 
     /// Sets the request timeout duration.
     ///
+    /// Also applies to requests sent with a client supplied with `client`,
+    /// which otherwise keep the timeout of that client.
+    ///
     /// #### Arguments
     ///
     /// * `timeout` - The duration to wait before a request times out.
@@ -97,6 +601,32 @@ impl RollingRequestsBuilder {
     /// ```
     pub fn timeout(mut self, timeout: Duration) -> Self {
         self.config.timeout = timeout;
+        self.config.client_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the time allowed to establish a connection.
+    ///
+    /// Connections that take longer fail with `RollingErrorKind::ConnectTimeout`,
+    /// while requests that exceed `timeout` once connected fail with
+    /// `RollingErrorKind::ReadTimeout`.
+    ///
+    /// #### Arguments
+    ///
+    /// * `timeout` - The duration to wait for a connection.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    /// use std::time::Duration;
+    ///
+    /// let builder = RollingRequestsBuilder::new()
+    ///     .connect_timeout(Duration::from_secs(2))
+    ///     .timeout(Duration::from_secs(30));
+    /// ```
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.config.connect_timeout = Some(timeout);
         self
     }
 
@@ -118,171 +648,5241 @@ impl RollingRequestsBuilder {
         self
     }
 
-    /// Builds the `RollingRequests` instance.
+    /// Sets the HTTP client requests are sent with, used as-is instead of one
+    /// built from the settings of the builder.
+    ///
+    /// The client is cloned for each request, and its clones share its
+    /// connection pool. When `timeout` is set, it applies to each request
+    /// without a timeout of its own, like `Request::set_timeout`, overriding
+    /// the timeout of the client; otherwise the client keeps its own. `connect_timeout`,
+    /// `force_http2`, `user_agent`, `cookie_store`, `proxy`, `proxy_pool`,
+    /// `no_redirects` and a `redirects` limit other than ten cannot be applied
+    /// to a built client and are rejected with
+    /// `ConfigError::UnsupportedWithClient`. Requests with their own proxy,
+    /// recorded redirects, or with redirects or decompression turned off
+    /// would need another client, so they fail with
+    /// `RollingErrorKind::InvalidRequest` without being sent.
+    ///
+    /// #### Arguments
+    ///
+    /// * `client` - The client to send requests with.
     ///
     /// #### Examples
     ///
     /// ```
+    /// use reqwest::Client;
     /// use rollingrequests::rolling::RollingRequestsBuilder;
+    /// use std::time::Duration;
     ///
-    /// let rolling_requests = RollingRequestsBuilder::new().build();
+    /// let client = Client::builder().pool_max_idle_per_host(4).build().unwrap();
+    /// let rolling_requests = RollingRequestsBuilder::new()
+    ///     .client(client.clone())
+    ///     .timeout(Duration::from_secs(60))
+    ///     .build();
     /// ```
-    pub fn build(self) -> RollingRequests {
-        RollingRequests::new(self.config)
+    pub fn client(mut self, client: Client) -> Self {
+        self.config.client = Some(client);
+        self
     }
-}
 
-impl RollingRequests {
-    /// Creates a new `RollingRequests` instance with the specified configuration.
+    /// Sets a hook customizing the HTTP clients built from the settings of
+    /// the builder.
+    ///
+    /// The hook is called with each `reqwest::ClientBuilder` once the
+    /// settings of this builder are applied, and the builder it returns is
+    /// built. It runs for the clients built by `build` and for those built
+    /// later for proxied requests, requests without redirects and requests
+    /// without decompression, but not for a client supplied with `client`.
+    /// A customized builder that fails to build makes `try_build` return
+    /// `ClientBuildError::Client`.
     ///
     /// #### Arguments
     ///
-    /// * `config` - The configuration for the requests.
+    /// * `hook` - Receives the client builder and returns it, customized.
     ///
     /// #### Examples
     ///
     /// ```
     /// use rollingrequests::rolling::RollingRequestsBuilder;
-    /// use std::time::Duration;
+    /// use std::net::{IpAddr, Ipv4Addr};
     ///
     /// let rolling_requests = RollingRequestsBuilder::new()
-    ///     .simultaneous_limit(5)
-    ///     .timeout(Duration::from_secs(10))
+    ///     .configure_client(|builder| {
+    ///         builder.local_address(IpAddr::V4(Ipv4Addr::LOCALHOST))
+    ///     })
     ///     .build();
     /// ```
-    pub fn new(config: RollingRequestsConfig) -> Self {
-        let client_builder = Client::builder().timeout(config.timeout);
-
-        let client = if config.force_http2 {
-            client_builder.http2_prior_knowledge().build().unwrap()
-        } else {
-            client_builder.build().unwrap()
-        };
+    pub fn configure_client<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(reqwest::ClientBuilder) -> reqwest::ClientBuilder + Send + Sync + 'static,
+    {
+        self.config.configure_client = Some(Box::new(hook));
+        self
+    }
 
-        RollingRequests {
-            simultaneous_limit: config.simultaneous_limit,
-            pending_requests: Arc::new(Mutex::new(Vec::new())),
-            client,
+    /// Sets a header sent with every request that does not set it itself.
+    ///
+    /// Request headers are matched case-insensitively, and a request setting
+    /// any value of the header sends only its own. The defaults are added to
+    /// each attempt as it is sent, whatever client sends it, and are not
+    /// copied into the request itself. An invalid name or value is reported
+    /// by `try_build`.
+    ///
+    /// #### Arguments
+    ///
+    /// * `key` - The header name.
+    /// * `value` - The header value, replacing any default value set before.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let rolling_requests = RollingRequestsBuilder::new()
+    ///     .default_header("Accept", "application/json")
+    ///     .default_header("X-Api-Version", "2")
+    ///     .build();
+    /// ```
+    pub fn default_header(mut self, key: &str, value: &str) -> Self {
+        match (
+            HeaderName::from_bytes(key.as_bytes()),
+            HeaderValue::from_str(value),
+        ) {
+            (Ok(name), Ok(value)) => {
+                self.config.default_headers.insert(name, value);
+            }
+            _ => self.header_errors.push(InvalidHeader {
+                name: key.to_string(),
+                value: value.to_string(),
+            }),
         }
+        self
     }
 
-    /// Adds a new request to the collection of pending requests.
+    /// Sets several headers sent with every request that does not set them
+    /// itself, like `default_header`.
     ///
     /// #### Arguments
     ///
-    /// * `request` - The `Request` to add.
+    /// * `headers` - The headers, replacing the default values of the same
+    ///   names set before.
     ///
     /// #### Examples
     ///
     /// ```
+    /// use reqwest::header::{ACCEPT, HeaderMap, HeaderValue};
     /// use rollingrequests::rolling::RollingRequestsBuilder;
-    /// use rollingrequests::request::Request;
-    /// use reqwest::Method;
-    /// use std::time::Duration;
     ///
-    /// let mut rolling_requests = RollingRequestsBuilder::new().build();
-    /// let request = Request::new("http://example.com", Method::GET);
-    /// rolling_requests.add_request(request);
+    /// let mut headers = HeaderMap::new();
+    /// headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+    /// let rolling_requests = RollingRequestsBuilder::new().default_headers(headers).build();
     /// ```
-    pub fn add_request(&mut self, request: Request) {
-        let mut pending = self.pending_requests.lock().unwrap();
-        pending.push(request);
+    pub fn default_headers(mut self, headers: HeaderMap) -> Self {
+        self.config.default_headers.extend(headers);
+        self
     }
 
-    /// Executes the pending requests up to the concurrency limit.
+    /// Sets the `User-Agent` header of the HTTP clients.
     ///
-    /// Returns a vector of results for each request, either a successful response
-    /// or an error.
+    /// Requests setting the header, themselves or through `default_header`,
+    /// send their own value. A value that is not visible ASCII is rejected
+    /// by `try_build` with `ConfigError::InvalidUserAgent`, and a client
+    /// supplied with `client` keeps its own.
+    ///
+    /// #### Arguments
+    ///
+    /// * `user_agent` - The `User-Agent` value.
     ///
     /// #### Examples
     ///
     /// ```
-    /// use rollingrequests::request::Request;
     /// use rollingrequests::rolling::RollingRequestsBuilder;
-    /// use reqwest::Method;
-    /// use tokio;
     ///
-    /// #[tokio::main]
-    /// async fn main() {
-    ///     let mut rolling_requests = RollingRequestsBuilder::new()
-    ///         .simultaneous_limit(2)
-    ///         .build();
+    /// let rolling_requests = RollingRequestsBuilder::new()
+    ///     .user_agent("inventory-sync/1.4 (+https://example.com/bot)")
+    ///     .build();
+    /// ```
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.config.user_agent = Some(user_agent.to_string());
+        self
+    }
+
+    /// Enables a cookie jar shared by every request of the instance.
     ///
-    ///     let url = "http://example.com";
+    /// Cookies set by responses, including redirects, are kept and sent with
+    /// later requests to matching URLs, so one login request can open the
+    /// session of those that follow. A request setting cookies, itself or
+    /// through `default_header`, sends them in place of jar cookies of the
+    /// same names. The jar can be read and seeded with
+    /// `RollingRequests::cookies` and `RollingRequests::add_cookie`.
     ///
-    ///     // Add requests to the queue
-    ///     for _ in 0..5 {
-    ///         let request = Request::new(url, Method::GET);
-    ///         rolling_requests.add_request(request);
-    ///     }
+    /// #### Arguments
+    ///
+    /// * `enabled` - Whether cookies are kept, `false` by default.
+    ///
+    /// #### Examples
     ///
-    ///     // Execute requests
-    ///     let responses = rolling_requests.execute_requests().await;
-    ///     for response in responses {
-    ///         match response {
-    ///             Ok(res) => println!("Response: {:?}", res),
-    ///             Err(err) => eprintln!("Error: {:?}", err),
-    ///         }
-    ///     }
-    /// }
     /// ```
-    pub async fn execute_requests(&self) -> Vec<Result<reqwest::Response, reqwest::Error>> {
-        let mut handles = vec![];
-        let mut responses = vec![];
-
-        let requests_to_process: Vec<Request> = {
-            let pending = self.pending_requests.lock().unwrap();
-            pending
-                .iter()
-                .take(self.simultaneous_limit)
-                .cloned()
-                .collect()
-        };
-
-        for req in &requests_to_process {
-            let client = self.client.clone();
-            let req = req.clone();
-
-            let handle = task::spawn(async move {
-                let mut req_builder = client.request(req.method.clone(), &req.url);
-
-                if let Some(headers) = &req.headers {
-                    let mut header_map = HeaderMap::new();
-                    for (key, value) in headers {
-                        if let (Ok(header_name), Ok(header_value)) = (
-                            HeaderName::from_bytes(key.as_bytes()),
-                            HeaderValue::from_str(value),
-                        ) {
-                            header_map.insert(header_name, header_value);
-                        }
-                    }
-                    req_builder = req_builder.headers(header_map);
-                }
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let rolling_requests = RollingRequestsBuilder::new().cookie_store(true).build();
+    /// ```
+    pub fn cookie_store(mut self, enabled: bool) -> Self {
+        self.config.cookie_store = enabled;
+        self
+    }
 
-                if let Some(form) = req.multipart_form_data {
-                    req_builder = req_builder.multipart(form);
-                } else if let Some(data) = &req.post_data {
-                    req_builder = req_builder.body(data.clone());
-                }
+    /// Enables the cookie jar, keeping it in a file across runs.
+    ///
+    /// The unexpired cookies of the file are loaded when the instance is
+    /// built, a missing file starting an empty jar; a file that cannot be
+    /// read as saved cookies fails `try_build` and is left as it was. The
+    /// cookies are saved back, including session cookies without an expiry,
+    /// when the instance and every execution using it are dropped, or earlier
+    /// with `RollingRequests::save_cookies`. The file holds one JSON cookie
+    /// per line, keeping its `Secure` and `HttpOnly` attributes, and is
+    /// created readable by its owner only on Unix, as it may hold
+    /// credentials.
+    ///
+    /// #### Arguments
+    ///
+    /// * `path` - The file of the cookies, created with its directory when missing.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let builder = RollingRequestsBuilder::new().cookie_store_path("state/cookies.json");
+    /// ```
+    #[cfg(feature = "persistent-cookies")]
+    pub fn cookie_store_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.cookie_store = true;
+        self.config.cookie_store_path = Some(path.into());
+        self
+    }
 
-                req_builder.send().await
-            });
+    /// Sends every request through a proxy, in place of the proxies of the
+    /// `HTTP_PROXY`, `HTTPS_PROXY` and `ALL_PROXY` environment variables.
+    ///
+    /// The proxy is used for every scheme. A request setting its own proxy
+    /// with `Request::set_proxy` is sent through that one instead. To spread
+    /// requests across several proxies, use `proxy_pool` in place of this.
+    ///
+    /// #### Arguments
+    ///
+    /// * `url` - The proxy URL, with an `http`, `https`, `socks5` or `socks5h`
+    ///   scheme; `http` is assumed without one. An invalid URL is rejected by
+    ///   `try_build` with `ClientBuildError::InvalidProxy`. reqwest resolves
+    ///   the host of a SOCKS proxy, `socks5h` included, once while building,
+    ///   so an unresolvable one is rejected the same way and a proxy moving to
+    ///   another address is not followed; name it by IP address to skip the
+    ///   lookup. `socks5h` leaves the hosts requested through the proxy for
+    ///   it to resolve.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let rolling_requests = RollingRequestsBuilder::new()
+    ///     .proxy("socks5://127.0.0.1:1080")
+    ///     .no_proxy("localhost,.internal.example.com")
+    ///     .build();
+    /// ```
+    pub fn proxy(mut self, url: &str) -> Self {
+        self.config.proxy = Some(url.to_string());
+        self
+    }
 
-            handles.push(handle);
-        }
+    /// Adds hosts reached without the proxy set with `proxy`, or those of
+    /// `proxy_pool`.
+    ///
+    /// Without either, `try_build` rejects this with
+    /// `ConfigError::ProxyRequired`.
+    ///
+    /// #### Arguments
+    ///
+    /// * `patterns` - Comma-separated hosts in `NO_PROXY` syntax: domains,
+    ///   which also match their subdomains, IP addresses, CIDR ranges such as
+    ///   `10.0.0.0/8`, or `*` for every host.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let builder = RollingRequestsBuilder::new()
+    ///     .proxy("http://proxy.example.com:8080")
+    ///     .no_proxy("localhost,127.0.0.1")
+    ///     .no_proxy("10.0.0.0/8");
+    /// ```
+    pub fn no_proxy(mut self, patterns: &str) -> Self {
+        self.config.no_proxy.push(patterns.to_string());
+        self
+    }
 
-        for handle in handles {
-            // Errors should now be handled by the caller when they occur
-            if let Ok(response) = handle.await {
-                responses.push(response);
-            }
-        }
+    /// Authenticates to the proxy set with `proxy`, or those of `proxy_pool`,
+    /// with basic credentials.
+    ///
+    /// Credentials in the proxy URL are used when none are set here. Without
+    /// `proxy` or `proxy_pool`, `try_build` rejects this with
+    /// `ConfigError::ProxyRequired`.
+    ///
+    /// #### Arguments
+    ///
+    /// * `username` - The username sent to the proxy.
+    /// * `password` - The password sent to the proxy.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let builder = RollingRequestsBuilder::new()
+    ///     .proxy("http://proxy.example.com:8080")
+    ///     .proxy_auth("crawler", "secret");
+    /// ```
+    pub fn proxy_auth(mut self, username: &str, password: &str) -> Self {
+        self.config.proxy_auth = Some((username.to_string(), password.to_string()));
+        self
+    }
 
-        // Automatically clear processed requests
-        let count = requests_to_process.len();
-        let mut pending = self.pending_requests.lock().unwrap();
-        pending.drain(0..count);
+    /// Spreads requests across a pool of proxies, each attempt being sent
+    /// through the proxy chosen by `proxy_rotation`.
+    ///
+    /// Each proxy gets a client of its own. Proxies that keep failing are
+    /// taken out of rotation for a while, see `proxy_ejection`, and the proxy
+    /// of each result is reported in `RollingResult::proxy`. A request
+    /// setting its own proxy with `Request::set_proxy` skips the pool.
+    ///
+    /// #### Arguments
+    ///
+    /// * `proxies` - The proxy URLs, accepted as by `proxy`. An invalid URL is
+    ///   rejected by `try_build` with `ClientBuildError::InvalidProxy`, and a pool
+    ///   set together with `proxy` with `ConfigError::Conflict`.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::proxy::ProxyRotation;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let rolling_requests = RollingRequestsBuilder::new()
+    ///     .proxy_pool(vec![
+    ///         "http://exit-1.example.com:8080".to_string(),
+    ///         "socks5://10.0.0.2:1080".to_string(),
+    ///     ])
+    ///     .proxy_rotation(ProxyRotation::Random)
+    ///     .build();
+    /// ```
+    pub fn proxy_pool(mut self, proxies: Vec<String>) -> Self {
+        self.config.proxy_pool = proxies;
+        self
+    }
+
+    /// Sets how the proxy of each attempt is chosen from the `proxy_pool`.
+    ///
+    /// #### Arguments
+    ///
+    /// * `rotation` - The rotation strategy, `ProxyRotation::RoundRobin` by
+    ///   default.
+    pub fn proxy_rotation(mut self, rotation: ProxyRotation) -> Self {
+        self.config.proxy_rotation = rotation;
+        self
+    }
+
+    /// Sets when a failing proxy of the `proxy_pool` is taken out of
+    /// rotation, and for how long.
+    ///
+    /// When every proxy is out of rotation, attempts are sent through the
+    /// one returning first.
+    ///
+    /// #### Arguments
+    ///
+    /// * `ejection` - The failure threshold and cool-down, three failures in
+    ///   a row and 30 seconds by default.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::proxy::ProxyEjection;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    /// use std::time::Duration;
+    ///
+    /// let builder = RollingRequestsBuilder::new().proxy_ejection(ProxyEjection {
+    ///     failure_threshold: 5,
+    ///     cooldown: Duration::from_secs(120),
+    /// });
+    /// ```
+    pub fn proxy_ejection(mut self, ejection: ProxyEjection) -> Self {
+        self.config.proxy_ejection = ejection;
+        self
+    }
+
+    /// Sets the maximum number of redirects followed by each request.
+    ///
+    /// A response redirecting again after `max` hops, or back to a URL
+    /// visited before, fails the request with `RollingErrorKind::Redirect`.
+    /// Requests can still opt out with `Request::set_follow_redirects`, or
+    /// follow and record their own chain with `Request::set_record_redirects`.
+    ///
+    /// #### Arguments
+    ///
+    /// * `max` - The maximum number of hops, ten by default; `0` disables
+    ///   redirects like `no_redirects`.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let rolling_requests = RollingRequestsBuilder::new().redirects(3).build();
+    /// ```
+    pub fn redirects(mut self, max: usize) -> Self {
+        self.config.max_redirects = max;
+        self
+    }
+
+    /// Stops requests from following redirects, so their result is the 3xx
+    /// response itself, with its `Location` header intact.
+    ///
+    /// A request can still follow them with
+    /// `Request::set_follow_redirects(true)`, up to ten hops. Rejected with
+    /// `ConfigError::UnsupportedWithClient` alongside `client`, whose own
+    /// redirect policy applies.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let rolling_requests = RollingRequestsBuilder::new().no_redirects().build();
+    /// ```
+    pub fn no_redirects(mut self) -> Self {
+        self.config.max_redirects = 0;
+        self
+    }
+
+    /// Sets the request rate applied to hosts without an explicit limit.
+    ///
+    /// Each host gets its own token bucket, so a throttled host never delays
+    /// requests bound for other hosts.
+    ///
+    /// #### Arguments
+    ///
+    /// * `requests_per_second` - The number of requests allowed per second per host.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let builder = RollingRequestsBuilder::new().default_rate_limit(10.0);
+    /// ```
+    pub fn default_rate_limit(mut self, requests_per_second: f64) -> Self {
+        self.config.default_rate_limit = Some(requests_per_second);
+        self
+    }
+
+    /// Sets the request rate for a specific host, overriding the default rate.
+    ///
+    /// #### Arguments
+    ///
+    /// * `host` - The host name as it appears in request URLs (e.g., `api.github.com`).
+    /// * `requests_per_second` - The number of requests allowed per second for the host.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let builder = RollingRequestsBuilder::new()
+    ///     .default_rate_limit(50.0)
+    ///     .per_host_rate_limit("api.github.com", 1.0);
+    /// ```
+    pub fn per_host_rate_limit(mut self, host: &str, requests_per_second: f64) -> Self {
+        self.config
+            .per_host_rate_limits
+            .insert(host.to_string(), requests_per_second);
+        self
+    }
+
+    /// Sets the minimum delay between two requests to the same host.
+    ///
+    /// The delay applies regardless of the concurrency limit, while requests to
+    /// other hosts proceed in the meantime.
+    ///
+    /// #### Arguments
+    ///
+    /// * `delay` - The minimum gap between successive dispatches to a host.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    /// use std::time::Duration;
+    ///
+    /// let builder = RollingRequestsBuilder::new().per_host_delay(Duration::from_millis(500));
+    /// ```
+    pub fn per_host_delay(mut self, delay: Duration) -> Self {
+        self.config.per_host_delay = Some(delay);
+        self
+    }
+
+    /// Sets the minimum delay between two requests to a specific host,
+    /// overriding the delay set with `per_host_delay`.
+    ///
+    /// #### Arguments
+    ///
+    /// * `host` - The host name as it appears in request URLs.
+    /// * `delay` - The minimum gap between successive dispatches to the host.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    /// use std::time::Duration;
+    ///
+    /// let builder = RollingRequestsBuilder::new()
+    ///     .per_host_delay(Duration::from_millis(500))
+    ///     .per_host_delay_override("example.com", Duration::from_secs(2));
+    /// ```
+    pub fn per_host_delay_override(mut self, host: &str, delay: Duration) -> Self {
+        self.config.per_host_delays.insert(host.to_string(), delay);
+        self
+    }
+
+    /// Enables adaptive concurrency control.
+    ///
+    /// The effective limit starts at `simultaneous_limit` and grows additively while
+    /// responses are fast and successful, then shrinks multiplicatively on timeouts,
+    /// connection errors, and 429/503 responses, staying within the configured bounds.
+    ///
+    /// #### Arguments
+    ///
+    /// * `settings` - The bounds and tuning for the controller.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::adaptive::AdaptiveConcurrency;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let builder = RollingRequestsBuilder::new()
+    ///     .simultaneous_limit(4)
+    ///     .adaptive_concurrency(AdaptiveConcurrency {
+    ///         min_limit: 1,
+    ///         max_limit: 32,
+    ///         ..Default::default()
+    ///     });
+    /// ```
+    pub fn adaptive_concurrency(mut self, settings: AdaptiveConcurrency) -> Self {
+        self.config.adaptive_concurrency = Some(settings);
+        self
+    }
+
+    /// Honors `Retry-After` on 429 and 503 responses.
+    ///
+    /// When enabled, a throttled request is re-queued within the same execution and
+    /// dispatching to its host is paused until the `Retry-After` delay (in seconds
+    /// or as an HTTP date) has elapsed, while other hosts continue. Without the
+    /// header the host is paused for one second. A request is re-queued at most
+    /// four times, however often it was retried for other reasons; after that
+    /// the throttled response is returned.
+    ///
+    /// #### Arguments
+    ///
+    /// * `respect` - A boolean indicating whether to honor `Retry-After`.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let builder = RollingRequestsBuilder::new().respect_retry_after(true);
+    /// ```
+    pub fn respect_retry_after(mut self, respect: bool) -> Self {
+        self.config.respect_retry_after = respect;
+        self
+    }
+
+    /// Follows rate limit headers advertised by servers.
+    ///
+    /// The remaining count and reset time are parsed from every response, and
+    /// dispatching to a host is paused until its window resets once the remaining
+    /// count drops to the configured threshold.
+    ///
+    /// #### Arguments
+    ///
+    /// * `headers` - The header names and threshold to use.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    /// use rollingrequests::throttle::RateLimitHeaders;
+    ///
+    /// let builder = RollingRequestsBuilder::new().rate_limit_headers(RateLimitHeaders {
+    ///     threshold: 5,
+    ///     ..Default::default()
+    /// });
+    /// ```
+    pub fn rate_limit_headers(mut self, headers: RateLimitHeaders) -> Self {
+        self.config.rate_limit_headers = Some(headers);
+        self
+    }
+
+    /// Adds a random delay in `[0, jitter]` before each request is dispatched.
+    ///
+    /// The delay is taken while the request holds its concurrency slot, so it
+    /// composes with the concurrency limit and the rate limiters.
+    ///
+    /// #### Arguments
+    ///
+    /// * `jitter` - The upper bound of the random delay.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    /// use std::time::Duration;
+    ///
+    /// let builder = RollingRequestsBuilder::new().launch_jitter(Duration::from_millis(100));
+    /// ```
+    pub fn launch_jitter(mut self, jitter: Duration) -> Self {
+        self.config.launch_jitter = Some(jitter);
+        self
+    }
+
+    /// Sets the minimum gap between any two request dispatches, regardless of host.
+    ///
+    /// #### Arguments
+    ///
+    /// * `spacing` - The minimum gap between successive dispatches.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    /// use std::time::Duration;
+    ///
+    /// let builder = RollingRequestsBuilder::new().launch_spacing(Duration::from_millis(20));
+    /// ```
+    pub fn launch_spacing(mut self, spacing: Duration) -> Self {
+        self.config.launch_spacing = Some(spacing);
+        self
+    }
+
+    /// Caps the combined download throughput of body reads.
+    ///
+    /// The cap is shared by every body read through `read_body`, so concurrent
+    /// downloads split the budget between them. Zero disables throttling.
+    ///
+    /// #### Arguments
+    ///
+    /// * `limit` - The number of bytes allowed per second.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let builder = RollingRequestsBuilder::new().max_bytes_per_second(1024 * 1024);
+    /// ```
+    pub fn max_bytes_per_second(mut self, limit: u64) -> Self {
+        self.config.max_bytes_per_second = Some(limit);
+        self
+    }
+
+    /// Ramps the concurrency up gradually at the start of each run.
+    ///
+    /// Every run starts with one simultaneous request and adds `step` more every
+    /// `interval` until `target_limit` is reached. The target takes the place of
+    /// `simultaneous_limit`.
+    ///
+    /// #### Arguments
+    ///
+    /// * `target_limit` - The concurrency reached at the end of the ramp.
+    /// * `step` - How many simultaneous requests are added at each step.
+    /// * `interval` - The time between two steps.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    /// use std::time::Duration;
+    ///
+    /// let builder = RollingRequestsBuilder::new().ramp_up(50, 1, Duration::from_secs(2));
+    /// ```
+    pub fn ramp_up(mut self, target_limit: usize, step: usize, interval: Duration) -> Self {
+        self.config.ramp_up = Some(RampUp {
+            target_limit,
+            step,
+            interval,
+        });
+        self
+    }
+
+    /// Switches to open-loop mode, starting requests at a constant rate.
+    ///
+    /// Requests are dispatched on a fixed schedule regardless of how many are
+    /// still in flight, so server slowdowns show up as growing latency instead of
+    /// a lower request rate. The number of requests in flight is capped by
+    /// `max_outstanding` rather than `simultaneous_limit`.
+    ///
+    /// #### Arguments
+    ///
+    /// * `per_second` - The number of requests started per second.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let builder = RollingRequestsBuilder::new()
+    ///     .arrival_rate(50.0)
+    ///     .max_outstanding(500);
+    /// ```
+    pub fn arrival_rate(mut self, per_second: f64) -> Self {
+        self.config.arrival_rate = Some(per_second);
+        self
+    }
+
+    /// Sets the cap on requests in flight in open-loop mode.
+    ///
+    /// When the cap is reached, the next arrival waits for a request to finish.
+    /// Defaults to 1024.
+    ///
+    /// #### Arguments
+    ///
+    /// * `limit` - The maximum number of outstanding requests.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let builder = RollingRequestsBuilder::new().max_outstanding(100);
+    /// ```
+    pub fn max_outstanding(mut self, limit: usize) -> Self {
+        self.config.max_outstanding = Some(limit);
+        self
+    }
+
+    /// Sets the retry policy for failed requests.
+    ///
+    /// Failed attempts accepted by the policy's predicate (by default, transient
+    /// errors of idempotent requests) are attempted again after an exponential
+    /// backoff until the policy's attempt count is reached. Requests with their
+    /// own policy, set with `Request::set_retry_policy`, use that instead.
+    ///
+    /// #### Arguments
+    ///
+    /// * `policy` - The retry policy to apply.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::retry::RetryPolicy;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let builder = RollingRequestsBuilder::new().retry_policy(RetryPolicy {
+    ///     max_attempts: 5,
+    ///     ..RetryPolicy::default()
+    /// });
+    /// ```
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.config.retry_policy = Some(policy);
+        self
+    }
+
+    /// Caps the number of retries made across all requests within a sliding window.
+    ///
+    /// Once the budget is exhausted, failed requests are returned right away
+    /// instead of being retried, so an outage does not multiply the request volume.
+    ///
+    /// #### Arguments
+    ///
+    /// * `budget` - The retry budget to enforce.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::retry::{RetryBudget, RetryPolicy};
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    /// use std::time::Duration;
+    ///
+    /// // Retries may add at most 20% to the requests sent in the last ten seconds
+    /// let builder = RollingRequestsBuilder::new()
+    ///     .retry_policy(RetryPolicy::default())
+    ///     .retry_budget(RetryBudget::Ratio {
+    ///         ratio: 0.2,
+    ///         window: Duration::from_secs(10),
+    ///     });
+    /// ```
+    pub fn retry_budget(mut self, budget: RetryBudget) -> Self {
+        self.config.retry_budget = Some(budget);
+        self
+    }
+
+    /// Caps the number of failed requests kept in the dead-letter queue.
+    ///
+    /// #### Arguments
+    ///
+    /// * `capacity` - The maximum number of failed requests kept.
+    /// * `overflow` - Which request is discarded when the queue is full.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::retry::DeadLetterOverflow;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let builder = RollingRequestsBuilder::new()
+    ///     .dead_letter_capacity(100, DeadLetterOverflow::DropNewest);
+    /// ```
+    pub fn dead_letter_capacity(mut self, capacity: usize, overflow: DeadLetterOverflow) -> Self {
+        self.config.dead_letter_capacity = Some(capacity);
+        self.config.dead_letter_overflow = overflow;
+        self
+    }
+
+    /// Caps the number of results of background executions kept until
+    /// `drain_results` is called.
+    ///
+    /// By default every result is kept until it is drained.
+    ///
+    /// #### Arguments
+    ///
+    /// * `capacity` - The maximum number of results kept.
+    /// * `overflow` - Whether the oldest result is discarded or the execution
+    ///   waits when the buffer is full.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::result::ResultOverflow;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let builder = RollingRequestsBuilder::new()
+    ///     .result_buffer_capacity(1000, ResultOverflow::Block);
+    /// ```
+    pub fn result_buffer_capacity(mut self, capacity: usize, overflow: ResultOverflow) -> Self {
+        self.config.result_buffer_capacity = Some(capacity);
+        self.config.result_buffer_overflow = overflow;
+        self
+    }
+
+    /// Enables hedged requests.
+    ///
+    /// When an attempt has not completed within `delay`, an identical attempt is
+    /// sent and whichever finishes first provides the result; the other one is
+    /// aborted. Only requests with an idempotent method and without multipart
+    /// form data are hedged, so uploads are never sent twice at once. Hedged
+    /// attempts count against the concurrency limit and the rate limits, so they
+    /// are only sent while there is room for them.
+    ///
+    /// #### Arguments
+    ///
+    /// * `delay` - How long to wait for an attempt before sending a duplicate.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    /// use std::time::Duration;
+    ///
+    /// let builder = RollingRequestsBuilder::new()
+    ///     .simultaneous_limit(10)
+    ///     .hedge_after(Duration::from_millis(250));
+    /// ```
+    pub fn hedge_after(mut self, delay: Duration) -> Self {
+        self.config.hedge_after = Some(delay);
+        self
+    }
+
+    /// Sets the statuses that make a request move on to its next fallback URL.
+    ///
+    /// Connection errors and timeouts always do. Defaults to 502, 503 and 504.
+    ///
+    /// #### Arguments
+    ///
+    /// * `statuses` - The status codes triggering a fallback.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let builder = RollingRequestsBuilder::new().fallback_statuses(&[404, 500, 502, 503, 504]);
+    /// ```
+    pub fn fallback_statuses(mut self, statuses: &[u16]) -> Self {
+        self.config.fallback_statuses = statuses.to_vec();
+        self
+    }
+
+    /// Adds a generated idempotency key to POST and PATCH requests.
+    ///
+    /// A random UUID is put in the `header` header when such a request is first
+    /// dispatched, and the same value is sent with every retry, fallback and hedge
+    /// of it. Requests that already carry the header keep their own value.
+    ///
+    /// #### Arguments
+    ///
+    /// * `header` - The name of the header, e.g. `Idempotency-Key`.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let builder = RollingRequestsBuilder::new().idempotency_key("Idempotency-Key");
+    /// ```
+    pub fn idempotency_key(mut self, header: &str) -> Self {
+        self.config.idempotency_key_header = Some(header.to_string());
+        self
+    }
+
+    /// Returns responses with a non-success status as errors.
+    ///
+    /// The conversion happens once retries and fallbacks are done, so a 503
+    /// can still be retried before it is surfaced as an error. The status is
+    /// available through `RollingError::status`, and the beginning of the
+    /// body through `RollingError::body_snippet`, see `error_body_snippet`.
+    /// Requests can override this with `Request::set_error_for_status`.
+    ///
+    /// #### Arguments
+    ///
+    /// * `enabled` - Whether non-success statuses become errors.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let builder = RollingRequestsBuilder::new().error_for_status(true);
+    /// ```
+    pub fn error_for_status(mut self, enabled: bool) -> Self {
+        self.config.error_for_status = enabled;
+        self
+    }
+
+    /// Sets how many bytes of the body of a response turned into an error by
+    /// `error_for_status` are kept in the error, 4 KiB by default.
+    ///
+    /// The snippet is read before the error is made, and is shown by the
+    /// `Display` output of the error after its message.
+    ///
+    /// #### Arguments
+    ///
+    /// * `limit` - The number of bytes kept; `0` leaves bodies unread.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let builder = RollingRequestsBuilder::new()
+    ///     .error_for_status(true)
+    ///     .error_body_snippet(512);
+    /// ```
+    pub fn error_body_snippet(mut self, limit: usize) -> Self {
+        self.config.error_body_snippet = limit;
+        self
+    }
+
+    /// Sets a hook refreshing the credential when a request is rejected with 401.
+    ///
+    /// The hook returns the new `Authorization` header value, or `None` when the
+    /// refresh failed. Concurrent 401 responses lead to a single call. The
+    /// rejected request is then sent once more with the new value, which also
+    /// replaces the `Authorization` header of every later request.
+    ///
+    /// #### Arguments
+    ///
+    /// * `hook` - Returns a future resolving to the new `Authorization` value.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let builder = RollingRequestsBuilder::new().on_unauthorized(|| async {
+    ///     // Fetch a new token from the identity provider here
+    ///     Some("Bearer new-token".to_string())
+    /// });
+    /// ```
+    pub fn on_unauthorized<F, Fut>(mut self, hook: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Option<String>> + Send + 'static,
+    {
+        self.config.on_unauthorized = Some(Box::new(move || Box::pin(hook())));
+        self
+    }
+
+    /// Sets the rules `check_health` uses to classify endpoints.
+    ///
+    /// #### Arguments
+    ///
+    /// * `check` - The statuses, latency threshold, body match and failure
+    ///   threshold to apply.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::health::HealthCheck;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    /// use std::time::Duration;
+    ///
+    /// let builder = RollingRequestsBuilder::new().health_check(HealthCheck {
+    ///     latency_threshold: Duration::from_millis(500),
+    ///     failure_threshold: 3,
+    ///     ..HealthCheck::default()
+    /// });
+    /// ```
+    pub fn health_check(mut self, check: HealthCheck) -> Self {
+        self.config.health_check = check;
+        self
+    }
+
+    /// Stops an execution as soon as a request fails.
+    ///
+    /// When a request produces an error after its retries (including a
+    /// non-success status under `error_for_status`), `execute_requests` and
+    /// `execute_all` stop dispatching, abort the requests still in flight and
+    /// return the results gathered so far. Requests that were never dispatched
+    /// stay in the queue.
+    ///
+    /// #### Arguments
+    ///
+    /// * `enabled` - Whether to stop on the first failure.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let builder = RollingRequestsBuilder::new()
+    ///     .error_for_status(true)
+    ///     .fail_fast(true);
+    /// ```
+    pub fn fail_fast(mut self, enabled: bool) -> Self {
+        self.config.fail_fast = enabled;
+        self
+    }
+
+    /// Sets the URL that relative request URLs are resolved against.
+    ///
+    /// Relative URLs are joined to the base when the request is sent, following
+    /// RFC 3986: a path starting with `/` replaces the whole base path, and any
+    /// other path replaces the last segment of the base path, so a base meant as
+    /// a directory should end with `/`. Absolute request URLs are sent as given.
+    ///
+    /// #### Arguments
+    ///
+    /// * `url` - The absolute base URL. `build` panics if it cannot be parsed,
+    ///   while `try_build` returns `ClientBuildError::InvalidBaseUrl`.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let mut rolling_requests = RollingRequestsBuilder::new()
+    ///     .base_url("https://api.example.com/v1/")
+    ///     .build();
+    /// // Sent to https://api.example.com/v1/things/42
+    /// rolling_requests.add_request(Request::get("things/42"));
+    /// ```
+    pub fn base_url(mut self, url: &str) -> Self {
+        self.config.base_url = Some(url.to_string());
+        self
+    }
+
+    /// Sets the maximum size of request bodies and of each multipart part.
+    ///
+    /// A request over the limit fails with `RollingErrorKind::BodyTooLarge`
+    /// without being sent, and without affecting the other requests. Files are
+    /// measured before they are streamed and cut off if they grow past the
+    /// limit. Response bodies read into memory for the `on_complete` and
+    /// continuation hooks of a request, or for the response cache, are
+    /// bounded by the same limit.
+    /// `Request::set_max_body_size` overrides the limit per request.
+    ///
+    /// #### Arguments
+    ///
+    /// * `max_body_size` - The maximum size in bytes.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let builder = RollingRequestsBuilder::new().max_body_size(100 * 1024 * 1024);
+    /// ```
+    pub fn max_body_size(mut self, max_body_size: u64) -> Self {
+        self.config.max_body_size = Some(max_body_size);
+        self
+    }
+
+    /// Answers repeated GET and HEAD requests from an in-memory cache.
+    ///
+    /// Cacheable responses are read into memory when they arrive and stored,
+    /// see `ResponseCache` for which ones. Their bodies are read within the
+    /// bandwidth limit and the maximum body size; a response announcing a
+    /// larger body is not stored. Requests with an output file, a body sink
+    /// or a line or event handler bypass the cache. A request for a stored
+    /// response is answered without being sent, and marked in
+    /// `RollingResult::cached`. A request matching one still in flight waits
+    /// for its response instead of being sent alongside it, so a URL queued
+    /// many times is fetched once. Responses rebuilt by the cache carry a
+    /// `CacheInfo` extension.
+    ///
+    /// #### Arguments
+    ///
+    /// * `settings` - The lifetime and number of cached responses.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::cache::ResponseCache;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    /// use std::time::Duration;
+    ///
+    /// let builder = RollingRequestsBuilder::new().response_cache(ResponseCache {
+    ///     ttl: Duration::from_secs(60),
+    ///     max_entries: 10_000,
+    /// });
+    /// ```
+    pub fn response_cache(mut self, settings: ResponseCache) -> Self {
+        self.config.response_cache = Some(settings);
+        self
+    }
+
+    /// Makes repeated GET and HEAD requests conditional with the validators
+    /// of earlier responses, see `ValidatorStore`.
+    ///
+    /// The store is shared, so it can be saved after the executions, or used
+    /// by several instances.
+    ///
+    /// #### Arguments
+    ///
+    /// * `store` - The store recording and providing the validators.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::cache::ValidatorStore;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    /// use std::sync::Arc;
+    ///
+    /// let store = Arc::new(ValidatorStore::new());
+    /// let builder = RollingRequestsBuilder::new().validator_store(store.clone());
+    /// ```
+    pub fn validator_store(mut self, store: Arc<ValidatorStore>) -> Self {
+        self.config.validator_store = Some(store);
+        self
+    }
+
+    /// Records every request sent with its response, for `export_har`.
+    ///
+    /// #### Arguments
+    ///
+    /// * `settings` - The body size recorded, the headers redacted and the
+    ///   number of entries kept.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::har::HarRecording;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let builder = RollingRequestsBuilder::new().record_har(HarRecording {
+    ///     body_limit: 1024,
+    ///     redact_headers: vec!["Authorization".to_string(), "X-Api-Key".to_string()],
+    ///     max_entries: 1000,
+    /// });
+    /// ```
+    #[cfg(feature = "har")]
+    pub fn record_har(mut self, settings: HarRecording) -> Self {
+        self.config.har_recording = Some(settings);
+        self
+    }
+
+    /// Sets the charset of response text that declares none, in place of UTF-8.
+    ///
+    /// Text is decoded with the charset of a byte order mark, of the
+    /// `Content-Type` header, or of a `meta` tag in the first kilobyte of an
+    /// HTML body, and with this charset when none of them is present. The
+    /// charset used is reported in `RollingResult::charset`.
+    ///
+    /// #### Arguments
+    ///
+    /// * `label` - The name of the charset, e.g. `Shift_JIS` or `ISO-8859-1`.
+    ///   A name unknown to the WHATWG Encoding Standard is rejected by
+    ///   `try_build` with `ClientBuildError::UnknownCharset`.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let builder = RollingRequestsBuilder::new().default_charset("Shift_JIS");
+    /// ```
+    #[cfg(feature = "charset")]
+    pub fn default_charset(mut self, label: &str) -> Self {
+        self.config.default_charset = Some(label.to_string());
+        self
+    }
+
+    /// Enables or disables dropping requests equal to one already pending.
+    ///
+    /// Requests are compared as by `Request::eq`: by method, URL, body,
+    /// headers, cookies and query and path parameters, ignoring `extra_info`
+    /// and settings, and also by output file, expected checksum and tag, so
+    /// downloads to other paths and requests queued under other tags are
+    /// kept. Only a 64-bit fingerprint of each request is remembered, not the
+    /// request itself. `add_request` returns the identifier of the pending
+    /// request for a duplicate, and `try_add_request` reports it as an error.
+    /// A request counts as pending until it is dispatched, see
+    /// `deduplicate_executed` to remember it afterwards.
+    ///
+    /// #### Arguments
+    ///
+    /// * `enabled` - Whether duplicates are dropped.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let mut rolling_requests = RollingRequestsBuilder::new().deduplicate(true).build();
+    /// let id = rolling_requests.add_request(Request::get("http://example.com"));
+    /// assert_eq!(rolling_requests.add_request(Request::get("http://example.com")), id);
+    /// ```
+    pub fn deduplicate(mut self, enabled: bool) -> Self {
+        self.config.deduplicate = enabled;
+        self
+    }
+
+    /// Enables or disables dropping requests equal to one already executed,
+    /// on top of those pending, when `deduplicate` is enabled.
+    ///
+    /// Executed requests are remembered until `forget_requests` is called.
+    ///
+    /// #### Arguments
+    ///
+    /// * `enabled` - Whether executed requests are remembered.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let builder = RollingRequestsBuilder::new()
+    ///     .deduplicate(true)
+    ///     .deduplicate_executed(true);
+    /// ```
+    pub fn deduplicate_executed(mut self, enabled: bool) -> Self {
+        self.config.deduplicate_executed = enabled;
+        self
+    }
+
+    /// Caps how many continuations may lead to a request, see
+    /// `Request::set_continuation`.
+    ///
+    /// Follow-ups deeper than the cap are dropped, which stops chains that
+    /// would otherwise loop forever.
+    ///
+    /// #### Arguments
+    ///
+    /// * `depth` - The maximum depth; `0` drops every follow-up.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let builder = RollingRequestsBuilder::new().max_chain_depth(8);
+    /// ```
+    pub fn max_chain_depth(mut self, depth: u32) -> Self {
+        self.config.max_chain_depth = Some(depth);
+        self
+    }
+
+    /// Caps the response bodies kept by the detailed and paired executions,
+    /// such as `execute_requests_detailed` and `execute_requests_paired`.
+    ///
+    /// Bodies are read up to the limit; the rest is discarded and the result
+    /// is marked as truncated.
+    ///
+    /// #### Arguments
+    ///
+    /// * `limit` - The number of body bytes to keep.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let builder = RollingRequestsBuilder::new().result_body_limit(64 * 1024);
+    /// ```
+    pub fn result_body_limit(mut self, limit: usize) -> Self {
+        self.config.result_body_limit = Some(limit);
+        self
+    }
+
+    /// Sets whether the detailed and paired executions only record the status,
+    /// final URL and headers of responses.
+    ///
+    /// Bodies are dropped without being read, which closes their connection
+    /// instead of returning it to the pool, and output files are not written.
+    ///
+    /// #### Arguments
+    ///
+    /// * `enabled` - Whether to leave response bodies unread.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let builder = RollingRequestsBuilder::new().headers_only(true);
+    /// ```
+    pub fn headers_only(mut self, enabled: bool) -> Self {
+        self.config.headers_only = enabled;
+        self
+    }
+
+    /// Sets whether requests are only checked for their status, e.g. to
+    /// validate links.
+    ///
+    /// GET requests are sent as HEAD, and sent again as GET when the server
+    /// rejects HEAD with 405 or 501, which `RollingResult::head_fallback`
+    /// records. As with `headers_only`, the detailed and paired executions
+    /// leave response bodies unread. `execute_all` and the other executions
+    /// handing responses back send requests as given.
+    ///
+    /// #### Arguments
+    ///
+    /// * `enabled` - Whether to check links instead of fetching them.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let builder = RollingRequestsBuilder::new()
+    ///     .simultaneous_limit(50)
+    ///     .link_check(true);
+    /// ```
+    pub fn link_check(mut self, enabled: bool) -> Self {
+        self.config.link_check = enabled;
+        self
+    }
+
+    /// Sets a hook receiving the progress of downloads to output files.
+    ///
+    /// The hook is called from the loop writing the body, after each chunk or
+    /// at most once per `progress_interval`, and always once with the final
+    /// size. It should return quickly, e.g. by sending the report over a
+    /// channel, as the download waits for it.
+    ///
+    /// #### Arguments
+    ///
+    /// * `hook` - Receives the bytes written for the request and for the
+    ///   whole execution.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let builder = RollingRequestsBuilder::new().on_progress(|progress| {
+    ///     if let Some(total) = progress.total {
+    ///         println!("{}/{} bytes", progress.bytes_done, total);
+    ///     }
+    /// });
+    /// ```
+    pub fn on_progress<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&Progress) + Send + Sync + 'static,
+    {
+        self.config.on_progress = Some(Box::new(hook));
+        self
+    }
+
+    /// Sets the minimum time between two progress reports of a download.
+    ///
+    /// #### Arguments
+    ///
+    /// * `interval` - The minimum time between two reports.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    /// use std::time::Duration;
+    ///
+    /// let builder = RollingRequestsBuilder::new().progress_interval(Duration::from_millis(250));
+    /// ```
+    pub fn progress_interval(mut self, interval: Duration) -> Self {
+        self.config.progress_interval = Some(interval);
+        self
+    }
+
+    /// Saves the body of every response of the detailed executions to a file
+    /// in `dir`, named from `template`.
+    ///
+    /// The template may reference `{id}`, the request identifier,
+    /// `{extra_info}`, `{url_hash}`, a hash of the request URL, and
+    /// `{status}`. Responses of any status are saved, streamed like those of
+    /// requests with an output file, which take precedence; the written file
+    /// is reported in `RollingResult::download`. See `save_collision` for
+    /// names that are taken.
+    ///
+    /// #### Arguments
+    ///
+    /// * `dir` - The directory the files are written to, created when missing.
+    /// * `template` - The template of the file names.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let builder = RollingRequestsBuilder::new().save_responses_to("responses", "{extra_info}_{status}.json");
+    /// ```
+    pub fn save_responses_to(mut self, dir: impl Into<PathBuf>, template: &str) -> Self {
+        self.config.save_responses = Some(SaveResponses {
+            dir: dir.into(),
+            template: template.to_string(),
+        });
+        self
+    }
+
+    /// Sets what happens when the file a response is saved to already exists,
+    /// or another response is being written to it.
+    ///
+    /// #### Arguments
+    ///
+    /// * `collision` - Whether to add a suffix, overwrite, or fail the request.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::download::SaveCollision;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let builder = RollingRequestsBuilder::new()
+    ///     .save_responses_to("responses", "{id}.json")
+    ///     .save_collision(SaveCollision::Overwrite);
+    /// ```
+    pub fn save_collision(mut self, collision: SaveCollision) -> Self {
+        self.config.save_collision = collision;
+        self
+    }
+
+    /// Builds the `RollingRequests` instance.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let rolling_requests = RollingRequestsBuilder::new().build();
+    /// ```
+    ///
+    /// #### Panics
+    ///
+    /// Panics if the configuration is rejected, see `try_build`.
+    pub fn build(self) -> RollingRequests {
+        self.try_build().unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Builds the `RollingRequests` instance, returning an error instead of
+    /// panicking when the configuration is rejected.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::{ClientBuildError, RollingRequestsBuilder};
+    ///
+    /// let result = RollingRequestsBuilder::new().base_url("not a url").try_build();
+    /// assert!(matches!(result, Err(ClientBuildError::InvalidBaseUrl { .. })));
+    /// ```
+    ///
+    /// #### Errors
+    ///
+    /// * `ClientBuildError::Client` - The HTTP client could not be built, for
+    ///   example by a builder returned from `configure_client`.
+    /// * `ClientBuildError::InvalidBaseUrl` - The base URL could not be parsed.
+    /// * `ClientBuildError::InvalidProxy` - The proxy URL could not be parsed, or
+    ///   has an unsupported scheme.
+    /// * `ClientBuildError::InvalidHeader` - A default header has an invalid name
+    ///   or value.
+    /// * `ClientBuildError::CookieStore` - The file of `cookie_store_path` exists
+    ///   but could not be read as saved cookies.
+    /// * `ClientBuildError::UnknownCharset` - The label of `default_charset`
+    ///   names no known charset.
+    /// * `ClientBuildError::Config` - A setting is out of range or conflicts with
+    ///   another, see `RollingRequestsConfig::validate`.
+    pub fn try_build(self) -> Result<RollingRequests, ClientBuildError> {
+        if let Some(header) = self.header_errors.into_iter().next() {
+            return Err(ClientBuildError::InvalidHeader(header));
+        }
+        RollingRequests::try_new(self.config)
+    }
+}
+
+/// The reason a `RollingRequests` instance could not be built by
+/// `RollingRequestsBuilder::try_build`.
+#[derive(Debug)]
+pub enum ClientBuildError {
+    /// The HTTP client could not be built.
+    Client(reqwest::Error),
+    /// The base URL could not be parsed.
+    InvalidBaseUrl {
+        /// The URL as given.
+        url: String,
+        /// Why the URL was rejected.
+        source: url::ParseError,
+    },
+    /// The proxy URL could not be parsed, or has an unsupported scheme.
+    InvalidProxy {
+        /// The URL as given.
+        url: String,
+        /// Why the URL was rejected.
+        source: reqwest::Error,
+    },
+    /// A default header has an invalid name or value.
+    InvalidHeader(InvalidHeader),
+    /// The file of `cookie_store_path` exists but could not be read as saved
+    /// cookies; it is left as it was.
+    CookieStore {
+        /// The file as given.
+        path: PathBuf,
+        /// Why the file was rejected.
+        source: io::Error,
+    },
+    /// The label of `default_charset` names no known charset.
+    UnknownCharset(String),
+    /// A setting is out of range or conflicts with another.
+    Config(ConfigError),
+}
+
+/// A setting of a `RollingRequestsConfig` that is out of range or conflicts
+/// with another, named as its builder method, see
+/// `RollingRequestsConfig::validate`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfigError {
+    /// `simultaneous_limit`, or the target limit of `ramp_up`, is zero, so no
+    /// request would ever be dispatched.
+    ZeroLimit(&'static str),
+    /// `timeout` or `connect_timeout` is zero, so every request would fail.
+    ZeroTimeout(&'static str),
+    /// A rate of `default_rate_limit`, `per_host_rate_limit` or
+    /// `arrival_rate` is zero, negative or not a number.
+    InvalidRate {
+        /// The setting the rate was given to.
+        setting: &'static str,
+        /// The host of a `per_host_rate_limit`.
+        host: Option<String>,
+        /// The rate as given.
+        rate: f64,
+    },
+    /// `max_outstanding` is zero, so open-loop mode would never dispatch.
+    ZeroOutstanding,
+    /// The step of `ramp_up` is zero, so the limit would never grow.
+    ZeroRampStep,
+    /// The bounds of `adaptive_concurrency` are zero or reversed, or its
+    /// decrease factor is not between zero and one.
+    InvalidAdaptiveConcurrency(&'static str),
+    /// `user_agent` has characters other than visible ASCII and spaces.
+    InvalidUserAgent(String),
+    /// A setting cannot be applied to the client supplied with `client`.
+    UnsupportedWithClient(&'static str),
+    /// `no_proxy` or `proxy_auth` is set without `proxy` or `proxy_pool`, so
+    /// it would apply to no proxy.
+    ProxyRequired(&'static str),
+    /// Two settings cannot be used together.
+    Conflict(&'static str, &'static str),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::ZeroLimit(setting) => write!(f, "{} must be at least 1", setting),
+            ConfigError::ZeroTimeout(setting) => write!(f, "{} must not be zero", setting),
+            ConfigError::InvalidRate {
+                setting,
+                host: Some(host),
+                rate,
+            } => write!(
+                f,
+                "{} for {:?} must be positive, got {}",
+                setting, host, rate
+            ),
+            ConfigError::InvalidRate {
+                setting,
+                host: None,
+                rate,
+            } => write!(f, "{} must be positive, got {}", setting, rate),
+            ConfigError::ZeroOutstanding => write!(f, "max_outstanding must be at least 1"),
+            ConfigError::ZeroRampStep => write!(f, "the step of ramp_up must be at least 1"),
+            ConfigError::InvalidAdaptiveConcurrency(reason) => {
+                write!(f, "adaptive_concurrency {}", reason)
+            }
+            ConfigError::InvalidUserAgent(user_agent) => {
+                write!(f, "user_agent {:?} must be visible ASCII", user_agent)
+            }
+            ConfigError::UnsupportedWithClient(setting) => write!(
+                f,
+                "{} cannot be applied to a client supplied to the builder",
+                setting
+            ),
+            ConfigError::ProxyRequired(setting) => {
+                write!(f, "{} requires proxy or proxy_pool", setting)
+            }
+            ConfigError::Conflict(setting, other) => {
+                write!(f, "{} cannot be used together with {}", setting, other)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl fmt::Display for ClientBuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientBuildError::Client(error) => write!(f, "failed to build HTTP client: {}", error),
+            ClientBuildError::InvalidBaseUrl { url, source } => {
+                write!(f, "invalid base URL {:?}: {}", url, source)
+            }
+            ClientBuildError::InvalidProxy { url, source } => {
+                write!(f, "invalid proxy URL {:?}: {}", redact_url(url), source)
+            }
+            ClientBuildError::InvalidHeader(header) => {
+                write!(f, "invalid default header: {}", header)
+            }
+            ClientBuildError::CookieStore { path, source } => {
+                write!(f, "invalid cookie file {:?}: {}", path, source)
+            }
+            ClientBuildError::UnknownCharset(label) => {
+                write!(f, "unknown default charset {:?}", label)
+            }
+            ClientBuildError::Config(error) => write!(f, "invalid configuration: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for ClientBuildError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ClientBuildError::Client(error) => Some(error),
+            ClientBuildError::InvalidBaseUrl { source, .. } => Some(source),
+            ClientBuildError::InvalidProxy { source, .. } => Some(source),
+            ClientBuildError::InvalidHeader(header) => Some(header),
+            ClientBuildError::CookieStore { source, .. } => Some(source),
+            ClientBuildError::UnknownCharset(_) => None,
+            ClientBuildError::Config(error) => Some(error),
+        }
+    }
+}
+
+impl RollingRequests {
+    /// Creates a new `RollingRequests` instance with the specified configuration.
+    ///
+    /// #### Arguments
+    ///
+    /// * `config` - The configuration for the requests.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    /// use std::time::Duration;
+    ///
+    /// let rolling_requests = RollingRequestsBuilder::new()
+    ///     .simultaneous_limit(5)
+    ///     .timeout(Duration::from_secs(10))
+    ///     .build();
+    /// ```
+    ///
+    /// #### Panics
+    ///
+    /// Panics if the configuration is rejected, see `try_new`.
+    pub fn new(config: RollingRequestsConfig) -> Self {
+        Self::try_new(config).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Creates a new `RollingRequests` instance with the specified
+    /// configuration, returning an error instead of panicking when it is
+    /// rejected.
+    ///
+    /// #### Arguments
+    ///
+    /// * `config` - The configuration for the requests.
+    ///
+    /// #### Errors
+    ///
+    /// See `RollingRequestsBuilder::try_build`.
+    pub fn try_new(mut config: RollingRequestsConfig) -> Result<Self, ClientBuildError> {
+        config.validate().map_err(ClientBuildError::Config)?;
+        #[cfg(feature = "charset")]
+        let default_charset = match config.default_charset.take() {
+            Some(label) => match encoding_rs::Encoding::for_label(label.as_bytes()) {
+                Some(encoding) => Some(encoding),
+                None => return Err(ClientBuildError::UnknownCharset(label)),
+            },
+            None => None,
+        };
+        #[cfg(feature = "persistent-cookies")]
+        let cookie_jar = match config.cookie_store_path.take() {
+            Some(path) if config.cookie_store => match CookieJar::load(path.clone()) {
+                Ok(jar) => Some(Arc::new(jar)),
+                Err(source) => return Err(ClientBuildError::CookieStore { path, source }),
+            },
+            _ => config.cookie_store.then(|| Arc::new(CookieJar::default())),
+        };
+        #[cfg(not(feature = "persistent-cookies"))]
+        let cookie_jar = config.cookie_store.then(|| Arc::new(CookieJar::default()));
+        let instance_proxy = |url: &str| match reqwest::Proxy::all(url) {
+            Ok(mut proxy) => {
+                if let Some((username, password)) = &config.proxy_auth {
+                    proxy = proxy.basic_auth(username, password);
+                }
+                let no_proxy = config.no_proxy.join(",");
+                Ok(proxy.no_proxy(reqwest::NoProxy::from_string(&no_proxy)))
+            }
+            Err(source) => Err(ClientBuildError::InvalidProxy {
+                url: url.to_string(),
+                source,
+            }),
+        };
+        let proxy = config.proxy.as_deref().map(instance_proxy).transpose()?;
+        let pool_proxies = config
+            .proxy_pool
+            .iter()
+            .map(|url| instance_proxy(url))
+            .collect::<Result<Vec<_>, _>>()?;
+        let client_settings = ClientSettings {
+            timeout: config.timeout,
+            connect_timeout: config.connect_timeout,
+            force_http2: config.force_http2,
+            user_agent: config.user_agent.take(),
+            cookie_jar: cookie_jar.clone(),
+            proxy,
+            pool_proxies,
+            max_redirects: match config.max_redirects {
+                0 => DEFAULT_MAX_REDIRECTS,
+                max => max,
+            },
+            configure: config.configure_client.take(),
+        };
+        let client_supplied = config.client.is_some();
+        let request_timeout = config.client_timeout.filter(|_| client_supplied);
+        let client = match config.client.take() {
+            Some(client) => client,
+            None => client_settings
+                .build(&ClientKey::default())
+                .map_err(ClientBuildError::Client)?,
+        };
+        let no_redirect_client = client_settings
+            .build(&ClientKey {
+                follow_redirects: false,
+                ..ClientKey::default()
+            })
+            .map_err(ClientBuildError::Client)?;
+        let base_url = match config.base_url.take() {
+            Some(url) => match Url::parse(&url) {
+                Ok(base_url) => Some(base_url),
+                Err(source) => return Err(ClientBuildError::InvalidBaseUrl { url, source }),
+            },
+            None => None,
+        };
+
+        let simultaneous_limit = config
+            .ramp_up
+            .as_ref()
+            .map_or(config.simultaneous_limit, |ramp| ramp.target_limit);
+
+        Ok(RollingRequests {
+            simultaneous_limit,
+            pending_requests: Arc::new(Mutex::new(Vec::new())),
+            client,
+            no_redirect_client,
+            client_supplied,
+            follow_redirects: config.max_redirects > 0,
+            client_settings,
+            extra_clients: Mutex::new(HashMap::new()),
+            throttle: Arc::new(Mutex::new(HostThrottle::new(&config))),
+            limiter: config.adaptive_concurrency.map(|settings| {
+                Arc::new(Mutex::new(AimdLimiter::new(settings, simultaneous_limit)))
+            }),
+            proxy_pool: (!config.proxy_pool.is_empty()).then(|| {
+                Mutex::new(ProxyPool::new(
+                    config.proxy_pool,
+                    config.proxy_rotation,
+                    config.proxy_ejection,
+                ))
+            }),
+            respect_retry_after: config.respect_retry_after,
+            rate_limit_headers: config.rate_limit_headers,
+            launch_jitter: config.launch_jitter,
+            bandwidth: config
+                .max_bytes_per_second
+                .filter(|limit| *limit > 0)
+                .map(|limit| Arc::new(Bandwidth::new(limit))),
+            ramp_up: config.ramp_up,
+            run_started: Mutex::new(None),
+            arrival_interval: config
+                .arrival_rate
+                .filter(|rate| *rate > 0.0)
+                .map(|rate| Duration::from_secs_f64(1.0 / rate)),
+            max_outstanding: config.max_outstanding.unwrap_or(DEFAULT_MAX_OUTSTANDING),
+            retry_policy: config.retry_policy,
+            retry_tracker: Mutex::new(RetryTracker::new(config.retry_budget)),
+            stats: StatsCollector::default(),
+            dead_letters: Mutex::new(DeadLetterQueue::new(
+                config.dead_letter_capacity,
+                config.dead_letter_overflow,
+            )),
+            background_results: ResultBuffer::new(
+                config.result_buffer_capacity,
+                config.result_buffer_overflow,
+            ),
+            hedge_after: config.hedge_after,
+            fallback_statuses: config.fallback_statuses,
+            idempotency_key_header: config.idempotency_key_header,
+            error_for_status: config.error_for_status,
+            error_body_snippet: config.error_body_snippet,
+            credentials: config
+                .on_unauthorized
+                .map(|hook| Arc::new(Credentials::new(hook))),
+            health_check: config.health_check,
+            health_failures: Mutex::new(HashMap::new()),
+            fail_fast: config.fail_fast,
+            max_body_size: config.max_body_size,
+            result_body_limit: config.result_body_limit,
+            headers_only: config.headers_only || config.link_check,
+            link_check: config.link_check,
+            on_progress: config.on_progress,
+            progress_interval: config.progress_interval,
+            save_responses: config.save_responses,
+            save_collision: config.save_collision,
+            saved_paths: Mutex::new(HashSet::new()),
+            base_url,
+            next_request_id: AtomicU64::new(0),
+            seen_requests: config.deduplicate.then(|| Mutex::new(HashMap::new())),
+            deduplicate_executed: config.deduplicate_executed,
+            max_chain_depth: config.max_chain_depth,
+            request_timeout,
+            send_defaults: Arc::new(SendDefaults {
+                headers: config.default_headers,
+                cookie_jar: cookie_jar.clone(),
+            }),
+            cookie_jar,
+            cache: config
+                .response_cache
+                .map(|settings| Arc::new(Cache::new(settings))),
+            validators: config.validator_store,
+            #[cfg(feature = "har")]
+            har: config
+                .har_recording
+                .map(|settings| Arc::new(HarLog::new(settings))),
+            #[cfg(feature = "charset")]
+            default_charset,
+        })
+    }
+
+    /// Adds a new request to the collection of pending requests.
+    ///
+    /// The request is assigned an identifier, never reused by this instance,
+    /// which is returned and available from `Request::id`, the `RequestId`
+    /// extension of its response and `RollingError::request_id`.
+    ///
+    /// #### Arguments
+    ///
+    /// * `request` - The `Request` to add.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    /// use rollingrequests::request::Request;
+    /// use std::time::Duration;
+    ///
+    /// let mut rolling_requests = RollingRequestsBuilder::new().build();
+    /// let request = Request::get("http://example.com");
+    /// rolling_requests.add_request(request);
+    /// ```
+    ///
+    /// Under `RollingRequestsBuilder::deduplicate`, a request equal to one
+    /// added before is dropped and the identifier of that request returned;
+    /// `try_add_request` tells the two apart.
+    pub fn add_request(&mut self, request: Request) -> u64 {
+        self.try_add_request(request)
+            .unwrap_or_else(|duplicate| duplicate.id)
+    }
+
+    /// Adds a new request unless it is a duplicate, see
+    /// `RollingRequestsBuilder::deduplicate`.
+    ///
+    /// Without deduplication, every request is added.
+    ///
+    /// #### Arguments
+    ///
+    /// * `request` - The `Request` to add.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let mut rolling_requests = RollingRequestsBuilder::new().deduplicate(true).build();
+    /// let id = rolling_requests
+    ///     .try_add_request(Request::get("http://example.com"))
+    ///     .unwrap();
+    ///
+    /// let mut duplicate = Request::get("http://example.com");
+    /// duplicate.set_extra_info("again");
+    /// assert_eq!(rolling_requests.try_add_request(duplicate).unwrap_err().id, id);
+    /// ```
+    ///
+    /// #### Errors
+    ///
+    /// * `DuplicateRequest` - An equal request was added before and is still
+    ///   pending, or was executed under `deduplicate_executed`.
+    pub fn try_add_request(&mut self, request: Request) -> Result<u64, DuplicateRequest> {
+        self.admit(request)
+    }
+
+    /// Queues `request` unless deduplication finds it was added before.
+    fn admit(&self, request: Request) -> Result<u64, DuplicateRequest> {
+        let Some(seen) = &self.seen_requests else {
+            return Ok(self.enqueue(request));
+        };
+        // The lookup and the insertion happen under one lock, so concurrent
+        // merges cannot both add the same request
+        let id = {
+            let mut seen = seen.lock().unwrap();
+            let fingerprint = fingerprint(&seen, &request);
+            if let Some(&id) = seen.get(&fingerprint) {
+                return Err(DuplicateRequest { id });
+            }
+            let id = self.next_request_id.fetch_add(1, Ordering::Relaxed);
+            seen.insert(fingerprint, id);
+            id
+        };
+        self.enqueue_as(request, id);
+        Ok(id)
+    }
+
+    /// Queues a copy of `request` for each host, returning the handle of the
+    /// group to execute with `execute_fanout`.
+    ///
+    /// Every copy keeps the path, query, headers and body of the request,
+    /// with its scheme and authority replaced by those of its host, and is
+    /// tagged with the host as given. A relative URL is resolved against the
+    /// `base_url` first. The copies are queued even under deduplication, so
+    /// every host has a copy of its own. Nothing is queued when a URL is
+    /// rejected.
+    ///
+    /// #### Arguments
+    ///
+    /// * `request` - The `Request` to send to every host.
+    /// * `hosts` - The base URLs of the hosts, such as `https://node-1:8443`;
+    ///   their paths are ignored.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let mut rolling_requests = RollingRequestsBuilder::new().build();
+    /// let fanout = rolling_requests
+    ///     .add_fanout(
+    ///         Request::post("http://localhost/cache/flush", Some("all")),
+    ///         &["http://node-1:8080", "http://node-2:8080"],
+    ///     )
+    ///     .unwrap();
+    /// assert_eq!(fanout.len(), 2);
+    /// assert_eq!(rolling_requests.pending_count_for(Some("http://node-2:8080")), 1);
+    /// ```
+    ///
+    /// #### Errors
+    ///
+    /// * `InvalidOrigin` - A host has no scheme or authority, or the URL of
+    ///   the request is relative without a `base_url`.
+    pub fn add_fanout(
+        &mut self,
+        request: Request,
+        hosts: &[&str],
+    ) -> Result<FanOut, InvalidOrigin> {
+        let urls = hosts
+            .iter()
+            .map(|host| fanout::rebase(&request.url, host, self.base_url.as_ref()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let members = hosts
+            .iter()
+            .zip(urls)
+            .map(|(host, url)| {
+                let mut copy = request.clone();
+                copy.url = url;
+                copy.tag = Some(host.to_string());
+                (host.to_string(), self.enqueue(copy))
+            })
+            .collect();
+        Ok(FanOut::new(members))
+    }
+
+    /// Forgets every request known to deduplication, so that equal requests
+    /// can be added again; pending requests stay queued.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let mut rolling_requests = RollingRequestsBuilder::new().deduplicate(true).build();
+    /// let id = rolling_requests.add_request(Request::get("http://example.com"));
+    /// rolling_requests.forget_requests();
+    /// assert_ne!(rolling_requests.add_request(Request::get("http://example.com")), id);
+    /// ```
+    pub fn forget_requests(&self) {
+        if let Some(seen) = &self.seen_requests {
+            seen.lock().unwrap().clear();
+        }
+    }
+
+    /// Assigns the next identifier to `request` and queues it.
+    fn enqueue(&self, request: Request) -> u64 {
+        let id = self.next_request_id.fetch_add(1, Ordering::Relaxed);
+        self.enqueue_as(request, id);
+        id
+    }
+
+    /// Queues `request` with the identifier `id`.
+    fn enqueue_as(&self, mut request: Request, id: u64) {
+        request.id = Some(id);
+        request.queued_at = Some(Instant::now());
+        let mut pending = self.pending_requests.lock().unwrap();
+        pending.push(request);
+    }
+
+    /// Calls the completion hook of a finished job and queues the requests
+    /// returned by its continuation, see `Request::set_on_complete` and
+    /// `Request::set_continuation`, handing its response back.
+    fn run_hooks(
+        &self,
+        job: &Job,
+        response: Result<reqwest::Response, RollingError>,
+        queues_follow_ups: bool,
+    ) -> Result<reqwest::Response, RollingError> {
+        if !job.request.has_hooks() {
+            return response;
+        }
+        let mut result = RollingResult::new(&job.request);
+        let response = match response {
+            Ok(res) => {
+                result.status = Some(res.status());
+                result.url = Some(match res.extensions().get::<CacheInfo>() {
+                    Some(info) => info.url.clone(),
+                    None => res.url().clone(),
+                });
+                result.headers = res.headers().clone();
+                if let Some(body) = &job.request.buffered_body {
+                    result.body = body.to_vec();
+                }
+                #[cfg(feature = "charset")]
+                {
+                    let encoding =
+                        charset::detect(&result.body, &result.headers, self.default_charset);
+                    result.charset = Some(encoding.name());
+                }
+                Some(res)
+            }
+            Err(err) => {
+                result.status = err.status();
+                result.url = err.url().cloned();
+                result.error = Some(err);
+                None
+            }
+        };
+        result.finish(
+            job.started_at,
+            job.request.queued_at,
+            job.attempt_latencies.clone(),
+        );
+        job.request.complete(&result);
+        if queues_follow_ups {
+            for request in job.request.follow_ups(&result, self.max_chain_depth) {
+                self.enqueue(request);
+            }
+        }
+        match (response, result.error) {
+            (Some(res), _) => Ok(res),
+            (None, Some(err)) => Err(err),
+            (None, None) => unreachable!("a failed result keeps its error"),
+        }
+    }
+
+    /// Queues the page after a successful response, see `Request::set_max_pages`.
+    fn queue_next_page(&self, request: &Request, response: &reqwest::Response) {
+        if !response.status().is_success() {
+            return;
+        }
+        if let Some(next) = request.next_page(response.url(), response.headers()) {
+            self.enqueue(next);
+        }
+    }
+
+    /// Returns the number of requests that may currently run simultaneously.
+    ///
+    /// This is the configured `simultaneous_limit` unless adaptive concurrency is
+    /// enabled, in which case it reflects the controller's current limit. During a
+    /// run with a ramp-up schedule, the limit is further capped by the ramp.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let rolling_requests = RollingRequestsBuilder::new().simultaneous_limit(3).build();
+    /// assert_eq!(rolling_requests.effective_limit(), 3);
+    /// ```
+    pub fn effective_limit(&self) -> usize {
+        let limit = match &self.limiter {
+            Some(limiter) => limiter.lock().unwrap().limit(),
+            None => self.simultaneous_limit,
+        };
+        match (&self.ramp_up, *self.run_started.lock().unwrap()) {
+            (Some(ramp), Some(started)) => limit.min(ramp.limit_at(started.elapsed())),
+            _ => limit,
+        }
+    }
+
+    /// Returns the rate limit state last advertised by `host`.
+    ///
+    /// This is only tracked when `rate_limit_headers` is configured.
+    ///
+    /// #### Arguments
+    ///
+    /// * `host` - The host name as it appears in request URLs.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let rolling_requests = RollingRequestsBuilder::new().build();
+    /// assert!(rolling_requests.rate_limit_info("example.com").is_none());
+    /// ```
+    pub fn rate_limit_info(&self, host: &str) -> Option<RateLimitInfo> {
+        self.throttle
+            .lock()
+            .unwrap()
+            .rate_limit(&host.to_ascii_lowercase())
+    }
+
+    /// Returns the retries made so far and the retry budget left.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let rolling_requests = RollingRequestsBuilder::new().build();
+    /// let stats = rolling_requests.retry_stats();
+    /// assert_eq!(stats.retries, 0);
+    /// assert_eq!(stats.budget_remaining, None);
+    /// ```
+    pub fn retry_stats(&self) -> RetryStats {
+        self.retry_tracker.lock().unwrap().stats(Instant::now())
+    }
+
+    /// Returns a snapshot of the requests executed so far, their outcomes and
+    /// the bytes transferred, counted across every execution since the
+    /// instance was built or `reset_stats` was called.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let rolling_requests = RollingRequestsBuilder::new().build();
+    /// let stats = rolling_requests.stats();
+    /// assert_eq!(stats.dispatched, 0);
+    /// assert!(stats.status_counts.is_empty());
+    /// ```
+    pub fn stats(&self) -> Stats {
+        self.stats.snapshot()
+    }
+
+    /// Sets every counter reported by `stats` back to zero.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let rolling_requests = RollingRequestsBuilder::new().build();
+    /// rolling_requests.reset_stats();
+    /// assert_eq!(rolling_requests.stats().succeeded, 0);
+    /// ```
+    pub fn reset_stats(&self) {
+        self.stats.reset();
+    }
+
+    /// Returns the requests whose final result was an error, oldest first.
+    ///
+    /// Each request keeps its `extra_info` and attempt count, and carries the
+    /// message of its last error in `response_error`. Requests replayed by
+    /// `execute_for` are not collected.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let rolling_requests = RollingRequestsBuilder::new().build();
+    /// assert!(rolling_requests.failed_requests().is_empty());
+    /// ```
+    pub fn failed_requests(&self) -> Vec<Request> {
+        self.dead_letters.lock().unwrap().snapshot()
+    }
+
+    /// Removes every response from the response cache, if enabled.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::cache::ResponseCache;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let rolling_requests = RollingRequestsBuilder::new()
+    ///     .response_cache(ResponseCache::default())
+    ///     .build();
+    /// rolling_requests.clear_cache();
+    /// ```
+    pub fn clear_cache(&self) {
+        if let Some(cache) = &self.cache {
+            cache.clear();
+        }
+    }
+
+    /// Writes the traffic recorded so far to `path` as a HAR 1.2 file.
+    ///
+    /// Without `record_har`, the file holds no entries. Bodies still being
+    /// read are written as far as they were read.
+    ///
+    /// #### Arguments
+    ///
+    /// * `path` - The file to write, replaced if it exists.
+    ///
+    /// #### Examples
+    ///
+    /// ```no_run
+    /// use rollingrequests::har::HarRecording;
+    /// use rollingrequests::request::Request;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> std::io::Result<()> {
+    ///     let mut rolling_requests = RollingRequestsBuilder::new()
+    ///         .record_har(HarRecording::default())
+    ///         .build();
+    ///     rolling_requests.add_request(Request::get("http://example.com"));
+    ///     rolling_requests.execute_all_detailed().await;
+    ///     rolling_requests.export_har("run.har")
+    /// }
+    /// ```
+    ///
+    /// #### Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    #[cfg(feature = "har")]
+    pub fn export_har(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        match &self.har {
+            Some(har) => har.export(path.as_ref()),
+            None => HarLog::new(HarRecording::default()).export(path.as_ref()),
+        }
+    }
+
+    /// Returns the number of requests recorded for HAR export, at most the
+    /// `max_entries` of the recording.
+    #[cfg(feature = "har")]
+    pub fn har_entries(&self) -> usize {
+        self.har.as_ref().map_or(0, |har| har.len())
+    }
+
+    /// Discards the traffic recorded for HAR export.
+    #[cfg(feature = "har")]
+    pub fn clear_har(&self) {
+        if let Some(har) = &self.har {
+            har.clear();
+        }
+    }
+
+    /// Returns the number of pending requests with the given tag.
+    ///
+    /// #### Arguments
+    ///
+    /// * `tag` - The tag of the requests to count, see `Request::set_tag`;
+    ///   `None` counts the untagged ones.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let mut rolling_requests = RollingRequestsBuilder::new().build();
+    /// let mut request = Request::get("http://example.com");
+    /// request.set_tag("crawl");
+    /// rolling_requests.add_request(request);
+    /// assert_eq!(rolling_requests.pending_count_for(Some("crawl")), 1);
+    /// assert_eq!(rolling_requests.pending_count_for(None), 0);
+    /// ```
+    pub fn pending_count_for(&self, tag: Option<&str>) -> usize {
+        let pending = self.pending_requests.lock().unwrap();
+        pending
+            .iter()
+            .filter(|request| request.get_tag() == tag)
+            .count()
+    }
+
+    /// Returns a summary of every pending request, in queue order, without
+    /// removing them.
+    ///
+    /// The summaries leave out bodies and headers and carry redacted URLs,
+    /// see `RequestSummary`. The queue is only locked while the fields are
+    /// copied, so executions running meanwhile are barely held up.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let mut rolling_requests = RollingRequestsBuilder::new().build();
+    /// rolling_requests.add_request(Request::get("http://example.com/?token=secret"));
+    /// let snapshot = rolling_requests.pending_snapshot();
+    /// assert_eq!(snapshot[0].url, "http://example.com/?token=***");
+    /// assert_eq!(rolling_requests.pending_count_for(None), 1);
+    /// ```
+    pub fn pending_snapshot(&self) -> Vec<RequestSummary> {
+        let mut snapshot: Vec<RequestSummary> = self
+            .pending_requests
+            .lock()
+            .unwrap()
+            .iter()
+            .map(RequestSummary::unredacted)
+            .collect();
+        // Parsing the URLs to redact them waits until the queue is released
+        snapshot.iter_mut().for_each(RequestSummary::redact);
+        snapshot
+    }
+
+    /// Returns the cookies of the jar sent to `url`, by name and value.
+    ///
+    /// Returns nothing when `RollingRequestsBuilder::cookie_store` is not
+    /// enabled or the URL cannot be parsed; relative URLs are resolved
+    /// against the base URL.
+    ///
+    /// #### Arguments
+    ///
+    /// * `url` - The URL the cookies would be sent to.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let rolling_requests = RollingRequestsBuilder::new().cookie_store(true).build();
+    /// rolling_requests
+    ///     .add_cookie("http://example.com/", "session=abc; Path=/")
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     rolling_requests.cookies("http://example.com/account"),
+    ///     vec![("session".to_string(), "abc".to_string())]
+    /// );
+    /// ```
+    pub fn cookies(&self, url: &str) -> Vec<(String, String)> {
+        match (&self.cookie_jar, self.cookie_url(url)) {
+            (Some(cookie_jar), Ok(url)) => cookie_jar.pairs(&url),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Stores a cookie in the jar as if a response from `url` had set it,
+    /// such as a session token obtained elsewhere.
+    ///
+    /// #### Arguments
+    ///
+    /// * `url` - The URL setting the cookie, which scopes its default domain
+    ///   and path; relative URLs are resolved against the base URL.
+    /// * `cookie` - The cookie, in `Set-Cookie` syntax.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let rolling_requests = RollingRequestsBuilder::new().cookie_store(true).build();
+    /// assert!(rolling_requests.add_cookie("http://example.com/", "theme=dark").is_ok());
+    /// assert!(rolling_requests.add_cookie("http://example.com/", "=").is_err());
+    /// ```
+    ///
+    /// #### Errors
+    ///
+    /// * `CookieError::Disabled` - The cookie store is not enabled.
+    /// * `CookieError::InvalidUrl` - The URL could not be parsed.
+    /// * `CookieError::InvalidCookie` - The cookie is malformed, or its
+    ///   domain does not match the URL.
+    pub fn add_cookie(&self, url: &str, cookie: &str) -> Result<(), CookieError> {
+        let cookie_jar = self.cookie_jar.as_ref().ok_or(CookieError::Disabled)?;
+        let url = self.cookie_url(url)?;
+        match cookie_jar.insert(cookie, &url) {
+            true => Ok(()),
+            false => Err(CookieError::InvalidCookie(cookie.to_string())),
+        }
+    }
+
+    /// Saves the cookie jar to the file set with
+    /// `RollingRequestsBuilder::cookie_store_path`, as is done when the
+    /// instance is dropped.
+    ///
+    /// Does nothing when the jar is not kept in a file.
+    ///
+    /// #### Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    #[cfg(feature = "persistent-cookies")]
+    pub fn save_cookies(&self) -> io::Result<()> {
+        match &self.cookie_jar {
+            Some(cookie_jar) => cookie_jar.save(),
+            None => Ok(()),
+        }
+    }
+
+    /// Parses the URL of a cookie, resolving it against the base URL.
+    fn cookie_url(&self, url: &str) -> Result<Url, CookieError> {
+        let resolved = self.resolve_url(url);
+        Url::parse(resolved.as_deref().unwrap_or(url))
+            .map_err(|_| CookieError::InvalidUrl(url.to_string()))
+    }
+
+    /// Removes the pending requests with the given tag from the queue,
+    /// returning how many were removed.
+    ///
+    /// Removed requests are also forgotten by deduplication.
+    ///
+    /// #### Arguments
+    ///
+    /// * `tag` - The tag of the requests to remove, see `Request::set_tag`;
+    ///   `None` removes the untagged ones.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let mut rolling_requests = RollingRequestsBuilder::new().build();
+    /// let mut request = Request::get("http://example.com");
+    /// request.set_tag("crawl");
+    /// rolling_requests.add_request(request);
+    /// assert_eq!(rolling_requests.clear_pending_tagged(Some("crawl")), 1);
+    /// assert_eq!(rolling_requests.pending_count_for(Some("crawl")), 0);
+    /// ```
+    pub fn clear_pending_tagged(&mut self, tag: Option<&str>) -> usize {
+        let mut pending = self.pending_requests.lock().unwrap();
+        let (removed, kept) = pending
+            .drain(..)
+            .partition::<Vec<_>, _>(|request| request.get_tag() == tag);
+        *pending = kept;
+        drop(pending);
+        self.forget(&removed);
+        removed.len()
+    }
+
+    /// Removes every pending request from the queue and returns them, in
+    /// queue order.
+    ///
+    /// The requests keep their tags and identifiers, and are forgotten by
+    /// deduplication. Executions running meanwhile simply find the queue
+    /// empty.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let mut rolling_requests = RollingRequestsBuilder::new().build();
+    /// rolling_requests.add_request(Request::get("http://example.com"));
+    /// assert_eq!(rolling_requests.take_pending().len(), 1);
+    /// assert!(rolling_requests.take_pending().is_empty());
+    /// ```
+    pub fn take_pending(&self) -> Vec<Request> {
+        let taken = std::mem::take(&mut *self.pending_requests.lock().unwrap());
+        self.forget(&taken);
+        taken
+    }
+
+    /// Moves every pending request of `other` to the end of this queue,
+    /// returning how many were added.
+    ///
+    /// The requests keep their order and tags and are assigned identifiers
+    /// of this instance; under `RollingRequestsBuilder::deduplicate`, those
+    /// equal to a request known here are dropped. Only one queue is locked at
+    /// a time, so executions may run on either instance meanwhile.
+    ///
+    /// #### Arguments
+    ///
+    /// * `other` - The instance whose pending requests are moved.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let rolling_requests = RollingRequestsBuilder::new().build();
+    /// let mut other = RollingRequestsBuilder::new().build();
+    /// other.add_request(Request::get("http://example.com"));
+    /// assert_eq!(rolling_requests.merge_from(&other), 1);
+    /// assert!(other.take_pending().is_empty());
+    /// ```
+    pub fn merge_from(&self, other: &RollingRequests) -> usize {
+        if std::ptr::eq(self, other) {
+            return 0;
+        }
+        other
+            .take_pending()
+            .into_iter()
+            .map(|request| self.admit(request))
+            .filter(Result::is_ok)
+            .count()
+    }
+
+    /// Removes every pending request from the queue and splits them into `n`
+    /// shards, for example to hand them to other instances.
+    ///
+    /// The shards are consecutive runs of the queue, in order, whose sizes
+    /// differ by at most one; `n` of zero is taken as one. The requests keep
+    /// their tags and identifiers, and are forgotten by deduplication.
+    ///
+    /// #### Arguments
+    ///
+    /// * `n` - The number of shards.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let mut rolling_requests = RollingRequestsBuilder::new().build();
+    /// for _ in 0..5 {
+    ///     rolling_requests.add_request(Request::get("http://example.com"));
+    /// }
+    /// let shards = rolling_requests.split_pending(2);
+    /// assert_eq!(shards[0].len(), 3);
+    /// assert_eq!(shards[1].len(), 2);
+    /// ```
+    pub fn split_pending(&self, n: usize) -> Vec<Vec<Request>> {
+        let n = n.max(1);
+        let mut requests = self.take_pending().into_iter();
+        let (size, larger) = (requests.len() / n, requests.len() % n);
+        (0..n)
+            .map(|shard| {
+                let len = size + usize::from(shard < larger);
+                requests.by_ref().take(len).collect()
+            })
+            .collect()
+    }
+
+    /// Moves every failed request back onto the pending queue.
+    ///
+    /// The attempt count of each request is reset so that it gets the full
+    /// retry policy again. Returns the number of requests moved.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let mut rolling_requests = RollingRequestsBuilder::new().build();
+    /// assert_eq!(rolling_requests.requeue_failed(), 0);
+    /// ```
+    pub fn requeue_failed(&mut self) -> usize {
+        let failed = self.dead_letters.lock().unwrap().drain();
+        let count = failed.len();
+        // Requeued requests keep their identifier
+        let mut pending = self.pending_requests.lock().unwrap();
+        for mut request in failed {
+            request.attempts = 0;
+            pending.push(request);
+        }
+        count
+    }
+
+    /// Reads the whole body of a response, honoring `max_bytes_per_second`.
+    ///
+    /// #### Arguments
+    ///
+    /// * `response` - The response whose body to read.
+    ///
+    /// #### Examples
+    ///
+    /// ```no_run
+    /// use rollingrequests::request::Request;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut rolling_requests = RollingRequestsBuilder::new()
+    ///         .max_bytes_per_second(512 * 1024)
+    ///         .build();
+    ///     rolling_requests.add_request(Request::get("http://example.com/file"));
+    ///
+    ///     for response in rolling_requests.execute_requests().await {
+    ///         let body = rolling_requests.read_body(response.unwrap()).await.unwrap();
+    ///         println!("Downloaded {} bytes", body.len());
+    ///     }
+    /// }
+    /// ```
+    pub async fn read_body(&self, response: reqwest::Response) -> Result<Vec<u8>, reqwest::Error> {
+        read_body(response, self.bandwidth.as_deref()).await
+    }
+
+    /// Executes the pending requests up to the concurrency limit.
+    ///
+    /// Returns a vector of results for each request, either a successful response
+    /// or an error.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::request::Request;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    /// use tokio;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut rolling_requests = RollingRequestsBuilder::new()
+    ///         .simultaneous_limit(2)
+    ///         .build();
+    ///
+    ///     let url = "http://example.com";
+    ///
+    ///     // Add requests to the queue
+    ///     for _ in 0..5 {
+    ///         let request = Request::get(url);
+    ///         rolling_requests.add_request(request);
+    ///     }
+    ///
+    ///     // Execute requests
+    ///     let responses = rolling_requests.execute_requests().await;
+    ///     for response in responses {
+    ///         match response {
+    ///             Ok(res) => println!("Response: {:?}", res),
+    ///             Err(err) => eprintln!("Error: {:?}", err),
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub async fn execute_requests(&self) -> Vec<Result<reqwest::Response, RollingError>> {
+        self.run(Some(self.effective_limit()), None).await
+    }
+
+    /// Executes every pending request, keeping up to the concurrency limit in flight.
+    ///
+    /// Unlike `execute_requests`, which runs a single batch, this starts a new
+    /// request as soon as a slot frees up and returns once the queue is drained.
+    /// Results are returned in queue order.
+    ///
+    /// #### Examples
+    ///
+    /// ```no_run
+    /// use rollingrequests::request::Request;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut rolling_requests = RollingRequestsBuilder::new()
+    ///         .simultaneous_limit(2)
+    ///         .build();
+    ///
+    ///     for _ in 0..5 {
+    ///         rolling_requests.add_request(Request::get("http://example.com"));
+    ///     }
+    ///
+    ///     let responses = rolling_requests.execute_all().await;
+    ///     assert_eq!(responses.len(), 5);
+    /// }
+    /// ```
+    pub async fn execute_all(&self) -> Vec<Result<reqwest::Response, RollingError>> {
+        self.run(None, None).await
+    }
+
+    /// Executes the pending requests with the given tag up to the concurrency
+    /// limit, like `execute_requests`, leaving the others queued.
+    ///
+    /// The requests share the concurrency and rate limits with every other
+    /// execution. Results are returned in queue order.
+    ///
+    /// #### Arguments
+    ///
+    /// * `tag` - The tag of the requests to execute, see `Request::set_tag`;
+    ///   `None` executes the untagged ones.
+    ///
+    /// #### Examples
+    ///
+    /// ```no_run
+    /// use rollingrequests::request::Request;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut rolling_requests = RollingRequestsBuilder::new().build();
+    ///     let mut request = Request::get("http://example.com/a");
+    ///     request.set_tag("job-a");
+    ///     rolling_requests.add_request(request);
+    ///     rolling_requests.add_request(Request::get("http://example.com/b"));
+    ///
+    ///     let responses = rolling_requests.execute_requests_tagged(Some("job-a")).await;
+    ///     assert_eq!(responses.len(), 1);
+    ///     assert_eq!(rolling_requests.pending_count_for(None), 1);
+    /// }
+    /// ```
+    pub async fn execute_requests_tagged(
+        &self,
+        tag: Option<&str>,
+    ) -> Vec<Result<reqwest::Response, RollingError>> {
+        self.run(Some(self.effective_limit()), Some(tag)).await
+    }
+
+    /// Executes every pending request with the given tag, like `execute_all`,
+    /// leaving the others queued.
+    ///
+    /// #### Arguments
+    ///
+    /// * `tag` - The tag of the requests to execute; `None` executes the
+    ///   untagged ones.
+    pub async fn execute_all_tagged(
+        &self,
+        tag: Option<&str>,
+    ) -> Vec<Result<reqwest::Response, RollingError>> {
+        self.run(None, Some(tag)).await
+    }
+
+    /// Executes the copies queued by `add_fanout` for `fanout`, returning the
+    /// result of each with its host, in host order.
+    ///
+    /// Only the copies are taken from the queue, and they run together up to
+    /// the concurrency limit; bodies are read like
+    /// `execute_requests_detailed`. A host that failed has the error in its
+    /// result, while the others keep their responses. Copies that already
+    /// left the queue, executed or cleared by another call, are missing from
+    /// the results.
+    ///
+    /// #### Arguments
+    ///
+    /// * `fanout` - The handle returned by `add_fanout`.
+    ///
+    /// #### Examples
+    ///
+    /// ```no_run
+    /// use rollingrequests::request::Request;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut rolling_requests = RollingRequestsBuilder::new().build();
+    ///     let fanout = rolling_requests
+    ///         .add_fanout(
+    ///             Request::get("http://localhost/version"),
+    ///             &["http://node-1:8080", "http://node-2:8080"],
+    ///         )
+    ///         .unwrap();
+    ///
+    ///     for (host, result) in rolling_requests.execute_fanout(&fanout).await {
+    ///         println!("{}: {:?} {}", host, result.status, result.text());
+    ///     }
+    /// }
+    /// ```
+    pub async fn execute_fanout(&self, fanout: &FanOut) -> Vec<(String, RollingResult)> {
+        let requests = {
+            let mut pending = self.pending_requests.lock().unwrap();
+            let (members, kept) = pending.drain(..).partition::<Vec<_>, _>(|request| {
+                request.id.is_some_and(|id| fanout.host_of(id).is_some())
+            });
+            *pending = kept;
+            members
+        };
+        requests
+            .iter()
+            .for_each(|request| self.forget_dispatched(request));
+        let totals = ProgressTotals::default();
+        let totals = &totals;
+        let mut results: Vec<_> = self
+            .finish_feed(
+                &mut Feed::Snapshot {
+                    requests,
+                    from_queue: true,
+                    holds_results: true,
+                    downloads: true,
+                },
+                |finished, response| self.read_result(finished, response, totals),
+            )
+            .await
+            .into_iter()
+            .filter_map(|result| {
+                let host = fanout.host_of(result.request_id?)?;
+                Some((host.to_string(), result))
+            })
+            .collect();
+        let order = |id: Option<u64>| fanout.ids().position(|member| Some(member) == id);
+        results.sort_by_key(|(_, result)| order(result.request_id));
+        results
+    }
+
+    /// Executes the pending requests up to the concurrency limit, like
+    /// `execute_requests`, and reads every response into a `RollingResult`.
+    ///
+    /// Bodies are read in full, or up to the `result_body_limit`, except for
+    /// successful responses to requests with an output file, which are
+    /// written to that file. Results are returned in queue order.
+    ///
+    /// #### Examples
+    ///
+    /// ```no_run
+    /// use rollingrequests::request::Request;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut rolling_requests = RollingRequestsBuilder::new().build();
+    ///     rolling_requests.add_request(Request::get("http://example.com"));
+    ///
+    ///     for result in rolling_requests.execute_requests_detailed().await {
+    ///         println!(
+    ///             "{:?} in {:?} after {} attempts: {} bytes",
+    ///             result.status,
+    ///             result.elapsed,
+    ///             result.attempts,
+    ///             result.body.len()
+    ///         );
+    ///     }
+    /// }
+    /// ```
+    pub async fn execute_requests_detailed(&self) -> Vec<RollingResult> {
+        let totals = ProgressTotals::default();
+        let totals = &totals;
+        self.run_finished(
+            Some(self.effective_limit()),
+            None,
+            true,
+            true,
+            |finished, response| self.read_result(finished, response, totals),
+        )
+        .await
+    }
+
+    /// Executes every pending request, like `execute_all`, and reads every
+    /// response into a `RollingResult`.
+    ///
+    /// Bodies are read in full, or up to the `result_body_limit`, except for
+    /// successful responses to requests with an output file, which are
+    /// written to that file. Results are returned in queue order.
+    pub async fn execute_all_detailed(&self) -> Vec<RollingResult> {
+        let totals = ProgressTotals::default();
+        let totals = &totals;
+        self.run_finished(None, None, true, true, |finished, response| {
+            self.read_result(finished, response, totals)
+        })
+        .await
+    }
+
+    /// Executes every pending request on a spawned task, keeping the results
+    /// until `drain_results` is called.
+    ///
+    /// The requests are executed up to the concurrency limit, like
+    /// `execute_all_detailed`, until the queue is drained, including next
+    /// pages and follow-ups queued along the way. Each result is added to the
+    /// buffer as soon as its body is read, subject to
+    /// `RollingRequestsBuilder::result_buffer_capacity`; with
+    /// `ResultOverflow::Block`, a result waiting for room keeps its place
+    /// under the concurrency limit, so no further request starts in its
+    /// stead. The returned handle finishes once the queue is drained.
+    ///
+    /// #### Examples
+    ///
+    /// ```no_run
+    /// use rollingrequests::request::Request;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    /// use std::sync::Arc;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut rolling_requests = RollingRequestsBuilder::new().build();
+    ///     rolling_requests.add_request(Request::get("http://example.com"));
+    ///
+    ///     let rolling_requests = Arc::new(rolling_requests);
+    ///     let worker = rolling_requests.execute_in_background();
+    ///     // ... later, or periodically
+    ///     worker.await.unwrap();
+    ///     for result in rolling_requests.drain_results() {
+    ///         println!("{:?}", result.status);
+    ///     }
+    /// }
+    /// ```
+    pub fn execute_in_background(self: &Arc<Self>) -> JoinHandle<()> {
+        let this = self.clone();
+        tokio::spawn(async move {
+            let this = &this;
+            let totals = ProgressTotals::default();
+            let totals = &totals;
+            let mut feed = Feed::Pending {
+                budget: None,
+                group: None,
+                holds_results: false,
+                downloads: true,
+                raw: false,
+            };
+            this.finish_feed(&mut feed, |finished, response| async move {
+                let result = this.read_result(finished, response, totals).await;
+                this.background_results.push(result).await;
+            })
+            .await;
+        })
+    }
+
+    /// Removes and returns the results of background executions completed
+    /// since the last call, oldest first.
+    ///
+    /// #### Examples
+    ///
+    /// ```
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// let rolling_requests = RollingRequestsBuilder::new().build();
+    /// assert!(rolling_requests.drain_results().is_empty());
+    /// ```
+    pub fn drain_results(&self) -> Vec<RollingResult> {
+        self.background_results.drain()
+    }
+
+    /// Executes the pending requests up to the concurrency limit, like
+    /// `execute_requests`, and records each outcome on a copy of its request.
+    ///
+    /// Every request is returned with its response fields set:
+    ///
+    /// * `response_text` - The body as text, up to the `result_body_limit`,
+    ///   with invalid UTF-8 sequences replaced; unset under `headers_only`.
+    /// * `response_info` - The status line, e.g. `HTTP/1.1 200 OK`, followed by
+    ///   one `name: value` line per header.
+    /// * `response_error` - The error, for failed requests.
+    /// * `response_errno` - `0` when a response was read, otherwise the
+    ///   `ErrorCode` of the failure.
+    ///
+    /// Each request is paired with the status of its response or its error.
+    /// Results are returned in queue order.
+    ///
+    /// #### Examples
+    ///
+    /// ```no_run
+    /// use rollingrequests::request::Request;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut rolling_requests = RollingRequestsBuilder::new().build();
+    ///     rolling_requests.add_request(Request::get("http://example.com"));
+    ///
+    ///     for (request, _) in rolling_requests.execute_requests_paired().await {
+    ///         println!("{}: {:?}", request, request.get_response_text());
+    ///     }
+    /// }
+    /// ```
+    pub async fn execute_requests_paired(
+        &self,
+    ) -> Vec<(Request, Result<StatusCode, RollingError>)> {
+        self.run_finished(
+            Some(self.effective_limit()),
+            None,
+            true,
+            false,
+            |finished, response| self.record_result(finished, response),
+        )
+        .await
+    }
+
+    /// Executes every pending request, like `execute_all`, and records each
+    /// outcome on a copy of its request, as `execute_requests_paired` does.
+    pub async fn execute_all_paired(&self) -> Vec<(Request, Result<StatusCode, RollingError>)> {
+        self.run_finished(None, None, true, false, |finished, response| {
+            self.record_result(finished, response)
+        })
+        .await
+    }
+
+    /// Replays the pending requests over and over until `duration` has elapsed.
+    ///
+    /// Each pass sends clones of the queued requests under the usual concurrency
+    /// and rate settings; requests still in flight at the deadline are awaited.
+    /// Responses are dropped as they arrive and only aggregate statistics are
+    /// kept. The queue itself is left untouched, so the run can be repeated.
+    ///
+    /// #### Arguments
+    ///
+    /// * `duration` - How long to keep sending requests.
+    ///
+    /// #### Examples
+    ///
+    /// ```no_run
+    /// use rollingrequests::request::Request;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    /// use std::time::Duration;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut rolling_requests = RollingRequestsBuilder::new()
+    ///         .simultaneous_limit(4)
+    ///         .build();
+    ///     rolling_requests.add_request(Request::get("http://example.com"));
+    ///
+    ///     let stats = rolling_requests.execute_for(Duration::from_secs(60)).await;
+    ///     println!("{} sent, {} failed", stats.total_sent, stats.failures);
+    /// }
+    /// ```
+    pub async fn execute_for(&self, duration: Duration) -> RunStats {
+        let started = Instant::now();
+        let templates = self.pending_requests.lock().unwrap().clone();
+        let mut feed = Feed::Cycle {
+            templates,
+            batch: Vec::new(),
+            deadline: started + duration,
+        };
+
+        let mut stats = RunStats::default();
+        self.drive(
+            &mut feed,
+            |_, response| future::ready(response),
+            |response| stats.record(&response),
+        )
+        .await;
+        stats.elapsed = started.elapsed();
+        stats
+    }
+
+    /// Polls every pending request once and classifies its endpoint.
+    ///
+    /// Clones of the queued requests are sent under the usual concurrency, rate
+    /// and retry settings, and the queue itself is left untouched so that checks
+    /// can be repeated. Each result is classified with the configured
+    /// `HealthCheck`: failures are counted per endpoint across checks, and an
+    /// endpoint is only reported down once `failure_threshold` checks in a row
+    /// have failed.
+    ///
+    /// Returns a report per endpoint, keyed by the request's `extra_info`, or by
+    /// its URL when it has none.
+    ///
+    /// #### Examples
+    ///
+    /// ```no_run
+    /// use rollingrequests::health::{HealthCheck, HealthStatus};
+    /// use rollingrequests::request::Request;
+    /// use rollingrequests::rolling::RollingRequestsBuilder;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut rolling_requests = RollingRequestsBuilder::new()
+    ///         .simultaneous_limit(16)
+    ///         .health_check(HealthCheck {
+    ///             failure_threshold: 3,
+    ///             ..HealthCheck::default()
+    ///         })
+    ///         .build();
+    ///     rolling_requests.add_request(Request::get("http://example.com/health"));
+    ///
+    ///     for (endpoint, report) in rolling_requests.check_health().await {
+    ///         if report.status != HealthStatus::Up {
+    ///             println!("{}: {:?}", endpoint, report.status);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub async fn check_health(&self) -> BTreeMap<String, HealthReport> {
+        let requests = self.pending_requests.lock().unwrap().clone();
+        let check = &self.health_check;
+        let mut reports = BTreeMap::new();
+        self.drive(
+            &mut Feed::Snapshot {
+                requests,
+                from_queue: false,
+                holds_results: false,
+                downloads: false,
+            },
+            |job, response| async move {
+                let key = job
+                    .request
+                    .get_extra_info()
+                    .cloned()
+                    .unwrap_or_else(|| job.request.get_url().clone());
+                let status_code = match &response {
+                    Ok(res) => Some(res.status().as_u16()),
+                    Err(err) => err.status().map(|status| status.as_u16()),
+                };
+
+                let failure = match response {
+                    Err(err) => Some(err.to_string()),
+                    Ok(res) if !check.up_statuses.contains(&res.status().as_u16()) => {
+                        Some(format!("unexpected status {}", res.status()))
+                    }
+                    Ok(res) => match &check.body_contains {
+                        Some(needle) => match self.read_body(res).await {
+                            Ok(body)
+                                if String::from_utf8_lossy(&body).contains(needle.as_str()) =>
+                            {
+                                None
+                            }
+                            Ok(_) => Some(format!("body does not contain {:?}", needle)),
+                            Err(err) => Some(err.to_string()),
+                        },
+                        None => None,
+                    },
+                };
+                (key, status_code, failure, job.latency)
+            },
+            |(key, status_code, failure, latency)| {
+                let consecutive_failures = {
+                    let mut failures = self.health_failures.lock().unwrap();
+                    let count = failures.entry(key.clone()).or_insert(0);
+                    *count = if failure.is_some() { *count + 1 } else { 0 };
+                    *count
+                };
+                reports.insert(
+                    key,
+                    HealthReport {
+                        status: check.classify(failure.as_deref(), latency, consecutive_failures),
+                        status_code,
+                        latency,
+                        consecutive_failures,
+                        error: failure,
+                    },
+                );
+            },
+        )
+        .await;
+        reports
+    }
+
+    /// Runs the scheduler until `budget` pending requests (or all of them, when
+    /// `None`) have produced a final result, taking only the requests tagged
+    /// `group` when given.
+    async fn run(
+        &self,
+        budget: Option<usize>,
+        group: Option<Option<&str>>,
+    ) -> Vec<Result<reqwest::Response, RollingError>> {
+        let mut feed = Feed::Pending {
+            budget,
+            group: group.map(|tag| tag.map(str::to_string)),
+            holds_results: false,
+            downloads: false,
+            raw: true,
+        };
+        self.finish_feed(&mut feed, |_, response| future::ready(response))
+            .await
+    }
+
+    /// Runs the scheduler like `run`, handing what is known of each request
+    /// besides its result to `read`, which reads the bodies of results held
+    /// until the end with `holds_results`, and writes downloads to their
+    /// output files with `downloads`.
+    async fn run_finished<R, Fut>(
+        &self,
+        budget: Option<usize>,
+        group: Option<Option<&str>>,
+        holds_results: bool,
+        downloads: bool,
+        read: R,
+    ) -> Vec<Fut::Output>
+    where
+        R: FnMut(Finished, Result<reqwest::Response, RollingError>) -> Fut,
+        Fut: Future,
+    {
+        let mut feed = Feed::Pending {
+            budget,
+            group: group.map(|tag| tag.map(str::to_string)),
+            holds_results,
+            downloads,
+            raw: false,
+        };
+        self.finish_feed(&mut feed, read).await
+    }
+
+    /// Runs the scheduler on the requests of `feed`, reading the final result
+    /// of each with `read` while it holds its place under the concurrency
+    /// limit, and returning what was read in dispatch order.
+    async fn finish_feed<R, Fut>(&self, feed: &mut Feed, mut read: R) -> Vec<Fut::Output>
+    where
+        R: FnMut(Finished, Result<reqwest::Response, RollingError>) -> Fut,
+        Fut: Future,
+    {
+        let mut results = Vec::new();
+        self.drive(
+            feed,
+            |mut job, response| {
+                if let Err(err) = &response {
+                    job.request.set_response_error(&err.to_string());
+                    self.dead_letters.lock().unwrap().push(job.request.clone());
+                }
+                let slot = job.slot;
+                let finished = Finished {
+                    request: job.request,
+                    started_at: job.started_at,
+                    attempt_latencies: job.attempt_latencies,
+                };
+                read(finished, response).map(move |output| (slot, output))
+            },
+            |(slot, output)| {
+                if results.len() <= slot {
+                    results.resize_with(slot + 1, || None);
+                }
+                results[slot] = Some(output);
+            },
+        )
+        .await;
+        results.into_iter().flatten().collect()
+    }
+
+    /// Reads the body of a finished request into a `RollingResult`.
+    async fn read_result(
+        &self,
+        finished: Finished,
+        response: Result<reqwest::Response, RollingError>,
+        totals: &ProgressTotals,
+    ) -> RollingResult {
+        let mut request = finished.request;
+        let mut result = RollingResult::new(&request);
+        match response {
+            Ok(mut res) => {
+                result.status = Some(res.status());
+                let url = match res.extensions().get::<CacheInfo>() {
+                    Some(info) => &info.url,
+                    None => res.url(),
+                };
+                result.url = Some(url.clone());
+                let output_file = match &request.output_file {
+                    Some(path) => Some(Ok(path.clone())).filter(|_| {
+                        res.status().is_success()
+                            || request.unsatisfied == Some(Unsatisfied::Complete)
+                    }),
+                    None => {
+                        self.save_path(&request, result.extra_info.as_deref(), res.status())
+                            .await
+                    }
+                };
+                if self.headers_only {
+                    // Dropping the response abandons the body unread
+                    result.headers = std::mem::take(res.headers_mut());
+                } else if let Some(path) = output_file {
+                    result.headers = res.headers().clone();
+                    let downloaded = match path {
+                        Ok(path) => {
+                            let downloaded = self.download(&mut request, res, &path, totals).await;
+                            self.saved_paths.lock().unwrap().remove(&path);
+                            downloaded
+                        }
+                        Err(err) => Err(err),
+                    };
+                    match downloaded {
+                        Ok(download) => {
+                            result.status = Some(download.status);
+                            result.download = Some(download);
+                        }
+                        Err(err) => result.error = Some(err.for_request(&request)),
+                    }
+                    result.attempts = request.attempts;
+                } else if let Some(sink) =
+                    request.stream_sink().filter(|_| res.status().is_success())
+                {
+                    result.headers = std::mem::take(res.headers_mut());
+                    if let Err(err) = self.stream(&mut request, res, sink).await {
+                        result.error = Some(err.for_request(&request));
+                    }
+                    result.attempts = request.attempts;
+                } else {
+                    result.headers = std::mem::take(res.headers_mut());
+                    let read = read_capped_body(
+                        res,
+                        self.bandwidth.as_deref(),
+                        self.result_body_limit,
+                        &mut result.body,
+                    )
+                    .await;
+                    match read {
+                        Ok(truncated) => {
+                            result.body_truncated = truncated;
+                            #[cfg(feature = "charset")]
+                            {
+                                let encoding = charset::detect(
+                                    &result.body,
+                                    &result.headers,
+                                    self.default_charset,
+                                );
+                                result.charset = Some(encoding.name());
+                            }
+                        }
+                        Err(err) => {
+                            result.error = Some(
+                                RollingError::new(err, request.attempts)
+                                    .tagged(request.id, result.metadata.clone()),
+                            );
+                        }
+                    }
+                }
+            }
+            Err(err) => {
+                result.status = err.status();
+                result.url = err.url().cloned();
+                result.error = Some(err);
+            }
+        }
+        result.finish(
+            finished.started_at,
+            request.queued_at,
+            finished.attempt_latencies,
+        );
+        result
+    }
+
+    /// Writes the body of `response` to the output file `path` of `request`.
+    ///
+    /// A resumable download interrupted while reading the body is continued
+    /// with new attempts, as long as the request has attempts left.
+    async fn download(
+        &self,
+        request: &mut Request,
+        mut response: reqwest::Response,
+        path: &Path,
+        totals: &ProgressTotals,
+    ) -> Result<Download, RollingError> {
+        let (policy, max_attempts) = self.attempt_limit(request);
+        loop {
+            let progress = self.on_progress.as_ref().map(|hook| ProgressReporter {
+                hook,
+                interval: self.progress_interval,
+                totals,
+                request_id: request.id,
+            });
+            let bandwidth = self.bandwidth.as_deref();
+            let err = match download(response, bandwidth, progress, path, request).await {
+                Ok(download) => return Ok(download),
+                Err(err) => err,
+            };
+            if !request.resume
+                || err.inner().is_none()
+                || request.attempts >= max_attempts
+                || !self.may_resume()
+            {
+                return Err(err);
+            }
+            if let Some(policy) = &policy {
+                time::sleep(policy.backoff(request.attempts)).await;
+            }
+            response = self.resend(request, max_attempts).await?;
+        }
+    }
+
+    /// Returns the file a response with `status` to `request` is saved to
+    /// under `save_responses_to`, claiming it until it is written.
+    ///
+    /// #### Errors
+    ///
+    /// Fails with `RollingErrorKind::Write` if the directory cannot be created,
+    /// or the file is taken under `SaveCollision::Error`.
+    async fn save_path(
+        &self,
+        request: &Request,
+        extra_info: Option<&str>,
+        status: StatusCode,
+    ) -> Option<Result<PathBuf, RollingError>> {
+        let save = self.save_responses.as_ref()?;
+        let write = |err| RollingError::write(err, request.attempts);
+        if let Err(err) = tokio::fs::create_dir_all(&save.dir).await {
+            return Some(Err(write(err)));
+        }
+        let mut url = request.url.clone();
+        if !request.query_params.is_empty() {
+            if let Ok(mut parsed) = Url::parse(&url) {
+                parsed.query_pairs_mut().extend_pairs(&request.query_params);
+                url = parsed.into();
+            }
+        }
+        let name = save.file_name(request.id, extra_info, &url, status);
+        let mut path = save.dir.join(&name);
+        let mut saved_paths = self.saved_paths.lock().unwrap();
+        let taken = |path: &PathBuf| saved_paths.contains(path) || path.exists();
+        match self.save_collision {
+            SaveCollision::Overwrite => {
+                let mut suffix = 0;
+                while saved_paths.contains(&path) {
+                    suffix += 1;
+                    path = save.dir.join(with_number(&name, suffix));
+                }
+            }
+            SaveCollision::Error if taken(&path) => {
+                let err = io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("{} already exists", path.display()),
+                );
+                return Some(Err(write(err)));
+            }
+            SaveCollision::Error => {}
+            SaveCollision::Suffix => {
+                let mut suffix = 0;
+                while taken(&path) {
+                    suffix += 1;
+                    path = save.dir.join(with_number(&name, suffix));
+                }
+            }
+        }
+        saved_paths.insert(path.clone());
+        Some(Ok(path))
+    }
+
+    /// Streams a successful response body to `sink`.
+    ///
+    /// A dropped event stream of a request that resumes is reconnected with
+    /// its last event ID, as long as the request has attempts left.
+    async fn stream(
+        &self,
+        request: &mut Request,
+        mut response: reqwest::Response,
+        mut sink: StreamSink,
+    ) -> Result<(), RollingError> {
+        let (policy, max_attempts) = self.attempt_limit(request);
+        loop {
+            let bandwidth = self.bandwidth.as_deref();
+            let err = match write_to_sink(response, bandwidth, sink.sink(), request.attempts).await
+            {
+                Ok(()) => return Ok(()),
+                Err(err) => err,
+            };
+            let events = match &mut sink {
+                StreamSink::Events(events) => events,
+                StreamSink::Body(_) => return Err(err),
+            };
+            if !request.resume
+                || err.inner().is_none()
+                || request.attempts >= max_attempts
+                || !self.may_resume()
+            {
+                return Err(err);
+            }
+            let delay = events.retry().or_else(|| {
+                policy
+                    .as_ref()
+                    .map(|policy| policy.backoff(request.attempts))
+            });
+            if let Some(delay) = delay {
+                time::sleep(delay).await;
+            }
+            events.reconnect();
+            match events.last_event_id().filter(|id| !id.is_empty()) {
+                Some(id) => request.add_header("Last-Event-ID", &id),
+                None => request.remove_header("Last-Event-ID"),
+            };
+            response = self.resend(request, max_attempts).await?;
+        }
+    }
+
+    /// Returns the retry policy of `request` and the number of attempts it
+    /// is allowed.
+    fn attempt_limit(&self, request: &Request) -> (Option<RetryPolicy>, u32) {
+        let policy = request
+            .retry_policy
+            .as_ref()
+            .or(self.retry_policy.as_ref())
+            .cloned();
+        let max_attempts = request
+            .max_attempts
+            .or(policy.as_ref().map(|policy| policy.max_attempts))
+            .unwrap_or(1);
+        (policy, max_attempts)
+    }
+
+    /// Sends `request` again to continue reading its body, as a new attempt
+    /// waiting on the throttle of its host and fed back to the adaptive
+    /// limiter. A download whose partial file no longer matches starts over
+    /// while `max_attempts` allows.
+    async fn resend(
+        &self,
+        request: &mut Request,
+        max_attempts: u32,
+    ) -> Result<reqwest::Response, RollingError> {
+        loop {
+            self.acquire_host(request).await;
+            request.attempts += 1;
+            self.stats.record_attempt(true, request.body_size());
+            let client = self
+                .client_for(request)
+                .map_err(|err| RollingError::new(err, request.attempts))?;
+            let authorization = self
+                .credentials
+                .as_ref()
+                .filter(|_| !request.cross_origin)
+                .and_then(|credentials| credentials.current().1);
+            let defaults = &self.send_defaults;
+            let dispatched_at = Instant::now();
+            let response = send_request(&client, request, defaults, authorization.as_deref()).await;
+            self.record_feedback(request, dispatched_at, dispatched_at.elapsed(), &response);
+            let response = match request.unsatisfied {
+                Some(Unsatisfied::Restart) if request.attempts < max_attempts => continue,
+                Some(Unsatisfied::Complete) => response,
+                _ => response.and_then(reqwest::Response::error_for_status),
+            };
+            return response.map_err(|err| RollingError::new(err, request.attempts));
+        }
+    }
+
+    /// Waits until the throttle lets another request go to the host of
+    /// `request`, taking its place.
+    async fn acquire_host(&self, request: &Request) {
+        let host = request.get_host().unwrap_or_default();
+        loop {
+            let now = Instant::now();
+            let acquired = {
+                let mut throttle = self.throttle.lock().unwrap();
+                throttle
+                    .next_launch(now)
+                    .and_then(|()| throttle.try_acquire(&host, now))
+            };
+            match acquired {
+                Ok(()) => return,
+                Err(at) => time::sleep_until(at.into()).await,
+            }
+        }
+    }
+
+    /// Returns whether the retry budget allows another attempt to continue
+    /// reading a body, counting it when it does.
+    fn may_resume(&self) -> bool {
+        self.retry_tracker.lock().unwrap().try_retry(Instant::now())
+    }
+
+    /// Reads the response of a finished request into the response fields of
+    /// the request.
+    async fn record_result(
+        &self,
+        finished: Finished,
+        response: Result<reqwest::Response, RollingError>,
+    ) -> (Request, Result<StatusCode, RollingError>) {
+        let mut request = finished.request;
+        request.response_text = None;
+        request.response_info = None;
+        request.response_error = None;
+        request.response_errno = None;
+        let outcome = match response {
+            Ok(res) if self.headers_only => {
+                request.set_response_info(&response_info(&res));
+                Ok(res.status())
+            }
+            Ok(res) => {
+                let status = res.status();
+                request.set_response_info(&response_info(&res));
+                match request.stream_sink().filter(|_| status.is_success()) {
+                    Some(sink) => self
+                        .stream(&mut request, res, sink)
+                        .await
+                        .map(|_| status)
+                        .map_err(|err| err.for_request(&request)),
+                    None => {
+                        let headers = res.headers().clone();
+                        let mut body = Vec::new();
+                        let read = read_capped_body(
+                            res,
+                            self.bandwidth.as_deref(),
+                            self.result_body_limit,
+                            &mut body,
+                        )
+                        .await;
+                        request.set_response_text(&self.body_text(&body, &headers));
+                        read.map(|_| status).map_err(|err| {
+                            RollingError::new(err, request.attempts).for_request(&request)
+                        })
+                    }
+                }
+            }
+            Err(err) => Err(err),
+        };
+        match &outcome {
+            Ok(_) => request.set_response_errno(0),
+            Err(err) => request
+                .set_response_error(&err.to_string())
+                .set_response_errno(i32::from(err.code())),
+        };
+        (request, outcome)
+    }
+
+    /// Returns a response body as text, decoded with its charset under the
+    /// `charset` feature, and as UTF-8 otherwise.
+    fn body_text(&self, body: &[u8], headers: &HeaderMap) -> String {
+        #[cfg(feature = "charset")]
+        {
+            charset::decode(body, charset::detect(body, headers, self.default_charset))
+        }
+        #[cfg(not(feature = "charset"))]
+        {
+            let _ = headers;
+            String::from_utf8_lossy(body).into_owned()
+        }
+    }
+
+    /// Dispatches requests from `feed` until it runs dry, handing every final
+    /// result to `process` together with its job, and what each processed
+    /// result produced to `on_result`.
+    ///
+    /// A result keeps its place under the concurrency limit until it is
+    /// processed, so reading its body counts against the limit like its
+    /// attempts did.
+    async fn drive<P, Fut, F>(&self, feed: &mut Feed, mut process: P, mut on_result: F)
+    where
+        P: FnMut(Job, Result<reqwest::Response, RollingError>) -> Fut,
+        Fut: Future,
+        F: FnMut(Fut::Output),
+    {
+        let started = Instant::now();
+        *self.run_started.lock().unwrap() = Some(started);
+
+        let mut in_flight = JoinSet::new();
+        let mut processing = FuturesUnordered::new();
+        let mut retries = VecDeque::new();
+        let mut hedging = Hedging::default();
+        let mut taken = 0;
+        let mut next_arrival = started;
+        // Only queue executions stop early, replays and checks run to the end
+        let stops_on_failure = self.fail_fast && matches!(feed, Feed::Pending { .. });
+        // Replays leave the queue untouched, so only their completion hooks run
+        let follows_pages = matches!(
+            feed,
+            Feed::Pending { .. }
+                | Feed::Snapshot {
+                    from_queue: true,
+                    ..
+                }
+        );
+        // Only executions writing downloads ask to continue partial files
+        let downloads = matches!(
+            feed,
+            Feed::Pending {
+                downloads: true,
+                ..
+            } | Feed::Snapshot {
+                downloads: true,
+                ..
+            }
+        );
+        let checks_links = self.link_check && !matches!(feed, Feed::Pending { raw: true, .. });
+        // Event streams may never end, so they would hold back every other result
+        let rejects_events = matches!(
+            feed,
+            Feed::Pending {
+                holds_results: true,
+                ..
+            } | Feed::Snapshot {
+                holds_results: true,
+                ..
+            }
+        );
+
+        'run: loop {
+            let limit = match self.arrival_interval {
+                Some(_) => self.max_outstanding,
+                None => self.effective_limit(),
+            };
+            let mut wake_at = None;
+            while in_flight.len() + processing.len() < limit {
+                // In open-loop mode, dispatches follow the arrival schedule
+                if let Some(interval) = self.arrival_interval {
+                    if Instant::now() < next_arrival {
+                        wake_at = Some(next_arrival);
+                        break;
+                    }
+                    next_arrival += interval;
+                }
+
+                // Due hedges go first, as their requests are already late
+                let mut hedge_at = None;
+                let next = match self.next_hedge(&mut hedging) {
+                    Next::Hedge(job) => Next::Hedge(job),
+                    other => {
+                        if let Next::Wait(at) = other {
+                            hedge_at = Some(at);
+                        }
+                        self.next_job(&mut retries, feed, taken)
+                    }
+                };
+
+                match next {
+                    Next::Fresh(request) => {
+                        let slot = taken;
+                        taken += 1;
+                        let mut job = Job::new(slot, request);
+                        job.request.downloads = downloads;
+                        job.request.checks_link = checks_links;
+                        self.apply_defaults(&mut job.request);
+                        let prepared = job.request.prepare().and_then(|()| {
+                            job.request
+                                .check_body_size()
+                                .map_err(RollingError::too_large)
+                        });
+                        let prepared = prepared.and_then(|()| self.check_client(&job.request));
+                        let prepared = prepared.and_then(|()| {
+                            if rejects_events && job.request.event_handler.is_some() {
+                                let reason = "event streams are only consumed by \
+                                              execute_in_background";
+                                return Err(RollingError::invalid_request(reason.to_string()));
+                            }
+                            Ok(())
+                        });
+                        if let Err(err) = prepared {
+                            let response = Err(err.for_request(&job.request));
+                            self.stats.record_result(&response);
+                            let response = self.run_hooks(&job, response, follows_pages);
+                            processing.push(process(job, response));
+                            if stops_on_failure {
+                                in_flight.shutdown().await;
+                                break 'run;
+                            }
+                            continue;
+                        }
+                        self.spawn_attempt(&mut in_flight, &mut hedging, job, false);
+                    }
+                    Next::Retry(job) => {
+                        self.spawn_attempt(&mut in_flight, &mut hedging, job, false)
+                    }
+                    Next::Hedge(job) => self.spawn_attempt(&mut in_flight, &mut hedging, job, true),
+                    Next::Wait(at) => {
+                        wake_at = Some(hedge_at.map_or(at, |hedge_at| hedge_at.min(at)));
+                        break;
+                    }
+                    Next::Empty => {
+                        wake_at = hedge_at;
+                        break;
+                    }
+                }
+            }
+
+            // Wake up for the next ramp step if it could let more requests start
+            if let Some(ramp) = &self.ramp_up {
+                if in_flight.len() + processing.len() >= limit && limit < ramp.target_limit {
+                    let step_at = started + ramp.next_step(started.elapsed());
+                    wake_at = Some(wake_at.map_or(step_at, |at: Instant| at.min(step_at)));
+                }
+            }
+
+            let wake = time::sleep_until(wake_at.unwrap_or(started).into());
+            let joined = tokio::select! {
+                joined = in_flight.join_next_with_id(), if !in_flight.is_empty() => joined,
+                Some(output) = processing.next(), if !processing.is_empty() => {
+                    on_result(output);
+                    continue;
+                }
+                _ = wake, if wake_at.is_some() => continue,
+                else => break,
+            };
+
+            // Errors should now be handled by the caller when they occur
+            match joined {
+                Some(Ok((id, attempt))) => {
+                    if !hedging.settle(attempt.job.slot, id) {
+                        continue;
+                    }
+                    if let Some((mut job, mut response)) =
+                        self.complete_attempt(attempt, &mut retries)
+                    {
+                        let failed = response.is_err();
+                        self.stats.record_result(&response);
+                        if follows_pages {
+                            if let Ok(res) = &response {
+                                self.queue_next_page(&job.request, res);
+                            }
+                        }
+                        response = self.run_hooks(&job, response, follows_pages);
+                        job.request.buffered_body = None;
+                        processing.push(process(job, response));
+                        if failed && stops_on_failure {
+                            in_flight.shutdown().await;
+                            break;
+                        }
+                    }
+                }
+                Some(Err(err)) => hedging.forget(err.id()),
+                None => {}
+            }
+        }
+
+        // Results that became final before an early stop are still processed
+        while let Some(output) = processing.next().await {
+            on_result(output);
+        }
+        *self.run_started.lock().unwrap() = None;
+    }
+
+    /// Picks the next job to dispatch.
+    ///
+    /// Re-queued jobs from the current execution take precedence over new
+    /// requests from `feed`, of which `taken` were dispatched so far. Jobs for
+    /// throttled hosts are skipped in favour of later ones for hosts that still
+    /// have budget; when every candidate is throttled, the earliest instant at
+    /// which one becomes available is returned instead.
+    fn next_job(&self, retries: &mut VecDeque<Job>, feed: &mut Feed, taken: usize) -> Next {
+        let (from_pending, group) = match feed {
+            Feed::Pending { group, .. } => (true, group.clone()),
+            _ => (false, None),
+        };
+        let in_group = |request: &Request| group.as_ref().is_none_or(|tag| request.tag == *tag);
+        let mut pending;
+        let fresh = match feed {
+            Feed::Pending { budget, .. } if budget.is_none_or(|budget| taken < budget) => {
+                pending = self.pending_requests.lock().unwrap();
+                Some(&mut *pending)
+            }
+            Feed::Cycle {
+                templates,
+                batch,
+                deadline,
+            } if Instant::now() < *deadline => {
+                if batch.is_empty() {
+                    batch.extend(templates.iter().cloned());
+                }
+                Some(batch)
+            }
+            Feed::Snapshot { requests, .. } => Some(requests),
+            _ => None,
+        }
+        .filter(|fresh| fresh.iter().any(&in_group));
+
+        let mut throttle = self.throttle.lock().unwrap();
+        if retries.is_empty() && fresh.is_none() {
+            return Next::Empty;
+        }
+
+        let now = Instant::now();
+        if let Err(at) = throttle.next_launch(now) {
+            return Next::Wait(at);
+        }
+
+        let mut wake_at: Option<Instant> = None;
+        let mut wait_for = |at: Instant| wake_at = Some(wake_at.map_or(at, |w| w.min(at)));
+
+        for index in 0..retries.len() {
+            if let Some(at) = retries[index].ready_at.filter(|at| now < *at) {
+                wait_for(at);
+                continue;
+            }
+            let host = retries[index].request.get_host().unwrap_or_default();
+            match throttle.try_acquire(&host, now) {
+                Ok(()) => return Next::Retry(retries.remove(index).unwrap()),
+                Err(at) => wait_for(at),
+            }
+        }
+
+        if let Some(fresh) = fresh {
+            for index in 0..fresh.len() {
+                if !in_group(&fresh[index]) {
+                    continue;
+                }
+                let host = self.host_of(&fresh[index]);
+                if self.awaits_cache(&fresh[index]) {
+                    continue;
+                }
+                match throttle.try_acquire(&host, now) {
+                    Ok(()) => {
+                        let request = fresh.remove(index);
+                        // Remaining repeats go to the back to interleave with the others
+                        if request.repeat > 1 {
+                            let mut rest = request.clone();
+                            rest.repeat -= 1;
+                            // The hooks run once, for the first execution
+                            rest.on_complete = None;
+                            rest.continuation = None;
+                            fresh.push(rest);
+                        } else if from_pending {
+                            self.forget_dispatched(&request);
+                        }
+                        return Next::Fresh(request);
+                    }
+                    Err(at) => wait_for(at),
+                }
+            }
+        }
+
+        match wake_at {
+            Some(at) => Next::Wait(at),
+            None => Next::Empty,
+        }
+    }
+
+    /// Returns the first due hedge that may be dispatched now, or when one will be.
+    fn next_hedge(&self, hedging: &mut Hedging) -> Next {
+        let Some(hedge) = hedging.pending.front() else {
+            return Next::Empty;
+        };
+        let now = Instant::now();
+        if now < hedge.due {
+            return Next::Wait(hedge.due);
+        }
+
+        let mut throttle = self.throttle.lock().unwrap();
+        if let Err(at) = throttle.next_launch(now) {
+            return Next::Wait(at);
+        }
+        let host = hedge.job.request.get_host().unwrap_or_default();
+        match throttle.try_acquire(&host, now) {
+            Ok(()) => Next::Hedge(hedging.pending.pop_front().unwrap().job),
+            Err(at) => Next::Wait(at),
+        }
+    }
+
+    /// Returns the client for a request, by its proxy, proxy of the pool,
+    /// redirect and decompression settings.
+    ///
+    /// Clients other than the two built up front are built on first use and
+    /// reused; a client that fails to build, e.g. for a proxy URL reqwest
+    /// rejects, fails the attempt instead.
+    fn client_for(&self, request: &Request) -> reqwest::Result<Client> {
+        let key = ClientKey {
+            proxy: request.proxy.clone(),
+            pool_proxy: request.pooled_proxy.as_ref().map(|(index, _)| *index),
+            // Recorded redirect chains are followed by the execution
+            follow_redirects: request.follow_redirects.unwrap_or(self.follow_redirects)
+                && request.record_redirects.is_none(),
+            decompress: request.decompress != Some(false),
+        };
+        match key {
+            ClientKey {
+                proxy: None,
+                pool_proxy: None,
+                follow_redirects: true,
+                decompress: true,
+            } => Ok(self.client.clone()),
+            ClientKey {
+                proxy: None,
+                pool_proxy: None,
+                follow_redirects: false,
+                decompress: true,
+            } => Ok(self.no_redirect_client.clone()),
+            key => {
+                let mut extra_clients = self.extra_clients.lock().unwrap();
+                if let Some(client) = extra_clients.get(&key) {
+                    return Ok(client.clone());
+                }
+                let client = self.client_settings.build(&key)?;
+                extra_clients.insert(key, client.clone());
+                Ok(client)
+            }
+        }
+    }
+
+    /// Checks that `request` can be sent with the client supplied to the
+    /// builder, if any, as its own proxy, redirect and decompression settings
+    /// need a client of their own.
+    fn check_client(&self, request: &Request) -> Result<(), RollingError> {
+        if !self.client_supplied {
+            return Ok(());
+        }
+        let setting = if request.proxy.is_some() {
+            "set_proxy"
+        } else if request.record_redirects.is_some() {
+            "set_record_redirects"
+        } else if request.follow_redirects == Some(false) {
+            "set_follow_redirects(false)"
+        } else if request.decompress == Some(false) {
+            "set_decompress(false)"
+        } else {
+            return Ok(());
+        };
+        Err(RollingError::invalid_request(format!(
+            "{} cannot be applied to a client supplied to the builder",
+            setting
+        )))
+    }
+
+    /// Fills in the settings `request` leaves to the instance: the body size
+    /// limit and the timeout of a supplied client.
+    ///
+    /// The default headers and jar cookies are merged in as each attempt is
+    /// sent instead, see `SendDefaults`.
+    fn apply_defaults(&self, request: &mut Request) {
+        if request.max_body_size.is_none() {
+            request.max_body_size = self.max_body_size;
+        }
+        if request.timeout.is_none() {
+            request.timeout = self.request_timeout;
+        }
+    }
+
+    /// Removes a request leaving the pending queue from deduplication, unless
+    /// executed requests are remembered.
+    fn forget_dispatched(&self, request: &Request) {
+        if !self.deduplicate_executed {
+            self.forget(std::slice::from_ref(request));
+        }
+    }
+
+    /// Removes requests from deduplication, unless an equal request added
+    /// later took their place.
+    fn forget(&self, requests: &[Request]) {
+        let Some(seen) = &self.seen_requests else {
+            return;
+        };
+        let mut seen = seen.lock().unwrap();
+        for request in requests {
+            let fingerprint = fingerprint(&seen, request);
+            if seen.get(&fingerprint) == request.id.as_ref() {
+                seen.remove(&fingerprint);
+            }
+        }
+    }
+
+    /// Returns true if a request for the cache key of `request` is in flight,
+    /// so it should wait to be answered from the cache.
+    fn awaits_cache(&self, request: &Request) -> bool {
+        let Some(cache) = &self.cache else {
+            return false;
+        };
+        let url = request
+            .expand_url(&request.url)
+            .unwrap_or_else(|_| request.url.clone());
+        let url = self.resolve_url(&url).unwrap_or(url);
+        Cache::key(request, &url).is_some_and(|key| cache.is_fetching(&key))
+    }
+
+    /// Resolves a relative request URL against the base URL.
+    ///
+    /// Returns `None` when the URL is absolute or no base URL is set.
+    fn resolve_url(&self, url: &str) -> Option<String> {
+        let base = self.base_url.as_ref()?;
+        match Url::parse(url) {
+            Err(url::ParseError::RelativeUrlWithoutBase) => base.join(url).ok().map(String::from),
+            _ => None,
+        }
+    }
+
+    /// Returns the lowercase host a queued request will be sent to.
+    fn host_of(&self, request: &Request) -> String {
+        let resolved = self.resolve_url(&request.url).and_then(|url| {
+            Url::parse(&url)
+                .ok()?
+                .host_str()
+                .map(|host| host.to_ascii_lowercase())
+        });
+        resolved.or_else(|| request.get_host()).unwrap_or_default()
+    }
+
+    /// Dispatches one attempt of `job` onto `in_flight`.
+    ///
+    /// Hedges share the attempt number of the attempt they duplicate. Other
+    /// attempts schedule a hedge of their own when hedging applies to them.
+    fn spawn_attempt(
+        &self,
+        in_flight: &mut JoinSet<Attempt>,
+        hedging: &mut Hedging,
+        mut job: Job,
+        is_hedge: bool,
+    ) {
+        // Requests are validated before their first attempt, so this cannot fail
+        if let Ok(url) = job.request.expand_url(&job.request.url) {
+            job.request.url = url;
+        }
+        if let Some(url) = self.resolve_url(&job.request.url) {
+            job.request.url = url;
+        }
+        let cache_key = match &self.cache {
+            Some(_) => Cache::key(&job.request, &job.request.url),
+            None => None,
+        };
+        let cached = match (&self.cache, &cache_key) {
+            (Some(cache), Some(key)) => cache.get(key, &job.request.headers),
+            _ => None,
+        };
+        job.request.cached = cached.is_some();
+        job.request.not_modified = false;
+        // Cached responses and requests with their own proxy skip the pool
+        job.request.pooled_proxy = match (&self.proxy_pool, &cached, &job.request.proxy) {
+            (Some(pool), None, None) => Some(pool.lock().unwrap().select(Instant::now())),
+            _ => None,
+        };
+        job.request.body_snippet = None;
+        let snippet_limit = match job
+            .request
+            .error_for_status
+            .unwrap_or(self.error_for_status)
+        {
+            true => self.error_body_snippet,
+            false => 0,
+        };
+        // Misses fill the cache, holding back requests for the same key
+        let fill = match (&self.cache, cache_key) {
+            (Some(cache), Some(key)) if cached.is_none() => Some(cache.start_fetch(key)),
+            _ => None,
+        };
+        // Requests the cache cannot answer ask whether their response changed
+        let validator_key = match &self.validators {
+            Some(_) if cached.is_none() => ValidatorStore::key(&job.request, &job.request.url),
+            _ => None,
+        };
+        let validators = match (&self.validators, validator_key) {
+            (Some(store), Some(key)) => {
+                if let Some(known) = store.get(&key) {
+                    if let Some(etag) = &known.etag {
+                        job.request.set_default_header("If-None-Match", etag);
+                    }
+                    if let Some(last_modified) = &known.last_modified {
+                        job.request
+                            .set_default_header("If-Modified-Since", last_modified);
+                    }
+                }
+                Some((store.clone(), key))
+            }
+            _ => None,
+        };
+        let client = self.client_for(&job.request);
+        let jitter = self
+            .launch_jitter
+            .map(|max| max.mul_f64(rand::thread_rng().gen_range(0.0..=1.0)));
+        if !is_hedge {
+            // Cached responses send nothing
+            let body_size = match cached {
+                Some(_) => Some(0),
+                None => job.request.body_size(),
+            };
+            self.stats
+                .record_attempt(job.request.attempts > 0, body_size);
+            if job.request.attempts == 0 {
+                self.retry_tracker
+                    .lock()
+                    .unwrap()
+                    .record_first_attempt(Instant::now());
+            }
+            if job.request.attempts == 0 {
+                self.add_idempotency_key(&mut job.request);
+                job.started_at = Some(Instant::now());
+            }
+            job.request.attempts += 1;
+        }
+
+        let hedge = match self.hedge_after {
+            Some(delay) if !is_hedge && is_hedgeable(&job.request) => Some(Hedge {
+                due: Instant::now() + delay,
+                job: Job {
+                    slot: job.slot,
+                    request: job.request.clone(),
+                    ready_at: None,
+                    fallback: job.fallback,
+                    primary_url: job.primary_url.clone(),
+                    latency: job.latency,
+                    attempt_latencies: job.attempt_latencies.clone(),
+                    started_at: job.started_at,
+                    throttled_requeues: job.throttled_requeues,
+                },
+            }),
+            _ => None,
+        };
+        let slot = job.slot;
+        let credentials = self
+            .credentials
+            .clone()
+            .filter(|_| !job.request.cross_origin);
+        let defaults = self.send_defaults.clone();
+        let bandwidth = self.bandwidth.clone();
+        #[cfg(feature = "har")]
+        let har = self.har.clone();
+
+        let handle = in_flight.spawn(async move {
+            if let Some(jitter) = jitter {
+                time::sleep(jitter).await;
+            }
+            let dispatched_at = Instant::now();
+            #[cfg(feature = "har")]
+            let started = SystemTime::now();
+            let request = &mut job.request;
+            let filling = fill.as_ref();
+            let throttle = bandwidth.as_deref();
+            // A panicking refresh hook fails this request, not the execution
+            let sent = match cached {
+                Some(cached) => Ok(Ok(cached.to_response(true))),
+                None => {
+                    AssertUnwindSafe(async move {
+                        let client = match client {
+                            Ok(client) => client,
+                            Err(err) => return Err(err),
+                        };
+                        let credentials = credentials.as_deref();
+                        let response = send_attempt(&client, request, &defaults, credentials).await;
+                        #[cfg(feature = "har")]
+                        let response = match &har {
+                            Some(har) => {
+                                har.record(request, started, dispatched_at.elapsed(), response)
+                            }
+                            None => response,
+                        };
+                        match response {
+                            Ok(res) => {
+                                let validators = validators.as_ref();
+                                revisit_response(request, res, filling, validators, throttle).await
+                            }
+                            Err(err) => Err(err),
+                        }
+                    })
+                    .catch_unwind()
+                    .await
+                }
+            };
+            drop(fill);
+            let mut sent = sent;
+            if let Ok(Ok(res)) = &mut sent {
+                if snippet_limit > 0 && res.error_for_status_ref().is_err() {
+                    job.request.body_snippet = read_snippet(res, snippet_limit).await;
+                }
+            }
+            job.request.buffered_body = None;
+            let sent = match sent {
+                Ok(Ok(res)) if job.request.buffers_body(res.status().is_success()) => {
+                    Ok(buffer_body(res, &mut job.request, bandwidth.as_deref()).await)
+                }
+                sent => sent,
+            };
+            Attempt {
+                latency: dispatched_at.elapsed(),
+                dispatched_at,
+                outcome: match sent {
+                    Ok(response) => Outcome::Sent(response),
+                    Err(panic) => Outcome::Panicked(panic_message(panic.as_ref())),
+                },
+                job,
+            }
+        });
+
+        if let Some(hedge) = hedge {
+            hedging.pending.push_back(hedge);
+            hedging.racing.insert(slot, vec![handle]);
+        } else if is_hedge {
+            hedging.racing.entry(slot).or_default().push(handle);
+        }
+    }
+
+    /// Adds a generated idempotency key to `request` when enabled and needed.
+    fn add_idempotency_key(&self, request: &mut Request) {
+        let Some(name) = &self.idempotency_key_header else {
+            return;
+        };
+        if request.method != Method::POST && request.method != Method::PATCH {
+            return;
+        }
+        request.set_default_header(name, &Uuid::new_v4().to_string());
+    }
+
+    /// Records the outcome of an attempt, returning the job with its final
+    /// result, or re-queueing the job when it should be attempted again.
+    fn complete_attempt(
+        &self,
+        attempt: Attempt,
+        retries: &mut VecDeque<Job>,
+    ) -> Option<(Job, Result<reqwest::Response, RollingError>)> {
+        let Attempt {
+            mut job,
+            dispatched_at,
+            latency,
+            outcome,
+        } = attempt;
+        job.latency = latency;
+        job.attempt_latencies.push(latency);
+        let response = match outcome {
+            Outcome::Sent(response) => response,
+            Outcome::Panicked(message) => {
+                let error = RollingError::panicked(message, job.request.attempts);
+                let error = error.for_request(&job.request);
+                return Some((job, Err(error)));
+            }
+        };
+        if let Some(error) = job.request.redirect_error.take() {
+            let error = RollingError::redirect(error, job.request.attempts);
+            let error = error.for_request(&job.request);
+            return Some((job, Err(error)));
+        }
+
+        self.record_feedback(&job.request, dispatched_at, latency, &response);
+
+        // A resumed download the server could not continue is complete
+        // already, or starts over without its partial file
+        match job.request.unsatisfied {
+            Some(Unsatisfied::Complete) => return Some(self.finish_attempt(job, response, false)),
+            Some(Unsatisfied::Restart) => {
+                retries.push_back(job);
+                return None;
+            }
+            None => {}
+        }
+
+        // A mirror beats waiting on a failing URL, so fallbacks come first
+        if job.fallback < job.request.fallback_urls.len() && self.should_fall_back(&response) {
+            let next = job.request.fallback_urls[job.fallback].clone();
+            job.fallback += 1;
+            let primary = std::mem::replace(&mut job.request.url, next);
+            job.primary_url.get_or_insert(primary);
+            retries.push_back(job);
+            return None;
+        }
+
+        if self.respect_retry_after && job.throttled_requeues < MAX_RETRY_AFTER_REQUEUES {
+            if let Ok(res) = &response {
+                if is_throttled(res.status()) {
+                    let delay = retry_after(res.headers()).unwrap_or(DEFAULT_RETRY_AFTER);
+                    let host = job.request.get_host().unwrap_or_default();
+                    self.throttle
+                        .lock()
+                        .unwrap()
+                        .pause(&host, Instant::now() + delay);
+                    job.throttled_requeues += 1;
+                    retries.push_back(job);
+                    return None;
+                }
+            }
+        }
+
+        // A request with its own attempt cap is retried even without a policy
+        let default_policy = RetryPolicy::default();
+        let policy = job
+            .request
+            .retry_policy
+            .as_ref()
+            .or(self.retry_policy.as_ref())
+            .or(job.request.max_attempts.map(|_| &default_policy));
+        if let Some(policy) = policy {
+            let failed = match &response {
+                Ok(res) => !res.status().is_success(),
+                Err(_) => true,
+            };
+            let max_attempts = job.request.max_attempts.unwrap_or(policy.max_attempts);
+            if failed && job.request.attempts < max_attempts {
+                let context = RetryContext {
+                    attempt: job.request.attempts,
+                    error: response.as_ref().err(),
+                    kind: response.as_ref().err().map(RollingErrorKind::of),
+                    status: response.as_ref().ok().map(|res| res.status()),
+                    method: &job.request.method,
+                    url: &job.request.url,
+                };
+                if policy.should_retry(&context)
+                    && self.retry_tracker.lock().unwrap().try_retry(Instant::now())
+                {
+                    let delay = policy.backoff(job.request.attempts);
+                    job.ready_at = Some(Instant::now() + delay);
+                    retries.push_back(job);
+                    return None;
+                }
+            }
+        }
+
+        let error_for_status = job
+            .request
+            .error_for_status
+            .unwrap_or(self.error_for_status);
+        Some(self.finish_attempt(job, response, error_for_status))
+    }
+
+    /// Turns the response to the last attempt of `job` into its final result,
+    /// an error for a non-success status with `error_for_status`.
+    fn finish_attempt(
+        &self,
+        mut job: Job,
+        response: Result<reqwest::Response, reqwest::Error>,
+        error_for_status: bool,
+    ) -> (Job, Result<reqwest::Response, RollingError>) {
+        if let Some(primary) = job.primary_url.take() {
+            job.request.url = primary;
+        }
+        let response = if error_for_status {
+            response.and_then(|res| res.error_for_status())
+        } else {
+            response
+        };
+        let attempts = job.request.attempts;
+        let response = match response {
+            Ok(mut res) => {
+                res.extensions_mut().insert(Attempts(attempts));
+                if let Some(id) = job.request.id {
+                    res.extensions_mut().insert(RequestId(id));
+                }
+                if let Some(metadata) = job.request.metadata.clone() {
+                    res.extensions_mut().insert(metadata);
+                }
+                Ok(res)
+            }
+            Err(err) => Err(RollingError::new(err, attempts)
+                .with_body_snippet(job.request.body_snippet.take())
+                .for_request(&job.request)),
+        };
+        (job, response)
+    }
+
+    /// Feeds the outcome of an attempt of `request`, dispatched at
+    /// `dispatched_at` and answered after `latency`, to the adaptive limiter,
+    /// the proxy pool and the rate limits of its host.
+    fn record_feedback(
+        &self,
+        request: &Request,
+        dispatched_at: Instant,
+        latency: Duration,
+        response: &Result<reqwest::Response, reqwest::Error>,
+    ) {
+        if let Some(limiter) = &self.limiter {
+            limiter
+                .lock()
+                .unwrap()
+                .record(dispatched_at, feedback(response, latency));
+        }
+
+        if let (Some(pool), Some((index, _))) = (&self.proxy_pool, &request.pooled_proxy) {
+            pool.lock()
+                .unwrap()
+                .record(*index, proxy_failed(response), Instant::now());
+        }
+
+        if let (Some(settings), Ok(res)) = (&self.rate_limit_headers, response) {
+            if let Some(info) = settings.parse(res.headers()) {
+                let host = request.get_host().unwrap_or_default();
+                self.throttle
+                    .lock()
+                    .unwrap()
+                    .record_rate_limit(&host, info, settings.threshold);
+            }
+        }
+    }
+
+    /// Returns whether a request should move on to its next fallback URL.
+    fn should_fall_back(&self, response: &Result<reqwest::Response, reqwest::Error>) -> bool {
+        match response {
+            Ok(res) => self.fallback_statuses.contains(&res.status().as_u16()),
+            Err(err) => err.is_connect() || err.is_timeout(),
+        }
+    }
+}
+
+/// Aggregate statistics collected by `RollingRequests::execute_for`.
+#[derive(Clone, Debug, Default)]
+pub struct RunStats {
+    /// The number of requests that produced a final result.
+    pub total_sent: usize,
+    /// The number of responses with a success status.
+    pub successes: usize,
+    /// The number of transport errors and responses with any other status.
+    pub failures: usize,
+    /// The number of responses received for each status code.
+    pub status_counts: BTreeMap<u16, usize>,
+    /// How long the run took, including requests awaited after the deadline.
+    pub elapsed: Duration,
+}
+
+impl RunStats {
+    /// Counts the final result of one request.
+    fn record(&mut self, response: &Result<reqwest::Response, RollingError>) {
+        self.total_sent += 1;
+        match response {
+            Ok(res) => {
+                *self.status_counts.entry(res.status().as_u16()).or_default() += 1;
+                if res.status().is_success() {
+                    self.successes += 1;
+                } else {
+                    self.failures += 1;
+                }
+            }
+            Err(err) => {
+                if let Some(status) = err.status() {
+                    *self.status_counts.entry(status.as_u16()).or_default() += 1;
+                }
+                self.failures += 1;
+            }
+        }
+    }
+}
+
+/// Where an execution takes new requests from.
+enum Feed {
+    /// Take requests out of the pending queue, up to an optional budget.
+    Pending {
+        /// How many requests may be taken, or `None` for all of them.
+        budget: Option<usize>,
+        /// The tag of the requests taken, `Some(None)` for untagged ones, or
+        /// `None` for every request.
+        group: Option<Option<String>>,
+        /// Whether the results are held until the execution ends, with
+        /// their bodies read.
+        holds_results: bool,
+        /// Whether the bodies of requests with an output file are written
+        /// to it, so interrupted downloads are resumed.
+        downloads: bool,
+        /// Whether the responses are handed back unread, so requests are
+        /// sent as given even when checking links.
+        raw: bool,
+    },
+    /// Replay clones of a snapshot of the queue until a deadline.
+    Cycle {
+        /// The requests replayed on every pass.
+        templates: Vec<Request>,
+        /// The clones still to be dispatched in the current pass.
+        batch: Vec<Request>,
+        /// No new pass or request is started after this instant.
+        deadline: Instant,
+    },
+    /// Dispatch a fixed list of requests once.
+    Snapshot {
+        /// The requests still to be dispatched.
+        requests: Vec<Request>,
+        /// Whether the requests were taken out of the queue, so that their
+        /// next pages and follow-ups are queued.
+        from_queue: bool,
+        /// Whether the results are held until the execution ends, with
+        /// their bodies read.
+        holds_results: bool,
+        /// Whether the bodies of requests with an output file are written
+        /// to it, so interrupted downloads are resumed.
+        downloads: bool,
+    },
+}
+
+/// What is kept of a request that produced its final result.
+struct Finished {
+    /// The request, as it was last sent.
+    request: Request,
+    /// When the first attempt was dispatched, unless the request was rejected.
+    started_at: Option<Instant>,
+    /// How long each attempt took until its response headers or its failure.
+    attempt_latencies: Vec<Duration>,
+}
+
+/// A request travelling through a single execution.
+struct Job {
+    /// The position of the request's result in the returned vector.
+    slot: usize,
+    /// The request being executed.
+    request: Request,
+    /// The job may not be dispatched again before this instant.
+    ready_at: Option<Instant>,
+    /// How many fallback URLs have been switched to.
+    fallback: usize,
+    /// The URL the request was queued with, once it moved to a fallback URL.
+    primary_url: Option<String>,
+    /// How long the last finished attempt took.
+    latency: Duration,
+    /// How long each finished attempt took, in order.
+    attempt_latencies: Vec<Duration>,
+    /// When the first attempt was dispatched.
+    started_at: Option<Instant>,
+    /// How many times the request was re-queued for `Retry-After`.
+    throttled_requeues: u32,
+}
+
+impl Job {
+    /// Creates the job for a request taken from the queue.
+    fn new(slot: usize, request: Request) -> Self {
+        Job {
+            slot,
+            request,
+            ready_at: None,
+            fallback: 0,
+            primary_url: None,
+            latency: Duration::ZERO,
+            attempt_latencies: Vec::new(),
+            started_at: None,
+            throttled_requeues: 0,
+        }
+    }
+}
+
+/// The headers and cookies of a `RollingRequests` instance merged into each
+/// request as it is sent, so that retries, hedges and derived requests never
+/// carry stale copies of them.
+struct SendDefaults {
+    /// The headers sent with every request that does not set them itself.
+    headers: HeaderMap,
+    /// The cookies kept from responses, when enabled.
+    cookie_jar: Option<Arc<CookieJar>>,
+}
+
+impl SendDefaults {
+    /// Adds the default headers `req` does not set to the `request` built
+    /// from it, and the jar cookies its cookies do not override.
+    ///
+    /// The `Authorization` and `Cookie` defaults are left out of requests
+    /// that moved to another origin.
+    fn apply(&self, req: &Request, request: &mut reqwest::Request) {
+        let headers = request.headers_mut();
+        for name in self.headers.keys() {
+            let withheld = req.cross_origin && (name == AUTHORIZATION || name == COOKIE);
+            if withheld || headers.contains_key(name) {
+                continue;
+            }
+            for value in self.headers.get_all(name) {
+                headers.append(name, value.clone());
+            }
+        }
+        // The clients skip the jar for requests with a Cookie header, so its
+        // cookies are merged in beneath those of the request
+        let Some(cookie_jar) = &self.cookie_jar else {
+            return;
+        };
+        if !headers.contains_key(COOKIE) {
+            return;
+        }
+        let own = headers
+            .get_all(COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(';'))
+            .map(str::trim)
+            .filter(|pair| !pair.is_empty())
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        let mut pairs: Vec<String> = cookie_jar
+            .pairs(request.url())
+            .into_iter()
+            .filter(|(name, _)| {
+                !own.iter()
+                    .any(|pair| pair.split('=').next().unwrap_or("").trim() == name)
+            })
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        pairs.extend(own);
+        if let Ok(value) = HeaderValue::from_str(&pairs.join("; ")) {
+            request.headers_mut().insert(COOKIE, value);
+        }
+    }
+}
+
+/// The settings shared by the clients of a `RollingRequests` instance.
+struct ClientSettings {
+    /// The total time allowed for a request.
+    timeout: Duration,
+    /// The time allowed to establish a connection.
+    connect_timeout: Option<Duration>,
+    /// Whether HTTP/2 is used without negotiation.
+    force_http2: bool,
+    /// The `User-Agent` header sent by default, when set.
+    user_agent: Option<String>,
+    /// The cookie jar of the clients, when enabled.
+    cookie_jar: Option<Arc<CookieJar>>,
+    /// The proxy of requests that do not set their own, when set.
+    proxy: Option<reqwest::Proxy>,
+    /// The proxies of the pool, in the order they were given.
+    pool_proxies: Vec<reqwest::Proxy>,
+    /// The maximum number of redirects followed by clients following them.
+    max_redirects: usize,
+    /// The hook applied to each client builder last, when set.
+    configure: Option<ClientHook>,
+}
+
+impl ClientSettings {
+    /// Builds a client with the settings and the variations given by `key`.
+    fn build(&self, key: &ClientKey) -> reqwest::Result<Client> {
+        let redirect = if key.follow_redirects {
+            // The limit of reqwest counts the original URL along with the hops
+            Policy::limited(self.max_redirects + 1)
+        } else {
+            Policy::none()
+        };
+        let mut client_builder = Client::builder().timeout(self.timeout).redirect(redirect);
+        if let Some(connect_timeout) = self.connect_timeout {
+            client_builder = client_builder.connect_timeout(connect_timeout);
+        }
+        if let Some(proxy) = &key.proxy {
+            client_builder = client_builder.proxy(reqwest::Proxy::all(proxy.as_str())?);
+        } else if let Some(proxy) = key
+            .pool_proxy
+            .and_then(|index| self.pool_proxies.get(index))
+        {
+            client_builder = client_builder.proxy(proxy.clone());
+        } else if let Some(proxy) = &self.proxy {
+            client_builder = client_builder.proxy(proxy.clone());
+        }
+        if !key.decompress {
+            client_builder = client_builder.no_gzip().no_brotli().no_deflate();
+        }
+        if self.force_http2 {
+            client_builder = client_builder.http2_prior_knowledge();
+        }
+        if let Some(user_agent) = &self.user_agent {
+            client_builder = client_builder.user_agent(user_agent.as_str());
+        }
+        if let Some(cookie_jar) = &self.cookie_jar {
+            client_builder = client_builder.cookie_provider(cookie_jar.clone());
+        }
+        if let Some(configure) = &self.configure {
+            client_builder = configure(client_builder);
+        }
+        client_builder.build()
+    }
+}
+
+/// The per-request settings that need a client of their own.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct ClientKey {
+    /// The proxy requests are sent through.
+    proxy: Option<String>,
+    /// The position of the proxy of the pool requests are sent through.
+    pool_proxy: Option<usize>,
+    /// Whether redirects are followed.
+    follow_redirects: bool,
+    /// Whether compressed responses are decoded.
+    decompress: bool,
+}
+
+impl Default for ClientKey {
+    fn default() -> Self {
+        ClientKey {
+            proxy: None,
+            pool_proxy: None,
+            follow_redirects: true,
+            decompress: true,
+        }
+    }
+}
+
+/// The outcome of one dispatched attempt of a job.
+struct Attempt {
+    /// The job the attempt belongs to.
+    job: Job,
+    /// When the attempt was dispatched.
+    dispatched_at: Instant,
+    /// How long the attempt took to produce a response or an error.
+    latency: Duration,
+    /// What the attempt produced.
+    outcome: Outcome,
+}
+
+/// What an attempt produced.
+enum Outcome {
+    /// The request was sent, producing a response or an error.
+    Sent(Result<reqwest::Response, reqwest::Error>),
+    /// The attempt panicked with the given message.
+    Panicked(String),
+}
+
+/// A duplicate attempt waiting to be sent for a slow request.
+struct Hedge {
+    /// When the duplicate is due.
+    due: Instant,
+    /// The duplicate to send.
+    job: Job,
+}
+
+/// Hedging state of a single execution.
+#[derive(Default)]
+struct Hedging {
+    /// Hedges not sent yet, in due order.
+    pending: VecDeque<Hedge>,
+    /// The running attempts of every slot that may be hedged.
+    racing: HashMap<usize, Vec<AbortHandle>>,
+    /// Attempts aborted because another attempt of their slot finished first.
+    discarded: HashSet<Id>,
+}
+
+impl Hedging {
+    /// Settles the race for `slot` now that attempt `id` has finished.
+    ///
+    /// The other attempts of the slot are aborted and its pending hedge is
+    /// dropped. Returns false when `id` itself lost the race and must be ignored.
+    fn settle(&mut self, slot: usize, id: Id) -> bool {
+        if self.discarded.remove(&id) {
+            return false;
+        }
+        if let Some(handles) = self.racing.remove(&slot) {
+            self.pending.retain(|hedge| hedge.job.slot != slot);
+            for handle in handles.into_iter().filter(|handle| handle.id() != id) {
+                handle.abort();
+                self.discarded.insert(handle.id());
+            }
+        }
+        true
+    }
+
+    /// Forgets attempt `id` after it was aborted.
+    fn forget(&mut self, id: Id) {
+        self.discarded.remove(&id);
+    }
+}
+
+/// What the scheduler should do next.
+enum Next {
+    /// Dispatch a request taken from the pending queue.
+    Fresh(Request),
+    /// Dispatch a job re-queued during the current execution.
+    Retry(Job),
+    /// Dispatch a duplicate of a slow attempt.
+    Hedge(Job),
+    /// Nothing can be dispatched before this instant.
+    Wait(Instant),
+    /// There is nothing left to dispatch.
+    Empty,
+}
+
+/// Records the validators of a successful response, answers a `304 Not
+/// Modified` from the cache when it still holds the response, and stores
+/// other responses in the cache when filling it, reading their bodies
+/// through `bandwidth` when given.
+///
+/// #### Errors
+///
+/// Returns the error of reading the body of a response stored in the cache,
+/// or a `BodyTooLarge` error once it grows past the maximum body size of the
+/// request.
+async fn revisit_response(
+    request: &mut Request,
+    response: reqwest::Response,
+    fill: Option<&Fetch>,
+    validators: Option<&(Arc<ValidatorStore>, String)>,
+    bandwidth: Option<&Bandwidth>,
+) -> Result<reqwest::Response, reqwest::Error> {
+    request.not_modified = response.status() == StatusCode::NOT_MODIFIED;
+    if let Some((store, url)) = validators {
+        if response.status().is_success() {
+            store.record(url, response.headers());
+        }
+    }
+    let Some(Fetch { cache, key }) = fill else {
+        return Ok(response);
+    };
+    if !request.not_modified {
+        return cache
+            .store(
+                key,
+                &request.headers,
+                response,
+                bandwidth,
+                request.max_body_size,
+            )
+            .await;
+    }
+    match cache.revalidate(key, &request.headers) {
+        Some(cached) => {
+            request.cached = true;
+            Ok(cached.to_response(true))
+        }
+        None => Ok(response),
+    }
+}
+
+/// Sends an attempt, with the shared credential when given.
+///
+/// When the execution checks links, a GET request is sent as a separate
+/// HEAD request, and sent itself only if the server rejects HEAD with 405 or
+/// 501. Requests that needed the GET fallback skip HEAD on later attempts.
+async fn send_attempt(
+    client: &Client,
+    req: &mut Request,
+    defaults: &SendDefaults,
+    credentials: Option<&Credentials>,
+) -> Result<reqwest::Response, reqwest::Error> {
+    if !(req.checks_link && req.method == Method::GET && !req.head_fallback) {
+        return send_with(client, req, defaults, credentials).await;
+    }
+    let mut head = req.clone();
+    head.method = Method::HEAD;
+    match send_with(client, &mut head, defaults, credentials).await {
+        Ok(res)
+            if matches!(
+                res.status(),
+                StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED
+            ) =>
+        {
+            req.head_fallback = true;
+            send_with(client, req, defaults, credentials).await
+        }
+        response => {
+            // What was learned while sending applies to the request
+            req.redirects = head.redirects;
+            req.redirect_error = head.redirect_error;
+            req.unsatisfied = head.unsatisfied;
+            response
+        }
+    }
+}
+
+/// Sends a request with the shared credential when given, following its
+/// redirects one by one when it records its redirect chain.
+async fn send_with(
+    client: &Client,
+    req: &mut Request,
+    defaults: &SendDefaults,
+    credentials: Option<&Credentials>,
+) -> Result<reqwest::Response, reqwest::Error> {
+    let Some(max_hops) = req.record_redirects else {
+        return match credentials {
+            Some(credentials) => send_authorized(client, req, defaults, credentials).await,
+            None => send_request(client, req, defaults, None).await,
+        };
+    };
+    req.redirects.clear();
+    req.redirect_error = None;
+    let response = match credentials {
+        Some(credentials) => send_authorized(client, req, defaults, credentials).await?,
+        None => send_request(client, req, defaults, None).await?,
+    };
+    follow_redirects(client, req, defaults, credentials, response, max_hops).await
+}
+
+/// Follows the redirects of `response` one by one, recording each hop on `req`.
+///
+/// Returns the first response that is not a redirect. A redirect past
+/// `max_hops` hops or back to a visited URL is returned as is, with the
+/// reason in `redirect_error`.
+async fn follow_redirects(
+    client: &Client,
+    req: &mut Request,
+    defaults: &SendDefaults,
+    credentials: Option<&Credentials>,
+    mut response: reqwest::Response,
+    max_hops: u32,
+) -> Result<reqwest::Response, reqwest::Error> {
+    let origin = response.url().origin();
+    let mut visited = HashSet::from([response.url().clone()]);
+    let mut hop_request = req.clone();
+    // The query parameters are part of the first URL already
+    hop_request.query_params.clear();
+    loop {
+        let status = response.status();
+        let location = match response.headers().get(LOCATION) {
+            Some(location) if status.is_redirection() => location,
+            _ => return Ok(response),
+        };
+        let location = String::from_utf8_lossy(location.as_bytes()).into_owned();
+        let Ok(next) = response.url().join(&location) else {
+            return Ok(response);
+        };
+        req.redirects.push(RedirectHop {
+            hop: req.redirects.len() as u32 + 1,
+            status,
+            url: response.url().clone(),
+            location,
+        });
+        if req.redirects.len() as u32 > max_hops {
+            req.redirect_error = Some(RedirectError::TooManyHops { limit: max_hops });
+            return Ok(response);
+        }
+        if !visited.insert(next.clone()) {
+            req.redirect_error = Some(RedirectError::Loop { url: next });
+            return Ok(response);
+        }
+
+        let rewrites_method = matches!(
+            status,
+            StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND | StatusCode::SEE_OTHER
+        );
+        if rewrites_method
+            && hop_request.method != Method::GET
+            && hop_request.method != Method::HEAD
+        {
+            hop_request.method = Method::GET;
+            hop_request.post_data = None;
+            hop_request.body_file = None;
+            hop_request.multipart_form_data = None;
+            hop_request.remove_header(CONTENT_TYPE.as_str());
+        }
+        let same_origin = next.origin() == origin;
+        if !same_origin {
+            hop_request
+                .remove_header(AUTHORIZATION.as_str())
+                .remove_header(COOKIE.as_str());
+            hop_request.cookies.clear();
+        }
+        hop_request.cross_origin = req.cross_origin || !same_origin;
+        hop_request.url = next.into();
+        response = match credentials {
+            Some(credentials) if same_origin => {
+                send_authorized(client, &mut hop_request, defaults, credentials).await?
+            }
+            _ => send_request(client, &mut hop_request, defaults, None).await?,
+        };
+    }
+}
+
+/// Sends a request with the shared credential, refreshing it once on 401.
+async fn send_authorized(
+    client: &Client,
+    req: &mut Request,
+    defaults: &SendDefaults,
+    credentials: &Credentials,
+) -> Result<reqwest::Response, reqwest::Error> {
+    let (generation, authorization) = credentials.current();
+    let response = send_request(client, req, defaults, authorization.as_deref()).await?;
+    if response.status() != StatusCode::UNAUTHORIZED {
+        return Ok(response);
+    }
+
+    match credentials.refresh(generation).await {
+        Some(authorization) => send_request(client, req, defaults, Some(&authorization)).await,
+        None => Ok(response),
+    }
+}
+
+/// Builds and sends a single request with the given client, merging in the
+/// instance `defaults`.
+///
+/// When given, `authorization` replaces the request's `Authorization` header.
+async fn send_request(
+    client: &Client,
+    req: &mut Request,
+    defaults: &SendDefaults,
+    authorization: Option<&str>,
+) -> Result<reqwest::Response, reqwest::Error> {
+    let body_file_length = match (&req.multipart_form_data, &req.body_file) {
+        (None, Some(path)) => tokio::fs::metadata(path)
+            .await
+            .ok()
+            .map(|metadata| metadata.len()),
+        _ => None,
+    };
+    req.unsatisfied = None;
+    let resume_point = match (&req.output_file, req.resume && req.downloads) {
+        (Some(path), true) => resume_point(path).await,
+        _ => None,
+    };
+    let mut builder = req.reqwest_builder(client, authorization, body_file_length);
+    if let Some((offset, validator)) = &resume_point {
+        builder = builder.header(RANGE, format!("bytes={}-", offset));
+        if let Some(validator) = validator {
+            builder = builder.header(IF_RANGE, validator);
+        }
+    }
+    let mut request = builder.build()?;
+    defaults.apply(req, &mut request);
+    let response = client.execute(request).await?;
+    if let (Some(path), Some((offset, _))) = (&req.output_file, &resume_point) {
+        if response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+            req.unsatisfied = Some(settle_unsatisfied(&response, path, *offset).await);
+        }
+    }
+    Ok(response)
+}
+
+/// Describes a response by its status line and headers, one per line.
+///
+/// Header values that are not valid UTF-8 are left out.
+fn response_info(response: &reqwest::Response) -> String {
+    let mut info = format!("{:?} {}", response.version(), response.status());
+    for (name, value) in response.headers() {
+        if let Ok(value) = value.to_str() {
+            info.push_str(&format!("\n{}: {}", name, value));
+        }
+    }
+    info
+}
+
+/// Reads a response body into `body` chunk by chunk, waiting on `bandwidth`
+/// when given, and stopping after `limit` bytes.
+///
+/// Returns whether the body was cut off at the limit.
+pub(crate) async fn read_capped_body(
+    mut response: reqwest::Response,
+    bandwidth: Option<&Bandwidth>,
+    limit: Option<usize>,
+    body: &mut Vec<u8>,
+) -> Result<bool, reqwest::Error> {
+    while let Some(chunk) = response.chunk().await? {
+        if let Some(bandwidth) = bandwidth {
+            bandwidth.consume(chunk.len()).await;
+        }
+        if let Some(limit) = limit {
+            if body.len() + chunk.len() > limit {
+                body.extend_from_slice(&chunk[..limit - body.len()]);
+                return Ok(true);
+            }
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(false)
+}
+
+/// Reads the body of `response` into `request` for its continuation, handing
+/// on a response rebuilt around the same bytes, or failing once the body
+/// grows past the maximum body size of the request.
+async fn buffer_body(
+    mut response: reqwest::Response,
+    request: &mut Request,
+    bandwidth: Option<&Bandwidth>,
+) -> Result<reqwest::Response, reqwest::Error> {
+    let mut builder = http::Response::builder()
+        .status(response.status())
+        .version(response.version())
+        .url(response.url().clone());
+    if let Some(headers) = builder.headers_mut() {
+        *headers = response.headers().clone();
+    }
+    if let Some(info) = response.extensions().get::<CacheInfo>() {
+        builder = builder.extension(info.clone());
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        if let Some(bandwidth) = bandwidth {
+            bandwidth.consume(chunk.len()).await;
+        }
+        let size = (body.len() + chunk.len()) as u64;
+        if let Some(limit) = request.max_body_size.filter(|limit| size > *limit) {
+            return Err(body_error(BodyTooLarge { size, limit }).await);
+        }
+        body.extend_from_slice(&chunk);
+    }
+    let body = Bytes::from(body);
+    request.buffered_body = Some(body.clone());
+    let response = builder
+        .body(body)
+        .expect("the parts of a received response are valid");
+    Ok(reqwest::Response::from(response))
+}
+
+/// Returns `error` as the body error of a response, as reqwest reports a
+/// file body growing past its limit.
+pub(crate) async fn body_error(error: BodyTooLarge) -> reqwest::Error {
+    let failing = futures_util::stream::once(future::ready(Err::<Bytes, _>(io::Error::new(
+        io::ErrorKind::InvalidData,
+        error,
+    ))));
+    reqwest::Response::from(http::Response::new(reqwest::Body::wrap_stream(failing)))
+        .bytes()
+        .await
+        .expect_err("the body fails on its first chunk")
+}
+
+/// Reads up to `limit` bytes of the body of a response with an error status,
+/// for its error, returning `None` for an empty body.
+async fn read_snippet(response: &mut reqwest::Response, limit: usize) -> Option<Box<str>> {
+    let mut body = Vec::new();
+    while body.len() < limit {
+        match response.chunk().await {
+            Ok(Some(chunk)) => {
+                body.extend_from_slice(&chunk[..chunk.len().min(limit - body.len())]);
+            }
+            // A body that fails to read leaves the snippet read so far
+            Ok(None) | Err(_) => break,
+        }
+    }
+    match body.is_empty() {
+        true => None,
+        false => Some(String::from_utf8_lossy(&body).into()),
+    }
+}
+
+/// Hands a response body to `sink` chunk by chunk, waiting on `bandwidth`
+/// when given.
+async fn write_to_sink(
+    mut response: reqwest::Response,
+    bandwidth: Option<&Bandwidth>,
+    sink: &dyn BodySink,
+    attempts: u32,
+) -> Result<(), RollingError> {
+    loop {
+        let chunk = match response.chunk().await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => break,
+            Err(err) => return Err(RollingError::new(err, attempts)),
+        };
+        if let Some(bandwidth) = bandwidth {
+            bandwidth.consume(chunk.len()).await;
+        }
+        sink.write_chunk(chunk)
+            .await
+            .map_err(|err| RollingError::sink(err, attempts))?;
+    }
+    sink.finish()
+        .await
+        .map_err(|err| RollingError::sink(err, attempts))
+}
+
+/// Reads a response body chunk by chunk, waiting on `bandwidth` when given.
+async fn read_body(
+    mut response: reqwest::Response,
+    bandwidth: Option<&Bandwidth>,
+) -> Result<Vec<u8>, reqwest::Error> {
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        if let Some(bandwidth) = bandwidth {
+            bandwidth.consume(chunk.len()).await;
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+/// Extracts the message of a panic payload.
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => payload
+            .downcast_ref::<String>()
+            .cloned()
+            .unwrap_or_else(|| "unknown panic".to_string()),
+    }
+}
+
+/// Returns the fingerprint deduplication knows `request` by, hashing what is
+/// sent along with its output file, expected checksum and tag, so downloads
+/// to other paths and requests queued under other tags are kept.
+///
+/// The hasher of `seen` is seeded randomly, so colliding requests cannot be
+/// crafted.
+fn fingerprint(seen: &HashMap<u64, u64>, request: &Request) -> u64 {
+    let mut hasher = seen.hasher().build_hasher();
+    request.hash(&mut hasher);
+    request.output_file.hash(&mut hasher);
+    request.expected_checksum.hash(&mut hasher);
+    request.tag.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Returns whether a duplicate of `request` may be sent while it is in flight.
+fn is_hedgeable(request: &Request) -> bool {
+    is_idempotent(&request.method) && request.multipart_form_data.is_none()
+}
+
+/// Returns whether `status` asks the client to slow down.
+fn is_throttled(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::SERVICE_UNAVAILABLE
+}
+
+/// Returns whether an outcome counts as a failure of the proxy it was sent
+/// through, see `ProxyEjection`.
+fn proxy_failed(response: &Result<reqwest::Response, reqwest::Error>) -> bool {
+    match response {
+        Ok(res) => res.status() == StatusCode::PROXY_AUTHENTICATION_REQUIRED,
+        Err(err) => err.is_connect() || err.is_timeout(),
+    }
+}
 
-        responses
+/// Classifies a request outcome for the adaptive concurrency controller.
+fn feedback(response: &Result<reqwest::Response, reqwest::Error>, latency: Duration) -> Feedback {
+    match response {
+        Ok(res) if is_throttled(res.status()) => Feedback::Overload,
+        Ok(res) if res.status().is_success() => Feedback::Success(latency),
+        Ok(_) => Feedback::Neutral,
+        Err(err) if err.is_timeout() || err.is_connect() => Feedback::Overload,
+        Err(_) => Feedback::Neutral,
     }
 }
@@ -0,0 +1,354 @@
+//! Streaming response bodies to custom consumers.
+//!
+//! This module provides the `BodySink` trait set with `Request::set_body_sink`,
+//! which receives a response body chunk by chunk as it is read, so bodies too
+//! large for memory can be processed within the executions, and the
+//! `LineHandler` set with `Request::set_line_handler`, which receives the
+//! body line by line, e.g. for NDJSON exports, and the `EventHandler` set
+//! with `Request::set_event_handler`, which receives the events of a
+//! Server-Sent Events stream.
+
+use bytes::Bytes;
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// The error a `BodySink` rejects a chunk with.
+pub type SinkError = Box<dyn Error + Send + Sync>;
+
+/// The future returned by `BodySink::write_chunk`.
+pub type SinkFuture<'a> = Pin<Box<dyn Future<Output = Result<(), SinkError>> + Send + 'a>>;
+
+/// A consumer of response bodies, fed one chunk at a time.
+///
+/// The detailed and paired executions hand the body of a successful response
+/// to the sink of its request instead of reading it into memory; chunks arrive
+/// in order, and the next chunk is only read once the previous one was
+/// written, so a slow sink slows the download. The request keeps its place
+/// under the concurrency limit until its sink is done. An error fails the
+/// request with `RollingErrorKind::Sink` and leaves the rest of the body
+/// unread.
+///
+/// Closures taking a `Bytes` and returning a future implement the trait.
+///
+/// #### Examples
+///
+/// ```no_run
+/// use bytes::Bytes;
+/// use rollingrequests::request::Request;
+/// use rollingrequests::rolling::RollingRequestsBuilder;
+/// use rollingrequests::sink::SinkError;
+/// use std::sync::Arc;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let lines = Arc::new(AtomicUsize::new(0));
+///     let counted = lines.clone();
+///
+///     let mut request = Request::get("http://example.com/export.ndjson");
+///     request.set_body_sink(move |chunk: Bytes| {
+///         let counted = counted.clone();
+///         async move {
+///             let newlines = chunk.iter().filter(|&&byte| byte == b'\n').count();
+///             counted.fetch_add(newlines, Ordering::Relaxed);
+///             Ok::<(), SinkError>(())
+///         }
+///     });
+///
+///     let mut rolling_requests = RollingRequestsBuilder::new().build();
+///     rolling_requests.add_request(request);
+///     rolling_requests.execute_requests_detailed().await;
+///     println!("{} lines", lines.load(Ordering::Relaxed));
+/// }
+/// ```
+pub trait BodySink: Send + Sync {
+    /// Consumes the next chunk of the body.
+    ///
+    /// #### Arguments
+    ///
+    /// * `chunk` - The bytes read from the response.
+    ///
+    /// #### Errors
+    ///
+    /// Any error aborts the request, and is reported by its `RollingError`.
+    fn write_chunk(&self, chunk: Bytes) -> SinkFuture<'_>;
+
+    /// Completes the body after its last chunk was written.
+    ///
+    /// Not called for bodies that failed. Does nothing by default.
+    ///
+    /// #### Errors
+    ///
+    /// Any error fails the request, like one from `write_chunk`.
+    fn finish(&self) -> SinkFuture<'_> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+impl<F, Fut> BodySink for F
+where
+    F: Fn(Bytes) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<(), SinkError>> + Send + 'static,
+{
+    fn write_chunk(&self, chunk: Bytes) -> SinkFuture<'_> {
+        Box::pin(self(chunk))
+    }
+}
+
+/// The handler of the lines of a response body, see `Request::set_line_handler`.
+pub type LineHandler = Arc<dyn Fn(&str) + Send + Sync>;
+
+/// The error of a line longer than the `max_line_length` of its request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LineTooLong {
+    /// The maximum length of a line in bytes.
+    pub limit: usize,
+}
+
+impl fmt::Display for LineTooLong {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line longer than {} bytes", self.limit)
+    }
+}
+
+impl Error for LineTooLong {}
+
+/// Splits one response body into lines for a `LineHandler`.
+///
+/// Each body gets its own sink, so clones of a request running at the same
+/// time do not mix their partial lines.
+pub(crate) struct LineSink {
+    /// The handler receiving complete lines.
+    handler: LineHandler,
+    /// The maximum length of a line in bytes.
+    max_line_length: Option<usize>,
+    /// The bytes of the line not yet terminated.
+    partial: Mutex<Vec<u8>>,
+}
+
+impl LineSink {
+    /// Creates a sink handing the lines of a body to `handler`.
+    pub(crate) fn new(handler: LineHandler, max_line_length: Option<usize>) -> Self {
+        LineSink {
+            handler,
+            max_line_length,
+            partial: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Fails if `line`, without its carriage return, exceeds the maximum
+    /// line length.
+    fn check(&self, line: &[u8]) -> Result<(), SinkError> {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        match self.max_line_length {
+            Some(limit) if line.len() > limit => Err(Box::new(LineTooLong { limit })),
+            _ => Ok(()),
+        }
+    }
+
+    /// Hands `line` to the handler, without its line ending.
+    fn emit(&self, line: &[u8]) {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        (self.handler)(&String::from_utf8_lossy(line));
+    }
+
+    /// Splits `chunk`, joining its first line to the partial one.
+    fn split(&self, chunk: &[u8]) -> Result<(), SinkError> {
+        let mut partial = self.partial.lock().unwrap();
+        let mut rest = chunk;
+        while let Some(end) = rest.iter().position(|&byte| byte == b'\n') {
+            partial.extend_from_slice(&rest[..end]);
+            self.check(&partial)?;
+            self.emit(&partial);
+            partial.clear();
+            rest = &rest[end + 1..];
+        }
+        partial.extend_from_slice(rest);
+        // A carriage return at the end may still start a CRLF ending
+        self.check(&partial)
+    }
+}
+
+impl BodySink for LineSink {
+    fn write_chunk(&self, chunk: Bytes) -> SinkFuture<'_> {
+        let split = self.split(&chunk);
+        Box::pin(async move { split })
+    }
+
+    fn finish(&self) -> SinkFuture<'_> {
+        let partial = std::mem::take(&mut *self.partial.lock().unwrap());
+        if !partial.is_empty() {
+            self.emit(&partial);
+        }
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// The handler of the events of an event stream, see `Request::set_event_handler`.
+pub type EventHandler = Arc<dyn Fn(&Event) + Send + Sync>;
+
+/// An event received from a `text/event-stream` response.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Event {
+    /// The type of the event, `message` unless an `event:` field set one.
+    pub event: String,
+    /// The `data:` fields of the event, joined by newlines.
+    pub data: String,
+    /// The last event ID of the stream when the event was dispatched, set by
+    /// this or an earlier event's `id:` field.
+    pub id: Option<String>,
+}
+
+/// The fields of the event being received, and of the stream so far.
+#[derive(Default)]
+struct EventState {
+    /// The `event:` field of the pending event.
+    event: Option<String>,
+    /// The `data:` fields of the pending event, each followed by a newline.
+    data: String,
+    /// The last event ID of the stream.
+    last_event_id: Option<String>,
+    /// The reconnection time sent in a `retry:` field.
+    retry: Option<Duration>,
+}
+
+impl EventState {
+    /// Processes one line of the stream, returning the event it completes.
+    fn feed(&mut self, line: &str) -> Option<Event> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            // Lines starting with a colon are comments
+            "" => {}
+            "event" => self.event = Some(value.to_string()),
+            "data" => {
+                self.data.push_str(value);
+                self.data.push('\n');
+            }
+            "id" if !value.contains('\0') => self.last_event_id = Some(value.to_string()),
+            "retry" => {
+                if let Ok(millis) = value.parse() {
+                    self.retry = Some(Duration::from_millis(millis));
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// Completes the pending event, unless it has no data.
+    fn dispatch(&mut self) -> Option<Event> {
+        let event = self.event.take();
+        if self.data.is_empty() {
+            return None;
+        }
+        let mut data = std::mem::take(&mut self.data);
+        data.pop();
+        Some(Event {
+            event: event.unwrap_or_else(|| "message".to_string()),
+            data,
+            id: self.last_event_id.clone(),
+        })
+    }
+}
+
+/// Parses one event stream for an `EventHandler`, across reconnections.
+pub(crate) struct EventSink {
+    /// The lines of the current connection.
+    lines: LineSink,
+    /// The state shared with the line handler of `lines`.
+    state: Arc<Mutex<EventState>>,
+    /// The handler receiving complete events.
+    handler: EventHandler,
+    /// The maximum length of a line in bytes.
+    max_line_length: Option<usize>,
+}
+
+impl EventSink {
+    /// Creates a sink handing the events of a stream to `handler`.
+    pub(crate) fn new(handler: EventHandler, max_line_length: Option<usize>) -> Self {
+        let state = Arc::new(Mutex::new(EventState::default()));
+        EventSink {
+            lines: EventSink::lines(&state, &handler, max_line_length),
+            state,
+            handler,
+            max_line_length,
+        }
+    }
+
+    /// Creates the line sink feeding `state` and dispatching to `handler`.
+    fn lines(
+        state: &Arc<Mutex<EventState>>,
+        handler: &EventHandler,
+        max_line_length: Option<usize>,
+    ) -> LineSink {
+        let state = state.clone();
+        let handler = handler.clone();
+        let on_line = move |line: &str| {
+            let event = state.lock().unwrap().feed(line);
+            if let Some(event) = event {
+                handler(&event);
+            }
+        };
+        LineSink::new(Arc::new(on_line), max_line_length)
+    }
+
+    /// Drops the partial line and event of a connection that failed, keeping
+    /// the last event ID and reconnection time.
+    pub(crate) fn reconnect(&mut self) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.event = None;
+            state.data.clear();
+        }
+        self.lines = EventSink::lines(&self.state, &self.handler, self.max_line_length);
+    }
+
+    /// Returns the last event ID of the stream, if one was received.
+    pub(crate) fn last_event_id(&self) -> Option<String> {
+        self.state.lock().unwrap().last_event_id.clone()
+    }
+
+    /// Returns the reconnection time sent by the server, if any.
+    pub(crate) fn retry(&self) -> Option<Duration> {
+        self.state.lock().unwrap().retry
+    }
+}
+
+impl BodySink for EventSink {
+    fn write_chunk(&self, chunk: Bytes) -> SinkFuture<'_> {
+        self.lines.write_chunk(chunk)
+    }
+
+    fn finish(&self) -> SinkFuture<'_> {
+        // An event without its closing blank line is discarded
+        self.lines.finish()
+    }
+}
+
+/// The consumer of a streamed response body.
+pub(crate) enum StreamSink {
+    /// A body sink, or a line handler.
+    Body(Arc<dyn BodySink>),
+    /// An event handler.
+    Events(EventSink),
+}
+
+impl StreamSink {
+    /// Returns the sink the chunks are written to.
+    pub(crate) fn sink(&self) -> &dyn BodySink {
+        match self {
+            StreamSink::Body(sink) => sink.as_ref(),
+            StreamSink::Events(events) => events,
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! Aggregate statistics of the requests executed by `RollingRequests`.
+//!
+//! This module provides the `Stats` snapshot returned by
+//! `RollingRequests::stats`, counting the requests dispatched, their outcomes
+//! and the bytes transferred by every execution since the instance was built
+//! or `RollingRequests::reset_stats` was called.
+
+use crate::download::content_length;
+use crate::error::{RollingError, RollingErrorKind};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// A snapshot of the counters of a `RollingRequests` instance.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// The number of requests whose first attempt was dispatched.
+    pub dispatched: u64,
+    /// The number of requests whose final response had a success status.
+    pub succeeded: u64,
+    /// The number of requests whose final result was an error or a response
+    /// with any other status.
+    pub failed: u64,
+    /// The number of requests whose final result was an error, by kind.
+    pub failed_by_kind: HashMap<RollingErrorKind, u64>,
+    /// The number of retries dispatched, including moves to fallback URLs.
+    pub retries: u64,
+    /// The number of final responses received for each status code,
+    /// including those turned into errors by `error_for_status`.
+    pub status_counts: BTreeMap<u16, u64>,
+    /// The body bytes of the first attempts and retries sent, when their
+    /// size is known up front; hedges and bodies of unknown size are not
+    /// counted.
+    pub request_bytes: u64,
+    /// The body bytes of the final responses, as declared by their
+    /// `Content-Length` header; responses without one, or turned into errors
+    /// by `error_for_status`, are not counted.
+    pub response_bytes: u64,
+}
+
+/// The counters behind `Stats`, updated by concurrent attempts.
+#[derive(Default)]
+pub(crate) struct StatsCollector {
+    /// See `Stats::dispatched`.
+    dispatched: AtomicU64,
+    /// See `Stats::succeeded`.
+    succeeded: AtomicU64,
+    /// See `Stats::failed`.
+    failed: AtomicU64,
+    /// See `Stats::failed_by_kind`.
+    failed_by_kind: Mutex<HashMap<RollingErrorKind, u64>>,
+    /// See `Stats::retries`.
+    retries: AtomicU64,
+    /// See `Stats::status_counts`.
+    status_counts: Mutex<BTreeMap<u16, u64>>,
+    /// See `Stats::request_bytes`.
+    request_bytes: AtomicU64,
+    /// See `Stats::response_bytes`.
+    response_bytes: AtomicU64,
+}
+
+impl StatsCollector {
+    /// Counts an attempt, the first of its request unless `is_retry`, sending
+    /// `body_size` bytes when known.
+    pub(crate) fn record_attempt(&self, is_retry: bool, body_size: Option<u64>) {
+        let counter = match is_retry {
+            true => &self.retries,
+            false => &self.dispatched,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        if let Some(size) = body_size {
+            self.request_bytes.fetch_add(size, Ordering::Relaxed);
+        }
+    }
+
+    /// Counts the final result of a request.
+    pub(crate) fn record_result(&self, response: &Result<reqwest::Response, RollingError>) {
+        let (status, success) = match response {
+            Ok(res) => {
+                if let Some(length) = content_length(res) {
+                    self.response_bytes.fetch_add(length, Ordering::Relaxed);
+                }
+                (Some(res.status()), res.status().is_success())
+            }
+            Err(err) => {
+                *self
+                    .failed_by_kind
+                    .lock()
+                    .unwrap()
+                    .entry(err.kind())
+                    .or_default() += 1;
+                (err.status(), false)
+            }
+        };
+        if let Some(status) = status {
+            *self
+                .status_counts
+                .lock()
+                .unwrap()
+                .entry(status.as_u16())
+                .or_default() += 1;
+        }
+        let counter = match success {
+            true => &self.succeeded,
+            false => &self.failed,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the current value of every counter.
+    pub(crate) fn snapshot(&self) -> Stats {
+        Stats {
+            dispatched: self.dispatched.load(Ordering::Relaxed),
+            succeeded: self.succeeded.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+            failed_by_kind: self.failed_by_kind.lock().unwrap().clone(),
+            retries: self.retries.load(Ordering::Relaxed),
+            status_counts: self.status_counts.lock().unwrap().clone(),
+            request_bytes: self.request_bytes.load(Ordering::Relaxed),
+            response_bytes: self.response_bytes.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Sets every counter back to zero.
+    pub(crate) fn reset(&self) {
+        for counter in [
+            &self.dispatched,
+            &self.succeeded,
+            &self.failed,
+            &self.retries,
+            &self.request_bytes,
+            &self.response_bytes,
+        ] {
+            counter.store(0, Ordering::Relaxed);
+        }
+        self.failed_by_kind.lock().unwrap().clear();
+        self.status_counts.lock().unwrap().clear();
+    }
+}
@@ -0,0 +1,323 @@
+//! Per-host pacing used by the request scheduler.
+//!
+//! This module keeps independent state for every host seen by `RollingRequests`
+//! so that throttling one host never delays requests bound for another. It also
+//! provides the `RateLimitHeaders` settings and `RateLimitInfo` values used to
+//! follow the rate limits advertised by servers.
+
+use crate::rolling::RollingRequestsConfig;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::time;
+
+/// Settings for following `X-RateLimit-*` style response headers.
+#[derive(Clone, Debug)]
+pub struct RateLimitHeaders {
+    /// The header holding the number of requests left in the current window.
+    pub remaining_header: String,
+    /// The header holding when the window resets, either as delay seconds or as
+    /// a Unix timestamp (values above one billion are treated as timestamps).
+    pub reset_header: String,
+    /// Dispatching to a host pauses once its remaining count drops to this value.
+    pub threshold: u64,
+}
+
+impl Default for RateLimitHeaders {
+    fn default() -> Self {
+        RateLimitHeaders {
+            remaining_header: "x-ratelimit-remaining".to_string(),
+            reset_header: "x-ratelimit-reset".to_string(),
+            threshold: 0, // Default pause when exhausted
+        }
+    }
+}
+
+/// The rate limit state last advertised by a host.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RateLimitInfo {
+    /// The number of requests left in the current window.
+    pub remaining: Option<u64>,
+    /// When the current window resets.
+    pub reset: Option<SystemTime>,
+}
+
+impl RateLimitHeaders {
+    /// Parses the rate limit headers of a response.
+    ///
+    /// Returns `None` when neither header is present or parseable.
+    pub(crate) fn parse(&self, headers: &HeaderMap) -> Option<RateLimitInfo> {
+        let number = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse::<u64>().ok())
+        };
+
+        let remaining = number(&self.remaining_header);
+        let reset = number(&self.reset_header).map(|value| {
+            if value > 1_000_000_000 {
+                SystemTime::UNIX_EPOCH + Duration::from_secs(value)
+            } else {
+                SystemTime::now() + Duration::from_secs(value)
+            }
+        });
+
+        if remaining.is_none() && reset.is_none() {
+            return None;
+        }
+        Some(RateLimitInfo { remaining, reset })
+    }
+}
+
+/// Parses the `Retry-After` header as either delay seconds or an HTTP date.
+///
+/// Dates in the past yield a zero delay.
+pub(crate) fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(date.duration_since(SystemTime::now()).unwrap_or_default())
+}
+
+/// A token bucket refilled continuously at a fixed rate.
+pub(crate) struct TokenBucket {
+    /// Tokens added per second.
+    rate: f64,
+    /// The maximum number of tokens the bucket can hold.
+    capacity: f64,
+    /// Tokens currently available.
+    tokens: f64,
+    /// The last time the bucket was refilled.
+    updated: Instant,
+}
+
+impl TokenBucket {
+    /// Creates a full bucket refilled at `rate` tokens per second.
+    ///
+    /// The capacity is one second worth of tokens, but never less than one.
+    pub(crate) fn new(rate: f64, now: Instant) -> Self {
+        let capacity = rate.max(1.0);
+        TokenBucket {
+            rate,
+            capacity,
+            tokens: capacity,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.updated = now;
+    }
+
+    /// Creates a full bucket refilled at `rate` tokens per second holding at most
+    /// `capacity` tokens.
+    pub(crate) fn with_capacity(rate: f64, capacity: f64, now: Instant) -> Self {
+        TokenBucket {
+            rate,
+            capacity,
+            tokens: capacity,
+            updated: now,
+        }
+    }
+
+    /// Returns the maximum number of tokens the bucket can hold.
+    pub(crate) fn capacity(&self) -> f64 {
+        self.capacity
+    }
+
+    /// Takes `amount` tokens, or returns the instant at which they will be available.
+    pub(crate) fn try_take(&mut self, amount: f64, now: Instant) -> Result<(), Instant> {
+        self.refill(now);
+        if self.tokens >= amount {
+            self.tokens -= amount;
+            Ok(())
+        } else {
+            let missing = amount - self.tokens;
+            Err(now + Duration::from_secs_f64(missing / self.rate))
+        }
+    }
+}
+
+/// A byte budget shared by every throttled body read.
+pub(crate) struct Bandwidth {
+    /// The bucket holding the bytes that may be read right now.
+    bucket: Mutex<TokenBucket>,
+}
+
+impl Bandwidth {
+    /// Creates a budget of `bytes_per_second`, allowing bursts of a tenth of a second.
+    pub(crate) fn new(bytes_per_second: u64) -> Self {
+        let rate = bytes_per_second as f64;
+        Bandwidth {
+            bucket: Mutex::new(TokenBucket::with_capacity(
+                rate,
+                (rate / 10.0).max(1.0),
+                Instant::now(),
+            )),
+        }
+    }
+
+    /// Waits until `bytes` bytes may be read.
+    ///
+    /// Amounts larger than the bucket are taken in several steps.
+    pub(crate) async fn consume(&self, bytes: usize) {
+        let mut remaining = bytes as f64;
+        while remaining > 0.0 {
+            let taken = {
+                let mut bucket = self.bucket.lock().unwrap();
+                let amount = remaining.min(bucket.capacity());
+                bucket.try_take(amount, Instant::now()).map(|()| amount)
+            };
+            match taken {
+                Ok(amount) => remaining -= amount,
+                Err(at) => time::sleep_until(at.into()).await,
+            }
+        }
+    }
+}
+
+/// Pacing state tracked for a single host.
+struct HostState {
+    /// The request rate limiter for the host, if one is configured.
+    bucket: Option<TokenBucket>,
+    /// The minimum gap between two dispatches to the host.
+    delay: Option<Duration>,
+    /// When the last request was dispatched to the host.
+    last_dispatch: Option<Instant>,
+    /// Dispatching to the host is paused until this instant.
+    paused_until: Option<Instant>,
+    /// The rate limit state last advertised by the host.
+    rate_limit: Option<RateLimitInfo>,
+}
+
+/// Per-host throttling state shared across executions.
+pub(crate) struct HostThrottle {
+    /// The rate applied to hosts without an explicit limit.
+    default_rate: Option<f64>,
+    /// Explicit request rates keyed by host.
+    rates: HashMap<String, f64>,
+    /// The politeness delay applied to hosts without an explicit delay.
+    default_delay: Option<Duration>,
+    /// Explicit politeness delays keyed by host.
+    delays: HashMap<String, Duration>,
+    /// State for every host seen so far.
+    hosts: HashMap<String, HostState>,
+    /// The minimum gap between any two dispatches, regardless of host.
+    spacing: Option<Duration>,
+    /// When the last request was dispatched to any host.
+    last_launch: Option<Instant>,
+}
+
+impl HostThrottle {
+    /// Creates a throttle from the rates and delays in `config`.
+    ///
+    /// Non-positive rates are treated as unlimited.
+    pub(crate) fn new(config: &RollingRequestsConfig) -> Self {
+        HostThrottle {
+            default_rate: config.default_rate_limit.filter(|rate| *rate > 0.0),
+            rates: config
+                .per_host_rate_limits
+                .iter()
+                .filter(|(_, rate)| **rate > 0.0)
+                .map(|(host, rate)| (host.to_ascii_lowercase(), *rate))
+                .collect(),
+            default_delay: config.per_host_delay,
+            delays: config
+                .per_host_delays
+                .iter()
+                .map(|(host, delay)| (host.to_ascii_lowercase(), *delay))
+                .collect(),
+            hosts: HashMap::new(),
+            spacing: config.launch_spacing,
+            last_launch: None,
+        }
+    }
+
+    /// Checks whether any request may be dispatched at `now` given the launch spacing.
+    ///
+    /// Returns the instant of the next allowed launch otherwise.
+    pub(crate) fn next_launch(&self, now: Instant) -> Result<(), Instant> {
+        match (self.spacing, self.last_launch) {
+            (Some(spacing), Some(last)) if now < last + spacing => Err(last + spacing),
+            _ => Ok(()),
+        }
+    }
+
+    /// Reserves a dispatch for `host` at `now`.
+    ///
+    /// Returns the instant at which the host will next have budget when it is
+    /// currently throttled. Nothing is reserved in that case.
+    pub(crate) fn try_acquire(&mut self, host: &str, now: Instant) -> Result<(), Instant> {
+        let state = self.state(host, now);
+
+        if let Some(until) = state.paused_until {
+            if now < until {
+                return Err(until);
+            }
+            state.paused_until = None;
+        }
+        if let (Some(delay), Some(last)) = (state.delay, state.last_dispatch) {
+            let ready_at = last + delay;
+            if now < ready_at {
+                return Err(ready_at);
+            }
+        }
+        if let Some(bucket) = &mut state.bucket {
+            bucket.try_take(1.0, now)?;
+        }
+
+        state.last_dispatch = Some(now);
+        self.last_launch = Some(now);
+        Ok(())
+    }
+
+    /// Pauses dispatching to `host` until `until`.
+    ///
+    /// An existing pause that lasts longer is kept.
+    pub(crate) fn pause(&mut self, host: &str, until: Instant) {
+        let state = self.state(host, Instant::now());
+        state.paused_until = Some(state.paused_until.map_or(until, |at| at.max(until)));
+    }
+
+    /// Records the rate limit state advertised by `host`.
+    ///
+    /// Dispatching to the host is paused until the window resets once the
+    /// remaining count drops to `threshold`.
+    pub(crate) fn record_rate_limit(&mut self, host: &str, info: RateLimitInfo, threshold: u64) {
+        if let (Some(remaining), Some(reset)) = (info.remaining, info.reset) {
+            if remaining <= threshold {
+                let delay = reset.duration_since(SystemTime::now()).unwrap_or_default();
+                self.pause(host, Instant::now() + delay);
+            }
+        }
+        self.state(host, Instant::now()).rate_limit = Some(info);
+    }
+
+    /// Returns the rate limit state last advertised by `host`.
+    pub(crate) fn rate_limit(&self, host: &str) -> Option<RateLimitInfo> {
+        self.hosts
+            .get(host)
+            .and_then(|state| state.rate_limit.clone())
+    }
+
+    /// Returns the state for `host`, creating it from the configuration if needed.
+    fn state(&mut self, host: &str, now: Instant) -> &mut HostState {
+        let rate = self.rates.get(host).copied().or(self.default_rate);
+        let delay = self.delays.get(host).copied().or(self.default_delay);
+        self.hosts
+            .entry(host.to_string())
+            .or_insert_with(|| HostState {
+                bucket: rate.map(|rate| TokenBucket::new(rate, now)),
+                delay,
+                last_dispatch: None,
+                paused_until: None,
+                rate_limit: None,
+            })
+    }
+}
@@ -0,0 +1,153 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::{Reply, TestServer};
+    use mockito::mock;
+    use reqwest::Method;
+    use rollingrequests::{
+        adaptive::AdaptiveConcurrency, request::Request, rolling::RollingRequestsBuilder,
+    };
+    use std::time::{Duration, Instant};
+
+    #[tokio::test]
+    async fn test_adaptive_concurrency_backs_off_and_recovers() {
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .simultaneous_limit(8)
+            .timeout(Duration::from_secs(5))
+            .adaptive_concurrency(AdaptiveConcurrency {
+                min_limit: 1,
+                max_limit: 8,
+                ..Default::default()
+            })
+            .build();
+
+        let url = &mockito::server_url();
+        assert_eq!(rolling_requests.effective_limit(), 8);
+
+        // Every 429 batch halves the limit until it reaches the lower bound
+        let throttled = mock("GET", "/adaptive").with_status(429).create();
+        for expected in [4, 2, 1, 1] {
+            for _ in 0..rolling_requests.effective_limit() {
+                let request = Request::new(&format!("{}/adaptive", url), Method::GET);
+                rolling_requests.add_request(request);
+            }
+            let responses = rolling_requests.execute_requests().await;
+            assert!(responses.iter().all(|response| response.is_ok()));
+            assert_eq!(rolling_requests.effective_limit(), expected);
+        }
+        drop(throttled);
+
+        // Fast successful batches grow the limit again
+        let _m = mock("GET", "/adaptive").with_status(200).create();
+        for _ in 0..4 {
+            for _ in 0..rolling_requests.effective_limit() {
+                let request = Request::new(&format!("{}/adaptive", url), Method::GET);
+                rolling_requests.add_request(request);
+            }
+            rolling_requests.execute_requests().await;
+        }
+        assert!(rolling_requests.effective_limit() >= 3);
+        assert!(rolling_requests.effective_limit() <= 8);
+    }
+
+    #[tokio::test]
+    async fn test_ramp_up_starts_with_one_request() {
+        let server = TestServer::start(|_| Reply::new(200).delay(Duration::from_millis(300)));
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .timeout(Duration::from_secs(5))
+            .ramp_up(3, 1, Duration::from_millis(200))
+            .build();
+
+        for _ in 0..6 {
+            rolling_requests.add_request(Request::new(&server.url(), Method::GET));
+        }
+
+        let start = Instant::now();
+        let responses = rolling_requests.execute_all().await;
+        assert_eq!(responses.len(), 6);
+        assert!(responses.iter().all(|response| response.is_ok()));
+
+        // Only one request may be in flight before the first step
+        let requests = server.requests();
+        let early = requests
+            .iter()
+            .filter(|request| {
+                request.received_at.duration_since(start) < Duration::from_millis(150)
+            })
+            .count();
+        assert_eq!(early, 1);
+
+        // Later on, the ramp lets several requests overlap, up to the target
+        let overlap = |at: Instant| {
+            requests
+                .iter()
+                .filter(|other| {
+                    other.received_at <= at && other.replied_at.is_none_or(|end| at < end)
+                })
+                .count()
+        };
+        let peak = requests
+            .iter()
+            .map(|request| overlap(request.received_at))
+            .max()
+            .unwrap();
+        assert!(peak > 1);
+        assert!(peak <= 3);
+    }
+
+    #[tokio::test]
+    async fn test_arrival_rate_dispatches_on_schedule_while_server_is_slow() {
+        let server = TestServer::start(|_| Reply::new(200).delay(Duration::from_millis(500)));
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .timeout(Duration::from_secs(5))
+            .arrival_rate(20.0)
+            .max_outstanding(10)
+            .build();
+
+        for _ in 0..6 {
+            rolling_requests.add_request(Request::new(&server.url(), Method::GET));
+        }
+
+        let start = Instant::now();
+        let responses = rolling_requests.execute_all().await;
+        assert_eq!(responses.len(), 6);
+        assert!(responses.iter().all(|response| response.is_ok()));
+
+        // Arrivals keep a steady 50ms cadence although each response takes 500ms
+        let requests = server.requests();
+        for pair in requests.windows(2) {
+            let gap = pair[1].received_at.duration_since(pair[0].received_at);
+            assert!(gap >= Duration::from_millis(30));
+            assert!(gap < Duration::from_millis(150));
+        }
+        let last = requests.last().unwrap().received_at;
+        assert!(last.duration_since(start) < Duration::from_millis(450));
+    }
+
+    #[tokio::test]
+    async fn test_max_outstanding_caps_open_loop_requests() {
+        let server = TestServer::start(|_| Reply::new(200).delay(Duration::from_millis(300)));
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .timeout(Duration::from_secs(5))
+            .arrival_rate(100.0)
+            .max_outstanding(2)
+            .build();
+
+        for _ in 0..4 {
+            rolling_requests.add_request(Request::new(&server.url(), Method::GET));
+        }
+
+        let start = Instant::now();
+        let responses = rolling_requests.execute_all().await;
+        assert_eq!(responses.len(), 4);
+
+        // The third arrival waits for one of the first two to finish
+        let requests = server.requests();
+        assert!(requests[1].received_at.duration_since(start) < Duration::from_millis(150));
+        assert!(requests[2].received_at.duration_since(start) >= Duration::from_millis(280));
+    }
+}
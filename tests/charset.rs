@@ -0,0 +1,75 @@
+mod common;
+
+#[cfg(all(test, feature = "charset"))]
+mod tests {
+    use super::common::{Reply, TestServer};
+    use rollingrequests::request::Request;
+    use rollingrequests::rolling::{ClientBuildError, RollingRequestsBuilder};
+
+    #[tokio::test]
+    async fn test_text_is_decoded_with_the_declared_charset() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/latin1" => Reply::new(200)
+                .header("Content-Type", "text/plain; charset=ISO-8859-1")
+                .body(b"caf\xe9 cr\xe8me".as_slice()),
+            "/meta" => Reply::new(200).header("Content-Type", "text/html").body(
+                [
+                    b"<html><head><meta charset=\"Shift_JIS\"></head><body>".as_slice(),
+                    b"\x93\xfa\x96\x7b",
+                    b"</body></html>",
+                ]
+                .concat(),
+            ),
+            _ => Reply::new(200).body(b"na\xefve".as_slice()),
+        });
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .default_charset("ISO-8859-1")
+            .build();
+        for path in ["/latin1", "/meta", "/undeclared"] {
+            let mut request = Request::get(&format!("{}{}", server.url(), path));
+            request.set_extra_info(path);
+            rolling_requests.add_request(request);
+        }
+
+        let results = rolling_requests.execute_all_detailed().await;
+        let result = |path: &str| {
+            results
+                .iter()
+                .find(|result| result.extra_info.as_deref() == Some(path))
+                .unwrap()
+        };
+        assert_eq!(result("/latin1").text(), "café crème");
+        assert_eq!(result("/latin1").charset, Some("windows-1252"));
+        assert!(result("/meta").text().contains("<body>日本</body>"));
+        assert_eq!(result("/meta").charset, Some("Shift_JIS"));
+        assert_eq!(result("/undeclared").text(), "naïve");
+
+        let mut rolling_requests = RollingRequestsBuilder::new().build();
+        rolling_requests.add_request(Request::get(&format!("{}/latin1", server.url())));
+        rolling_requests.add_request(Request::get(&format!("{}/undeclared", server.url())));
+        let results = rolling_requests.execute_all_paired().await;
+        let texts: Vec<_> = results
+            .iter()
+            .map(|(request, _)| request.get_response_text().unwrap().as_str())
+            .collect();
+        assert!(texts.contains(&"café crème"));
+        assert!(texts.contains(&"na\u{fffd}ve"));
+    }
+
+    #[test]
+    fn test_unknown_default_charset_is_rejected() {
+        match RollingRequestsBuilder::new()
+            .default_charset("no-such-charset")
+            .try_build()
+        {
+            Err(ClientBuildError::UnknownCharset(label)) => assert_eq!(label, "no-such-charset"),
+            other => panic!("expected an unknown charset, got {:?}", other.err()),
+        }
+        assert!(
+            RollingRequestsBuilder::new()
+                .default_charset("latin1")
+                .try_build()
+                .is_ok()
+        );
+    }
+}
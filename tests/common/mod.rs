@@ -0,0 +1,309 @@
+//! A small threaded HTTP server for tests that mockito cannot express.
+//!
+//! The mockito server handles one connection at a time, so it cannot hold a
+//! response open while other requests are served. This server handles every
+//! connection on its own thread and lets each test decide the reply per request.
+
+#![allow(dead_code)]
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A request received by the test server.
+#[derive(Clone, Debug)]
+pub struct ReceivedRequest {
+    /// The position of the request in arrival order, starting at zero.
+    pub index: usize,
+    /// The request method.
+    pub method: String,
+    /// The request target, including any query string.
+    pub path: String,
+    /// The request headers, with lowercase names, in the order they were sent.
+    pub headers: Vec<(String, String)>,
+    /// The decoded request body.
+    pub body: Vec<u8>,
+    /// When the request head was received.
+    pub received_at: Instant,
+    /// When the reply was completely written.
+    pub replied_at: Option<Instant>,
+}
+
+impl ReceivedRequest {
+    /// Returns the first value of the header `name`.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.header_values(name).into_iter().next()
+    }
+
+    /// Returns every value of the header `name`.
+    pub fn header_values(&self, name: &str) -> Vec<&str> {
+        let name = name.to_ascii_lowercase();
+        self.headers
+            .iter()
+            .filter(|(key, _)| *key == name)
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+}
+
+/// The reply the test server sends for a request.
+pub struct Reply {
+    status: u16,
+    headers: Vec<(String, String)>,
+    chunks: Vec<Vec<u8>>,
+    delay: Duration,
+    chunk_delay: Duration,
+    chunked: bool,
+}
+
+impl Reply {
+    /// Creates an empty reply with the given status.
+    pub fn new(status: u16) -> Self {
+        Reply {
+            status,
+            headers: Vec::new(),
+            chunks: Vec::new(),
+            delay: Duration::ZERO,
+            chunk_delay: Duration::ZERO,
+            chunked: false,
+        }
+    }
+
+    /// Adds a response header.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Sets the response body.
+    pub fn body(mut self, body: impl AsRef<[u8]>) -> Self {
+        self.chunks = vec![body.as_ref().to_vec()];
+        self
+    }
+
+    /// Sends the body with chunked transfer encoding, pausing `gap` before each chunk.
+    pub fn chunks(mut self, chunks: Vec<Vec<u8>>, gap: Duration) -> Self {
+        self.chunks = chunks;
+        self.chunk_delay = gap;
+        self.chunked = true;
+        self
+    }
+
+    /// Waits before sending anything back.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+}
+
+type Handler = dyn Fn(&ReceivedRequest) -> Reply + Send + Sync;
+
+/// A running test server.
+pub struct TestServer {
+    addr: SocketAddr,
+    requests: Arc<Mutex<Vec<ReceivedRequest>>>,
+}
+
+impl TestServer {
+    /// Starts a server answering every request with `handler`.
+    pub fn start<F>(handler: F) -> Self
+    where
+        F: Fn(&ReceivedRequest) -> Reply + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind test server");
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let handler: Arc<Handler> = Arc::new(handler);
+
+        let accepted = requests.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let requests = accepted.clone();
+                let handler = handler.clone();
+                thread::spawn(move || {
+                    let _ = serve(stream, &requests, handler.as_ref());
+                });
+            }
+        });
+
+        TestServer { addr, requests }
+    }
+
+    /// Returns the base URL of the server, without a trailing slash.
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Returns the requests received so far, in arrival order.
+    pub fn requests(&self) -> Vec<ReceivedRequest> {
+        let mut requests = self.requests.lock().unwrap().clone();
+        requests.sort_by_key(|request| request.index);
+        requests
+    }
+
+    /// Returns the number of requests received so far.
+    pub fn hits(&self) -> usize {
+        self.requests.lock().unwrap().len()
+    }
+}
+
+/// Serves every request sent on one connection.
+fn serve(
+    stream: TcpStream,
+    requests: &Mutex<Vec<ReceivedRequest>>,
+    handler: &Handler,
+) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+
+    loop {
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line)? == 0 {
+            return Ok(());
+        }
+        let received_at = Instant::now();
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next().unwrap_or_default().to_string();
+        let path = parts.next().unwrap_or_default().to_string();
+
+        let mut headers = Vec::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line)?;
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+            }
+        }
+
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.clone())
+        };
+        let body = if header("transfer-encoding").is_some_and(|value| value.contains("chunked")) {
+            read_chunked(&mut reader)?
+        } else {
+            let length = header("content-length")
+                .and_then(|value| value.parse::<usize>().ok())
+                .unwrap_or(0);
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body)?;
+            body
+        };
+
+        let index = {
+            let mut requests = requests.lock().unwrap();
+            let index = requests.len();
+            requests.push(ReceivedRequest {
+                index,
+                method,
+                path,
+                headers,
+                body,
+                received_at,
+                replied_at: None,
+            });
+            index
+        };
+
+        let request = requests.lock().unwrap()[index].clone();
+        let reply = handler(&request);
+        write_reply(&mut writer, &reply, request.method == "HEAD")?;
+        requests.lock().unwrap()[index].replied_at = Some(Instant::now());
+
+        if request.header("connection") == Some("close") {
+            return Ok(());
+        }
+    }
+}
+
+/// Reads a chunked request body.
+fn read_chunked(reader: &mut BufReader<TcpStream>) -> std::io::Result<Vec<u8>> {
+    let mut body = Vec::new();
+    loop {
+        let mut size_line = String::new();
+        reader.read_line(&mut size_line)?;
+        let size = usize::from_str_radix(size_line.trim().split(';').next().unwrap_or("0"), 16)
+            .unwrap_or(0);
+        let mut chunk = vec![0; size + 2];
+        reader.read_exact(&mut chunk)?;
+        if size == 0 {
+            return Ok(body);
+        }
+        body.extend_from_slice(&chunk[..size]);
+    }
+}
+
+/// Writes a reply, honoring its delays. Bodies are left out for HEAD requests.
+fn write_reply(writer: &mut TcpStream, reply: &Reply, head_only: bool) -> std::io::Result<()> {
+    thread::sleep(reply.delay);
+
+    let mut head = format!("HTTP/1.1 {} Test\r\n", reply.status);
+    for (name, value) in &reply.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    if reply.chunked {
+        head.push_str("transfer-encoding: chunked\r\n\r\n");
+    } else {
+        let length: usize = reply.chunks.iter().map(Vec::len).sum();
+        head.push_str(&format!("content-length: {}\r\n\r\n", length));
+    }
+    writer.write_all(head.as_bytes())?;
+    writer.flush()?;
+    if head_only {
+        return Ok(());
+    }
+
+    for chunk in &reply.chunks {
+        if reply.chunked {
+            thread::sleep(reply.chunk_delay);
+            writer.write_all(format!("{:x}\r\n", chunk.len()).as_bytes())?;
+            writer.write_all(chunk)?;
+            writer.write_all(b"\r\n")?;
+        } else {
+            writer.write_all(chunk)?;
+        }
+        writer.flush()?;
+    }
+    if reply.chunked {
+        writer.write_all(b"0\r\n\r\n")?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+/// A listener whose accept backlog is full, so new connections never complete.
+pub struct StalledListener {
+    listener: TcpListener,
+    _connections: Vec<TcpStream>,
+}
+
+impl StalledListener {
+    /// Binds a listener that never accepts and fills its backlog.
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind stalled listener");
+        let addr = listener.local_addr().unwrap();
+        let mut connections = Vec::new();
+        // Connections succeed until the backlog is full; the first one that
+        // times out shows that further connects will hang
+        while let Ok(stream) = TcpStream::connect_timeout(&addr, Duration::from_millis(200)) {
+            connections.push(stream);
+        }
+        StalledListener {
+            listener,
+            _connections: connections,
+        }
+    }
+
+    /// Returns the base URL of the listener, without a trailing slash.
+    pub fn url(&self) -> String {
+        format!("http://{}", self.listener.local_addr().unwrap())
+    }
+}
@@ -0,0 +1,140 @@
+#[cfg(all(test, feature = "har"))]
+mod tests {
+    use mockito::{Matcher, mock};
+    use rollingrequests::har::HarRecording;
+    use rollingrequests::request::Request;
+    use rollingrequests::rolling::RollingRequestsBuilder;
+    use serde_json::Value;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_exported_har_lists_every_request() {
+        let _ok = mock("GET", "/har/ok")
+            .match_query(Matcher::Any)
+            .with_status(200)
+            .with_header("content-type", "text/plain")
+            .with_header("set-cookie", "session=secret")
+            .with_body("hello")
+            .create();
+        let _missing = mock("POST", "/har/missing")
+            .with_status(404)
+            .with_body("not here")
+            .create();
+
+        let mut recording = HarRecording::default();
+        recording.redact_headers.push("X-Api-Key".to_string());
+        recording.body_limit = 3;
+        let mut rolling_requests = RollingRequestsBuilder::new().record_har(recording).build();
+        let mut ok = Request::get(&format!("{}/har/ok", mockito::server_url()));
+        ok.add_header("X-Api-Key", "12345")
+            .add_query_param("q", "a b");
+        rolling_requests.add_request(ok);
+        let missing = Request::post(
+            &format!("{}/har/missing", mockito::server_url()),
+            Some("payload"),
+        );
+        rolling_requests.add_request(missing);
+        rolling_requests.execute_all_detailed().await;
+        assert_eq!(rolling_requests.har_entries(), 2);
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("run.har");
+        rolling_requests.export_har(&path).unwrap();
+        let har: Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        let log = &har["log"];
+        assert_eq!(log["version"], "1.2");
+        assert_eq!(log["creator"]["name"], "rollingrequests");
+        let entries = log["entries"].as_array().unwrap();
+        assert_eq!(entries.len(), 2);
+
+        let ok = entries
+            .iter()
+            .find(|entry| entry["request"]["method"] == "GET")
+            .unwrap();
+        let url = format!("{}/har/ok?q=a+b", mockito::server_url());
+        assert_eq!(ok["request"]["url"], url.as_str());
+        assert_eq!(ok["request"]["queryString"][0]["value"], "a b");
+        let api_key = ok["request"]["headers"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|header| header["name"] == "x-api-key")
+            .unwrap();
+        assert_eq!(api_key["value"], "[redacted]");
+        let cookie = ok["response"]["headers"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|header| header["name"] == "set-cookie")
+            .unwrap();
+        assert_eq!(cookie["value"], "[redacted]");
+        assert_eq!(ok["response"]["status"], 200);
+        assert_eq!(ok["response"]["content"]["size"], 5);
+        assert_eq!(ok["response"]["content"]["text"], "hel");
+        assert!(ok["startedDateTime"].as_str().unwrap().ends_with('Z'));
+        assert!(ok["timings"]["wait"].as_f64().unwrap() >= 0.0);
+
+        let missing = entries
+            .iter()
+            .find(|entry| entry["request"]["method"] == "POST")
+            .unwrap();
+        assert_eq!(missing["response"]["status"], 404);
+        assert_eq!(missing["request"]["postData"]["text"], "pay");
+        assert_eq!(missing["request"]["bodySize"], 7);
+
+        rolling_requests.clear_har();
+        assert_eq!(rolling_requests.har_entries(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_har_log_keeps_the_latest_entries() {
+        let _pages = mock("GET", Matcher::Regex("^/har/latest/".to_string()))
+            .with_status(200)
+            .with_body([0xff, 0x00, 0x10])
+            .expect(3)
+            .create();
+
+        let recording = HarRecording {
+            max_entries: 2,
+            ..HarRecording::default()
+        };
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .simultaneous_limit(1)
+            .record_har(recording)
+            .build();
+        for page in 0..3 {
+            rolling_requests.add_request(Request::get(&format!(
+                "{}/har/latest/{}",
+                mockito::server_url(),
+                page
+            )));
+        }
+        rolling_requests.execute_all_detailed().await;
+        assert_eq!(rolling_requests.har_entries(), 2);
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("latest.har");
+        rolling_requests.export_har(&path).unwrap();
+        let har: Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        let entries = har["log"]["entries"].as_array().unwrap();
+        let urls: Vec<&str> = entries
+            .iter()
+            .map(|entry| entry["request"]["url"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            urls,
+            [1, 2].map(|page| format!("{}/har/latest/{}", mockito::server_url(), page))
+        );
+        let content = &entries[0]["response"]["content"];
+        assert_eq!(content["text"], "/wAQ");
+        assert_eq!(content["encoding"], "base64");
+        let started = entries[0]["startedDateTime"].as_str().unwrap();
+        let shape: String = started
+            .chars()
+            .map(|c| if c.is_ascii_digit() { '0' } else { c })
+            .collect();
+        assert_eq!(shape, "0000-00-00T00:00:00.000Z");
+        assert!(started > "2024-01-01");
+    }
+}
@@ -0,0 +1,132 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::{Reply, TestServer};
+    use reqwest::Method;
+    use rollingrequests::{
+        health::{HealthCheck, HealthStatus},
+        request::Request,
+        rolling::RollingRequestsBuilder,
+    };
+    use std::time::Duration;
+
+    /// A check reporting responses slower than 200ms as degraded.
+    fn fast_check(failure_threshold: u32) -> HealthCheck {
+        HealthCheck {
+            latency_threshold: Duration::from_millis(200),
+            failure_threshold,
+            ..HealthCheck::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_health_classifies_endpoints() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/fast" => Reply::new(200).body("ok"),
+            "/slow" => Reply::new(200).body("ok").delay(Duration::from_millis(400)),
+            _ => Reply::new(503),
+        });
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .simultaneous_limit(3)
+            .timeout(Duration::from_secs(5))
+            .health_check(fast_check(1))
+            .build();
+        for path in ["/fast", "/slow"] {
+            rolling_requests.add_request(Request::new(
+                &format!("{}{}", server.url(), path),
+                Method::GET,
+            ));
+        }
+        let mut down = Request::new(&format!("{}/down", server.url()), Method::GET);
+        down.set_extra_info("backend");
+        rolling_requests.add_request(down);
+
+        let reports = rolling_requests.check_health().await;
+        assert_eq!(reports.len(), 3);
+
+        let fast = &reports[&format!("{}/fast", server.url())];
+        assert_eq!(fast.status, HealthStatus::Up);
+        assert_eq!(fast.status_code, Some(200));
+        assert!(fast.error.is_none());
+
+        let slow = &reports[&format!("{}/slow", server.url())];
+        assert_eq!(slow.status, HealthStatus::Degraded);
+        assert!(slow.latency >= Duration::from_millis(400));
+
+        // Requests with extra info are reported under it
+        let backend = &reports["backend"];
+        assert_eq!(backend.status, HealthStatus::Down);
+        assert_eq!(backend.status_code, Some(503));
+        assert_eq!(backend.consecutive_failures, 1);
+        assert!(backend.error.is_some());
+
+        // The queue is left untouched so checks can be repeated
+        assert_eq!(rolling_requests.check_health().await.len(), 3);
+        assert_eq!(server.hits(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_check_health_requires_consecutive_failures() {
+        // The endpoint blips once, recovers, then fails for good
+        let server = TestServer::start(|request| match request.index {
+            0 | 2 | 3 | 4 => Reply::new(500),
+            _ => Reply::new(200),
+        });
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .timeout(Duration::from_secs(5))
+            .health_check(fast_check(3))
+            .build();
+        rolling_requests.add_request(Request::new(&server.url(), Method::GET));
+
+        let mut statuses = Vec::new();
+        for _ in 0..5 {
+            let reports = rolling_requests.check_health().await;
+            statuses.push(reports[&server.url()].status);
+        }
+        assert_eq!(
+            statuses,
+            vec![
+                HealthStatus::Degraded,
+                HealthStatus::Up,
+                HealthStatus::Degraded,
+                HealthStatus::Degraded,
+                HealthStatus::Down,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_health_matches_body() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/healthy" => Reply::new(200).body("{\"status\":\"ok\"}"),
+            _ => Reply::new(200).body("{\"status\":\"maintenance\"}"),
+        });
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .simultaneous_limit(2)
+            .timeout(Duration::from_secs(5))
+            .health_check(HealthCheck {
+                body_contains: Some("\"ok\"".to_string()),
+                ..HealthCheck::default()
+            })
+            .build();
+        for path in ["/healthy", "/maintenance"] {
+            rolling_requests.add_request(Request::new(
+                &format!("{}{}", server.url(), path),
+                Method::GET,
+            ));
+        }
+
+        let reports = rolling_requests.check_health().await;
+        assert_eq!(
+            reports[&format!("{}/healthy", server.url())].status,
+            HealthStatus::Up
+        );
+        let maintenance = &reports[&format!("{}/maintenance", server.url())];
+        assert_eq!(maintenance.status, HealthStatus::Down);
+        assert_eq!(maintenance.status_code, Some(200));
+    }
+}
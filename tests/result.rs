@@ -0,0 +1,1350 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::{Reply, StalledListener, TestServer};
+    use bytes::Bytes;
+    use rollingrequests::{
+        download::{ChecksumAlgorithm, Progress, SaveCollision},
+        error::{ErrorCode, RollingErrorKind, error_code},
+        request::Request,
+        result::{JsonError, ResultOverflow},
+        retry::{RetryBudget, RetryPolicy},
+        rolling::RollingRequestsBuilder,
+        sink::{Event, SinkError},
+    };
+    use serde::Deserialize;
+    use std::fs;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    struct Job(u32);
+
+    #[tokio::test]
+    async fn test_detailed_results_report_every_field() {
+        let server = TestServer::start(|request| match (request.path.as_str(), request.index) {
+            ("/flaky", 0) => Reply::new(503),
+            ("/flaky", _) => Reply::new(200)
+                .header("X-Served-By", "edge-1")
+                .body("recovered")
+                .delay(Duration::from_millis(20)),
+            _ => Reply::new(404).body("missing"),
+        });
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .timeout(Duration::from_secs(5))
+            .retry_policy(
+                RetryPolicy {
+                    initial_delay: Duration::from_millis(10),
+                    ..RetryPolicy::default()
+                }
+                .retry_if(|ctx| ctx.status.is_some_and(|status| status.is_server_error())),
+            )
+            .error_for_status(true)
+            .build();
+        let mut flaky = Request::get(&format!("{}/flaky", server.url()));
+        flaky.set_extra_info("flaky").set_metadata(Job(7));
+        let mut missing = Request::get(&format!("{}/missing", server.url()));
+        missing.set_extra_info("missing");
+        let mut invalid = Request::get(&format!("{}/{{id}}", server.url()));
+        invalid.set_extra_info("invalid");
+        let flaky_id = rolling_requests.add_request(flaky);
+        let missing_id = rolling_requests.add_request(missing);
+        rolling_requests.add_request(invalid);
+
+        let results = rolling_requests.execute_all_detailed().await;
+        assert_eq!(results.len(), 3);
+
+        let flaky = &results[0];
+        assert!(flaky.is_success());
+        assert_eq!(flaky.request_id, Some(flaky_id));
+        assert_eq!(flaky.extra_info.as_deref(), Some("flaky"));
+        assert_eq!(flaky.metadata::<Job>().map(|job| job.0), Some(7));
+        assert_eq!(flaky.status.map(|status| status.as_u16()), Some(200));
+        assert_eq!(flaky.headers["x-served-by"], "edge-1");
+        assert_eq!(flaky.text(), "recovered");
+        assert!(!flaky.body_truncated);
+        assert_eq!(flaky.attempts, 2);
+        assert!(
+            flaky.elapsed >= Duration::from_millis(20),
+            "{:?}",
+            flaky.elapsed
+        );
+        assert!(flaky.error.is_none());
+        assert_eq!(flaky.code, 0);
+        assert_eq!(flaky.timing.attempts.len(), 2);
+        assert!(flaky.timing.total >= flaky.elapsed);
+
+        let missing = &results[1];
+        assert!(!missing.is_success());
+        assert_eq!(missing.request_id, Some(missing_id));
+        assert_eq!(missing.status.map(|status| status.as_u16()), Some(404));
+        assert_eq!(missing.attempts, 1);
+        let error = missing.error.as_ref().unwrap();
+        assert_eq!(error.kind(), RollingErrorKind::Status(404));
+        assert_eq!(missing.code, ErrorCode::ClientError.code());
+
+        let invalid = &results[2];
+        assert_eq!(invalid.extra_info.as_deref(), Some("invalid"));
+        assert_eq!(invalid.status, None);
+        assert!(invalid.headers.is_empty());
+        assert_eq!((invalid.attempts, invalid.elapsed), (0, Duration::ZERO));
+        assert_eq!(
+            invalid.error.as_ref().unwrap().kind(),
+            RollingErrorKind::InvalidRequest
+        );
+        assert_eq!(invalid.code, ErrorCode::InvalidRequest.code());
+    }
+
+    #[tokio::test]
+    async fn test_detailed_result_body_is_capped() {
+        let server = TestServer::start(|_| Reply::new(200).body("0123456789"));
+
+        let mut rolling_requests = RollingRequestsBuilder::new().result_body_limit(4).build();
+        rolling_requests.add_request(Request::get(&server.url()));
+
+        let results = rolling_requests.execute_requests_detailed().await;
+        assert_eq!(results[0].body, b"0123");
+        assert!(results[0].body_truncated);
+        assert!(results[0].is_success());
+    }
+
+    #[tokio::test]
+    async fn test_paired_results_fill_response_fields() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/ok" => Reply::new(200).header("X-Id", "1").body("héllo wörld"),
+            _ => Reply::new(500).body("boom"),
+        });
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .error_for_status(true)
+            .result_body_limit(5)
+            .build();
+        let mut ok = Request::get(&format!("{}/ok", server.url()));
+        ok.set_extra_info("ok");
+        rolling_requests.add_request(ok);
+        rolling_requests.add_request(Request::get(&format!("{}/fail", server.url())));
+
+        let results = rolling_requests.execute_all_paired().await;
+        let (ok, status) = &results[0];
+        assert_eq!(status.as_ref().unwrap().as_u16(), 200);
+        assert_eq!(ok.get_extra_info(), Some(&"ok".to_string()));
+        assert_eq!(
+            ok.get_response_text().map(String::as_str),
+            Some("h\u{e9}ll")
+        );
+        let info = ok.get_response_info().unwrap();
+        assert!(info.starts_with("HTTP/1.1 200 OK\n"), "{}", info);
+        assert!(info.contains("\nx-id: 1"), "{}", info);
+        assert_eq!(ok.get_response_error(), None);
+        assert_eq!(ok.get_response_errno(), Some(0));
+
+        let (failed, error) = &results[1];
+        let error = error.as_ref().unwrap_err();
+        assert_eq!(error.status().map(|status| status.as_u16()), Some(500));
+        assert_eq!(failed.get_response_text(), None);
+        assert_eq!(failed.get_response_error(), Some(&error.to_string()));
+        assert_eq!(failed.get_response_errno(), Some(92));
+    }
+
+    #[tokio::test]
+    async fn test_connect_timeout_error_code() {
+        let listener = StalledListener::start();
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .connect_timeout(Duration::from_millis(200))
+            .timeout(Duration::from_secs(5))
+            .build();
+        rolling_requests.add_request(Request::get(&listener.url()));
+
+        let results = rolling_requests.execute_all_paired().await;
+        let (request, result) = &results[0];
+        let error = result.as_ref().unwrap_err();
+        assert_eq!(error.code(), ErrorCode::ConnectTimeout.code());
+        assert_eq!(error_code(error.inner().unwrap()), 90);
+        assert_eq!(request.get_response_errno(), Some(90));
+    }
+
+    #[tokio::test]
+    async fn test_tls_failure_error_code() {
+        let server = TestServer::start(|_| Reply::new(200));
+        let url = server.url().replacen("http://", "https://", 1);
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .timeout(Duration::from_secs(5))
+            .build();
+        rolling_requests.add_request(Request::get(&url));
+
+        let results = rolling_requests.execute_all_detailed().await;
+        let error = results[0].error.as_ref().unwrap();
+        assert_eq!(error.kind(), RollingErrorKind::Tls);
+        assert_eq!(error_code(error.inner().unwrap()), 35);
+        assert_eq!(results[0].code, ErrorCode::Tls.code());
+    }
+
+    fn failing_refresh() -> Option<String> {
+        panic!("refresh failed")
+    }
+
+    #[tokio::test]
+    async fn test_panicking_attempt_fails_its_request() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/secure" => Reply::new(401),
+            _ => Reply::new(200),
+        });
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .on_unauthorized(|| async { failing_refresh() })
+            .build();
+        rolling_requests.add_request(Request::get(&format!("{}/secure", server.url())));
+        rolling_requests.add_request(Request::get(&format!("{}/open", server.url())));
+
+        let results = rolling_requests.execute_all_detailed().await;
+        let error = results[0].error.as_ref().unwrap();
+        assert_eq!(error.kind(), RollingErrorKind::Panicked);
+        assert_eq!(error.to_string(), "request panicked: refresh failed");
+        assert_eq!(results[0].code, ErrorCode::TaskPanic.code());
+        assert!(results[1].is_success());
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct User {
+        id: u32,
+        name: String,
+    }
+
+    #[tokio::test]
+    async fn test_json_results_decode_or_explain_failure() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/user" => Reply::new(200).body(r#"{"id": 1, "name": "alice"}"#),
+            "/broken" => Reply::new(200).body(r#"{"id": 1, "name""#),
+            _ => Reply::new(404).body("no such user"),
+        });
+
+        let mut rolling_requests = RollingRequestsBuilder::new().build();
+        for path in ["user", "broken", "missing"] {
+            rolling_requests.add_request(Request::get(&format!("{}/{}", server.url(), path)));
+        }
+
+        let results = rolling_requests.execute_all_detailed().await;
+        assert_eq!(
+            results[0].json::<User>().unwrap(),
+            User {
+                id: 1,
+                name: "alice".to_string()
+            }
+        );
+
+        let error = results[1].json::<User>().unwrap_err();
+        assert!(matches!(error, JsonError::Decode { .. }), "{:?}", error);
+        assert_eq!(error.body(), Some(&br#"{"id": 1, "name""#[..]));
+
+        let error = results[2].json::<User>().unwrap_err();
+        match &error {
+            JsonError::Status { status, body } => {
+                assert_eq!(status.as_u16(), 404);
+                assert_eq!(body, b"no such user");
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_json_result_of_failed_request() {
+        let listener = StalledListener::start();
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .connect_timeout(Duration::from_millis(200))
+            .build();
+        rolling_requests.add_request(Request::get(&listener.url()));
+
+        let results = rolling_requests.execute_requests_detailed().await;
+        match results[0].json::<User>().unwrap_err() {
+            JsonError::Request { code, .. } => assert_eq!(code, ErrorCode::ConnectTimeout.code()),
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_output_file_receives_the_body() {
+        let content: Vec<u8> = (0..64 * 1024).map(|i| (i % 251) as u8).collect();
+        let served = content.clone();
+        let server = TestServer::start(move |request| match request.path.as_str() {
+            "/archive.bin" => Reply::new(200).body(&served),
+            _ => Reply::new(404).body("missing"),
+        });
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested/deeper/archive.bin");
+        let missing_path = dir.path().join("missing.bin");
+
+        let mut rolling_requests = RollingRequestsBuilder::new().build();
+        let mut request = Request::get(&format!("{}/archive.bin", server.url()));
+        request.set_output_file(&path);
+        rolling_requests.add_request(request);
+        let mut missing = Request::get(&format!("{}/missing", server.url()));
+        missing.set_output_file(&missing_path);
+        rolling_requests.add_request(missing);
+
+        let results = rolling_requests.execute_all_detailed().await;
+        let download = results[0].download.as_ref().unwrap();
+        assert_eq!(download.path, path);
+        assert_eq!(download.size, content.len() as u64);
+        assert_eq!(download.status.as_u16(), 200);
+        assert!(results[0].body.is_empty());
+        assert_eq!(fs::read(&path).unwrap(), content);
+        assert!(!dir.path().join("nested/deeper/archive.bin.part").exists());
+
+        assert!(results[1].download.is_none());
+        assert_eq!(results[1].text(), "missing");
+        assert!(!missing_path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_failed_download_removes_partial_file() {
+        let server = TestServer::start(|_| {
+            Reply::new(200).chunks(
+                vec![b"first".to_vec(), b"second".to_vec()],
+                Duration::from_millis(600),
+            )
+        });
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("slow.bin");
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .timeout(Duration::from_millis(300))
+            .build();
+        let mut request = Request::get(&server.url());
+        request.set_output_file(&path);
+        rolling_requests.add_request(request);
+
+        let results = rolling_requests.execute_requests_detailed().await;
+        assert!(results[0].error.is_some());
+        assert!(results[0].download.is_none());
+        assert!(!path.exists());
+        assert!(!dir.path().join("slow.bin.part").exists());
+    }
+
+    #[tokio::test]
+    async fn test_progress_reports_downloads() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/small" => Reply::new(200).body(vec![1; 1000]),
+            _ => Reply::new(200).chunks(vec![vec![2; 3000]; 4], Duration::from_millis(20)),
+        });
+        let dir = tempfile::tempdir().unwrap();
+        let reports = Arc::new(Mutex::new(Vec::<Progress>::new()));
+        let sink = reports.clone();
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .simultaneous_limit(2)
+            .on_progress(move |progress| sink.lock().unwrap().push(*progress))
+            .build();
+        let mut small = Request::get(&format!("{}/small", server.url()));
+        small.set_output_file(&dir.path().join("small.bin"));
+        let small_id = rolling_requests.add_request(small);
+        let mut large = Request::get(&format!("{}/large", server.url()));
+        large.set_output_file(&dir.path().join("large.bin"));
+        let large_id = rolling_requests.add_request(large);
+
+        let results = rolling_requests.execute_requests_detailed().await;
+        assert!(results.iter().all(|result| result.download.is_some()));
+
+        let reports = reports.lock().unwrap();
+        let of = |id| {
+            reports
+                .iter()
+                .filter(|progress| progress.request_id == Some(id))
+                .collect::<Vec<_>>()
+        };
+        let small = of(small_id);
+        let last = small.last().unwrap();
+        assert_eq!((last.bytes_done, last.total), (1000, Some(1000)));
+        let large = of(large_id);
+        assert!(large.len() >= 4, "{:?}", large);
+        let last = large.last().unwrap();
+        assert_eq!(last.bytes_done, 12000);
+        assert_eq!(last.total, None);
+        assert_eq!(
+            fs::metadata(dir.path().join("large.bin")).unwrap().len(),
+            last.bytes_done
+        );
+        let all = reports.iter().map(|progress| progress.all_bytes_done).max();
+        assert_eq!(all, Some(13000));
+        assert!(reports.iter().any(|progress| progress.all_total.is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_download_resumes_with_range() {
+        let content: Vec<u8> = (0..20_000).map(|i| (i % 241) as u8).collect();
+        let served = content.clone();
+        let server = TestServer::start(move |request| match request.index {
+            0 => Reply::new(200).header("ETag", "\"v1\"").chunks(
+                vec![served[..10_000].to_vec(), served[10_000..].to_vec()],
+                Duration::from_millis(300),
+            ),
+            _ => {
+                assert_eq!(request.header("range"), Some("bytes=10000-"));
+                assert_eq!(request.header("if-range"), Some("\"v1\""));
+                Reply::new(206)
+                    .header("ETag", "\"v1\"")
+                    .header("Content-Range", "bytes 10000-19999/20000")
+                    .body(&served[10_000..])
+            }
+        });
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dataset.bin");
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .timeout(Duration::from_millis(500))
+            .build();
+        let mut request = Request::get(&server.url());
+        request
+            .set_output_file(&path)
+            .set_resume(true)
+            .set_max_attempts(2);
+        rolling_requests.add_request(request);
+
+        let results = rolling_requests.execute_requests_detailed().await;
+        assert!(results[0].error.is_none(), "{:?}", results[0].error);
+        let download = results[0].download.as_ref().unwrap();
+        assert_eq!(download.size, 20_000);
+        assert_eq!(download.status.as_u16(), 206);
+        assert_eq!(results[0].attempts, 2);
+        assert_eq!(server.hits(), 2);
+        assert_eq!(fs::read(&path).unwrap(), content);
+        assert!(!dir.path().join("dataset.bin.part").exists());
+        assert!(!dir.path().join("dataset.bin.part.validator").exists());
+    }
+
+    #[tokio::test]
+    async fn test_resume_restarts_when_range_is_ignored() {
+        let server = TestServer::start(|_| Reply::new(200).body("fresh content"));
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("page.txt");
+        fs::write(dir.path().join("page.txt.part"), "stale").unwrap();
+
+        let mut rolling_requests = RollingRequestsBuilder::new().build();
+        let mut request = Request::get(&server.url());
+        request.set_output_file(&path).set_resume(true);
+        rolling_requests.add_request(request);
+
+        let results = rolling_requests.execute_requests_detailed().await;
+        assert_eq!(results[0].download.as_ref().unwrap().size, 13);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fresh content");
+        let requests = server.requests();
+        assert_eq!(requests[0].header("range"), Some("bytes=5-"));
+        assert_eq!(requests[0].header("if-range"), None);
+    }
+
+    #[tokio::test]
+    async fn test_unsatisfiable_resume_completes_or_restarts() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/done" => Reply::new(416).header("Content-Range", "bytes */8"),
+            _ if request.header("range").is_some() => {
+                Reply::new(416).header("Content-Range", "bytes */100")
+            }
+            _ => Reply::new(200).body("fresh content"),
+        });
+        let dir = tempfile::tempdir().unwrap();
+        let done = dir.path().join("done.txt");
+        let changed = dir.path().join("changed.txt");
+        fs::write(dir.path().join("done.txt.part"), "complete").unwrap();
+        fs::write(dir.path().join("changed.txt.part"), "stale").unwrap();
+
+        let mut rolling_requests = RollingRequestsBuilder::new().error_for_status(true).build();
+        let mut request = Request::get(&format!("{}/done", server.url()));
+        request.set_output_file(&done).set_resume(true);
+        rolling_requests.add_request(request);
+        let mut request = Request::get(&format!("{}/changed", server.url()));
+        request.set_output_file(&changed).set_resume(true);
+        rolling_requests.add_request(request);
+
+        let results = rolling_requests.execute_all_detailed().await;
+        assert!(results[0].is_success(), "{:?}", results[0].error);
+        let download = results[0].download.as_ref().unwrap();
+        assert_eq!((download.size, download.status.as_u16()), (8, 200));
+        assert_eq!(fs::read_to_string(&done).unwrap(), "complete");
+        assert!(!dir.path().join("done.txt.part").exists());
+
+        assert!(results[1].is_success(), "{:?}", results[1].error);
+        assert_eq!(results[1].attempts, 2);
+        assert_eq!(fs::read_to_string(&changed).unwrap(), "fresh content");
+        let requests = server.requests();
+        let changed: Vec<_> = requests
+            .iter()
+            .filter(|request| request.path == "/changed")
+            .collect();
+        assert_eq!(changed[0].header("range"), Some("bytes=5-"));
+        assert_eq!(changed[1].header("range"), None);
+    }
+
+    #[tokio::test]
+    async fn test_resume_range_is_only_sent_by_downloading_executions() {
+        let server = TestServer::start(|_| Reply::new(200).body("whole body"));
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file.txt");
+        fs::write(dir.path().join("file.txt.part"), "partial").unwrap();
+
+        let mut rolling_requests = RollingRequestsBuilder::new().build();
+        let mut request = Request::get(&server.url());
+        request.set_output_file(&path).set_resume(true);
+        rolling_requests.add_request(request);
+        let responses = rolling_requests.execute_all().await;
+        let response = responses.into_iter().next().unwrap().unwrap();
+        assert_eq!(response.text().await.unwrap(), "whole body");
+        assert_eq!(server.requests()[0].header("range"), None);
+        assert!(dir.path().join("file.txt.part").exists());
+    }
+
+    #[tokio::test]
+    async fn test_resumed_downloads_draw_on_the_retry_budget() {
+        let server = TestServer::start(|_| {
+            Reply::new(200).chunks(
+                vec![b"first half".to_vec(), b"second half".to_vec()],
+                Duration::from_millis(300),
+            )
+        });
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.bin");
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .timeout(Duration::from_millis(150))
+            .retry_budget(RetryBudget::Fixed {
+                retries: 0,
+                window: Duration::from_secs(60),
+            })
+            .build();
+        let mut request = Request::get(&server.url());
+        request
+            .set_output_file(&path)
+            .set_resume(true)
+            .set_max_attempts(3);
+        rolling_requests.add_request(request);
+
+        let results = rolling_requests.execute_all_detailed().await;
+        assert!(results[0].error.is_some());
+        assert_eq!(results[0].attempts, 1);
+        assert_eq!(server.hits(), 1);
+        assert!(dir.path().join("data.bin.part").exists());
+    }
+
+    const HELLO_SHA256: &str = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+
+    #[cfg(feature = "checksum")]
+    #[tokio::test]
+    async fn test_download_checksums() {
+        let server = TestServer::start(|_| Reply::new(200).body("hello world"));
+        let dir = tempfile::tempdir().unwrap();
+        let path = |name: &str| dir.path().join(name);
+
+        let mut rolling_requests = RollingRequestsBuilder::new().build();
+        let mut matching = Request::get(&server.url());
+        matching
+            .set_output_file(&path("matching.txt"))
+            .set_expected_checksum(ChecksumAlgorithm::Sha256, &HELLO_SHA256.to_uppercase());
+        rolling_requests.add_request(matching);
+        let mut mismatching = Request::get(&server.url());
+        mismatching
+            .set_output_file(&path("mismatching.txt"))
+            .set_expected_checksum(ChecksumAlgorithm::Sha256, &"0".repeat(64));
+        rolling_requests.add_request(mismatching);
+        let mut unchecked = Request::get(&server.url());
+        unchecked.set_output_file(&path("unchecked.txt"));
+        rolling_requests.add_request(unchecked);
+
+        let results = rolling_requests.execute_all_detailed().await;
+        let checksum = results[0]
+            .download
+            .as_ref()
+            .unwrap()
+            .checksum
+            .clone()
+            .unwrap();
+        assert_eq!(checksum.hex, HELLO_SHA256);
+        assert_eq!(
+            fs::read_to_string(path("matching.txt")).unwrap(),
+            "hello world"
+        );
+
+        let error = results[1].error.as_ref().unwrap();
+        assert_eq!(error.kind(), RollingErrorKind::ChecksumMismatch);
+        assert_eq!(results[1].code, ErrorCode::ChecksumMismatch.code());
+        let mismatch = error.checksum_mismatch().unwrap();
+        assert_eq!(mismatch.actual.hex, HELLO_SHA256);
+        assert!(!path("mismatching.txt").exists());
+        assert!(!path("mismatching.txt.part").exists());
+
+        let checksum = results[2]
+            .download
+            .as_ref()
+            .unwrap()
+            .checksum
+            .clone()
+            .unwrap();
+        assert_eq!(checksum.algorithm, ChecksumAlgorithm::Sha256);
+        assert_eq!(checksum.to_string(), format!("sha256:{}", HELLO_SHA256));
+    }
+
+    #[cfg(not(feature = "checksum"))]
+    #[tokio::test]
+    async fn test_expected_checksum_requires_feature() {
+        let server = TestServer::start(|_| Reply::new(200).body("hello world"));
+        let dir = tempfile::tempdir().unwrap();
+
+        let mut rolling_requests = RollingRequestsBuilder::new().build();
+        let mut request = Request::get(&server.url());
+        request
+            .set_output_file(&dir.path().join("hello.txt"))
+            .set_expected_checksum(ChecksumAlgorithm::Sha256, HELLO_SHA256);
+        rolling_requests.add_request(request);
+
+        let results = rolling_requests.execute_requests_detailed().await;
+        let error = results[0].error.as_ref().unwrap();
+        assert_eq!(error.kind(), RollingErrorKind::InvalidRequest);
+        assert_eq!(server.hits(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_results_keep_headers_and_final_url() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/old" => Reply::new(301).header("Location", "/new"),
+            _ => Reply::new(200)
+                .header("ETag", "\"abc\"")
+                .header("X-RateLimit-Remaining", "41")
+                .body(vec![b'x'; 100_000]),
+        });
+
+        let mut rolling_requests = RollingRequestsBuilder::new().result_body_limit(10).build();
+        rolling_requests.add_request(Request::get(&format!("{}/old", server.url())));
+
+        let results = rolling_requests.execute_requests_detailed().await;
+        let result = &results[0];
+        assert_eq!(result.headers["etag"], "\"abc\"");
+        assert_eq!(result.headers["x-ratelimit-remaining"], "41");
+        assert_eq!(result.headers["content-length"], "100000");
+        assert_eq!(result.url.as_ref().unwrap().path(), "/new");
+        assert_eq!(result.body.len(), 10);
+        assert!(result.body_truncated);
+    }
+
+    #[tokio::test]
+    async fn test_headers_only_leaves_bodies_unread() {
+        let server = TestServer::start(|_| {
+            Reply::new(200)
+                .header("ETag", "\"v2\"")
+                .chunks(vec![b"slow".to_vec(); 3], Duration::from_millis(500))
+        });
+        let dir = tempfile::tempdir().unwrap();
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .simultaneous_limit(2)
+            .headers_only(true)
+            .build();
+        rolling_requests.add_request(Request::get(&server.url()));
+        let mut download = Request::get(&server.url());
+        download.set_output_file(&dir.path().join("slow.txt"));
+        rolling_requests.add_request(download);
+
+        let started = std::time::Instant::now();
+        let results = rolling_requests.execute_requests_detailed().await;
+        assert!(started.elapsed() < Duration::from_millis(500));
+        for result in &results {
+            assert!(result.is_success());
+            assert_eq!(result.headers["etag"], "\"v2\"");
+            assert!(result.body.is_empty());
+            assert!(result.download.is_none());
+        }
+        assert!(!dir.path().join("slow.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_timing_of_delayed_requests() {
+        let delay = Duration::from_millis(100);
+        let server = TestServer::start(move |_| Reply::new(200).body("slow").delay(delay));
+
+        let mut rolling_requests = RollingRequestsBuilder::new().build();
+        for _ in 0..3 {
+            rolling_requests.add_request(Request::get(&server.url()));
+        }
+
+        let results = rolling_requests.execute_all_detailed().await;
+        for result in &results {
+            assert!(result.elapsed >= delay, "{:?}", result.timing);
+            assert!(result.timing.time_to_first_byte.unwrap() >= delay);
+            assert_eq!(result.timing.attempts.len(), 1);
+            assert!(result.timing.total >= result.timing.queue_wait + delay);
+        }
+        let waits: Vec<_> = results
+            .iter()
+            .map(|result| result.timing.queue_wait)
+            .collect();
+        assert!(waits[0] < waits[1] && waits[1] < waits[2], "{:?}", waits);
+        assert!(waits[2] >= delay * 2, "{:?}", waits);
+    }
+
+    #[tokio::test]
+    async fn test_link_check_falls_back_to_get_once() {
+        let server = TestServer::start(|req| match req.method.as_str() {
+            "HEAD" => Reply::new(405),
+            _ => Reply::new(200).body("page"),
+        });
+
+        let mut rolling_requests = RollingRequestsBuilder::new().link_check(true).build();
+        rolling_requests.add_request(Request::get(&format!("{}/page", server.url())));
+
+        let results = rolling_requests.execute_requests_detailed().await;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].status, Some(reqwest::StatusCode::OK));
+        assert!(results[0].head_fallback);
+        assert!(results[0].body.is_empty());
+        assert!(results[0].url.as_ref().unwrap().path().ends_with("/page"));
+        let methods: Vec<String> = server
+            .requests()
+            .into_iter()
+            .map(|req| req.method)
+            .collect();
+        assert_eq!(methods, ["HEAD", "GET"]);
+    }
+
+    #[tokio::test]
+    async fn test_link_check_sends_head() {
+        let server = TestServer::start(|_| Reply::new(200).body("page"));
+
+        let mut rolling_requests = RollingRequestsBuilder::new().link_check(true).build();
+        rolling_requests.add_request(Request::get(&server.url()));
+
+        let results = rolling_requests.execute_requests_detailed().await;
+        assert!(results[0].is_success());
+        assert!(!results[0].head_fallback);
+        let methods: Vec<String> = server
+            .requests()
+            .into_iter()
+            .map(|req| req.method)
+            .collect();
+        assert_eq!(methods, ["HEAD"]);
+    }
+
+    #[tokio::test]
+    async fn test_link_check_leaves_raw_executions_and_requests_as_given() {
+        let server = TestServer::start(|_| Reply::new(200).body("page"));
+        let mut rolling_requests = RollingRequestsBuilder::new().link_check(true).build();
+
+        rolling_requests.add_request(Request::get(&server.url()));
+        let response = rolling_requests.execute_all().await.remove(0).unwrap();
+        assert_eq!(response.text().await.unwrap(), "page");
+
+        rolling_requests.add_request(Request::get(&server.url()));
+        let (request, status) = rolling_requests.execute_all_paired().await.remove(0);
+        assert_eq!(status.unwrap(), reqwest::StatusCode::OK);
+        assert_eq!(request.get_method(), &reqwest::Method::GET);
+
+        let methods: Vec<String> = server
+            .requests()
+            .into_iter()
+            .map(|req| req.method)
+            .collect();
+        assert_eq!(methods, ["GET", "HEAD"]);
+    }
+
+    #[tokio::test]
+    async fn test_body_sink_receives_every_chunk() {
+        let chunks: Vec<Vec<u8>> = (0..5)
+            .map(|n| format!("{{\"line\":{}}}\n", n).into_bytes())
+            .collect();
+        let body = chunks.concat();
+        let server = TestServer::start(move |_| {
+            Reply::new(200).chunks(chunks.clone(), Duration::from_millis(20))
+        });
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sunk = received.clone();
+        let mut request = Request::get(&server.url());
+        request.set_body_sink(move |chunk: Bytes| {
+            let sunk = sunk.clone();
+            async move {
+                sunk.lock().unwrap().push(chunk);
+                Ok::<(), SinkError>(())
+            }
+        });
+        let mut rolling_requests = RollingRequestsBuilder::new().build();
+        rolling_requests.add_request(request);
+
+        let results = rolling_requests.execute_requests_detailed().await;
+        assert!(results[0].is_success());
+        assert!(results[0].body.is_empty());
+        let received = received.lock().unwrap();
+        assert!(received.len() > 1);
+        assert_eq!(received.concat(), body);
+    }
+
+    #[tokio::test]
+    async fn test_body_sinks_run_within_the_simultaneous_limit() {
+        let server = TestServer::start(|_| {
+            Reply::new(200).chunks(vec![b"chunk".to_vec(); 3], Duration::from_millis(20))
+        });
+
+        // The number of chunks being written, and the most written at once
+        let running = Arc::new(Mutex::new((0, 0)));
+        let mut rolling_requests = RollingRequestsBuilder::new().simultaneous_limit(2).build();
+        for _ in 0..6 {
+            let running = running.clone();
+            let mut request = Request::get(&server.url());
+            request.set_body_sink(move |chunk: Bytes| {
+                let running = running.clone();
+                async move {
+                    {
+                        let mut running = running.lock().unwrap();
+                        running.0 += 1;
+                        running.1 = running.1.max(running.0);
+                    }
+                    tokio::time::sleep(Duration::from_millis(30)).await;
+                    running.lock().unwrap().0 -= 1;
+                    assert_eq!(chunk.as_ref(), b"chunk");
+                    Ok::<(), SinkError>(())
+                }
+            });
+            rolling_requests.add_request(request);
+        }
+
+        let results = rolling_requests.execute_all_detailed().await;
+        assert_eq!(results.len(), 6);
+        assert!(results.iter().all(|result| result.is_success()));
+        let (_, most) = *running.lock().unwrap();
+        assert!((1..=2).contains(&most), "{} sinks ran at once", most);
+    }
+
+    #[tokio::test]
+    async fn test_body_sink_error_fails_the_request() {
+        let server = TestServer::start(|_| {
+            Reply::new(200).chunks(vec![b"first".to_vec(); 3], Duration::from_millis(20))
+        });
+
+        let mut request = Request::get(&server.url());
+        request.set_body_sink(|_: Bytes| async { Err::<(), SinkError>("disk full".into()) });
+        let mut rolling_requests = RollingRequestsBuilder::new().build();
+        rolling_requests.add_request(request.clone());
+
+        let results = rolling_requests.execute_requests_detailed().await;
+        let error = results[0].error.as_ref().unwrap();
+        assert_eq!(error.kind(), RollingErrorKind::Sink);
+        assert_eq!(error.to_string(), "body sink failed: disk full");
+        assert_eq!(results[0].code, ErrorCode::Write.code());
+
+        rolling_requests.add_request(request);
+        let paired = rolling_requests.execute_requests_paired().await;
+        let (request, outcome) = &paired[0];
+        assert_eq!(outcome.as_ref().unwrap_err().kind(), RollingErrorKind::Sink);
+        assert_eq!(request.get_response_errno(), Some(23));
+    }
+
+    #[tokio::test]
+    async fn test_line_handler_joins_lines_across_chunks() {
+        let chunks = vec![
+            b"{\"id\":1}\r".to_vec(),
+            b"\n{\"id\"".to_vec(),
+            b":2}\n{\"id\":3".to_vec(),
+            b"}\r\n\n{\"id\":".to_vec(),
+            b"4}".to_vec(),
+        ];
+        let server = TestServer::start(move |_| {
+            Reply::new(200).chunks(chunks.clone(), Duration::from_millis(20))
+        });
+
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let handled = lines.clone();
+        let mut request = Request::get(&server.url());
+        request.set_line_handler(move |line| handled.lock().unwrap().push(line.to_string()));
+        let mut rolling_requests = RollingRequestsBuilder::new().build();
+        rolling_requests.add_request(request);
+
+        let results = rolling_requests.execute_requests_detailed().await;
+        assert!(results[0].is_success());
+        assert_eq!(
+            *lines.lock().unwrap(),
+            ["{\"id\":1}", "{\"id\":2}", "{\"id\":3}", "", "{\"id\":4}"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_max_line_length_stops_buffering() {
+        let server = TestServer::start(|_| {
+            Reply::new(200).chunks(
+                vec![b"short\r\n".to_vec(), vec![b'x'; 16], vec![b'x'; 16]],
+                Duration::from_millis(20),
+            )
+        });
+
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let handled = lines.clone();
+        let mut request = Request::get(&server.url());
+        request
+            .set_line_handler(move |line| handled.lock().unwrap().push(line.to_string()))
+            .set_max_line_length(20);
+        let mut rolling_requests = RollingRequestsBuilder::new().build();
+        rolling_requests.add_request(request);
+
+        let results = rolling_requests.execute_requests_detailed().await;
+        let error = results[0].error.as_ref().unwrap();
+        assert_eq!(error.kind(), RollingErrorKind::Sink);
+        assert_eq!(
+            error.to_string(),
+            "body sink failed: line longer than 20 bytes"
+        );
+        assert_eq!(*lines.lock().unwrap(), ["short"]);
+    }
+
+    #[tokio::test]
+    async fn test_event_handler_parses_event_stream() {
+        let chunks = vec![
+            b": connected\n\nevent: update\nid: 7\r\ndata: first line\n".to_vec(),
+            b"data:second line\n\ndata: {\"n\"".to_vec(),
+            b":2}\n\nid\ndata: no id\n\ndata: unfinished\n".to_vec(),
+        ];
+        let server = TestServer::start(move |_| {
+            Reply::new(200)
+                .header("Content-Type", "text/event-stream")
+                .chunks(chunks.clone(), Duration::from_millis(20))
+        });
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let handled = events.clone();
+        let mut request = Request::get(&server.url());
+        request.set_event_handler(move |event| handled.lock().unwrap().push(event.clone()));
+        let mut rolling_requests = RollingRequestsBuilder::new().build();
+        rolling_requests.add_request(request);
+
+        let rolling_requests = Arc::new(rolling_requests);
+        rolling_requests.execute_in_background().await.unwrap();
+        let results = rolling_requests.drain_results();
+        assert!(results[0].is_success());
+        let event = |event: &str, data: &str, id: Option<&str>| Event {
+            event: event.to_string(),
+            data: data.to_string(),
+            id: id.map(str::to_string),
+        };
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                event("update", "first line\nsecond line", Some("7")),
+                event("message", "{\"n\":2}", Some("7")),
+                event("message", "no id", Some("")),
+            ]
+        );
+        let received = &server.requests()[0];
+        assert_eq!(received.header("accept"), Some("text/event-stream"));
+        assert_eq!(received.header("cache-control"), Some("no-cache"));
+    }
+
+    #[tokio::test]
+    async fn test_event_stream_reconnects_with_last_event_id() {
+        let server = TestServer::start(|req| match req.index {
+            0 => Reply::new(200).chunks(
+                vec![
+                    b"retry: 10\nid: 1\ndata: first\n\n".to_vec(),
+                    b"data: lost".to_vec(),
+                ],
+                Duration::from_millis(300),
+            ),
+            _ => Reply::new(200).body("data: second\n\n"),
+        });
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let handled = events.clone();
+        let mut request = Request::get(&server.url());
+        request
+            .set_event_handler(move |event| handled.lock().unwrap().push(event.data.clone()))
+            .set_timeout(Duration::from_millis(500))
+            .set_resume(true)
+            .set_max_attempts(2);
+        let mut rolling_requests = RollingRequestsBuilder::new().build();
+        rolling_requests.add_request(request);
+
+        let rolling_requests = Arc::new(rolling_requests);
+        rolling_requests.execute_in_background().await.unwrap();
+        let results = rolling_requests.drain_results();
+        assert!(results[0].is_success());
+        assert_eq!(results[0].attempts, 2);
+        assert_eq!(*events.lock().unwrap(), ["first", "second"]);
+        let requests = server.requests();
+        assert_eq!(requests[0].header("last-event-id"), None);
+        assert_eq!(requests[1].header("last-event-id"), Some("1"));
+    }
+
+    #[tokio::test]
+    async fn test_event_streams_are_rejected_by_batch_executions() {
+        let server = TestServer::start(|_| Reply::new(200).body("data: never read\n\n"));
+        let mut rolling_requests = RollingRequestsBuilder::new().build();
+        let mut request = Request::get(&server.url());
+        request.set_event_handler(|_| {});
+        rolling_requests.add_request(request.clone());
+        rolling_requests.add_request(Request::get(&server.url()));
+
+        let results = rolling_requests.execute_all_detailed().await;
+        let error = results[0].error.as_ref().unwrap();
+        assert_eq!(error.kind(), RollingErrorKind::InvalidRequest);
+        assert!(error.to_string().contains("execute_in_background"));
+        assert!(results[1].is_success());
+
+        rolling_requests.add_request(request);
+        let paired = rolling_requests.execute_all_paired().await;
+        let error = paired[0].1.as_ref().unwrap_err();
+        assert_eq!(error.kind(), RollingErrorKind::InvalidRequest);
+        assert_eq!(server.hits(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_responses_are_saved_from_the_template() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/missing" => Reply::new(404).body("{\"error\":true}"),
+            path => Reply::new(200).body(format!("{{\"path\":\"{}\"}}", path)),
+        });
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("b_200.json"), "old").unwrap();
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .save_responses_to(dir.path(), "{extra_info}_{status}.json")
+            .simultaneous_limit(4)
+            .build();
+        for (extra_info, path) in [
+            ("a", "/one"),
+            ("b", "/two"),
+            ("a", "/three"),
+            ("c/d", "/missing"),
+        ] {
+            let mut request = Request::get(&format!("{}{}", server.url(), path));
+            request.set_extra_info(extra_info);
+            rolling_requests.add_request(request);
+        }
+
+        let results = rolling_requests.execute_all_detailed().await;
+        let mut saved: Vec<_> = results
+            .iter()
+            .map(|result| {
+                let download = result.download.as_ref().unwrap();
+                let name = download
+                    .path
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned();
+                (name, fs::read_to_string(&download.path).unwrap())
+            })
+            .collect();
+        saved.sort();
+        let mut bodies = vec![saved[0].1.clone(), saved[1].1.clone()];
+        bodies.sort();
+        assert_eq!(
+            (saved[0].0.as_str(), saved[1].0.as_str()),
+            ("a_200-1.json", "a_200.json")
+        );
+        assert_eq!(bodies, ["{\"path\":\"/one\"}", "{\"path\":\"/three\"}"]);
+        assert_eq!(
+            saved[2],
+            (
+                "b_200-1.json".to_string(),
+                "{\"path\":\"/two\"}".to_string()
+            )
+        );
+        assert_eq!(
+            saved[3],
+            ("c_d_404.json".to_string(), "{\"error\":true}".to_string())
+        );
+        assert_eq!(
+            fs::read_to_string(dir.path().join("b_200.json")).unwrap(),
+            "old"
+        );
+        assert!(results.iter().all(|result| result.body.is_empty()));
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .save_responses_to(dir.path(), "{extra_info}_{status}.json")
+            .save_collision(SaveCollision::Error)
+            .build();
+        let mut request = Request::get(&format!("{}/two", server.url()));
+        request.set_extra_info("b");
+        rolling_requests.add_request(request);
+        let results = rolling_requests.execute_all_detailed().await;
+        let error = results[0].error.as_ref().unwrap();
+        assert_eq!(error.kind(), RollingErrorKind::Write);
+        assert!(results[0].download.is_none());
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .save_responses_to(dir.path(), "{url_hash}.txt")
+            .save_collision(SaveCollision::Overwrite)
+            .build();
+        let mut paths = Vec::new();
+        for _ in 0..2 {
+            rolling_requests.add_request(Request::get(&format!("{}/two", server.url())));
+            let results = rolling_requests.execute_all_detailed().await;
+            paths.push(results[0].download.as_ref().unwrap().path.clone());
+        }
+        assert_eq!(paths[0], paths[1]);
+        assert_eq!(
+            paths[0].file_name().unwrap().len(),
+            "0123456789abcdef.txt".len()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_status_errors_keep_a_body_snippet() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/invalid" => Reply::new(400).body("{\"message\":\"name is required\"}"),
+            _ => Reply::new(422).body("x".repeat(100)),
+        });
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .error_for_status(true)
+            .error_body_snippet(16)
+            .build();
+        rolling_requests.add_request(Request::get(&format!("{}/invalid", server.url())));
+        let results = rolling_requests.execute_all_detailed().await;
+        let error = results[0].error.as_ref().unwrap();
+        assert_eq!(error.status(), Some(reqwest::StatusCode::BAD_REQUEST));
+        assert_eq!(error.body_snippet(), Some("{\"message\":\"name"));
+        assert!(error.to_string().ends_with(": {\"message\":\"name"));
+
+        let mut rolling_requests = RollingRequestsBuilder::new().error_for_status(true).build();
+        rolling_requests.add_request(Request::get(&format!("{}/invalid", server.url())));
+        rolling_requests.add_request(Request::get(&format!("{}/long", server.url())));
+        let responses = rolling_requests.execute_all().await;
+        let error = responses[0].as_ref().unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("{\"message\":\"name is required\"}")
+        );
+        let error = responses[1].as_ref().unwrap_err();
+        assert_eq!(error.body_snippet().map(str::len), Some(100));
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .error_for_status(true)
+            .error_body_snippet(0)
+            .build();
+        rolling_requests.add_request(Request::get(&format!("{}/invalid", server.url())));
+        let responses = rolling_requests.execute_all().await;
+        assert_eq!(responses[0].as_ref().unwrap_err().body_snippet(), None);
+    }
+
+    #[tokio::test]
+    async fn test_on_complete_fires_once_per_request() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/rows/1" => Reply::new(200).body("one"),
+            _ => Reply::new(503),
+        });
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .error_for_status(true)
+            .retry_policy(
+                RetryPolicy {
+                    initial_delay: Duration::from_millis(10),
+                    ..RetryPolicy::default()
+                }
+                .retry_if(|ctx| ctx.status.is_some_and(|status| status.is_server_error())),
+            )
+            .build();
+
+        let first = Arc::new(Mutex::new(Vec::new()));
+        let calls = first.clone();
+        let mut request = Request::get(&format!("{}/rows/1", server.url()));
+        request.set_on_complete(move |result| {
+            calls
+                .lock()
+                .unwrap()
+                .push((result.status, result.text(), result.error.is_some()));
+        });
+        rolling_requests.add_request(request);
+
+        let second = Arc::new(Mutex::new(Vec::new()));
+        let calls = second.clone();
+        let request = Request::builder(&format!("{}/rows/2", server.url()), reqwest::Method::GET)
+            .on_complete(move |result| {
+                calls
+                    .lock()
+                    .unwrap()
+                    .push((result.status, result.attempts, result.code));
+            })
+            .build()
+            .unwrap();
+        rolling_requests.add_request(request);
+
+        let mut request = Request::get(&format!("{}/rows/1", server.url()));
+        request.set_on_complete(|_| panic!("hook failure"));
+        rolling_requests.add_request(request);
+
+        let results = rolling_requests.execute_all_detailed().await;
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].text(), "one");
+        assert_eq!(results[2].text(), "one");
+        assert_eq!(
+            *first.lock().unwrap(),
+            vec![(Some(reqwest::StatusCode::OK), "one".to_string(), false)]
+        );
+        assert_eq!(
+            *second.lock().unwrap(),
+            vec![(
+                Some(reqwest::StatusCode::SERVICE_UNAVAILABLE),
+                3,
+                results[1].code
+            )]
+        );
+        assert_ne!(results[1].code, 0);
+    }
+
+    #[tokio::test]
+    async fn test_hooks_run_for_every_execution_once_per_request() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/items" => Reply::new(200).header("Link", "</items?page=2>; rel=next"),
+            _ => Reply::new(200),
+        });
+        let other = TestServer::start(|_| Reply::new(200));
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let hooked = |path: &str| {
+            let calls = calls.clone();
+            let mut request = Request::get(&format!("{}{}", server.url(), path));
+            request.set_on_complete(move |result| {
+                let url = result.url.as_ref().unwrap();
+                calls.lock().unwrap().push(url.path().to_string());
+            });
+            request
+        };
+        let mut rolling_requests = RollingRequestsBuilder::new().build();
+
+        // Next pages and repetitions do not call the hook again
+        let mut paginated = hooked("/items");
+        paginated.set_max_pages(2);
+        rolling_requests.add_request(paginated);
+        let mut repeated = hooked("/repeated");
+        repeated.set_repeat(3);
+        rolling_requests.add_request(repeated);
+        let results = rolling_requests.execute_all_detailed().await;
+        assert_eq!(results.len(), 5);
+        assert_eq!(*calls.lock().unwrap(), ["/items", "/repeated"]);
+
+        calls.lock().unwrap().clear();
+        let hosts = [server.url(), other.url()];
+        let hosts: Vec<&str> = hosts.iter().map(String::as_str).collect();
+        let fanout = rolling_requests
+            .add_fanout(hooked("/fanout"), &hosts)
+            .unwrap();
+        assert_eq!(rolling_requests.execute_fanout(&fanout).await.len(), 2);
+        assert_eq!(*calls.lock().unwrap(), ["/fanout", "/fanout"]);
+
+        // Replays call the completion hook but queue no follow-ups
+        calls.lock().unwrap().clear();
+        let mut replayed = hooked("/replayed");
+        let follow_up = format!("{}/follow-up", server.url());
+        replayed.set_continuation(move |_| vec![Request::get(&follow_up)]);
+        rolling_requests.add_request(replayed);
+        let stats = rolling_requests
+            .execute_for(Duration::from_millis(100))
+            .await;
+        assert_eq!(calls.lock().unwrap().len(), stats.total_sent);
+        assert!(stats.total_sent > 0);
+        assert_eq!(rolling_requests.pending_snapshot().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_drain_results_of_background_execution() {
+        let server = TestServer::start(|request| Reply::new(200).body(request.path.clone()));
+        let mut rolling_requests = RollingRequestsBuilder::new().simultaneous_limit(2).build();
+        for index in 0..5 {
+            rolling_requests.add_request(Request::get(&format!("{}/{}", server.url(), index)));
+        }
+        let rolling_requests = Arc::new(rolling_requests);
+        rolling_requests.execute_in_background().await.unwrap();
+
+        let results = rolling_requests.drain_results();
+        assert_eq!(results.len(), 5);
+        let mut bodies: Vec<_> = results.iter().map(|result| result.text()).collect();
+        bodies.sort();
+        assert_eq!(bodies, ["/0", "/1", "/2", "/3", "/4"]);
+        assert!(rolling_requests.drain_results().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_background_results_arrive_as_requests_complete() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/slow" => Reply::new(200)
+                .body("slow")
+                .delay(Duration::from_millis(800)),
+            _ => Reply::new(200).body("fast"),
+        });
+        let mut rolling_requests = RollingRequestsBuilder::new().simultaneous_limit(2).build();
+        rolling_requests.add_request(Request::get(&format!("{}/slow", server.url())));
+        for _ in 0..3 {
+            rolling_requests.add_request(Request::get(&format!("{}/fast", server.url())));
+        }
+        let rolling_requests = Arc::new(rolling_requests);
+        let worker = rolling_requests.execute_in_background();
+
+        // The fast requests use the free slot without waiting for the slow one
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let early = rolling_requests.drain_results();
+        assert_eq!(early.len(), 3);
+        assert!(early.iter().all(|result| result.text() == "fast"));
+        assert!(!worker.is_finished());
+
+        worker.await.unwrap();
+        let late = rolling_requests.drain_results();
+        assert_eq!(late.len(), 1);
+        assert_eq!(late[0].text(), "slow");
+    }
+
+    #[tokio::test]
+    async fn test_result_buffer_overflow_policies() {
+        let server = TestServer::start(|_| Reply::new(200));
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .simultaneous_limit(1)
+            .result_buffer_capacity(2, ResultOverflow::Block)
+            .build();
+        for _ in 0..5 {
+            rolling_requests.add_request(Request::get(&server.url()));
+        }
+        let rolling_requests = Arc::new(rolling_requests);
+        let worker = rolling_requests.execute_in_background();
+        let mut drained = Vec::new();
+        while drained.len() < 5 {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            let results = rolling_requests.drain_results();
+            assert!(results.len() <= 2);
+            drained.extend(results);
+        }
+        worker.await.unwrap();
+        let ids: Vec<_> = drained.iter().map(|result| result.request_id).collect();
+        assert_eq!(ids, (0..5).map(Some).collect::<Vec<_>>());
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .result_buffer_capacity(2, ResultOverflow::DropOldest)
+            .build();
+        for _ in 0..4 {
+            rolling_requests.add_request(Request::get(&server.url()));
+        }
+        let rolling_requests = Arc::new(rolling_requests);
+        rolling_requests.execute_in_background().await.unwrap();
+        let ids: Vec<_> = rolling_requests
+            .drain_results()
+            .iter()
+            .map(|result| result.request_id)
+            .collect();
+        assert_eq!(ids, [Some(2), Some(3)]);
+    }
+
+    #[tokio::test]
+    async fn test_bodies_are_read_within_the_simultaneous_limit() {
+        let arrivals = Arc::new(Mutex::new(Vec::new()));
+        let seen = arrivals.clone();
+        let server = TestServer::start(move |_| {
+            seen.lock().unwrap().push(std::time::Instant::now());
+            Reply::new(200).chunks(vec![b"slow".to_vec(); 3], Duration::from_millis(150))
+        });
+
+        let mut rolling_requests = RollingRequestsBuilder::new().simultaneous_limit(1).build();
+        rolling_requests.add_request(Request::get(&server.url()));
+        rolling_requests.add_request(Request::get(&server.url()));
+
+        let results = rolling_requests.execute_all_detailed().await;
+        assert_eq!(results.len(), 2);
+        for result in &results {
+            assert_eq!(result.text(), "slowslowslow");
+            assert!(result.elapsed >= Duration::from_millis(300));
+        }
+        // The second request only starts once the body of the first is read
+        let arrivals = arrivals.lock().unwrap();
+        assert!(arrivals[1] - arrivals[0] >= Duration::from_millis(300));
+    }
+}
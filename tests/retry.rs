@@ -0,0 +1,657 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::{Reply, StalledListener, TestServer};
+    use mockito::mock;
+    use reqwest::{Method, StatusCode};
+    use rollingrequests::{
+        error::RollingErrorKind,
+        request::Request,
+        retry::{Attempts, DeadLetterOverflow, RetryBudget, RetryPolicy},
+        rolling::RollingRequestsBuilder,
+    };
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::{Duration, Instant};
+
+    /// A policy retrying quickly, so tests do not wait on the backoff.
+    fn fast_policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_delay: Duration::from_millis(10),
+            multiplier: 2.0,
+            max_delay: Duration::from_millis(50),
+            ..RetryPolicy::default()
+        }
+    }
+
+    /// Starts a server answering `first` to the first request and 200 afterwards.
+    fn flaky_server(first: u16) -> TestServer {
+        TestServer::start(move |request| match request.index {
+            0 => Reply::new(first),
+            _ => Reply::new(200),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_retry_policy_retries_timeouts_until_success() {
+        // The first two attempts time out, the third one is answered right away
+        let server = TestServer::start(|request| {
+            let delay = if request.index < 2 { 1000 } else { 0 };
+            Reply::new(200)
+                .body("ok")
+                .delay(Duration::from_millis(delay))
+        });
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .timeout(Duration::from_millis(300))
+            .retry_policy(fast_policy(3))
+            .build();
+        rolling_requests.add_request(Request::new(&server.url(), Method::GET));
+
+        let responses = rolling_requests.execute_requests().await;
+        assert_eq!(responses.len(), 1);
+        let response = responses.into_iter().next().unwrap().unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(server.hits(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_backoff_that_cannot_be_computed_waits_max_delay() {
+        let policies = [
+            (Duration::from_millis(10), f64::NAN),
+            (Duration::ZERO, f64::INFINITY),
+        ];
+        for (initial_delay, multiplier) in policies {
+            let server = TestServer::start(|request| match request.index {
+                0 | 1 => Reply::new(502),
+                _ => Reply::new(200),
+            });
+            let mut rolling_requests = RollingRequestsBuilder::new()
+                .retry_policy(RetryPolicy {
+                    initial_delay,
+                    multiplier,
+                    ..fast_policy(3)
+                })
+                .build();
+            rolling_requests.add_request(Request::get(&server.url()));
+            let results = rolling_requests.execute_all_detailed().await;
+            assert_eq!(results[0].status, Some(StatusCode::OK));
+            assert_eq!(server.hits(), 3);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_policy_reports_attempts_when_exhausted() {
+        let server = TestServer::start(|_| Reply::new(200).delay(Duration::from_millis(1000)));
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .timeout(Duration::from_millis(200))
+            .retry_policy(fast_policy(5))
+            .build();
+
+        // The request's own policy takes precedence over the builder's
+        let mut request = Request::new(&server.url(), Method::GET);
+        request.set_retry_policy(fast_policy(2));
+        rolling_requests.add_request(request);
+
+        let responses = rolling_requests.execute_requests().await;
+        let err = responses.into_iter().next().unwrap().unwrap_err();
+        assert!(err.is_timeout());
+        assert_eq!(err.attempts(), 2);
+        assert!(err.to_string().contains("after 2 attempts"));
+        assert_eq!(server.hits(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_default_predicate_retries_transient_statuses_only() {
+        let unavailable = flaky_server(503);
+        let bad_request = flaky_server(400);
+        let post = flaky_server(503);
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .simultaneous_limit(3)
+            .timeout(Duration::from_secs(5))
+            .retry_policy(fast_policy(3))
+            .build();
+        rolling_requests.add_request(Request::new(&unavailable.url(), Method::GET));
+        rolling_requests.add_request(Request::new(&bad_request.url(), Method::GET));
+        rolling_requests.add_request(Request::new(&post.url(), Method::POST));
+
+        let statuses: Vec<_> = rolling_requests
+            .execute_requests()
+            .await
+            .into_iter()
+            .map(|response| response.unwrap().status())
+            .collect();
+
+        // 503 is retried, while 400 and non-idempotent requests are returned as is
+        assert_eq!(
+            statuses,
+            [
+                StatusCode::OK,
+                StatusCode::BAD_REQUEST,
+                StatusCode::SERVICE_UNAVAILABLE
+            ]
+        );
+        assert_eq!(unavailable.hits(), 2);
+        assert_eq!(bad_request.hits(), 1);
+        assert_eq!(post.hits(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_retry_if_predicate_overrides_default() {
+        let server = flaky_server(418);
+
+        let policy = fast_policy(3).retry_if(|context| {
+            assert_eq!(context.attempt, 1);
+            assert!(context.url.ends_with('/'));
+            context.method == Method::POST && context.status == Some(StatusCode::IM_A_TEAPOT)
+        });
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .timeout(Duration::from_secs(5))
+            .retry_policy(policy)
+            .build();
+        rolling_requests.add_request(Request::new(&format!("{}/", server.url()), Method::POST));
+
+        let responses = rolling_requests.execute_requests().await;
+        let response = responses.into_iter().next().unwrap().unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(server.hits(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_retry_budget_stops_retries_once_exhausted() {
+        let server = TestServer::start(|_| Reply::new(503));
+
+        // Without jitter, every retry waits long enough for the next fresh request to go first
+        let policy = RetryPolicy {
+            jitter: false,
+            ..fast_policy(3)
+        };
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .timeout(Duration::from_secs(5))
+            .retry_policy(policy)
+            .retry_budget(RetryBudget::Fixed {
+                retries: 2,
+                window: Duration::from_secs(60),
+            })
+            .build();
+        for _ in 0..3 {
+            rolling_requests.add_request(Request::new(&server.url(), Method::GET));
+        }
+
+        let responses = rolling_requests.execute_all().await;
+        assert_eq!(responses.len(), 3);
+        assert!(
+            responses
+                .iter()
+                .all(|response| response.as_ref().unwrap().status() == 503)
+        );
+
+        // Two retries are granted, every later failure is returned as is
+        assert_eq!(server.hits(), 5);
+        let stats = rolling_requests.retry_stats();
+        assert_eq!(stats.retries, 2);
+        assert_eq!(stats.denied, 3);
+        assert_eq!(stats.budget_remaining, Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_failed_requests_land_in_dead_letter_queue() {
+        // Nothing listens on port 1, so every attempt fails to connect
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .timeout(Duration::from_secs(5))
+            .retry_policy(fast_policy(2))
+            .build();
+        let mut request = Request::new("http://127.0.0.1:1/unreachable", Method::GET);
+        request.set_extra_info("job-42");
+        rolling_requests.add_request(request);
+
+        let responses = rolling_requests.execute_requests().await;
+        assert!(responses[0].is_err());
+
+        let failed = rolling_requests.failed_requests();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].get_url(), "http://127.0.0.1:1/unreachable");
+        assert_eq!(
+            failed[0].get_extra_info().map(String::as_str),
+            Some("job-42")
+        );
+        assert_eq!(failed[0].get_attempts(), 2);
+        assert!(failed[0].get_response_error().is_some());
+
+        // Requeued requests are attempted again with a fresh attempt count
+        assert_eq!(rolling_requests.requeue_failed(), 1);
+        assert!(rolling_requests.failed_requests().is_empty());
+        let responses = rolling_requests.execute_requests().await;
+        assert_eq!(responses[0].as_ref().unwrap_err().attempts(), 2);
+        assert_eq!(rolling_requests.failed_requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_dead_letter_capacity_drops_newest() {
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .timeout(Duration::from_secs(5))
+            .dead_letter_capacity(1, DeadLetterOverflow::DropNewest)
+            .build();
+        for name in ["first", "second"] {
+            let mut request = Request::new("http://127.0.0.1:1/unreachable", Method::GET);
+            request.set_extra_info(name);
+            rolling_requests.add_request(request);
+        }
+
+        let responses = rolling_requests.execute_all().await;
+        assert!(responses.iter().all(|response| response.is_err()));
+
+        let failed = rolling_requests.failed_requests();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(
+            failed[0].get_extra_info().map(String::as_str),
+            Some("first")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hedge_after_takes_first_response() {
+        // The first attempt stalls, the hedged duplicate is answered right away
+        let server = TestServer::start(|request| match request.index {
+            0 => Reply::new(200)
+                .body("slow")
+                .delay(Duration::from_millis(1500)),
+            _ => Reply::new(200).body("fast"),
+        });
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .simultaneous_limit(2)
+            .timeout(Duration::from_secs(5))
+            .hedge_after(Duration::from_millis(100))
+            .build();
+        rolling_requests.add_request(Request::new(&server.url(), Method::GET));
+
+        let start = Instant::now();
+        let responses = rolling_requests.execute_requests().await;
+        assert!(start.elapsed() < Duration::from_millis(1000));
+        assert_eq!(responses.len(), 1);
+        let response = responses.into_iter().next().unwrap().unwrap();
+        assert_eq!(response.text().await.unwrap(), "fast");
+        assert_eq!(server.hits(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_hedge_after_skips_non_idempotent_requests() {
+        let server = TestServer::start(|_| Reply::new(200).delay(Duration::from_millis(400)));
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .simultaneous_limit(2)
+            .timeout(Duration::from_secs(5))
+            .hedge_after(Duration::from_millis(100))
+            .build();
+        rolling_requests.add_request(Request::new(&server.url(), Method::POST));
+
+        let responses = rolling_requests.execute_requests().await;
+        assert!(responses[0].is_ok());
+        assert_eq!(server.hits(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_fallback_urls_are_tried_in_order() {
+        let unavailable = TestServer::start(|_| Reply::new(503));
+        let mirror = TestServer::start(|_| Reply::new(200).body("mirror"));
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .timeout(Duration::from_secs(5))
+            .build();
+
+        // Nothing listens on port 1, then the first mirror is unavailable
+        let mut request = Request::new("http://127.0.0.1:1/file", Method::GET);
+        request.add_fallback_url(&format!("{}/file", unavailable.url()));
+        request.add_fallback_url(&format!("{}/file", mirror.url()));
+        rolling_requests.add_request(request);
+
+        let responses = rolling_requests.execute_requests().await;
+        assert_eq!(responses.len(), 1);
+        let response = responses.into_iter().next().unwrap().unwrap();
+        assert_eq!(response.url().as_str(), format!("{}/file", mirror.url()));
+        assert_eq!(response.text().await.unwrap(), "mirror");
+        assert_eq!(unavailable.hits(), 1);
+        assert_eq!(mirror.hits(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_fallback_reports_last_failing_url() {
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .timeout(Duration::from_secs(5))
+            .build();
+
+        let mut request = Request::new("http://127.0.0.1:1/primary", Method::GET);
+        request.add_fallback_url("http://127.0.0.1:1/mirror");
+        rolling_requests.add_request(request);
+
+        let responses = rolling_requests.execute_requests().await;
+        let err = responses.into_iter().next().unwrap().unwrap_err();
+        assert_eq!(err.attempts(), 2);
+        assert_eq!(err.url().unwrap().as_str(), "http://127.0.0.1:1/mirror");
+
+        // The failed request keeps its original URL so it can be requeued as is
+        let failed = rolling_requests.failed_requests();
+        assert_eq!(failed[0].get_url(), "http://127.0.0.1:1/primary");
+    }
+
+    #[tokio::test]
+    async fn test_idempotency_key_is_reused_across_retries() {
+        let server = flaky_server(503);
+
+        let policy = fast_policy(2).retry_if(|context| context.status.is_some());
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .timeout(Duration::from_secs(5))
+            .retry_policy(policy)
+            .idempotency_key("X-Idempotency-Key")
+            .build();
+        let mut post = Request::new(&server.url(), Method::POST);
+        post.set_post_data(Some("order"));
+        rolling_requests.add_request(post);
+        rolling_requests.add_request(Request::new(&server.url(), Method::GET));
+
+        let responses = rolling_requests.execute_all().await;
+        assert!(responses.iter().all(|response| response.is_ok()));
+
+        let requests = server.requests();
+        let keys: Vec<_> = requests
+            .iter()
+            .filter(|request| request.method == "POST")
+            .map(|request| request.header("x-idempotency-key").unwrap().to_string())
+            .collect();
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys[0], keys[1]);
+        assert_eq!(keys[0].len(), 36);
+
+        // Idempotent methods do not need a key
+        let get = requests.iter().find(|request| request.method == "GET");
+        assert!(get.unwrap().header("x-idempotency-key").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_error_for_status_returns_non_success_as_errors() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/missing" => Reply::new(404),
+            "/broken" => Reply::new(500),
+            _ => Reply::new(200),
+        });
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .simultaneous_limit(4)
+            .timeout(Duration::from_secs(5))
+            .error_for_status(true)
+            .build();
+        for path in ["/missing", "/broken", "/ok"] {
+            let url = format!("{}{}", server.url(), path);
+            rolling_requests.add_request(Request::new(&url, Method::GET));
+        }
+        // Requests can opt out of the builder's setting
+        let mut lenient = Request::new(&format!("{}/missing", server.url()), Method::GET);
+        lenient.set_error_for_status(false);
+        rolling_requests.add_request(lenient);
+
+        let responses = rolling_requests.execute_requests().await;
+        assert_eq!(responses.len(), 4);
+        let missing = responses[0].as_ref().unwrap_err();
+        assert!(missing.is_status());
+        assert_eq!(missing.status(), Some(StatusCode::NOT_FOUND));
+        assert_eq!(
+            responses[1].as_ref().unwrap_err().status(),
+            Some(StatusCode::INTERNAL_SERVER_ERROR)
+        );
+        assert_eq!(responses[2].as_ref().unwrap().status(), StatusCode::OK);
+        assert_eq!(
+            responses[3].as_ref().unwrap().status(),
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn test_error_for_status_applies_after_retries() {
+        let server = TestServer::start(|_| Reply::new(503));
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .timeout(Duration::from_secs(5))
+            .retry_policy(fast_policy(3))
+            .error_for_status(true)
+            .build();
+        rolling_requests.add_request(Request::new(&server.url(), Method::GET));
+
+        let responses = rolling_requests.execute_requests().await;
+        let err = responses.into_iter().next().unwrap().unwrap_err();
+        assert_eq!(err.status(), Some(StatusCode::SERVICE_UNAVAILABLE));
+        assert_eq!(err.attempts(), 3);
+        assert_eq!(server.hits(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_max_attempts_overrides_policy_per_request() {
+        // Every server fails twice before answering 200
+        let flaky = || {
+            TestServer::start(|request| match request.index {
+                0 | 1 => Reply::new(503),
+                _ => Reply::new(200),
+            })
+        };
+        let once = flaky();
+        let default = flaky();
+        let important = flaky();
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .simultaneous_limit(3)
+            .timeout(Duration::from_secs(5))
+            .retry_policy(fast_policy(2))
+            .build();
+        let mut request = Request::new(&once.url(), Method::GET);
+        request.set_max_attempts(1);
+        rolling_requests.add_request(request);
+        rolling_requests.add_request(Request::new(&default.url(), Method::GET));
+        let mut request = Request::new(&important.url(), Method::GET);
+        request.set_max_attempts(3);
+        rolling_requests.add_request(request);
+
+        let responses = rolling_requests.execute_requests().await;
+        let summary: Vec<_> = responses
+            .iter()
+            .map(|response| {
+                let response = response.as_ref().unwrap();
+                let attempts = response.extensions().get::<Attempts>().unwrap().0;
+                (response.status(), attempts)
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (StatusCode::SERVICE_UNAVAILABLE, 1),
+                (StatusCode::SERVICE_UNAVAILABLE, 2),
+                (StatusCode::OK, 3)
+            ]
+        );
+        assert_eq!(once.hits(), 1);
+        assert_eq!(default.hits(), 2);
+        assert_eq!(important.hits(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_on_unauthorized_refreshes_token_once() {
+        let _expired = mock("GET", "/secure")
+            .match_header("authorization", "Bearer expired")
+            .with_status(401)
+            .create();
+        let fresh = mock("GET", "/secure")
+            .match_header("authorization", "Bearer fresh")
+            .with_status(200)
+            .expect(3)
+            .create();
+
+        let refreshes = Arc::new(AtomicUsize::new(0));
+        let counter = refreshes.clone();
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .simultaneous_limit(3)
+            .timeout(Duration::from_secs(5))
+            .on_unauthorized(move || {
+                let counter = counter.clone();
+                async move {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    Some("Bearer fresh".to_string())
+                }
+            })
+            .build();
+
+        let url = format!("{}/secure", mockito::server_url());
+        for _ in 0..3 {
+            let mut request = Request::new(&url, Method::GET);
+            let headers =
+                HashMap::from([("Authorization".to_string(), "Bearer expired".to_string())]);
+            request.set_headers(headers);
+            rolling_requests.add_request(request);
+        }
+
+        let responses = rolling_requests.execute_requests().await;
+        assert_eq!(responses.len(), 3);
+        for response in responses {
+            assert_eq!(response.unwrap().status(), StatusCode::OK);
+        }
+        assert_eq!(refreshes.load(Ordering::SeqCst), 1);
+        fresh.assert();
+    }
+
+    #[tokio::test]
+    async fn test_connect_timeout_is_classified() {
+        let listener = StalledListener::start();
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .connect_timeout(Duration::from_millis(200))
+            .timeout(Duration::from_secs(5))
+            .build();
+        rolling_requests.add_request(Request::new(&listener.url(), Method::GET));
+
+        let start = Instant::now();
+        let responses = rolling_requests.execute_requests().await;
+        let error = responses.into_iter().next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), RollingErrorKind::ConnectTimeout);
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_read_timeout_is_classified() {
+        let server = TestServer::start(|_| Reply::new(200).delay(Duration::from_secs(1)));
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .connect_timeout(Duration::from_secs(1))
+            .timeout(Duration::from_millis(300))
+            .build();
+        rolling_requests.add_request(Request::new(&server.url(), Method::GET));
+
+        let responses = rolling_requests.execute_requests().await;
+        let error = responses.into_iter().next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), RollingErrorKind::ReadTimeout);
+        assert!(error.is_timeout());
+    }
+
+    #[tokio::test]
+    async fn test_status_error_is_classified() {
+        let server = TestServer::start(|_| Reply::new(503));
+
+        let mut rolling_requests = RollingRequestsBuilder::new().error_for_status(true).build();
+        rolling_requests.add_request(Request::new(&server.url(), Method::GET));
+
+        let responses = rolling_requests.execute_requests().await;
+        let error = responses.into_iter().next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), RollingErrorKind::Status(503));
+    }
+
+    #[tokio::test]
+    async fn test_retry_if_matches_error_kind() {
+        // Only read timeouts are retried, connect timeouts give up at once
+        let server = TestServer::start(|request| {
+            let delay = if request.index == 0 { 1000 } else { 0 };
+            Reply::new(200).delay(Duration::from_millis(delay))
+        });
+        let listener = StalledListener::start();
+
+        let policy = fast_policy(3).retry_if(|ctx| ctx.kind == Some(RollingErrorKind::ReadTimeout));
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .simultaneous_limit(2)
+            .connect_timeout(Duration::from_millis(200))
+            .timeout(Duration::from_millis(400))
+            .retry_policy(policy)
+            .build();
+        rolling_requests.add_request(Request::new(&server.url(), Method::GET));
+        rolling_requests.add_request(Request::new(&listener.url(), Method::GET));
+
+        let responses = rolling_requests.execute_requests().await;
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0].as_ref().unwrap().status(), 200);
+        let error = responses[1].as_ref().unwrap_err();
+        assert_eq!(error.kind(), RollingErrorKind::ConnectTimeout);
+        assert_eq!(error.attempts(), 1);
+        assert_eq!(server.hits(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_fail_fast_stops_on_first_error() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            // The failure waits a little so that the slow request reaches the server
+            "/fail" => Reply::new(500).delay(Duration::from_millis(50)),
+            _ => Reply::new(200).delay(Duration::from_millis(300)),
+        });
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .simultaneous_limit(2)
+            .timeout(Duration::from_secs(5))
+            .error_for_status(true)
+            .fail_fast(true)
+            .build();
+        for index in 0..10 {
+            let path = if index == 1 { "/fail" } else { "/ok" };
+            rolling_requests.add_request(Request::new(
+                &format!("{}{}", server.url(), path),
+                Method::GET,
+            ));
+        }
+
+        let start = Instant::now();
+        let responses = rolling_requests.execute_all().await;
+        // The slow request in flight is aborted rather than awaited
+        assert!(start.elapsed() < Duration::from_millis(250));
+        assert_eq!(responses.len(), 1);
+        assert_eq!(
+            responses[0].as_ref().unwrap_err().status(),
+            Some(StatusCode::INTERNAL_SERVER_ERROR)
+        );
+        assert_eq!(server.hits(), 2);
+        assert_eq!(rolling_requests.failed_requests().len(), 1);
+
+        // Requests that were never dispatched are still queued
+        let responses = rolling_requests.execute_all().await;
+        assert_eq!(responses.len(), 8);
+        assert!(responses.iter().all(|response| response.is_ok()));
+        assert_eq!(server.hits(), 10);
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_requeues_are_counted_apart_from_retries() {
+        // Three failures use up most of the attempts, then four throttled answers
+        let server = TestServer::start(|req| match req.index {
+            0..=2 => Reply::new(502),
+            3..=6 => Reply::new(429).header("Retry-After", "0"),
+            _ => Reply::new(200),
+        });
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .retry_policy(fast_policy(4))
+            .respect_retry_after(true)
+            .build();
+        rolling_requests.add_request(Request::get(&server.url()));
+
+        let response = rolling_requests.execute_all().await.remove(0).unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(server.hits(), 8);
+    }
+}
@@ -0,0 +1,82 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use mockito::mock;
+    use rollingrequests::{
+        error::RollingErrorKind, request::Request, retry::RetryPolicy,
+        rolling::RollingRequestsBuilder, stats::Stats,
+    };
+    use std::collections::{BTreeMap, HashMap};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_stats_count_a_mixed_batch() {
+        let ok = mock("GET", "/stats/ok")
+            .with_status(200)
+            .with_body("hello")
+            .expect(2)
+            .create();
+        let missing = mock("GET", "/stats/missing")
+            .with_status(404)
+            .with_body("nope")
+            .create();
+        let created = mock("POST", "/stats/items")
+            .with_status(201)
+            .match_body("abc")
+            .create();
+        let flaky = mock("GET", "/stats/flaky")
+            .with_status(503)
+            .expect(2)
+            .create();
+
+        let url = &mockito::server_url();
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .retry_policy(
+                RetryPolicy {
+                    max_attempts: 2,
+                    initial_delay: Duration::from_millis(10),
+                    ..RetryPolicy::default()
+                }
+                .retry_if(|ctx| ctx.status.is_some_and(|status| status.is_server_error())),
+            )
+            .build();
+        for _ in 0..2 {
+            rolling_requests.add_request(Request::get(&format!("{}/stats/ok", url)));
+        }
+        let mut request = Request::get(&format!("{}/stats/missing", url));
+        request.set_error_for_status(true);
+        rolling_requests.add_request(request);
+        rolling_requests.add_request(Request::post(&format!("{}/stats/items", url), Some("abc")));
+        rolling_requests.add_request(Request::get(&format!("{}/stats/flaky", url)));
+        rolling_requests.add_request(Request::get(&format!("{}/stats/{{id}}", url)));
+
+        let responses = rolling_requests.execute_all().await;
+        assert_eq!(responses.len(), 6);
+        ok.assert();
+        missing.assert();
+        created.assert();
+        flaky.assert();
+
+        let stats = rolling_requests.stats();
+        assert_eq!(
+            stats,
+            Stats {
+                dispatched: 5,
+                succeeded: 3,
+                failed: 3,
+                failed_by_kind: HashMap::from([
+                    (RollingErrorKind::Status(404), 1),
+                    (RollingErrorKind::InvalidRequest, 1),
+                ]),
+                retries: 1,
+                status_counts: BTreeMap::from([(200, 2), (201, 1), (404, 1), (503, 1)]),
+                request_bytes: 3,
+                response_bytes: 10,
+            }
+        );
+
+        rolling_requests.reset_stats();
+        assert_eq!(rolling_requests.stats(), Stats::default());
+    }
+}
@@ -0,0 +1,270 @@
+#[cfg(test)]
+mod tests {
+    use mockito::mock;
+    use reqwest::Method;
+    use rollingrequests::{
+        request::Request, rolling::RollingRequestsBuilder, throttle::RateLimitHeaders,
+    };
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    /// Creates a GET mock that records the instant of every hit.
+    fn timed_mock(path: &str, hits: Arc<Mutex<Vec<Instant>>>) -> mockito::Mock {
+        mock("GET", path)
+            .with_status(200)
+            .with_body_from_fn(move |w| {
+                hits.lock().unwrap().push(Instant::now());
+                w.write_all(b"ok")
+            })
+            .create()
+    }
+
+    #[tokio::test]
+    async fn test_per_host_rate_limit_does_not_block_other_hosts() {
+        let slow_hits = Arc::new(Mutex::new(Vec::new()));
+        let fast_hits = Arc::new(Mutex::new(Vec::new()));
+        let _m1 = timed_mock("/slow", slow_hits.clone());
+        let _m2 = timed_mock("/fast", fast_hits.clone());
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .simultaneous_limit(6)
+            .timeout(Duration::from_secs(5))
+            .per_host_rate_limit("localhost", 2.0)
+            .build();
+
+        // Both hosts resolve to the mock server but are throttled independently
+        let url = mockito::server_url();
+        let throttled = url.replace("127.0.0.1", "localhost");
+        for _ in 0..3 {
+            rolling_requests.add_request(Request::new(&format!("{}/slow", throttled), Method::GET));
+            rolling_requests.add_request(Request::new(&format!("{}/fast", url), Method::GET));
+        }
+
+        let start = Instant::now();
+        let responses = rolling_requests.execute_requests().await;
+        assert_eq!(responses.len(), 6);
+        assert!(responses.iter().all(|response| response.is_ok()));
+
+        let fast_hits = fast_hits.lock().unwrap();
+        let slow_hits = slow_hits.lock().unwrap();
+        assert_eq!(fast_hits.len(), 3);
+        assert_eq!(slow_hits.len(), 3);
+
+        // The unthrottled host is served immediately...
+        for hit in fast_hits.iter() {
+            assert!(hit.duration_since(start) < Duration::from_millis(300));
+        }
+        // ...while the third request to the throttled host waits for a new token
+        let last_slow = slow_hits.iter().max().unwrap();
+        assert!(last_slow.duration_since(start) >= Duration::from_millis(450));
+    }
+
+    #[tokio::test]
+    async fn test_per_host_delay_spaces_same_host_requests() {
+        let delayed_hits = Arc::new(Mutex::new(Vec::new()));
+        let other_hits = Arc::new(Mutex::new(Vec::new()));
+        let _m1 = timed_mock("/polite", delayed_hits.clone());
+        let _m2 = timed_mock("/other", other_hits.clone());
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .simultaneous_limit(5)
+            .timeout(Duration::from_secs(5))
+            .per_host_delay_override("localhost", Duration::from_millis(200))
+            .build();
+
+        let url = mockito::server_url();
+        let polite = url.replace("127.0.0.1", "localhost");
+        for _ in 0..3 {
+            rolling_requests.add_request(Request::new(&format!("{}/polite", polite), Method::GET));
+        }
+        for _ in 0..2 {
+            rolling_requests.add_request(Request::new(&format!("{}/other", url), Method::GET));
+        }
+
+        let start = Instant::now();
+        let responses = rolling_requests.execute_requests().await;
+        assert_eq!(responses.len(), 5);
+
+        let mut delayed_hits = delayed_hits.lock().unwrap().clone();
+        delayed_hits.sort();
+        assert_eq!(delayed_hits.len(), 3);
+        for pair in delayed_hits.windows(2) {
+            // Allow for scheduling noise between dispatch and the server seeing the hit
+            assert!(pair[1].duration_since(pair[0]) >= Duration::from_millis(180));
+        }
+
+        // Requests to other hosts are not held back by the delay
+        for hit in other_hits.lock().unwrap().iter() {
+            assert!(hit.duration_since(start) < Duration::from_millis(150));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_requeues_and_pauses_host() {
+        let hits = Arc::new(Mutex::new(Vec::new()));
+        let throttled_hits = hits.clone();
+        let _m1 = mock("GET", "/retry-after")
+            .with_status(429)
+            .with_header("retry-after", "1")
+            .with_body_from_fn(move |w| {
+                throttled_hits.lock().unwrap().push(Instant::now());
+                w.write_all(b"slow down")
+            })
+            .expect(1)
+            .create();
+        let _m2 = timed_mock("/retry-after", hits.clone());
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .simultaneous_limit(1)
+            .timeout(Duration::from_secs(5))
+            .respect_retry_after(true)
+            .build();
+
+        let url = &mockito::server_url();
+        rolling_requests.add_request(Request::new(&format!("{}/retry-after", url), Method::GET));
+
+        let responses = rolling_requests.execute_requests().await;
+        assert_eq!(responses.len(), 1);
+        let response = responses.into_iter().next().unwrap().unwrap();
+        assert_eq!(response.status(), 200);
+
+        let hits = hits.lock().unwrap();
+        assert_eq!(hits.len(), 2);
+        assert!(hits[1].duration_since(hits[0]) >= Duration::from_millis(950));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_headers_pause_exhausted_host() {
+        let hits = Arc::new(Mutex::new(Vec::new()));
+        let window = |remaining: &str, hits: Arc<Mutex<Vec<Instant>>>| {
+            mock("GET", "/limited")
+                .with_status(200)
+                .with_header("x-ratelimit-remaining", remaining)
+                .with_header("x-ratelimit-reset", "1")
+                .with_body_from_fn(move |w| {
+                    hits.lock().unwrap().push(Instant::now());
+                    w.write_all(b"ok")
+                })
+                .expect(1)
+                .create()
+        };
+        let _m1 = window("1", hits.clone());
+        let _m2 = window("0", hits.clone());
+        let _m3 = window("59", hits.clone());
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .simultaneous_limit(1)
+            .timeout(Duration::from_secs(5))
+            .rate_limit_headers(RateLimitHeaders::default())
+            .build();
+
+        let url = &mockito::server_url();
+        for _ in 0..3 {
+            rolling_requests.add_request(Request::new(&format!("{}/limited", url), Method::GET));
+        }
+        for _ in 0..3 {
+            let responses = rolling_requests.execute_requests().await;
+            assert_eq!(responses.len(), 1);
+        }
+
+        let hits = hits.lock().unwrap();
+        assert_eq!(hits.len(), 3);
+        // Plenty of budget left after the first response, so no pause
+        assert!(hits[1].duration_since(hits[0]) < Duration::from_millis(500));
+        // The exhausted window pauses the host until it resets
+        assert!(hits[2].duration_since(hits[1]) >= Duration::from_millis(950));
+
+        let info = rolling_requests.rate_limit_info("127.0.0.1").unwrap();
+        assert_eq!(info.remaining, Some(59));
+        assert!(info.reset.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_launch_jitter_spreads_dispatches() {
+        let hits = Arc::new(Mutex::new(Vec::new()));
+        let _m = timed_mock("/jitter", hits.clone());
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .simultaneous_limit(5)
+            .timeout(Duration::from_secs(5))
+            .launch_jitter(Duration::from_millis(200))
+            .build();
+
+        let url = &mockito::server_url();
+        for _ in 0..5 {
+            rolling_requests.add_request(Request::new(&format!("{}/jitter", url), Method::GET));
+        }
+
+        let responses = rolling_requests.execute_requests().await;
+        assert_eq!(responses.len(), 5);
+
+        let hits = hits.lock().unwrap();
+        let first = hits.iter().min().unwrap();
+        let last = hits.iter().max().unwrap();
+        assert!(last.duration_since(*first) > Duration::from_millis(5));
+    }
+
+    #[tokio::test]
+    async fn test_launch_spacing_separates_dispatches() {
+        let hits = Arc::new(Mutex::new(Vec::new()));
+        let _m = timed_mock("/spacing", hits.clone());
+
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .simultaneous_limit(4)
+            .timeout(Duration::from_secs(5))
+            .launch_spacing(Duration::from_millis(100))
+            .build();
+
+        let url = &mockito::server_url();
+        for _ in 0..4 {
+            rolling_requests.add_request(Request::new(&format!("{}/spacing", url), Method::GET));
+        }
+
+        let responses = rolling_requests.execute_requests().await;
+        assert_eq!(responses.len(), 4);
+
+        let mut hits = hits.lock().unwrap().clone();
+        hits.sort();
+        for pair in hits.windows(2) {
+            assert!(pair[1].duration_since(pair[0]) >= Duration::from_millis(80));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_max_bytes_per_second_caps_download_throughput() {
+        let body = vec![b'x'; 100 * 1024];
+        let _m = mock("GET", "/download")
+            .with_status(200)
+            .with_body(&body)
+            .create();
+
+        let limit = 100 * 1024;
+        let mut rolling_requests = RollingRequestsBuilder::new()
+            .simultaneous_limit(2)
+            .timeout(Duration::from_secs(10))
+            .max_bytes_per_second(limit)
+            .build();
+
+        let url = &mockito::server_url();
+        for _ in 0..2 {
+            rolling_requests.add_request(Request::new(&format!("{}/download", url), Method::GET));
+        }
+
+        let start = Instant::now();
+        let responses = rolling_requests.execute_requests().await;
+        assert_eq!(responses.len(), 2);
+
+        // Both bodies are read concurrently and share the same budget
+        let mut responses = responses.into_iter().map(|response| response.unwrap());
+        let (first, second) = tokio::join!(
+            rolling_requests.read_body(responses.next().unwrap()),
+            rolling_requests.read_body(responses.next().unwrap()),
+        );
+        let total = first.unwrap().len() + second.unwrap().len();
+        assert_eq!(total, 2 * body.len());
+
+        // The bucket allows a burst of a tenth of a second up front
+        let expected = Duration::from_secs_f64(total as f64 / limit as f64 - 0.1);
+        assert!(start.elapsed() >= expected);
+    }
+}